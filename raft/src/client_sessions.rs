@@ -0,0 +1,231 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+
+use prost::Message;
+
+use crate::net::rpc::grpc;
+
+/// The sessions of the clients: the last sequence applied for each client along with its response.
+/// A command with an already applied sequence is not applied again, the client receives the stored response of the last sequence.
+/// A client opens its session with sequence 0, a later sequence without a session belongs to a session that has expired.
+/// Sessions expire by log time (the leader's clock stamped on the entries), so all the replicas expire the same sessions at the same index.
+pub(crate) struct ClientSessions {
+    sessions: HashMap<u64, ClientSession>,
+    log_time: u64,
+    session_timeout: Duration,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct ClientSession {
+    last_sequence: u64,
+    last_response: Vec<u8>,
+    last_active_time: u64,
+}
+
+/// A command of a client session that can not be answered.
+#[derive(Debug, Eq, PartialEq)]
+pub(crate) enum ClientSessionError {
+    /// The sequence is older than the last sequence of the session (client id, sequence, last sequence), its response is no longer stored.
+    StaleSequence(u64, u64, u64),
+    /// The session of the client has expired (client id, sequence), whether the sequence was already applied is no longer known.
+    SessionExpired(u64, u64),
+}
+
+impl Display for ClientSessionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClientSessionError::StaleSequence(client_id, sequence, last_sequence) =>
+                write!(f, "sequence {} of client {} is older than the last sequence {} of its session", sequence, client_id, last_sequence),
+            ClientSessionError::SessionExpired(client_id, sequence) =>
+                write!(f, "session of client {} has expired before sequence {}", client_id, sequence),
+        }
+    }
+}
+
+impl Error for ClientSessionError {}
+
+impl ClientSessions {
+    pub(crate) fn new(session_timeout: Duration) -> Self {
+        return ClientSessions { sessions: HashMap::new(), log_time: 0, session_timeout };
+    }
+
+    /// Applies the command of the client (using `apply`) unless its sequence was already applied, returns the response of the command.
+    /// A command without a client is always applied.
+    /// A command older than the last sequence of its session is not applied, and fails with `ClientSessionError::StaleSequence`.
+    /// A command with a sequence above 0 and no session is not applied, and fails with `ClientSessionError::SessionExpired`.
+    pub(crate) fn apply<F>(&mut self, client_id: Option<u64>, sequence: u64, time: u64, apply: F) -> Result<Vec<u8>, ClientSessionError>
+        where F: FnOnce() -> Vec<u8> {
+        self.advance_log_time(time);
+        let client_id = match client_id {
+            None => return Ok(apply()),
+            Some(client_id) => client_id
+        };
+        match self.sessions.get_mut(&client_id) {
+            //the command may have been applied before the session expired, applying it again could apply it twice
+            None if sequence > 0 => return Err(ClientSessionError::SessionExpired(client_id, sequence)),
+            None => {}
+            Some(session) => {
+                session.last_active_time = self.log_time;
+                if sequence == session.last_sequence {
+                    return Ok(session.last_response.clone());
+                }
+                //the response of an older sequence is no longer stored, the client has already moved past it
+                if sequence < session.last_sequence {
+                    return Err(ClientSessionError::StaleSequence(client_id, sequence, session.last_sequence));
+                }
+            }
+        }
+        let response = apply();
+        self.sessions.insert(client_id, ClientSession {
+            last_sequence: sequence,
+            last_response: response.clone(),
+            last_active_time: self.log_time,
+        });
+        return Ok(response);
+    }
+
+    pub(crate) fn encode(&self) -> Vec<u8> {
+        let mut client_ids: Vec<&u64> = self.sessions.keys().collect();
+        client_ids.sort();
+
+        return grpc::ClientSessions {
+            log_time: self.log_time,
+            sessions: client_ids.into_iter().map(|client_id| {
+                let session = &self.sessions[client_id];
+                grpc::ClientSession {
+                    client_id: *client_id,
+                    last_sequence: session.last_sequence,
+                    last_response: session.last_response.clone(),
+                    last_active_time: session.last_active_time,
+                }
+            }).collect(),
+        }.encode_to_vec();
+    }
+
+    /// Replaces the sessions with the encoded ones, no sessions if the bytes are empty or can not be decoded.
+    pub(crate) fn restore(&mut self, bytes: &[u8]) {
+        let client_sessions = grpc::ClientSessions::decode(bytes).unwrap_or_default();
+        self.log_time = client_sessions.log_time;
+        self.sessions = client_sessions.sessions.into_iter().map(|session| {
+            (session.client_id, ClientSession {
+                last_sequence: session.last_sequence,
+                last_response: session.last_response,
+                last_active_time: session.last_active_time,
+            })
+        }).collect();
+    }
+
+    /// Log time never moves back, even if the clock of a new leader is behind the clock of the previous one.
+    fn advance_log_time(&mut self, time: u64) {
+        if time <= self.log_time {
+            return;
+        }
+        self.log_time = time;
+        let log_time = self.log_time;
+        let session_timeout = self.session_timeout.as_millis() as u64;
+        self.sessions.retain(|_, session| log_time - session.last_active_time < session_timeout);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::client_sessions::{ClientSessionError, ClientSessions};
+
+    #[test]
+    fn apply_a_command_without_a_client() {
+        let mut client_sessions = ClientSessions::new(Duration::from_millis(100));
+
+        let response = client_sessions.apply(None, 0, 10, || "applied".as_bytes().to_vec());
+        assert_eq!(Ok("applied".as_bytes().to_vec()), response);
+
+        let response = client_sessions.apply(None, 0, 10, || "applied again".as_bytes().to_vec());
+        assert_eq!(Ok("applied again".as_bytes().to_vec()), response);
+    }
+
+    #[test]
+    fn do_not_apply_a_duplicate_command() {
+        let mut client_sessions = ClientSessions::new(Duration::from_millis(100));
+
+        let response = client_sessions.apply(Some(1), 0, 10, || "applied".as_bytes().to_vec());
+        assert_eq!(Ok("applied".as_bytes().to_vec()), response);
+
+        let response = client_sessions.apply(Some(1), 0, 20, || panic!("duplicate command must not be applied"));
+        assert_eq!(Ok("applied".as_bytes().to_vec()), response);
+    }
+
+    #[test]
+    fn apply_the_next_sequence() {
+        let mut client_sessions = ClientSessions::new(Duration::from_millis(100));
+        let _ = client_sessions.apply(Some(1), 0, 10, || "zero".as_bytes().to_vec());
+
+        let response = client_sessions.apply(Some(1), 1, 20, || "one".as_bytes().to_vec());
+        assert_eq!(Ok("one".as_bytes().to_vec()), response);
+
+        let response = client_sessions.apply(Some(1), 0, 30, || panic!("older command must not be applied"));
+        assert_eq!(Err(ClientSessionError::StaleSequence(1, 0, 1)), response);
+    }
+
+    #[test]
+    fn do_not_apply_a_command_of_a_client_without_a_session() {
+        let mut client_sessions = ClientSessions::new(Duration::from_millis(100));
+
+        let response = client_sessions.apply(Some(1), 1, 10, || panic!("command without a session must not be applied"));
+        assert_eq!(Err(ClientSessionError::SessionExpired(1, 1)), response);
+    }
+
+    #[test]
+    fn expire_an_inactive_session_by_log_time() {
+        let mut client_sessions = ClientSessions::new(Duration::from_millis(100));
+        let _ = client_sessions.apply(Some(1), 0, 10, || "zero".as_bytes().to_vec());
+        let _ = client_sessions.apply(Some(2), 0, 50, || "zero".as_bytes().to_vec());
+
+        let _ = client_sessions.apply(None, 0, 120, Vec::new);
+
+        let response = client_sessions.apply(Some(1), 1, 120, || panic!("command of an expired session must not be applied"));
+        assert_eq!(Err(ClientSessionError::SessionExpired(1, 1)), response);
+
+        let response = client_sessions.apply(Some(2), 0, 120, || panic!("duplicate command must not be applied"));
+        assert_eq!(Ok("zero".as_bytes().to_vec()), response);
+    }
+
+    #[test]
+    fn open_a_new_session_after_the_session_expires() {
+        let mut client_sessions = ClientSessions::new(Duration::from_millis(100));
+        let _ = client_sessions.apply(Some(1), 0, 10, || "zero".as_bytes().to_vec());
+
+        let response = client_sessions.apply(Some(1), 0, 120, || "zero of the new session".as_bytes().to_vec());
+        assert_eq!(Ok("zero of the new session".as_bytes().to_vec()), response);
+
+        let response = client_sessions.apply(Some(1), 1, 130, || "one".as_bytes().to_vec());
+        assert_eq!(Ok("one".as_bytes().to_vec()), response);
+    }
+
+    #[test]
+    fn log_time_does_not_move_back() {
+        let mut client_sessions = ClientSessions::new(Duration::from_millis(100));
+        let _ = client_sessions.apply(Some(1), 0, 200, || "zero".as_bytes().to_vec());
+
+        let _ = client_sessions.apply(None, 0, 10, Vec::new);
+        let _ = client_sessions.apply(None, 0, 250, Vec::new);
+
+        let response = client_sessions.apply(Some(1), 0, 250, || panic!("duplicate command must not be applied"));
+        assert_eq!(Ok("zero".as_bytes().to_vec()), response);
+    }
+
+    #[test]
+    fn encode_and_restore() {
+        let mut client_sessions = ClientSessions::new(Duration::from_millis(100));
+        let _ = client_sessions.apply(Some(1), 0, 10, || "zero".as_bytes().to_vec());
+        let _ = client_sessions.apply(Some(1), 3, 10, || "three".as_bytes().to_vec());
+
+        let mut restored = ClientSessions::new(Duration::from_millis(100));
+        restored.restore(&client_sessions.encode());
+
+        let response = restored.apply(Some(1), 3, 20, || panic!("duplicate command must not be applied"));
+        assert_eq!(Ok("three".as_bytes().to_vec()), response);
+    }
+}
@@ -1,11 +1,12 @@
 use std::sync::Arc;
 
-use replicate::callback::async_quorum_callback::AsyncQuorumCallback;
+use replicate::callback::quorum_completion_response::QuorumCompletionResponse;
 use replicate::net::connect::correlation_id::RESERVED_CORRELATION_ID;
+use replicate::net::replica::Replica;
 use replicate::net::request_waiting_list::response_callback::ResponseCallback;
 
 use crate::net::factory::service_request::{BuiltInServiceRequestFactory, ServiceRequestFactory};
-use crate::net::rpc::grpc::RequestVoteResponse;
+use crate::net::rpc::grpc::{PreVoteResponse, RequestVoteResponse};
 use crate::state::State;
 
 pub struct Election {
@@ -23,12 +24,31 @@ impl Election {
     }
 
     pub fn start(&self) {
+        self.start_with(self.state.is_pre_vote_enabled(), false);
+    }
+
+    /// Starts an election right away, without a pre-vote round. Used by the target of a leadership transfer,
+    /// the current leader has asked for the election, so the other replicas need not confirm that the leader is gone.
+    /// The vote requests are marked as a leadership transfer, so the voters grant them within the leader lease (thesis §4.2.3).
+    pub(crate) fn start_for_leadership_transfer(&self) {
+        self.start_with(false, true);
+    }
+
+    fn start_with(&self, pre_vote: bool, leadership_transfer: bool) {
         let replica = self.state.get_replica();
         let inner_replica = replica.clone();
         let state = self.state.clone();
         let service_request_factory = self.service_request_factory.clone();
 
         replica.add_spawn_to_queue(async move {
+            if !state.is_voter() {
+                println!("replica is not a voter, not starting an election");
+                return;
+            }
+            if pre_vote && !Self::pre_vote(state.clone(), inner_replica.clone(), service_request_factory.clone()).await {
+                println!("pre-vote did not receive the majority, not starting an election");
+                return;
+            }
             let term = state.change_to_candidate();
             println!("starting election with term {}", term);
 
@@ -36,15 +56,14 @@ impl Election {
                 service_request_factory.request_vote(
                     inner_replica.get_id(),
                     term,
+                    state.get_replicated_log(),
+                    leadership_transfer,
                 )
             };
             let success_condition = Box::new(|response: &RequestVoteResponse| response.voted);
-            let expected_responses = inner_replica.cluster_size();
-            let async_quorum_callback = AsyncQuorumCallback::<RequestVoteResponse>::new_with_success_condition(
-                inner_replica.cluster_size(),
-                expected_responses,
-                success_condition,
-            );
+            //a replica with a higher term has moved past this election, no need to wait for the other responses
+            let stop_condition = Box::new(move |response: &RequestVoteResponse| response.term > term);
+            let async_quorum_callback = state.quorum_callback_with_stop_condition::<RequestVoteResponse>(success_condition, Some(stop_condition));
             let _ = inner_replica.send_to_replicas(
                 service_request_constructor,
                 async_quorum_callback.clone(),
@@ -58,16 +77,60 @@ impl Election {
 
             let quorum_completion_response = async_quorum_callback.handle().await;
             if quorum_completion_response.is_success() {
-                state.change_to_leader();
+                if !state.change_to_leader_if_candidate_in(term) {
+                    println!("won the election with term {} but the replica has moved past it", term);
+                }
             } else {
-                state.change_to_follower(term); //TODO: Change the term to the highest term received
+                let highest_term = Self::highest_term(term, &quorum_completion_response);
+                println!("lost the election with term {}, highest term received {}", term, highest_term);
+                state.change_to_follower_after_lost_election(term, highest_term);
             }
         });
     }
+
+    /// The highest term among the election's own term and the terms of the vote responses.
+    /// Error responses carry no term, so an election that fails with errors falls back to its own term.
+    fn highest_term(term: u64, quorum_completion_response: &QuorumCompletionResponse<RequestVoteResponse>) -> u64 {
+        let responses = match quorum_completion_response {
+            QuorumCompletionResponse::Success(responses) => responses,
+            QuorumCompletionResponse::SuccessConditionNotMet(responses) => responses,
+            QuorumCompletionResponse::Error(_) => return term,
+        };
+        return responses.values().map(|response| response.term).fold(term, u64::max);
+    }
+
+    async fn pre_vote(state: Arc<State>, replica: Arc<Replica>, service_request_factory: Arc<dyn ServiceRequestFactory>) -> bool {
+        let term = state.get_term() + 1;
+        println!("starting pre-vote with term {}", term);
+
+        let service_request_constructor = || {
+            service_request_factory.pre_vote(
+                replica.get_id(),
+                term,
+                state.get_replicated_log(),
+            )
+        };
+        let success_condition = Box::new(|response: &PreVoteResponse| response.voted);
+        let async_quorum_callback = state.quorum_callback::<PreVoteResponse>(success_condition);
+        let _ = replica.send_to_replicas(
+            service_request_constructor,
+            async_quorum_callback.clone(),
+        ).await;
+
+        async_quorum_callback.on_response(replica.get_self_address(), Ok(Box::new(PreVoteResponse {
+            term,
+            voted: true,
+            correlation_id: RESERVED_CORRELATION_ID,
+        })));
+
+        return async_quorum_callback.handle().await.is_success();
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashMap;
+    use std::error::Error;
     use std::net::{IpAddr, Ipv4Addr};
     use std::sync::{Arc, RwLock};
     use std::sync::atomic::AtomicU64;
@@ -76,6 +139,7 @@ mod tests {
 
     use tokio::runtime::Builder;
 
+    use replicate::callback::quorum_completion_response::QuorumCompletionResponse;
     use replicate::clock::clock::SystemClock;
     use replicate::net::connect::host_and_port::HostAndPort;
     use replicate::net::replica::Replica;
@@ -83,8 +147,8 @@ mod tests {
 
     use crate::election::election::Election;
     use crate::election::election::tests::setup::IncrementingCorrelationIdServiceRequestFactory;
-    use crate::heartbeat_config::HeartbeatConfig;
     use crate::net::rpc::grpc::RequestVoteResponse;
+    use crate::raft_config::RaftConfig;
     use crate::state::{ReplicaRole, State};
 
     mod setup {
@@ -102,13 +166,14 @@ mod tests {
 
         use crate::net::factory::service_request::ServiceRequestFactory;
         use crate::net::rpc::grpc::RequestVote;
+        use crate::replicated_log::ReplicatedLog;
 
         pub(crate) struct IncrementingCorrelationIdServiceRequestFactory {
             pub(crate) base_correlation_id: RwLock<AtomicU64>,
         }
 
         impl ServiceRequestFactory for IncrementingCorrelationIdServiceRequestFactory {
-            fn request_vote(&self, replica_id: ReplicaId, term: u64) -> ServiceRequest<RequestVote, ()> {
+            fn request_vote(&self, replica_id: ReplicaId, term: u64, _: &ReplicatedLog, leadership_transfer: bool) -> ServiceRequest<RequestVote, ()> {
                 {
                     let write_guard = self.base_correlation_id.write().unwrap();
                     write_guard.fetch_add(1, Ordering::SeqCst);
@@ -122,6 +187,9 @@ mod tests {
                         replica_id,
                         term,
                         correlation_id,
+                        last_log_index: None,
+                        last_log_term: None,
+                        leadership_transfer,
                     },
                     Box::new(TestRequestVoteClient {}),
                     correlation_id,
@@ -159,7 +227,7 @@ mod tests {
 
         let inner_replica = some_replica.clone();
         let state = blocking_runtime.block_on(async move {
-            return State::new(inner_replica, HeartbeatConfig::default());
+            return State::new(inner_replica, RaftConfig::default());
         });
 
         let election = Election::new_with(
@@ -206,7 +274,7 @@ mod tests {
 
         let inner_replica = some_replica.clone();
         let state = blocking_runtime.block_on(async move {
-            return State::new(inner_replica, HeartbeatConfig::default());
+            return State::new(inner_replica, RaftConfig::default());
         });
 
         let election = Election::new_with(
@@ -237,9 +305,51 @@ mod tests {
             thread::sleep(Duration::from_millis(100));
 
             assert_eq!(ReplicaRole::Follower, state.get_role());
+            assert_eq!(2, state.get_term());
         });
     }
 
+    #[test]
+    fn stop_the_election_on_a_response_with_a_higher_term() {
+        let self_host = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1971);
+        let peer_host = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1297);
+        let peer_other_host = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1298);
+
+        let some_replica = Arc::new(Replica::new(
+            10,
+            self_host,
+            vec![peer_host, peer_other_host],
+            Arc::new(SystemClock::new()),
+        ));
+        let blocking_runtime = Builder::new_current_thread().enable_all().build().unwrap();
+
+        let inner_replica = some_replica.clone();
+        let state = blocking_runtime.block_on(async move {
+            return State::new(inner_replica, RaftConfig::default());
+        });
+
+        let election = Election::new_with(
+            state.clone(),
+            Arc::new(IncrementingCorrelationIdServiceRequestFactory {
+                base_correlation_id: RwLock::new(AtomicU64::new(0)),
+            }),
+        );
+        election.start();
+
+        let response_with_higher_term = RequestVoteResponse {
+            term: 5,
+            voted: false,
+            correlation_id: 1,
+        };
+
+        thread::sleep(Duration::from_millis(20));
+        some_replica.register_response(1, peer_host, Ok(Box::new(response_with_higher_term)));
+
+        thread::sleep(Duration::from_millis(20));
+        assert_eq!(ReplicaRole::Follower, state.get_role());
+        assert_eq!(5, state.get_term());
+    }
+
     #[test]
     fn lose_the_election_with_request_timeout() {
         let self_host = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1971);
@@ -262,7 +372,7 @@ mod tests {
 
         let inner_replica = some_replica.clone();
         let state = blocking_runtime.block_on(async move {
-            return State::new(inner_replica, HeartbeatConfig::default());
+            return State::new(inner_replica, RaftConfig::default());
         });
 
         let election = Election::new_with(
@@ -280,6 +390,58 @@ mod tests {
             thread::sleep(Duration::from_millis(100));
 
             assert_eq!(ReplicaRole::Follower, state.get_role());
+            assert_eq!(1, state.get_term());
         });
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn do_not_start_an_election_given_replica_is_not_a_voter() {
+        let self_host = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1971);
+        let peer_host = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1297);
+        let peer_other_host = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1298);
+
+        let some_replica = Arc::new(Replica::new(
+            10,
+            self_host,
+            vec![peer_host, peer_other_host],
+            Arc::new(SystemClock::new()),
+        ));
+        let blocking_runtime = Builder::new_current_thread().enable_all().build().unwrap();
+
+        let state = blocking_runtime.block_on(async move {
+            let state = State::new(some_replica, RaftConfig::default());
+            let membership = state.get_membership().without_voter(&self_host).complete();
+            state.get_replicated_log().append_membership(&membership, 0);
+            return state;
+        });
+
+        Election::new(state.clone()).start();
+
+        thread::sleep(Duration::from_millis(20));
+        assert_eq!(0, state.get_term());
+        assert_eq!(ReplicaRole::Follower, state.get_role());
+    }
+
+    #[test]
+    fn highest_term_among_the_vote_responses() {
+        let peer_host = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1297);
+        let peer_other_host = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1298);
+
+        let quorum_completion_response = QuorumCompletionResponse::SuccessConditionNotMet(HashMap::from([
+            (peer_host, RequestVoteResponse { term: 5, voted: false, correlation_id: 1 }),
+            (peer_other_host, RequestVoteResponse { term: 1, voted: false, correlation_id: 2 }),
+        ]));
+
+        assert_eq!(5, Election::highest_term(2, &quorum_completion_response));
+    }
+
+    #[test]
+    fn highest_term_of_an_election_failing_with_errors_is_its_own_term() {
+        let peer_host = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1297);
+        let error: Box<dyn Error + Send + Sync> = Box::new(tonic::Status::deadline_exceeded("request timed out"));
+
+        let quorum_completion_response = QuorumCompletionResponse::<RequestVoteResponse>::Error(HashMap::from([(peer_host, error)]));
+
+        assert_eq!(2, Election::highest_term(2, &quorum_completion_response));
+    }
+}
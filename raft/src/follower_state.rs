@@ -1,30 +1,89 @@
-use std::sync::Arc;
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
 
 use dashmap::DashMap;
-use tokio::task::JoinHandle;
+use tokio::sync::mpsc;
+use tokio::sync::mpsc::UnboundedSender;
 
 use replicate::net::connect::async_network::AsyncNetwork;
+use replicate::net::connect::correlation_id::CorrelationId;
 use replicate::net::connect::error::ServiceResponseError;
 use replicate::net::connect::host_and_port::HostAndPort;
 use replicate::net::connect::service_client::ServiceRequest;
 
 use crate::net::factory::service_request::ServiceRequestFactory;
-use crate::net::rpc::grpc::{AppendEntries, AppendEntriesResponse, Command, Entry};
-use crate::state::State;
+use crate::net::rpc::grpc::{AppendEntries, AppendEntriesResponse, Entry, InstallSnapshotResponse};
+use crate::replication_config::ReplicationConfig;
+use crate::state::{ReplicaRole, State};
+use crate::storage::snapshot_storage::Snapshot;
 
 type NextLogIndex = u64;
+type MatchLogIndex = u64;
 
+/// Replication to a peer runs in a task of its own, which sends the requests to the peer one after the other.
 pub(crate) struct FollowerState {
-    peers: Vec<HostAndPort>,
     state: Arc<State>,
     next_log_index_by_peer: DashMap<HostAndPort, NextLogIndex>,
+    match_log_index_by_peer: DashMap<HostAndPort, MatchLogIndex>,
+    replication_window_by_peer: DashMap<HostAndPort, ReplicationWindow>,
+    replication_task_by_peer: DashMap<HostAndPort, UnboundedSender<ReplicationTrigger>>,
     service_request_factory: Arc<dyn ServiceRequestFactory>,
+    replication_config: ReplicationConfig,
+}
+
+/// The requests sent to a peer that are yet to be answered. While pipelining, the leader sends up to the maximum in-flight requests
+/// and moves the peer's next log index past the entries in flight, assuming that they will match.
+/// After a rejection, the leader probes the peer with one request at a time until a request succeeds.
+struct ReplicationWindow {
+    in_flight: Vec<InFlightRequest>,
+    probing: bool,
+}
+
+struct InFlightRequest {
+    correlation_id: CorrelationId,
+    next_log_index: NextLogIndex,
+    sent_at: Instant,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq)]
+enum ReplicationTrigger {
+    //replication is requested, a request is sent even without new entries (to probe the peer or to carry the commit index)
+    Requested,
+    //a response has arrived, a request is sent only for the entries not yet sent
+    Answered,
+}
+
+enum NextRequest {
+    AppendEntries(ServiceRequest<AppendEntries, ()>, NextLogIndex),
+    InstallSnapshot(Snapshot),
+}
+
+impl ReplicationWindow {
+    fn new() -> Self {
+        return ReplicationWindow { in_flight: Vec::new(), probing: false };
+    }
+
+    fn complete(&mut self, correlation_id: CorrelationId) -> Option<InFlightRequest> {
+        let position = self.in_flight.iter().position(|request| request.correlation_id == correlation_id)?;
+        return Some(self.in_flight.remove(position));
+    }
+
+    fn start_probing(&mut self) {
+        self.probing = true;
+        self.in_flight.clear();
+    }
+
+    /// A request whose response is lost would otherwise hold its place in the window forever.
+    fn expire(&mut self, in_flight_timeout: Duration) {
+        self.in_flight.retain(|request| request.sent_at.elapsed() < in_flight_timeout);
+    }
 }
 
 impl FollowerState {
     pub(crate) fn new(
         state: Arc<State>,
         service_request_factory: Arc<dyn ServiceRequestFactory>,
+        replication_config: ReplicationConfig,
     ) -> Self {
         let replica = state.get_replica_reference();
         let peers = replica.get_peers();
@@ -35,76 +94,290 @@ impl FollowerState {
         });
 
         let follower_state = FollowerState {
-            peers,
             state,
             next_log_index_by_peer,
+            match_log_index_by_peer: DashMap::new(),
+            replication_window_by_peer: DashMap::new(),
+            replication_task_by_peer: DashMap::new(),
             service_request_factory,
+            replication_config,
         };
         return follower_state;
     }
 
-    pub(crate) fn replicate_log(&self) -> Vec<JoinHandle<Result<(), ServiceResponseError>>> {
-        let term = self.state.get_term();
-        let source_address = self.state.get_replica_reference().get_self_address();
-        let mut task_handles = Vec::new();
+    #[cfg(test)]
+    pub(crate) fn get_replication_config(&self) -> &ReplicationConfig {
+        return &self.replication_config;
+    }
 
-        for peer in &self.peers {
-            let next_log_index_by_peer = self.next_log_index_by_peer_for(peer);
-            println!("replicating log at log index {} for the peer {:?}", next_log_index_by_peer.1, peer);
+    /// Forgets the replication progress of all the peers, a new leader learns the progress afresh by probing.
+    /// The progress recorded during an earlier leadership may no longer hold, the peers might have accepted another leader's log since.
+    /// Every peer starts right after the entries the leader had before its election, that is at its no-op entry (the last entry),
+    /// a peer whose log differs moves the next log index back with the conflict hint of its rejection.
+    pub(crate) fn reset_progress(&self) {
+        let next_log_index = self.next_log_index_after_the_last_entry().saturating_sub(1);
+        self.next_log_index_by_peer.clear();
+        self.match_log_index_by_peer.clear();
+        self.replication_window_by_peer.clear();
+        for peer in self.state.get_replica_reference().get_peers() {
+            self.next_log_index_by_peer.insert(peer, next_log_index);
+        }
+    }
 
-            let service_request = self.service_request(next_log_index_by_peer, term);
-            let target_address = peer.clone();
+    pub(crate) fn replicate_log(self: &Arc<FollowerState>) {
+        for peer in self.state.get_replica_reference().get_peers() {
+            println!("replicating log at log index {} for the peer {:?}", self.next_log_index_by_peer_for(&peer).1, peer);
+            self.trigger_replication(&peer, ReplicationTrigger::Requested);
+        }
+    }
 
-            task_handles.push(
-                tokio::spawn(async move {
-                    AsyncNetwork::send_with_source_footprint(
-                        service_request,
-                        source_address,
-                        target_address,
-                    ).await
-                })
-            );
+    pub(crate) fn replicate_log_to_peer(self: &Arc<FollowerState>, peer: &HostAndPort) {
+        self.trigger_replication(peer, ReplicationTrigger::Requested);
+    }
+
+    /// A peer is caught up when it has acknowledged the last entry of the leader's log.
+    pub(crate) fn is_caught_up(&self, peer: &HostAndPort) -> bool {
+        let total_log_entries = self.state.get_replicated_log().total_log_entries() as u64;
+        if total_log_entries == 0 {
+            return true;
         }
-        return task_handles;
+        return match self.match_log_index_by_peer.get(peer) {
+            None => false,
+            Some(match_log_index) => *match_log_index.value() + 1 >= total_log_entries
+        };
+    }
+
+    /// The highest log index replicated on a majority of the voters (of both the memberships while joint):
+    /// the leader matches its entire log, a peer matches the highest index it has acknowledged.
+    pub(crate) fn majority_match_index(&self) -> Option<u64> {
+        let self_address = self.state.get_replica_reference().get_self_address();
+        let (last_log_index, _) = self.state.get_replicated_log().get_last_log_index_term();
+        return self.state.get_membership().majority_index(|voter| {
+            if *voter == self_address {
+                return last_log_index;
+            }
+            return self.match_log_index_by_peer.get(voter).map(|match_log_index| *match_log_index.value());
+        });
     }
 
-    pub(crate) fn register(&self, response: AppendEntriesResponse, from: HostAndPort) {
+    pub(crate) fn register(self: Arc<FollowerState>, response: AppendEntriesResponse, from: HostAndPort) {
+        let (in_flight_request, probing) = {
+            let mut replication_window = self.replication_window_by_peer.entry(from.clone()).or_insert_with(ReplicationWindow::new);
+            (replication_window.complete(response.correlation_id), replication_window.probing)
+        };
         if response.success {
-            self.acknowledge_log_index(response, from);
+            self.acknowledge_log_index(response, from.clone());
+            if self.has_entries_to_send(&from) {
+                self.trigger_replication(&from, ReplicationTrigger::Answered);
+            }
+            return;
+        }
+        //the requests pipelined behind a rejected request are abandoned, so are their rejections
+        if probing && in_flight_request.is_none() {
             return;
         }
-        self.retry_reducing_log_index(from);
+        let next_log_index = in_flight_request.map(|request| request.next_log_index);
+        self.retry_reducing_log_index(response, from, next_log_index);
     }
 
     fn acknowledge_log_index(&self, response: AppendEntriesResponse, peer: HostAndPort) {
-        let response_log_entry_index = response.log_entry_index.unwrap();
-        self.next_log_index_by_peer.entry(peer)
-            .and_modify(|next_log_index| *next_log_index = response_log_entry_index + 1);
+        if let Some(response_log_entry_index) = response.log_entry_index {
+            self.next_log_index_by_peer.entry(peer.clone())
+                .and_modify(|next_log_index| *next_log_index = (*next_log_index).max(response_log_entry_index + 1));
+            self.match_log_index_by_peer.entry(peer.clone())
+                .and_modify(|match_log_index| *match_log_index = (*match_log_index).max(response_log_entry_index))
+                .or_insert(response_log_entry_index);
+        }
+        self.replication_window_by_peer.entry(peer).or_insert_with(ReplicationWindow::new).probing = false;
     }
 
-    fn retry_reducing_log_index(&self, peer: HostAndPort) {
-        let next_log_index_by_peer = self.next_log_index_by_peer_for(&peer);
-        let (previous_log_index, _) = self.previous_log_index_term(&next_log_index_by_peer);
+    /// Reduces the peer's next log index using the conflict hint of the rejection: just after the leader's last entry of the conflicting term,
+    /// or to the first index of the conflicting term (skipping the whole term) if the leader has no entry of it,
+    /// or to the end of the peer's log if it is shorter. Without a hint, the next log index is reduced by one.
+    /// The reduction starts from the next log index of the rejected request, the peer is probed until a request succeeds.
+    fn retry_reducing_log_index(self: Arc<FollowerState>, response: AppendEntriesResponse, peer: HostAndPort, next_log_index: Option<NextLogIndex>) {
+        let next_log_index = match next_log_index {
+            None => self.next_log_index_by_peer_for(&peer).1,
+            Some(next_log_index) => next_log_index
+        };
+        let (previous_log_index, _) = self.previous_log_index_term(&(peer.clone(), next_log_index));
 
         if let Some(previous_log_index) = previous_log_index {
+            let reduced_log_index = match response.conflict_index {
+                None => previous_log_index,
+                Some(conflict_index) => {
+                    let last_index_of_conflict_term = response.conflict_term
+                        .and_then(|conflict_term| self.state.get_replicated_log().last_index_of_term(conflict_term));
+
+                    let hinted_log_index = match last_index_of_conflict_term {
+                        None => conflict_index,
+                        Some(last_index) => last_index + 1
+                    };
+                    hinted_log_index.min(previous_log_index)
+                }
+            };
             {
-                self.next_log_index_by_peer.entry(peer.clone())
-                    .and_modify(|next_log_index| *next_log_index = previous_log_index);
+                self.replication_window_by_peer.entry(peer.clone()).or_insert_with(ReplicationWindow::new).start_probing();
+                self.next_log_index_by_peer.insert(peer.clone(), reduced_log_index);
+            }
+
+            println!("retrying log replication at log index {} for the peer {:?}", reduced_log_index, peer);
+            self.trigger_replication(&peer, ReplicationTrigger::Requested);
+        }
+    }
+
+    fn has_entries_to_send(&self, peer: &HostAndPort) -> bool {
+        let next_log_index = self.next_log_index_by_peer_for(peer).1;
+        return next_log_index < self.state.get_replicated_log().total_log_entries() as u64;
+    }
+
+    /// Hands the trigger to the replication task of the peer, the task is started on the first trigger for the peer.
+    /// The task holds a weak reference, it ends once the follower state is dropped.
+    fn trigger_replication(self: &Arc<FollowerState>, peer: &HostAndPort, trigger: ReplicationTrigger) {
+        let sender = self.replication_task_by_peer
+            .entry(peer.clone())
+            .or_insert_with(|| Self::spawn_replication_task(Arc::downgrade(self), peer.clone()))
+            .clone();
+        let _ = sender.send(trigger);
+    }
+
+    fn spawn_replication_task(follower_state: Weak<FollowerState>, peer: HostAndPort) -> UnboundedSender<ReplicationTrigger> {
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            while let Some(trigger) = receiver.recv().await {
+                let follower_state = match follower_state.upgrade() {
+                    None => return,
+                    Some(follower_state) => follower_state
+                };
+                let _ = follower_state.replicate_to(&peer, trigger).await;
             }
+        });
+        return sender;
+    }
 
+    /// Sends requests to the peer while the replica is the leader, the window allows and there are entries to send.
+    async fn replicate_to(self: Arc<FollowerState>, peer: &HostAndPort, trigger: ReplicationTrigger) -> Result<(), ServiceResponseError> {
+        let source_address = self.state.get_replica_reference().get_self_address();
+        let mut send_without_entries = trigger == ReplicationTrigger::Requested;
+        loop {
+            //a replica that has stepped down (or is a candidate of the next term) must not send its log with the new term
+            if self.state.get_role() != ReplicaRole::Leader {
+                return Ok(());
+            }
             let term = self.state.get_term();
-            let source_address = self.state.get_replica_reference().get_self_address();
-            let next_log_index_by_peer = self.next_log_index_by_peer_for(&peer);
-            let service_request = self.service_request(next_log_index_by_peer, term);
-
-            println!("retrying log replication at log index {} for the peer {:?}", next_log_index_by_peer.1, peer);
-            tokio::spawn(async move {
-                AsyncNetwork::send_with_source_footprint(
-                    service_request,
-                    source_address,
-                    peer,
-                ).await
-            });
+            match self.next_request_to(peer, term, send_without_entries) {
+                None => return Ok(()),
+                Some(NextRequest::InstallSnapshot(snapshot)) => {
+                    return self.clone().install_snapshot_on(peer.clone(), snapshot, term).await;
+                }
+                Some(NextRequest::AppendEntries(service_request, next_log_index)) => {
+                    let correlation_id = service_request.get_payload().correlation_id;
+                    let send_result = AsyncNetwork::send_with_source_footprint(
+                        service_request,
+                        source_address,
+                        peer.clone(),
+                    ).await;
+                    if let Err(err) = send_result {
+                        self.abandon(peer, correlation_id, next_log_index);
+                        return Err(err);
+                    }
+                }
+            }
+            send_without_entries = false;
+        }
+    }
+
+    fn next_request_to(&self, peer: &HostAndPort, term: u64, send_without_entries: bool) -> Option<NextRequest> {
+        let mut replication_window = self.replication_window_by_peer.entry(peer.clone()).or_insert_with(ReplicationWindow::new);
+        replication_window.expire(self.state.get_heartbeat_config().get_heartbeat_timeout());
+
+        let max_in_flight_requests = if replication_window.probing {
+            1
+        } else {
+            self.replication_config.get_max_in_flight_requests()
+        };
+        if replication_window.in_flight.len() >= max_in_flight_requests {
+            return None;
+        }
+
+        let next_log_index_by_peer = self.next_log_index_by_peer_for(peer);
+        let next_log_index = next_log_index_by_peer.1;
+        if self.state.get_replicated_log().is_compacted(next_log_index) {
+            if let Some(snapshot) = self.state.get_replicated_log().get_snapshot() {
+                replication_window.start_probing();
+                return Some(NextRequest::InstallSnapshot(snapshot));
+            }
+        }
+        let service_request = self.service_request(next_log_index_by_peer, term);
+        let payload = service_request.get_payload();
+        if payload.entries.is_empty() && (!send_without_entries || !replication_window.in_flight.is_empty()) {
+            return None;
+        }
+        if !replication_window.probing {
+            if let Some(last_entry) = payload.entries.last() {
+                self.next_log_index_by_peer.insert(peer.clone(), last_entry.index + 1);
+            }
+        }
+        replication_window.in_flight.push(InFlightRequest {
+            correlation_id: payload.correlation_id,
+            next_log_index,
+            sent_at: Instant::now(),
+        });
+        return Some(NextRequest::AppendEntries(service_request, next_log_index));
+    }
+
+    /// A request that could not be sent is not in the peer's log, the peer is probed from the next log index of that request.
+    fn abandon(&self, peer: &HostAndPort, correlation_id: CorrelationId, next_log_index: NextLogIndex) {
+        let mut replication_window = self.replication_window_by_peer.entry(peer.clone()).or_insert_with(ReplicationWindow::new);
+        if replication_window.complete(correlation_id).is_some() {
+            replication_window.start_probing();
+            self.next_log_index_by_peer.entry(peer.clone())
+                .and_modify(|peer_next_log_index| *peer_next_log_index = (*peer_next_log_index).min(next_log_index));
+        }
+    }
+
+    async fn install_snapshot_on(self: Arc<FollowerState>, peer: HostAndPort, snapshot: Snapshot, term: u64) -> Result<(), ServiceResponseError> {
+        println!("installing snapshot till log index {} on the peer {:?}", snapshot.get_last_included_index(), peer);
+
+        let source_address = self.state.get_replica_reference().get_self_address();
+        let service_request = self.service_request_factory.install_snapshot(
+            term,
+            self.state.get_replica_reference().get_id(),
+            &snapshot,
+        );
+        let response = AsyncNetwork::send_with_source_footprint(
+            service_request,
+            source_address,
+            peer,
+        ).await?;
+        self.register_install_snapshot_response(response, peer, snapshot.get_last_included_index()).await;
+        return Ok(());
+    }
+
+    async fn register_install_snapshot_response(self: Arc<FollowerState>, response: InstallSnapshotResponse, peer: HostAndPort, last_included_index: u64) {
+        if response.term > self.state.get_term() {
+            let state = self.state.clone();
+            let _ = self.state.get_replica_reference().add_async_to_queue(async move {
+                if response.term > state.get_term() {
+                    state.change_to_follower(response.term);
+                }
+            }).await;
+            return;
+        }
+        //a response to a request of an earlier term says nothing about the peer's log under the current leadership
+        if !response.success || response.term != self.state.get_term() {
+            return;
+        }
+        let next_log_index = last_included_index + 1;
+        self.next_log_index_by_peer.entry(peer.clone())
+            .and_modify(|peer_next_log_index| *peer_next_log_index = next_log_index);
+        self.match_log_index_by_peer.entry(peer.clone())
+            .and_modify(|match_log_index| *match_log_index = (*match_log_index).max(last_included_index))
+            .or_insert(last_included_index);
+        self.replication_window_by_peer.entry(peer.clone()).or_insert_with(ReplicationWindow::new).probing = false;
+
+        if self.has_entries_to_send(&peer) {
+            self.trigger_replication(&peer, ReplicationTrigger::Answered);
         }
     }
 
@@ -112,26 +385,40 @@ impl FollowerState {
         let next_log_index = next_log_index_by_peer.1;
         let (previous_log_index, previous_log_term) = self.previous_log_index_term(&next_log_index_by_peer);
 
-        let entry = match self.state.get_replicated_log().get_log_entry_at(next_log_index as usize) {
-            None => None,
-            Some(entry) => {
-                Some(
-                    Entry {
-                        command: Some(Command { command: entry.get_bytes_as_vec() }),
-                        term: entry.get_term(),
-                        index: entry.get_index(),
-                    }
-                )
-            }
-        };
         return self.service_request_factory.replicate_log(
             term,
             self.state.get_replica_reference().get_id(),
             previous_log_index,
             previous_log_term,
             self.state.get_replicated_log().get_commit_index(),
-            entry,
+            self.entries_starting_at(next_log_index),
+        );
+    }
+
+    fn entries_starting_at(&self, next_log_index: NextLogIndex) -> Vec<Entry> {
+        let from_index = next_log_index as usize;
+        let log_entries = self.state.get_replicated_log().get_log_entries_between(
+            from_index,
+            from_index + self.replication_config.get_max_entries_per_request(),
         );
+
+        let mut entries = Vec::new();
+        let mut total_bytes = 0;
+        for log_entry in log_entries {
+            let command = log_entry.get_command();
+            total_bytes = total_bytes + command.command.len();
+            if !entries.is_empty() && total_bytes > self.replication_config.get_max_bytes_per_request() {
+                break;
+            }
+            entries.push(Entry {
+                command: Some(command),
+                term: log_entry.get_term(),
+                index: log_entry.get_index(),
+                entry_type: log_entry.get_entry_type().as_entry_type(),
+                time: log_entry.get_time(),
+            });
+        }
+        return entries;
     }
 
     fn previous_log_index_term(&self, next_log_index_by_peer: &(HostAndPort, NextLogIndex)) -> (Option<u64>, Option<u64>) {
@@ -149,9 +436,14 @@ impl FollowerState {
         return (previous_log_index, previous_log_term);
     }
 
+    fn next_log_index_after_the_last_entry(&self) -> NextLogIndex {
+        return self.state.get_replicated_log().total_log_entries() as NextLogIndex;
+    }
+
     fn next_log_index_by_peer_for(&self, peer: &HostAndPort) -> (HostAndPort, NextLogIndex) {
         return {
-            let next_log_index_by_peer = self.next_log_index_by_peer.get(peer).unwrap();
+            //a peer that joins later starts after the leader's last entry, like the others at the election
+            let next_log_index_by_peer = self.next_log_index_by_peer.entry(peer.clone()).or_insert_with(|| self.next_log_index_after_the_last_entry());
             (next_log_index_by_peer.key().clone(), *next_log_index_by_peer.value())
         };
     }
@@ -169,11 +461,12 @@ mod tests {
     use replicate::net::connect::service_client::ServiceRequest;
     use replicate::net::replica::Replica;
 
-    use crate::follower_state::FollowerState;
-    use crate::heartbeat_config::HeartbeatConfig;
+    use crate::follower_state::{FollowerState, NextRequest, ReplicationTrigger, ReplicationWindow};
     use crate::net::factory::service_request::BuiltInServiceRequestFactory;
-    use crate::net::rpc::grpc::{AppendEntries, AppendEntriesResponse, Command};
+    use crate::net::rpc::grpc::{AppendEntries, AppendEntriesResponse, Command, InstallSnapshotResponse};
+    use crate::raft_config::RaftConfig;
     use crate::state::State;
+    use crate::storage::snapshot_storage::Snapshot;
 
     #[test]
     fn service_request_with_term() {
@@ -189,7 +482,7 @@ mod tests {
         );
 
         let state = runtime.block_on(async move {
-            let state = State::new(Arc::new(replica), HeartbeatConfig::default());
+            let state = State::new(Arc::new(replica), RaftConfig::default());
             state.change_to_candidate();
             return state;
         });
@@ -197,6 +490,7 @@ mod tests {
         let follower_state = FollowerState::new(
             state,
             Arc::new(BuiltInServiceRequestFactory::new()),
+            RaftConfig::default().get_replication_config(),
         );
 
         let service_request: ServiceRequest<AppendEntries, ()> = follower_state.service_request(
@@ -222,12 +516,13 @@ mod tests {
         );
 
         let state = runtime.block_on(async move {
-            return State::new(Arc::new(replica), HeartbeatConfig::default());
+            return State::new(Arc::new(replica), RaftConfig::default());
         });
 
         let follower_state = FollowerState::new(
             state,
             Arc::new(BuiltInServiceRequestFactory::new()),
+            RaftConfig::default().get_replication_config(),
         );
 
         let service_request: ServiceRequest<AppendEntries, ()> = follower_state.service_request(
@@ -253,23 +548,22 @@ mod tests {
         );
 
         let state = runtime.block_on(async move {
-            let state = State::new(Arc::new(replica), HeartbeatConfig::default());
+            let state = State::new(Arc::new(replica), RaftConfig::default());
             let content = String::from("Content");
-            let command = Command { command: content.as_bytes().to_vec() };
+            let command = Command { command: content.as_bytes().to_vec(), client_id: None, sequence: 0 };
             state.get_replicated_log().append_command(
                 &command,
                 1,
             );
 
-            state.get_replicated_log().acknowledge_log_entry_at(0);
-            state.get_replicated_log().acknowledge_log_entry_at(0);
-            state.get_replicated_log().commit(|_| {});
+            state.get_replicated_log().commit(0, 1);
             return state;
         });
 
         let follower_state = FollowerState::new(
             state,
             Arc::new(BuiltInServiceRequestFactory::new()),
+            RaftConfig::default().get_replication_config(),
         );
 
         let service_request: ServiceRequest<AppendEntries, ()> = follower_state.service_request(
@@ -295,12 +589,13 @@ mod tests {
         );
 
         let state = runtime.block_on(async move {
-            return State::new(Arc::new(replica), HeartbeatConfig::default());
+            return State::new(Arc::new(replica), RaftConfig::default());
         });
 
         let follower_state = FollowerState::new(
             state,
             Arc::new(BuiltInServiceRequestFactory::new()),
+            RaftConfig::default().get_replication_config(),
         );
 
         let service_request: ServiceRequest<AppendEntries, ()> = follower_state.service_request(
@@ -327,9 +622,9 @@ mod tests {
         );
 
         let state = runtime.block_on(async move {
-            let state = State::new(Arc::new(replica), HeartbeatConfig::default());
+            let state = State::new(Arc::new(replica), RaftConfig::default());
             let content = String::from("Content");
-            let command = Command { command: content.as_bytes().to_vec() };
+            let command = Command { command: content.as_bytes().to_vec(), client_id: None, sequence: 0 };
             state.get_replicated_log().append_command(
                 &command,
                 1,
@@ -340,6 +635,7 @@ mod tests {
         let follower_state = FollowerState::new(
             state,
             Arc::new(BuiltInServiceRequestFactory::new()),
+            RaftConfig::default().get_replication_config(),
         );
 
         let service_request: ServiceRequest<AppendEntries, ()> = follower_state.service_request(
@@ -366,12 +662,13 @@ mod tests {
         );
 
         let state = runtime.block_on(async move {
-            return State::new(Arc::new(replica), HeartbeatConfig::default());
+            return State::new(Arc::new(replica), RaftConfig::default());
         });
 
         let follower_state = FollowerState::new(
             state,
             Arc::new(BuiltInServiceRequestFactory::new()),
+            RaftConfig::default().get_replication_config(),
         );
 
         let service_request: ServiceRequest<AppendEntries, ()> = follower_state.service_request(
@@ -380,7 +677,7 @@ mod tests {
         );
 
         let payload = service_request.get_payload();
-        assert_eq!(None, payload.entry);
+        assert!(payload.entries.is_empty());
     }
 
     #[test]
@@ -397,9 +694,9 @@ mod tests {
         );
 
         let state = runtime.block_on(async move {
-            let state = State::new(Arc::new(replica), HeartbeatConfig::default());
+            let state = State::new(Arc::new(replica), RaftConfig::default());
             let content = String::from("Content");
-            let command = Command { command: content.as_bytes().to_vec() };
+            let command = Command { command: content.as_bytes().to_vec(), client_id: None, sequence: 0 };
             state.get_replicated_log().append_command(
                 &command,
                 1,
@@ -414,6 +711,7 @@ mod tests {
         let follower_state = FollowerState::new(
             state,
             Arc::new(BuiltInServiceRequestFactory::new()),
+            RaftConfig::default().get_replication_config(),
         );
 
         let service_request: ServiceRequest<AppendEntries, ()> = follower_state.service_request(
@@ -422,13 +720,89 @@ mod tests {
         );
 
         let payload = service_request.get_payload();
-        let entry = payload.entry.as_ref().unwrap();
+        assert_eq!(1, payload.entries.len());
+        let entry = payload.entries.first().unwrap();
         assert_eq!(1, entry.term);
         assert_eq!(1, entry.index);
     }
 
     #[test]
-    fn register_success_response_from_peer() {
+    fn service_request_with_entries_limited_by_max_entries() {
+        let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2060);
+        let peer = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2061);
+
+        let runtime = Builder::new_multi_thread().worker_threads(4).enable_all().build().unwrap();
+        let replica = Replica::new(
+            30,
+            self_host_and_port.clone(),
+            vec![peer],
+            Arc::new(SystemClock::new()),
+        );
+
+        let state = runtime.block_on(async move {
+            let state = State::new(Arc::new(replica), RaftConfig::default());
+            let command = Command { command: "Content".as_bytes().to_vec(), client_id: None, sequence: 0 };
+            for _count in 1..=5 {
+                state.get_replicated_log().append_command(&command, 1);
+            }
+            return state;
+        });
+
+        let follower_state = FollowerState::new(
+            state,
+            Arc::new(BuiltInServiceRequestFactory::new()),
+            RaftConfig::builder().with_max_entries_per_append(3).with_max_bytes_per_append(1024).build().unwrap().get_replication_config(),
+        );
+
+        let service_request: ServiceRequest<AppendEntries, ()> = follower_state.service_request(
+            (peer, 1),
+            1,
+        );
+
+        let payload = service_request.get_payload();
+        assert_eq!(vec![1, 2, 3], payload.entries.iter().map(|entry| entry.index).collect::<Vec<u64>>());
+        assert_eq!(Some(0), payload.previous_log_index);
+    }
+
+    #[test]
+    fn service_request_with_entries_limited_by_max_bytes() {
+        let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2060);
+        let peer = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2061);
+
+        let runtime = Builder::new_multi_thread().worker_threads(4).enable_all().build().unwrap();
+        let replica = Replica::new(
+            30,
+            self_host_and_port.clone(),
+            vec![peer],
+            Arc::new(SystemClock::new()),
+        );
+
+        let state = runtime.block_on(async move {
+            let state = State::new(Arc::new(replica), RaftConfig::default());
+            let command = Command { command: "Content".as_bytes().to_vec(), client_id: None, sequence: 0 };
+            for _count in 1..=5 {
+                state.get_replicated_log().append_command(&command, 1);
+            }
+            return state;
+        });
+
+        let follower_state = FollowerState::new(
+            state,
+            Arc::new(BuiltInServiceRequestFactory::new()),
+            RaftConfig::builder().with_max_entries_per_append(10).with_max_bytes_per_append(15).build().unwrap().get_replication_config(),
+        );
+
+        let service_request: ServiceRequest<AppendEntries, ()> = follower_state.service_request(
+            (peer, 0),
+            1,
+        );
+
+        let payload = service_request.get_payload();
+        assert_eq!(vec![0, 1], payload.entries.iter().map(|entry| entry.index).collect::<Vec<u64>>());
+    }
+
+    #[test]
+    fn service_request_with_an_entry_larger_than_max_bytes() {
         let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2060);
         let peer = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2061);
 
@@ -441,18 +815,103 @@ mod tests {
         );
 
         let state = runtime.block_on(async move {
-            return State::new(Arc::new(replica), HeartbeatConfig::default());
+            let state = State::new(Arc::new(replica), RaftConfig::default());
+            let command = Command { command: "Content".as_bytes().to_vec(), client_id: None, sequence: 0 };
+            state.get_replicated_log().append_command(&command, 1);
+            state.get_replicated_log().append_command(&command, 1);
+            return state;
+        });
+
+        let follower_state = FollowerState::new(
+            state,
+            Arc::new(BuiltInServiceRequestFactory::new()),
+            RaftConfig::builder().with_max_entries_per_append(10).with_max_bytes_per_append(2).build().unwrap().get_replication_config(),
+        );
+
+        let service_request: ServiceRequest<AppendEntries, ()> = follower_state.service_request(
+            (peer, 0),
+            1,
+        );
+
+        let payload = service_request.get_payload();
+        assert_eq!(vec![0], payload.entries.iter().map(|entry| entry.index).collect::<Vec<u64>>());
+    }
+
+    #[test]
+    fn majority_match_index_counts_the_log_of_the_leader() {
+        let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2060);
+        let peer = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2061);
+        let peer_other = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2062);
+
+        let runtime = Builder::new_multi_thread().worker_threads(4).enable_all().build().unwrap();
+        let replica = Replica::new(
+            30,
+            self_host_and_port.clone(),
+            vec![peer, peer_other],
+            Arc::new(SystemClock::new()),
+        );
+
+        let state = runtime.block_on(async move {
+            let state = State::new(Arc::new(replica), RaftConfig::default());
+            for _ in 0..5 {
+                let command = Command { command: "Content".as_bytes().to_vec(), client_id: None, sequence: 0 };
+                state.get_replicated_log().append_command(&command, 1);
+            }
+            return state;
         });
 
         let follower_state = FollowerState::new(
             state,
             Arc::new(BuiltInServiceRequestFactory::new()),
+            RaftConfig::default().get_replication_config(),
         );
+        assert_eq!(None, follower_state.majority_match_index());
 
-        follower_state.register(AppendEntriesResponse {
+        let response = AppendEntriesResponse {
+            term: 1,
+            success: true,
+            log_entry_index: Some(2),
+            conflict_term: None,
+            conflict_index: None,
+            correlation_id: 10,
+        };
+        follower_state.acknowledge_log_index(response.clone(), peer.clone());
+        assert_eq!(Some(2), follower_state.majority_match_index());
+
+        //a duplicate acknowledgement does not move the match index of the peer
+        follower_state.acknowledge_log_index(response, peer.clone());
+        assert_eq!(Some(2), follower_state.majority_match_index());
+    }
+
+    #[test]
+    fn register_success_response_from_peer() {
+        let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2060);
+        let peer = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2061);
+
+        let runtime = Builder::new_multi_thread().worker_threads(4).enable_all().build().unwrap();
+        let replica = Replica::new(
+            30,
+            self_host_and_port.clone(),
+            vec![peer],
+            Arc::new(SystemClock::new()),
+        );
+
+        let state = runtime.block_on(async move {
+            return State::new(Arc::new(replica), RaftConfig::default());
+        });
+
+        let follower_state = Arc::new(FollowerState::new(
+            state,
+            Arc::new(BuiltInServiceRequestFactory::new()),
+            RaftConfig::default().get_replication_config(),
+        ));
+
+        follower_state.clone().register(AppendEntriesResponse {
             term: 1,
             success: true,
             log_entry_index: Some(10),
+            conflict_term: None,
+            conflict_index: None,
             correlation_id: 10,
         }, peer.clone());
 
@@ -460,4 +919,544 @@ mod tests {
         let next_log_index_by_peer = follower_state.next_log_index_by_peer.get(&peer).unwrap();
         assert_eq!(11, *(next_log_index_by_peer.value()));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn register_rejection_skips_the_conflicting_term() {
+        let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2060);
+        let peer = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2061);
+        let other_peer = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2062);
+
+        let runtime = Builder::new_multi_thread().worker_threads(4).enable_all().build().unwrap();
+        let replica = Replica::new(
+            30,
+            self_host_and_port.clone(),
+            vec![peer, other_peer],
+            Arc::new(SystemClock::new()),
+        );
+
+        let state = runtime.block_on(async move {
+            let state = State::new(Arc::new(replica), RaftConfig::default());
+            for term in vec![1, 1, 3, 3, 3, 3] {
+                let command = Command { command: "Content".as_bytes().to_vec(), client_id: None, sequence: 0 };
+                state.get_replicated_log().append_command(&command, term);
+            }
+            return state;
+        });
+
+        let follower_state = Arc::new(FollowerState::new(
+            state,
+            Arc::new(BuiltInServiceRequestFactory::new()),
+            RaftConfig::default().get_replication_config(),
+        ));
+        let rejection = |conflict_term: Option<u64>, conflict_index: Option<u64>| AppendEntriesResponse {
+            term: 3,
+            success: false,
+            log_entry_index: None,
+            conflict_term,
+            conflict_index,
+            correlation_id: 10,
+        };
+
+        let inner_follower_state = follower_state.clone();
+        runtime.block_on(async move {
+            inner_follower_state.next_log_index_by_peer.insert(peer, 6);
+            inner_follower_state.clone().register(rejection(Some(2), Some(2)), peer.clone());
+
+            inner_follower_state.next_log_index_by_peer.insert(other_peer, 6);
+            inner_follower_state.clone().register(rejection(Some(1), Some(0)), other_peer.clone());
+        });
+        assert_eq!(2, *follower_state.next_log_index_by_peer.get(&peer).unwrap().value());
+        assert_eq!(2, *follower_state.next_log_index_by_peer.get(&other_peer).unwrap().value());
+    }
+
+    #[test]
+    fn register_rejection_from_a_peer_with_a_shorter_log() {
+        let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2060);
+        let peer = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2061);
+
+        let runtime = Builder::new_multi_thread().worker_threads(4).enable_all().build().unwrap();
+        let replica = Replica::new(
+            30,
+            self_host_and_port.clone(),
+            vec![peer],
+            Arc::new(SystemClock::new()),
+        );
+
+        let state = runtime.block_on(async move {
+            let state = State::new(Arc::new(replica), RaftConfig::default());
+            for _ in 0..6 {
+                let command = Command { command: "Content".as_bytes().to_vec(), client_id: None, sequence: 0 };
+                state.get_replicated_log().append_command(&command, 1);
+            }
+            return state;
+        });
+
+        let follower_state = Arc::new(FollowerState::new(
+            state,
+            Arc::new(BuiltInServiceRequestFactory::new()),
+            RaftConfig::default().get_replication_config(),
+        ));
+
+        let inner_follower_state = follower_state.clone();
+        runtime.block_on(async move {
+            inner_follower_state.next_log_index_by_peer.insert(peer, 6);
+            inner_follower_state.clone().register(AppendEntriesResponse {
+                term: 1,
+                success: false,
+                log_entry_index: None,
+                conflict_term: None,
+                conflict_index: Some(2),
+                correlation_id: 10,
+            }, peer.clone());
+        });
+        assert_eq!(2, *follower_state.next_log_index_by_peer.get(&peer).unwrap().value());
+    }
+
+    #[test]
+    fn register_rejection_without_a_hint() {
+        let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2060);
+        let peer = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2061);
+
+        let runtime = Builder::new_multi_thread().worker_threads(4).enable_all().build().unwrap();
+        let replica = Replica::new(
+            30,
+            self_host_and_port.clone(),
+            vec![peer],
+            Arc::new(SystemClock::new()),
+        );
+
+        let state = runtime.block_on(async move {
+            return State::new(Arc::new(replica), RaftConfig::default());
+        });
+
+        let follower_state = Arc::new(FollowerState::new(
+            state,
+            Arc::new(BuiltInServiceRequestFactory::new()),
+            RaftConfig::default().get_replication_config(),
+        ));
+
+        let inner_follower_state = follower_state.clone();
+        runtime.block_on(async move {
+            inner_follower_state.next_log_index_by_peer.insert(peer, 6);
+            inner_follower_state.clone().register(AppendEntriesResponse {
+                term: 1,
+                success: false,
+                log_entry_index: None,
+                conflict_term: None,
+                conflict_index: None,
+                correlation_id: 10,
+            }, peer.clone());
+        });
+        assert_eq!(5, *follower_state.next_log_index_by_peer.get(&peer).unwrap().value());
+    }
+
+    #[test]
+    fn pipeline_requests_up_to_the_max_in_flight_requests() {
+        let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2060);
+        let peer = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2061);
+
+        let runtime = Builder::new_multi_thread().worker_threads(4).enable_all().build().unwrap();
+        let replica = Replica::new(
+            30,
+            self_host_and_port.clone(),
+            vec![peer],
+            Arc::new(SystemClock::new()),
+        );
+
+        let state = runtime.block_on(async move {
+            let state = State::new(Arc::new(replica), RaftConfig::default());
+            for _ in 0..6 {
+                let command = Command { command: "Content".as_bytes().to_vec(), client_id: None, sequence: 0 };
+                state.get_replicated_log().append_command(&command, 1);
+            }
+            return state;
+        });
+
+        let follower_state = FollowerState::new(
+            state,
+            Arc::new(BuiltInServiceRequestFactory::new()),
+            RaftConfig::builder().with_max_entries_per_append(2).with_max_bytes_per_append(1024).with_max_in_flight_appends(2).build().unwrap().get_replication_config(),
+        );
+
+        let next_log_indices = (0..3)
+            .map(|_| match follower_state.next_request_to(&peer, 1, true) {
+                Some(NextRequest::AppendEntries(_, next_log_index)) => Some(next_log_index),
+                _ => None
+            })
+            .collect::<Vec<Option<u64>>>();
+
+        assert_eq!(vec![Some(1), Some(3), None], next_log_indices);
+        assert_eq!(5, *follower_state.next_log_index_by_peer.get(&peer).unwrap().value());
+    }
+
+    #[test]
+    fn probe_a_peer_with_one_request_at_a_time() {
+        let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2060);
+        let peer = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2061);
+
+        let runtime = Builder::new_multi_thread().worker_threads(4).enable_all().build().unwrap();
+        let replica = Replica::new(
+            30,
+            self_host_and_port.clone(),
+            vec![peer],
+            Arc::new(SystemClock::new()),
+        );
+
+        let state = runtime.block_on(async move {
+            let state = State::new(Arc::new(replica), RaftConfig::default());
+            for _ in 0..6 {
+                let command = Command { command: "Content".as_bytes().to_vec(), client_id: None, sequence: 0 };
+                state.get_replicated_log().append_command(&command, 1);
+            }
+            return state;
+        });
+
+        let follower_state = FollowerState::new(
+            state,
+            Arc::new(BuiltInServiceRequestFactory::new()),
+            RaftConfig::builder().with_max_entries_per_append(2).with_max_bytes_per_append(1024).with_max_in_flight_appends(2).build().unwrap().get_replication_config(),
+        );
+        follower_state.replication_window_by_peer.entry(peer).or_insert_with(ReplicationWindow::new).start_probing();
+
+        let correlation_id = match follower_state.next_request_to(&peer, 1, true) {
+            Some(NextRequest::AppendEntries(service_request, _)) => service_request.get_payload().correlation_id,
+            _ => panic!("expected a request to probe the peer")
+        };
+        assert!(follower_state.next_request_to(&peer, 1, true).is_none());
+        assert_eq!(1, *follower_state.next_log_index_by_peer.get(&peer).unwrap().value());
+
+        let response = AppendEntriesResponse {
+            term: 1,
+            success: true,
+            log_entry_index: Some(2),
+            conflict_term: None,
+            conflict_index: None,
+            correlation_id,
+        };
+
+        let follower_state = Arc::new(follower_state);
+        let inner_follower_state = follower_state.clone();
+        runtime.block_on(async move {
+            inner_follower_state.register(response, peer.clone());
+        });
+        assert_eq!(false, follower_state.replication_window_by_peer.get(&peer).unwrap().probing);
+        assert_eq!(2, *follower_state.match_log_index_by_peer.get(&peer).unwrap().value());
+    }
+
+    #[test]
+    fn peer_is_caught_up_given_an_empty_log() {
+        let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2060);
+        let peer = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2061);
+
+        let runtime = Builder::new_multi_thread().worker_threads(4).enable_all().build().unwrap();
+        let replica = Replica::new(
+            30,
+            self_host_and_port.clone(),
+            vec![peer],
+            Arc::new(SystemClock::new()),
+        );
+
+        let state = runtime.block_on(async move {
+            return State::new(Arc::new(replica), RaftConfig::default());
+        });
+
+        let follower_state = FollowerState::new(
+            state,
+            Arc::new(BuiltInServiceRequestFactory::new()),
+            RaftConfig::default().get_replication_config(),
+        );
+
+        assert!(follower_state.is_caught_up(&peer));
+    }
+
+    #[test]
+    fn peer_is_caught_up_after_acknowledging_the_last_log_entry() {
+        let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2060);
+        let peer = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2061);
+
+        let runtime = Builder::new_multi_thread().worker_threads(4).enable_all().build().unwrap();
+        let replica = Replica::new(
+            30,
+            self_host_and_port.clone(),
+            vec![peer],
+            Arc::new(SystemClock::new()),
+        );
+
+        let state = runtime.block_on(async move {
+            let state = State::new(Arc::new(replica), RaftConfig::default());
+            let content = String::from("Content");
+            let command = Command { command: content.as_bytes().to_vec(), client_id: None, sequence: 0 };
+            state.get_replicated_log().append_command(&command, 1);
+            state.get_replicated_log().append_command(&command, 1);
+            return state;
+        });
+
+        let follower_state = Arc::new(FollowerState::new(
+            state,
+            Arc::new(BuiltInServiceRequestFactory::new()),
+            RaftConfig::default().get_replication_config(),
+        ));
+        assert_eq!(false, follower_state.is_caught_up(&peer));
+
+        //the peer is not caught up after the first acknowledgement, the leader sends it the rest of the log
+        let _runtime_guard = runtime.enter();
+        follower_state.clone().register(AppendEntriesResponse {
+            term: 1,
+            success: true,
+            log_entry_index: Some(0),
+            conflict_term: None,
+            conflict_index: None,
+            correlation_id: 10,
+        }, peer.clone());
+        assert_eq!(false, follower_state.is_caught_up(&peer));
+
+        follower_state.clone().register(AppendEntriesResponse {
+            term: 1,
+            success: true,
+            log_entry_index: Some(1),
+            conflict_term: None,
+            conflict_index: None,
+            correlation_id: 20,
+        }, peer.clone());
+        assert!(follower_state.is_caught_up(&peer));
+    }
+
+    #[test]
+    fn entry_is_replicated_given_a_majority_of_both_the_joint_memberships_match() {
+        let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2060);
+        let peer = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2061);
+        let peer_other = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2062);
+        let new_voter = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2063);
+
+        let runtime = Builder::new_multi_thread().worker_threads(4).enable_all().build().unwrap();
+        let replica = Replica::new(
+            30,
+            self_host_and_port.clone(),
+            vec![peer, peer_other],
+            Arc::new(SystemClock::new()),
+        );
+
+        let state = runtime.block_on(async move {
+            let state = State::new(Arc::new(replica), RaftConfig::default());
+            let joint_membership = state.get_membership().with_voter(new_voter);
+            state.get_replicated_log().append_membership(&joint_membership, 1);
+            state.refresh_membership();
+            return state;
+        });
+
+        let follower_state = Arc::new(FollowerState::new(
+            state,
+            Arc::new(BuiltInServiceRequestFactory::new()),
+            RaftConfig::default().get_replication_config(),
+        ));
+        follower_state.clone().register(AppendEntriesResponse {
+            term: 1,
+            success: true,
+            log_entry_index: Some(0),
+            conflict_term: None,
+            conflict_index: None,
+            correlation_id: 10,
+        }, peer.clone());
+        assert_eq!(None, follower_state.majority_match_index());
+
+        follower_state.clone().register(AppendEntriesResponse {
+            term: 1,
+            success: true,
+            log_entry_index: Some(0),
+            conflict_term: None,
+            conflict_index: None,
+            correlation_id: 20,
+        }, new_voter.clone());
+        assert_eq!(Some(0), follower_state.majority_match_index());
+    }
+
+    #[test]
+    fn reset_progress_starts_every_peer_at_the_no_op_entry_of_the_new_leader() {
+        let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2060);
+        let peer = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2061);
+        let other_peer = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2062);
+        let new_peer = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2063);
+
+        let runtime = Builder::new_multi_thread().worker_threads(4).enable_all().build().unwrap();
+        let replica = Replica::new(
+            30,
+            self_host_and_port.clone(),
+            vec![peer, other_peer],
+            Arc::new(SystemClock::new()),
+        );
+
+        let state = runtime.block_on(async move {
+            let state = State::new(Arc::new(replica), RaftConfig::default());
+            for _ in 0..5 {
+                let command = Command { command: "Content".as_bytes().to_vec(), client_id: None, sequence: 0 };
+                state.get_replicated_log().append_command(&command, 1);
+            }
+            state.change_to_candidate();
+            state.clone().change_to_leader();
+            state.stop_heartbeats();
+            return state;
+        });
+
+        let follower_state = FollowerState::new(
+            state,
+            Arc::new(BuiltInServiceRequestFactory::new()),
+            RaftConfig::default().get_replication_config(),
+        );
+        follower_state.next_log_index_by_peer.insert(peer, 3);
+        follower_state.reset_progress();
+
+        assert_eq!(5, *follower_state.next_log_index_by_peer.get(&peer).unwrap().value());
+        assert_eq!(5, *follower_state.next_log_index_by_peer.get(&other_peer).unwrap().value());
+        assert_eq!((new_peer, 6), follower_state.next_log_index_by_peer_for(&new_peer));
+    }
+
+    #[test]
+    fn register_success_install_snapshot_response_from_peer() {
+        let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2060);
+        let peer = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2061);
+
+        let runtime = Builder::new_multi_thread().worker_threads(4).enable_all().build().unwrap();
+        let replica = Replica::new(
+            30,
+            self_host_and_port.clone(),
+            vec![peer],
+            Arc::new(SystemClock::new()),
+        );
+
+        let state = runtime.block_on(async move {
+            return State::new(Arc::new(replica), RaftConfig::default());
+        });
+
+        let follower_state = Arc::new(FollowerState::new(
+            state,
+            Arc::new(BuiltInServiceRequestFactory::new()),
+            RaftConfig::default().get_replication_config(),
+        ));
+
+        let inner_follower_state = follower_state.clone();
+        runtime.block_on(async move {
+            inner_follower_state.register_install_snapshot_response(InstallSnapshotResponse {
+                term: 0,
+                success: true,
+                correlation_id: 10,
+            }, peer.clone(), 5).await;
+        });
+
+        let next_log_index_by_peer = follower_state.next_log_index_by_peer.get(&peer).unwrap();
+        assert_eq!(6, *(next_log_index_by_peer.value()));
+        assert_eq!(5, *follower_state.match_log_index_by_peer.get(&peer).unwrap().value());
+    }
+
+    #[test]
+    fn ignore_success_install_snapshot_response_of_an_earlier_term() {
+        let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2060);
+        let peer = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2061);
+
+        let runtime = Builder::new_multi_thread().worker_threads(4).enable_all().build().unwrap();
+        let replica = Replica::new(
+            30,
+            self_host_and_port.clone(),
+            vec![peer],
+            Arc::new(SystemClock::new()),
+        );
+
+        let state = runtime.block_on(async move {
+            let state = State::new(Arc::new(replica), RaftConfig::default());
+            state.change_to_candidate();
+            state.change_to_candidate();
+            return state;
+        });
+
+        let follower_state = Arc::new(FollowerState::new(
+            state,
+            Arc::new(BuiltInServiceRequestFactory::new()),
+            RaftConfig::default().get_replication_config(),
+        ));
+
+        let inner_follower_state = follower_state.clone();
+        runtime.block_on(async move {
+            inner_follower_state.register_install_snapshot_response(InstallSnapshotResponse {
+                term: 1,
+                success: true,
+                correlation_id: 10,
+            }, peer.clone(), 5).await;
+        });
+
+        assert_eq!(1, *follower_state.next_log_index_by_peer.get(&peer).unwrap().value());
+        assert!(follower_state.match_log_index_by_peer.get(&peer).is_none());
+    }
+
+    #[test]
+    fn peer_is_caught_up_after_installing_the_snapshot() {
+        let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2060);
+        let peer = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2061);
+
+        let runtime = Builder::new_multi_thread().worker_threads(4).enable_all().build().unwrap();
+        let replica = Replica::new(
+            30,
+            self_host_and_port.clone(),
+            vec![peer],
+            Arc::new(SystemClock::new()),
+        );
+
+        let state = runtime.block_on(async move {
+            let state = State::new(Arc::new(replica), RaftConfig::default());
+            state.get_replicated_log().install_snapshot(Snapshot::new(5, 0, Vec::new()));
+            return state;
+        });
+
+        let follower_state = Arc::new(FollowerState::new(
+            state,
+            Arc::new(BuiltInServiceRequestFactory::new()),
+            RaftConfig::default().get_replication_config(),
+        ));
+
+        let inner_follower_state = follower_state.clone();
+        runtime.block_on(async move {
+            inner_follower_state.register_install_snapshot_response(InstallSnapshotResponse {
+                term: 0,
+                success: true,
+                correlation_id: 10,
+            }, peer.clone(), 5).await;
+        });
+
+        assert!(follower_state.is_caught_up(&peer));
+    }
+
+    #[test]
+    fn service_request_for_a_compacted_log_index_installs_snapshot() {
+        let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2060);
+        let peer = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2061);
+
+        let runtime = Builder::new_multi_thread().worker_threads(4).enable_all().build().unwrap();
+        let replica = Replica::new(
+            30,
+            self_host_and_port.clone(),
+            vec![peer],
+            Arc::new(SystemClock::new()),
+        );
+
+        let state = runtime.block_on(async move {
+            let state = State::new(Arc::new(replica), RaftConfig::default());
+            state.get_replicated_log().install_snapshot(Snapshot::new(3, 1, Vec::new()));
+            state.clone().change_to_leader();
+            return state;
+        });
+
+        let follower_state = Arc::new(FollowerState::new(
+            state.clone(),
+            Arc::new(BuiltInServiceRequestFactory::new()),
+            RaftConfig::default().get_replication_config(),
+        ));
+
+        assert!(state.get_replicated_log().is_compacted(1));
+        let inner_follower_state = follower_state.clone();
+        let result = runtime.block_on(async move {
+            return inner_follower_state.replicate_to(&peer, ReplicationTrigger::Requested).await;
+        });
+        assert!(result.is_err());
+
+        let next_log_index_by_peer = follower_state.next_log_index_by_peer.get(&peer).unwrap();
+        assert_eq!(1, *(next_log_index_by_peer.value()));
+    }
+}
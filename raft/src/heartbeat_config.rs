@@ -1,45 +1,92 @@
+use std::ops::RangeInclusive;
+use std::sync::Mutex;
 use std::time::Duration;
 
-use rand::{Rng, thread_rng};
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
 
+use crate::raft_config::RaftConfig;
+
+/// The heartbeat settings of a `RaftConfig` along with the heartbeat (election) timeout of the current election round.
 pub struct HeartbeatConfig {
     heartbeat_interval: Duration,
-    heartbeat_timeout: Duration,
+    heartbeat_timeout_range: RangeInclusive<Duration>,
+    heartbeat_timeout: Mutex<Duration>,
+    random: Mutex<StdRng>,
+    pre_vote: bool,
+    check_quorum: bool,
+    leader_lease_clock_drift_bound: Option<Duration>,
 }
 
 impl HeartbeatConfig {
-    const MAXIMUM_HEARTBEAT_INTERVAL: Duration = Duration::from_millis(100);
-    const HEARTBEAT_TIMEOUT_RANGE_MS: std::ops::RangeInclusive<u32> = 150..=300;
-
-    pub fn default() -> Self {
-        return HeartbeatConfig::new(Duration::from_millis(50));
-    }
+    const HEARTBEAT_CHECK_INTERVAL: Duration = Duration::from_millis(5);
 
-    pub fn new(heartbeat_interval: Duration) -> Self {
-        if heartbeat_interval.gt(&Self::MAXIMUM_HEARTBEAT_INTERVAL) {
-            panic!(
-                "heartbeat interval can not be greater than the maximum interval defined {:?}",
-                Self::MAXIMUM_HEARTBEAT_INTERVAL
-            );
-        }
-        return HeartbeatConfig {
-            heartbeat_interval,
-            heartbeat_timeout: Self::heartbeat_timeout(),
+    pub(crate) fn new(raft_config: &RaftConfig) -> Self {
+        let heartbeat_timeout_range = raft_config.get_election_timeout_range().clone();
+        let random = match raft_config.get_random_seed() {
+            None => StdRng::from_entropy(),
+            Some(random_seed) => StdRng::seed_from_u64(random_seed),
         };
+        let heartbeat_config = HeartbeatConfig {
+            heartbeat_interval: raft_config.get_heartbeat_interval(),
+            heartbeat_timeout: Mutex::new(*heartbeat_timeout_range.start()),
+            heartbeat_timeout_range,
+            random: Mutex::new(random),
+            pre_vote: raft_config.is_pre_vote_enabled(),
+            check_quorum: raft_config.is_check_quorum_enabled(),
+            leader_lease_clock_drift_bound: raft_config.get_leader_lease_clock_drift_bound(),
+        };
+        heartbeat_config.next_heartbeat_timeout();
+        return heartbeat_config;
     }
 
     pub fn get_heartbeat_interval(&self) -> Duration {
         return self.heartbeat_interval;
     }
 
+    /// The interval of checking the heartbeat timeout, fine enough to tell apart the timeouts drawn by the different replicas.
+    pub fn get_heartbeat_check_interval(&self) -> Duration {
+        return Self::HEARTBEAT_CHECK_INTERVAL;
+    }
+
+    /// The heartbeat timeout of the current election round.
     pub fn get_heartbeat_timeout(&self) -> Duration {
-        return self.heartbeat_timeout;
+        return *self.heartbeat_timeout.lock().unwrap();
     }
 
-    fn heartbeat_timeout() -> Duration {
-        return Duration::from_millis(
-            u64::from(thread_rng().gen_range(Self::HEARTBEAT_TIMEOUT_RANGE_MS))
-        );
+    pub fn get_heartbeat_timeout_range(&self) -> &RangeInclusive<Duration> {
+        return &self.heartbeat_timeout_range;
+    }
+
+    pub fn is_pre_vote_enabled(&self) -> bool {
+        return self.pre_vote;
+    }
+
+    pub fn is_check_quorum_enabled(&self) -> bool {
+        return self.check_quorum;
+    }
+
+    pub fn is_leader_lease_enabled(&self) -> bool {
+        return self.leader_lease_clock_drift_bound.is_some();
+    }
+
+    pub fn get_leader_lease_duration(&self) -> Option<Duration> {
+        return self.leader_lease_clock_drift_bound
+            .map(|clock_drift_bound| self.minimum_heartbeat_timeout() - clock_drift_bound);
+    }
+
+    /// Draws the heartbeat timeout of the next election round from the heartbeat timeout range.
+    pub(crate) fn next_heartbeat_timeout(&self) -> Duration {
+        let start = self.heartbeat_timeout_range.start().as_millis() as u64;
+        let end = self.heartbeat_timeout_range.end().as_millis() as u64;
+        let heartbeat_timeout = Duration::from_millis(self.random.lock().unwrap().gen_range(start..=end));
+
+        *self.heartbeat_timeout.lock().unwrap() = heartbeat_timeout;
+        return heartbeat_timeout;
+    }
+
+    pub(crate) fn minimum_heartbeat_timeout(&self) -> Duration {
+        return *self.heartbeat_timeout_range.start();
     }
 }
 
@@ -48,19 +95,82 @@ mod tests {
     use std::time::Duration;
 
     use crate::heartbeat_config::HeartbeatConfig;
-
-    #[test]
-    #[should_panic]
-    fn heartbeat_config_with_heartbeat_interval_greater_than_maximum() {
-        HeartbeatConfig::new(Duration::from_millis(130));
-    }
+    use crate::raft_config::RaftConfig;
 
     #[test]
     fn heartbeat_config_heartbeat_timeout() {
-        let heartbeat_config = HeartbeatConfig::new(Duration::from_millis(100));
+        let heartbeat_config = HeartbeatConfig::new(&RaftConfig::default());
         let duration = heartbeat_config.get_heartbeat_timeout();
 
         assert!(duration.ge(&Duration::from_millis(150)));
         assert!(duration.le(&Duration::from_millis(300)));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn heartbeat_config_without_pre_vote() {
+        let heartbeat_config = HeartbeatConfig::new(&RaftConfig::default());
+        assert_eq!(false, heartbeat_config.is_pre_vote_enabled());
+    }
+
+    #[test]
+    fn heartbeat_config_with_pre_vote() {
+        let heartbeat_config = HeartbeatConfig::new(&RaftConfig::builder().with_pre_vote().build().unwrap());
+        assert!(heartbeat_config.is_pre_vote_enabled());
+    }
+
+    #[test]
+    fn heartbeat_config_without_check_quorum() {
+        let heartbeat_config = HeartbeatConfig::new(&RaftConfig::builder().without_check_quorum().build().unwrap());
+        assert_eq!(false, heartbeat_config.is_check_quorum_enabled());
+    }
+
+    #[test]
+    fn heartbeat_config_without_leader_lease() {
+        let heartbeat_config = HeartbeatConfig::new(&RaftConfig::default());
+        assert_eq!(false, heartbeat_config.is_leader_lease_enabled());
+        assert_eq!(None, heartbeat_config.get_leader_lease_duration());
+    }
+
+    #[test]
+    fn heartbeat_config_with_leader_lease() {
+        let heartbeat_config = HeartbeatConfig::new(&RaftConfig::builder().with_leader_lease(Duration::from_millis(20)).build().unwrap());
+        assert!(heartbeat_config.is_leader_lease_enabled());
+        assert_eq!(Some(Duration::from_millis(130)), heartbeat_config.get_leader_lease_duration());
+    }
+
+    #[test]
+    fn heartbeat_config_draws_the_next_heartbeat_timeout_from_the_range() {
+        let raft_config = RaftConfig::builder()
+            .with_election_timeout_range(Duration::from_millis(200)..=Duration::from_millis(210))
+            .build()
+            .unwrap();
+        let heartbeat_config = HeartbeatConfig::new(&raft_config);
+
+        for _count in 1..=20 {
+            let heartbeat_timeout = heartbeat_config.next_heartbeat_timeout();
+            assert!(heartbeat_timeout.ge(&Duration::from_millis(200)));
+            assert!(heartbeat_timeout.le(&Duration::from_millis(210)));
+            assert_eq!(heartbeat_timeout, heartbeat_config.get_heartbeat_timeout());
+        }
+    }
+
+    #[test]
+    fn heartbeat_config_with_the_same_random_seed_draws_the_same_heartbeat_timeouts() {
+        let raft_config = RaftConfig::builder().with_random_seed(7).build().unwrap();
+        let heartbeat_config = HeartbeatConfig::new(&raft_config);
+        let other_heartbeat_config = HeartbeatConfig::new(&raft_config);
+
+        assert_eq!(heartbeat_config.get_heartbeat_timeout(), other_heartbeat_config.get_heartbeat_timeout());
+        for _count in 1..=20 {
+            assert_eq!(heartbeat_config.next_heartbeat_timeout(), other_heartbeat_config.next_heartbeat_timeout());
+        }
+    }
+
+    #[test]
+    fn heartbeat_config_redraws_the_heartbeat_timeout() {
+        let heartbeat_config = HeartbeatConfig::new(&RaftConfig::builder().with_random_seed(7).build().unwrap());
+
+        let heartbeat_timeouts: Vec<Duration> = (1..=20).map(|_| heartbeat_config.next_heartbeat_timeout()).collect();
+        assert!(heartbeat_timeouts.iter().any(|heartbeat_timeout| *heartbeat_timeout != heartbeat_timeouts[0]));
+    }
+}
@@ -3,5 +3,14 @@ pub mod state;
 pub mod net;
 pub mod heartbeat_config;
 pub mod log_entry;
+pub mod membership;
+pub mod raft_config;
+pub mod raft_node;
 pub mod replicated_log;
-mod follower_state;
\ No newline at end of file
+pub mod state_machine;
+pub mod storage;
+mod follower_state;
+mod client_sessions;
+mod replication_config;
+#[cfg(test)]
+mod test_support;
\ No newline at end of file
@@ -1,18 +1,30 @@
 use bytes::Bytes;
 
-use crate::net::rpc::grpc::Command;
+use crate::net::rpc::grpc::{Command, EntryType};
 
 #[derive(PartialEq, Debug)]
 pub struct LogEntry {
     term: u64,
     index: u64,
-    acknowledgements: u64,
     command: LogCommand,
+    entry_type: LogEntryType,
+    time: u64,
+}
+
+/// A command entry is applied to the state machine, a configuration entry changes the voters of the cluster,
+/// a no-op entry is appended by a new leader and changes nothing.
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+pub enum LogEntryType {
+    Command,
+    Configuration,
+    NoOp,
 }
 
 #[derive(PartialEq, Debug)]
 pub(crate) struct LogCommand {
     bytes: Bytes,
+    client_id: Option<u64>,
+    sequence: u64,
 }
 
 impl LogEntry {
@@ -23,7 +35,8 @@ impl LogEntry {
             term,
             index,
             command: LogCommand::from(command),
-            acknowledgements: 0
+            entry_type: LogEntryType::Command,
+            time: 0,
         };
     }
 
@@ -31,11 +44,27 @@ impl LogEntry {
         return LogEntry {
             term: entry.term,
             index: entry.index,
-            command: LogCommand { bytes: entry.command.bytes.clone() },
-            acknowledgements: entry.acknowledgements
+            command: LogCommand {
+                bytes: entry.command.bytes.clone(),
+                client_id: entry.command.client_id,
+                sequence: entry.command.sequence,
+            },
+            entry_type: entry.entry_type,
+            time: entry.time,
         };
     }
 
+    pub(crate) fn with_entry_type(mut self, entry_type: LogEntryType) -> Self {
+        self.entry_type = entry_type;
+        return self;
+    }
+
+    /// Stamps the entry with the log time, the leader's clock in milliseconds since the unix epoch when it appended the entry.
+    pub(crate) fn with_time(mut self, time: u64) -> Self {
+        self.time = time;
+        return self;
+    }
+
     pub(crate) fn matches_term(&self, term: u64) -> bool {
         return self.term == term;
     }
@@ -48,18 +77,10 @@ impl LogEntry {
         return command.command == self.command.bytes.to_vec();
     }
 
-    pub(crate) fn acknowledge(&mut self) {
-        self.acknowledgements = self.acknowledgements + 1;
-    }
-
-    pub(crate) fn get_index(&self) -> u64 {
+    pub fn get_index(&self) -> u64 {
         return self.index;
     }
 
-    pub(crate) fn is_replicated(&self, quorum: usize) -> bool {
-        return self.acknowledgements >= quorum as u64;
-    }
-
     pub fn get_term(&self) -> u64 {
         return self.term;
     }
@@ -68,28 +89,77 @@ impl LogEntry {
         return self.command.bytes.to_vec();
     }
 
-    pub fn get_acknowledgements(&self) -> u64 {
-        return self.acknowledgements;
+    pub fn get_client_id(&self) -> Option<u64> {
+        return self.command.client_id;
+    }
+
+    pub fn get_sequence(&self) -> u64 {
+        return self.command.sequence;
+    }
+
+    pub fn get_time(&self) -> u64 {
+        return self.time;
+    }
+
+    /// The command of the entry, along with the client session it belongs to.
+    pub(crate) fn get_command(&self) -> Command {
+        return Command {
+            command: self.get_bytes_as_vec(),
+            client_id: self.command.client_id,
+            sequence: self.command.sequence,
+        };
+    }
+
+    pub fn get_entry_type(&self) -> LogEntryType {
+        return self.entry_type;
+    }
+
+    pub(crate) fn is_configuration(&self) -> bool {
+        return self.entry_type == LogEntryType::Configuration;
+    }
+
+    pub(crate) fn is_no_op(&self) -> bool {
+        return self.entry_type == LogEntryType::NoOp;
+    }
+}
+
+impl LogEntryType {
+    pub(crate) fn from(entry_type: i32) -> Self {
+        return match EntryType::from_i32(entry_type) {
+            Some(EntryType::Configuration) => LogEntryType::Configuration,
+            Some(EntryType::Noop) => LogEntryType::NoOp,
+            _ => LogEntryType::Command
+        };
+    }
+
+    pub(crate) fn as_entry_type(&self) -> i32 {
+        return match self {
+            LogEntryType::Command => EntryType::Command as i32,
+            LogEntryType::Configuration => EntryType::Configuration as i32,
+            LogEntryType::NoOp => EntryType::Noop as i32,
+        };
     }
 }
 
 impl LogCommand {
     pub(crate) fn from(command: &Command) -> Self {
         return LogCommand {
-            bytes: Bytes::copy_from_slice(command.command.as_ref())
+            bytes: Bytes::copy_from_slice(command.command.as_ref()),
+            client_id: command.client_id,
+            sequence: command.sequence,
         };
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::log_entry::LogEntry;
-    use crate::net::rpc::grpc::Command;
+    use crate::log_entry::{LogEntry, LogEntryType};
+    use crate::net::rpc::grpc::{Command, EntryType};
 
     #[test]
     fn matches_term() {
         let content = String::from("Content");
-        let command = Command { command: content.as_bytes().to_vec() };
+        let command = Command { command: content.as_bytes().to_vec(), client_id: None, sequence: 0 };
 
         let log_entry = LogEntry::new(1, 0, &command);
         assert!(log_entry.matches_term(1));
@@ -98,7 +168,7 @@ mod tests {
     #[test]
     fn does_not_match_term() {
         let content = String::from("Content");
-        let command = Command { command: content.as_bytes().to_vec() };
+        let command = Command { command: content.as_bytes().to_vec(), client_id: None, sequence: 0 };
 
         let log_entry = LogEntry::new(10, 0, &command);
         assert_eq!(false, log_entry.matches_term(1));
@@ -107,7 +177,7 @@ mod tests {
     #[test]
     fn matches_index() {
         let content = String::from("Content");
-        let command = Command { command: content.as_bytes().to_vec() };
+        let command = Command { command: content.as_bytes().to_vec(), client_id: None, sequence: 0 };
 
         let log_entry = LogEntry::new(1, 0, &command);
         assert!(log_entry.matches_index(0));
@@ -116,7 +186,7 @@ mod tests {
     #[test]
     fn does_not_match_index() {
         let content = String::from("Content");
-        let command = Command { command: content.as_bytes().to_vec() };
+        let command = Command { command: content.as_bytes().to_vec(), client_id: None, sequence: 0 };
 
         let log_entry = LogEntry::new(10, 0, &command);
         assert_eq!(false, log_entry.matches_index(10));
@@ -125,7 +195,7 @@ mod tests {
     #[test]
     fn matches_command() {
         let content = String::from("Content");
-        let command = Command { command: content.as_bytes().to_vec() };
+        let command = Command { command: content.as_bytes().to_vec(), client_id: None, sequence: 0 };
 
         let log_entry = LogEntry::new(1, 0, &command);
         assert!(log_entry.matches_command(&command));
@@ -134,11 +204,41 @@ mod tests {
     #[test]
     fn does_not_match_command() {
         let content = String::from("Content");
-        let command = Command { command: content.as_bytes().to_vec() };
+        let command = Command { command: content.as_bytes().to_vec(), client_id: None, sequence: 0 };
 
         let log_entry = LogEntry::new(10, 0, &command);
 
-        let another_command = Command { command: "fail".as_bytes().to_vec() };
+        let another_command = Command { command: "fail".as_bytes().to_vec(), client_id: None, sequence: 0 };
         assert_eq!(false, log_entry.matches_command(&another_command));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn command_entry_type_by_default() {
+        let command = Command { command: "Content".as_bytes().to_vec(), client_id: None, sequence: 0 };
+
+        let log_entry = LogEntry::new(1, 0, &command);
+        assert_eq!(LogEntryType::Command, log_entry.get_entry_type());
+        assert_eq!(false, log_entry.is_configuration());
+    }
+
+    #[test]
+    fn configuration_entry_type() {
+        let command = Command { command: "Content".as_bytes().to_vec(), client_id: None, sequence: 0 };
+
+        let log_entry = LogEntry::new(1, 0, &command).with_entry_type(LogEntryType::Configuration);
+        assert!(log_entry.is_configuration());
+        assert_eq!(LogEntryType::Configuration, LogEntryType::from(LogEntryType::Configuration.as_entry_type()));
+        assert_eq!(EntryType::Configuration as i32, LogEntryType::Configuration.as_entry_type());
+    }
+
+    #[test]
+    fn no_op_entry_type() {
+        let command = Command { command: Vec::new(), client_id: None, sequence: 0 };
+
+        let log_entry = LogEntry::new(1, 0, &command).with_entry_type(LogEntryType::NoOp);
+        assert!(log_entry.is_no_op());
+        assert_eq!(false, log_entry.is_configuration());
+        assert_eq!(LogEntryType::NoOp, LogEntryType::from(LogEntryType::NoOp.as_entry_type()));
+        assert_eq!(EntryType::Noop as i32, LogEntryType::NoOp.as_entry_type());
+    }
+}
@@ -0,0 +1,296 @@
+use prost::Message;
+
+use replicate::net::connect::host_and_port::HostAndPort;
+
+use crate::net::rpc::grpc::{Configuration, Member};
+
+/// The voters and the learners of the cluster. While the voters change, the membership is joint (C_old,new):
+/// elections and commits need a majority of the current voters and a majority of the next voters.
+/// Learners receive the log, but are not a part of any majority.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Membership {
+    voters: Vec<HostAndPort>,
+    next_voters: Vec<HostAndPort>,
+    learners: Vec<HostAndPort>,
+}
+
+impl Membership {
+    pub fn new(voters: Vec<HostAndPort>) -> Self {
+        return Membership { voters, next_voters: Vec::new(), learners: Vec::new() };
+    }
+
+    pub(crate) fn with_learner(&self, learner: HostAndPort) -> Self {
+        let mut membership = self.clone();
+        membership.learners.push(learner);
+        return membership;
+    }
+
+    /// The joint membership (C_old,new) moving from the current voters to the current voters with the learner,
+    /// the learner is no longer a learner.
+    pub(crate) fn promoting(&self, learner: &HostAndPort) -> Self {
+        let mut membership = self.with_voter(learner.clone());
+        membership.learners.retain(|current_learner| current_learner != learner);
+        return membership;
+    }
+
+    /// The joint membership (C_old,new) moving from the current voters to the current voters with `voter`.
+    pub(crate) fn with_voter(&self, voter: HostAndPort) -> Self {
+        let mut next_voters = self.voters.clone();
+        next_voters.push(voter);
+        return Membership { voters: self.voters.clone(), next_voters, learners: self.learners.clone() };
+    }
+
+    /// The joint membership (C_old,new) moving from the current voters to the current voters without `voter`.
+    pub(crate) fn without_voter(&self, voter: &HostAndPort) -> Self {
+        let next_voters = self.voters.iter().filter(|current_voter| *current_voter != voter).map(|current_voter| current_voter.clone()).collect();
+        return Membership { voters: self.voters.clone(), next_voters, learners: self.learners.clone() };
+    }
+
+    /// The membership (C_new) that a joint membership moves to.
+    pub(crate) fn complete(&self) -> Self {
+        if !self.is_joint() {
+            return self.clone();
+        }
+        return Membership { voters: self.next_voters.clone(), next_voters: Vec::new(), learners: self.learners.clone() };
+    }
+
+    pub fn is_joint(&self) -> bool {
+        return !self.next_voters.is_empty();
+    }
+
+    pub fn is_voter(&self, address: &HostAndPort) -> bool {
+        return self.voters.contains(address) || self.next_voters.contains(address);
+    }
+
+    pub fn is_learner(&self, address: &HostAndPort) -> bool {
+        return self.learners.contains(address);
+    }
+
+    pub fn get_voters(&self) -> &Vec<HostAndPort> {
+        return &self.voters;
+    }
+
+    pub fn get_next_voters(&self) -> &Vec<HostAndPort> {
+        return &self.next_voters;
+    }
+
+    pub fn get_learners(&self) -> &Vec<HostAndPort> {
+        return &self.learners;
+    }
+
+    /// All the members that receive the log, the current voters followed by the next voters that are not current voters and the learners.
+    pub(crate) fn get_members(&self) -> Vec<HostAndPort> {
+        let mut members = self.voters.clone();
+        for member in self.next_voters.iter().chain(self.learners.iter()) {
+            if !members.contains(member) {
+                members.push(member.clone());
+            }
+        }
+        return members;
+    }
+
+    pub(crate) fn has_majority<F>(&self, acknowledged: F) -> bool
+        where F: Fn(&HostAndPort) -> bool {
+        let majority_of = |voters: &Vec<HostAndPort>| {
+            voters.iter().filter(|voter| acknowledged(voter)).count() > voters.len() / 2
+        };
+        if !majority_of(&self.voters) {
+            return false;
+        }
+        return !self.is_joint() || majority_of(&self.next_voters);
+    }
+
+    /// The highest index that a majority of the voters (of both the memberships while joint) match,
+    /// the majority-th highest of the voters' match indices.
+    pub(crate) fn majority_index<F>(&self, match_index: F) -> Option<u64>
+        where F: Fn(&HostAndPort) -> Option<u64> {
+        let majority_index_of = |voters: &Vec<HostAndPort>| {
+            let mut match_indices: Vec<Option<u64>> = voters.iter().map(&match_index).collect();
+            match_indices.sort_by(|one, other| other.cmp(one));
+            return match_indices.get(voters.len() / 2).copied().flatten();
+        };
+        let majority_index = majority_index_of(&self.voters);
+        if !self.is_joint() {
+            return majority_index;
+        }
+        return majority_index.min(majority_index_of(&self.next_voters));
+    }
+
+    pub(crate) fn has_majority_of(&self, addresses: &[HostAndPort]) -> bool {
+        return self.has_majority(|voter| addresses.contains(voter));
+    }
+
+    pub(crate) fn encode(&self) -> Vec<u8> {
+        let as_members = |voters: &Vec<HostAndPort>| {
+            voters.iter().map(|voter| Member { host: voter.host_as_string(), port: voter.port() as u32 }).collect()
+        };
+        return Configuration {
+            voters: as_members(&self.voters),
+            next_voters: as_members(&self.next_voters),
+            learners: as_members(&self.learners),
+        }.encode_to_vec();
+    }
+
+    pub(crate) fn decode(bytes: &[u8]) -> Option<Self> {
+        let configuration = Configuration::decode(bytes).ok()?;
+        let as_voters = |members: Vec<Member>| -> Option<Vec<HostAndPort>> {
+            members.iter().map(|member| {
+                let port = u16::try_from(member.port).ok()?;
+                HostAndPort::try_new(&member.host, port).ok()
+            }).collect()
+        };
+        return Some(Membership {
+            voters: as_voters(configuration.voters)?,
+            next_voters: as_voters(configuration.next_voters)?,
+            learners: as_voters(configuration.learners)?,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{IpAddr, Ipv4Addr};
+
+    use replicate::net::connect::host_and_port::HostAndPort;
+
+    use crate::membership::Membership;
+
+    fn voter(port: u16) -> HostAndPort {
+        return HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), port);
+    }
+
+    #[test]
+    fn majority_of_the_voters() {
+        let membership = Membership::new(vec![voter(2010), voter(2011), voter(2012)]);
+
+        assert!(membership.has_majority_of(&vec![voter(2010), voter(2012)]));
+        assert_eq!(false, membership.has_majority_of(&vec![voter(2011)]));
+    }
+
+    #[test]
+    fn majority_ignores_the_non_voters() {
+        let membership = Membership::new(vec![voter(2010), voter(2011), voter(2012)]);
+
+        assert_eq!(false, membership.has_majority_of(&vec![voter(2010), voter(2020), voter(2021)]));
+    }
+
+    #[test]
+    fn majority_of_a_joint_membership_needs_both_the_majorities() {
+        let membership = Membership::new(vec![voter(2010), voter(2011), voter(2012)])
+            .with_voter(voter(2013));
+
+        assert!(membership.is_joint());
+        assert_eq!(false, membership.has_majority_of(&vec![voter(2010), voter(2011)]));
+        assert!(membership.has_majority_of(&vec![voter(2010), voter(2011), voter(2013)]));
+    }
+
+    #[test]
+    fn majority_of_a_joint_membership_removing_a_voter() {
+        let membership = Membership::new(vec![voter(2010), voter(2011), voter(2012)])
+            .without_voter(&voter(2010));
+
+        assert!(membership.is_joint());
+        assert_eq!(false, membership.has_majority_of(&vec![voter(2010), voter(2011)]));
+        assert!(membership.has_majority_of(&vec![voter(2011), voter(2012)]));
+    }
+
+    #[test]
+    fn majority_index_of_the_voters() {
+        let membership = Membership::new(vec![voter(2010), voter(2011), voter(2012)]);
+        let match_index = |voter: &HostAndPort| match voter.port() {
+            2010 => Some(5),
+            2011 => Some(3),
+            _ => None
+        };
+
+        assert_eq!(Some(3), membership.majority_index(match_index));
+    }
+
+    #[test]
+    fn no_majority_index_given_a_majority_matches_nothing() {
+        let membership = Membership::new(vec![voter(2010), voter(2011), voter(2012)]);
+        let match_index = |voter: &HostAndPort| match voter.port() {
+            2010 => Some(5),
+            _ => None
+        };
+
+        assert_eq!(None, membership.majority_index(match_index));
+    }
+
+    #[test]
+    fn majority_index_of_a_joint_membership_is_the_lower_of_both_the_majorities() {
+        let membership = Membership::new(vec![voter(2010), voter(2011), voter(2012)])
+            .without_voter(&voter(2010));
+        let match_index = |voter: &HostAndPort| match voter.port() {
+            2010 => Some(5),
+            2011 => Some(4),
+            2012 => Some(2),
+            _ => None
+        };
+
+        assert_eq!(Some(2), membership.majority_index(match_index));
+    }
+
+    #[test]
+    fn complete_a_joint_membership() {
+        let membership = Membership::new(vec![voter(2010), voter(2011)])
+            .with_voter(voter(2012))
+            .complete();
+
+        assert_eq!(false, membership.is_joint());
+        assert_eq!(&vec![voter(2010), voter(2011), voter(2012)], membership.get_voters());
+    }
+
+    #[test]
+    fn members_of_a_joint_membership() {
+        let membership = Membership::new(vec![voter(2010), voter(2011)])
+            .with_voter(voter(2012));
+
+        assert_eq!(vec![voter(2010), voter(2011), voter(2012)], membership.get_members());
+        assert!(membership.is_voter(&voter(2012)));
+        assert_eq!(false, membership.is_voter(&voter(2013)));
+    }
+
+    #[test]
+    fn encode_and_decode() {
+        let membership = Membership::new(vec![voter(2010), voter(2011)])
+            .without_voter(&voter(2011));
+
+        let decoded = Membership::decode(&membership.encode()).unwrap();
+        assert_eq!(membership, decoded);
+    }
+
+    #[test]
+    fn majority_ignores_the_learners() {
+        let membership = Membership::new(vec![voter(2010), voter(2011), voter(2012)])
+            .with_learner(voter(2013));
+
+        assert_eq!(false, membership.has_majority_of(&vec![voter(2010), voter(2013)]));
+        assert!(membership.is_learner(&voter(2013)));
+        assert_eq!(false, membership.is_voter(&voter(2013)));
+        assert_eq!(vec![voter(2010), voter(2011), voter(2012), voter(2013)], membership.get_members());
+    }
+
+    #[test]
+    fn promote_a_learner() {
+        let membership = Membership::new(vec![voter(2010), voter(2011)])
+            .with_learner(voter(2012))
+            .promoting(&voter(2012));
+
+        assert!(membership.is_joint());
+        assert_eq!(false, membership.is_learner(&voter(2012)));
+
+        let membership = membership.complete();
+        assert_eq!(&vec![voter(2010), voter(2011), voter(2012)], membership.get_voters());
+        assert!(membership.get_learners().is_empty());
+    }
+
+    #[test]
+    fn encode_and_decode_with_learners() {
+        let membership = Membership::new(vec![voter(2010), voter(2011)])
+            .with_learner(voter(2012));
+
+        let decoded = Membership::decode(&membership.encode()).unwrap();
+        assert_eq!(membership, decoded);
+    }
+}
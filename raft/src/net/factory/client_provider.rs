@@ -7,20 +7,37 @@ use replicate::net::connect::error::ServiceResponseError;
 
 use crate::net::rpc::grpc::RequestVote;
 use crate::net::rpc::grpc::RequestVoteResponse;
+use crate::net::rpc::grpc::PreVote;
+use crate::net::rpc::grpc::PreVoteResponse;
 use crate::net::rpc::grpc::AppendEntries;
 use crate::net::rpc::grpc::AppendEntriesResponse;
+use crate::net::rpc::grpc::InstallSnapshot;
+use crate::net::rpc::grpc::InstallSnapshotResponse;
+use crate::net::rpc::grpc::TimeoutNow;
+use crate::net::rpc::grpc::Command;
+use crate::net::rpc::grpc::CommandResponse;
 use crate::net::rpc::grpc::raft_client::RaftClient;
 
 pub struct RequestVoteClient {}
 
 pub struct RequestVoteResponseClient {}
 
+pub struct PreVoteClient {}
+
+pub struct PreVoteResponseClient {}
+
 pub struct HeartbeatServiceClient {}
 
 pub struct ReplicateLogClient {}
 
 pub struct ReplicateLogResponseClient {}
 
+pub struct InstallSnapshotClient {}
+
+pub struct TimeoutNowClient {}
+
+pub struct ExecuteClient {}
+
 #[async_trait]
 impl ServiceClientProvider<RequestVote, ()> for RequestVoteClient {
     async fn call(&self, request: Request<RequestVote>, address: HostAndPort) -> Result<Response<()>, ServiceResponseError> {
@@ -39,6 +56,24 @@ impl ServiceClientProvider<RequestVoteResponse, ()> for RequestVoteResponseClien
     }
 }
 
+#[async_trait]
+impl ServiceClientProvider<PreVote, ()> for PreVoteClient {
+    async fn call(&self, request: Request<PreVote>, address: HostAndPort) -> Result<Response<()>, ServiceResponseError> {
+        let mut client = RaftClient::connect(address.as_string_with_http()).await?;
+        let response = client.acknowledge_pre_vote(request).await?;
+        return Ok(response);
+    }
+}
+
+#[async_trait]
+impl ServiceClientProvider<PreVoteResponse, ()> for PreVoteResponseClient {
+    async fn call(&self, request: Request<PreVoteResponse>, address: HostAndPort) -> Result<Response<()>, ServiceResponseError> {
+        let mut client = RaftClient::connect(address.as_string_with_http()).await?;
+        let response = client.finish_pre_vote(request).await?;
+        return Ok(response);
+    }
+}
+
 #[async_trait]
 impl ServiceClientProvider<AppendEntries, AppendEntriesResponse> for HeartbeatServiceClient {
     async fn call(&self, request: Request<AppendEntries>, address: HostAndPort) -> Result<Response<AppendEntriesResponse>, ServiceResponseError> {
@@ -66,17 +101,49 @@ impl ServiceClientProvider<AppendEntriesResponse, ()> for ReplicateLogResponseCl
     }
 }
 
+#[async_trait]
+impl ServiceClientProvider<InstallSnapshot, InstallSnapshotResponse> for InstallSnapshotClient {
+    async fn call(&self, request: Request<InstallSnapshot>, address: HostAndPort) -> Result<Response<InstallSnapshotResponse>, ServiceResponseError> {
+        let mut client = RaftClient::connect(address.as_string_with_http()).await?;
+        let response = client.install_snapshot(request).await?;
+        return Ok(response);
+    }
+}
+
+#[async_trait]
+impl ServiceClientProvider<TimeoutNow, ()> for TimeoutNowClient {
+    async fn call(&self, request: Request<TimeoutNow>, address: HostAndPort) -> Result<Response<()>, ServiceResponseError> {
+        let mut client = RaftClient::connect(address.as_string_with_http()).await?;
+        let response = client.timeout_now(request).await?;
+        return Ok(response);
+    }
+}
+
+#[async_trait]
+impl ServiceClientProvider<Command, CommandResponse> for ExecuteClient {
+    async fn call(&self, request: Request<Command>, address: HostAndPort) -> Result<Response<CommandResponse>, ServiceResponseError> {
+        let mut client = RaftClient::connect(address.as_string_with_http()).await?;
+        let response = client.execute(request).await?;
+        return Ok(response);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::net::{IpAddr, Ipv4Addr};
     use tonic::Request;
     use replicate::net::connect::host_and_port::HostAndPort;
     use replicate::net::connect::service_client::ServiceClientProvider;
-    use crate::net::factory::client_provider::{HeartbeatServiceClient, ReplicateLogClient, ReplicateLogResponseClient, RequestVoteClient, RequestVoteResponseClient};
+    use crate::net::factory::client_provider::{HeartbeatServiceClient, InstallSnapshotClient, PreVoteClient, PreVoteResponseClient, ReplicateLogClient, ReplicateLogResponseClient, RequestVoteClient, RequestVoteResponseClient, TimeoutNowClient, ExecuteClient};
     use crate::net::rpc::grpc::RequestVote;
     use crate::net::rpc::grpc::RequestVoteResponse;
+    use crate::net::rpc::grpc::PreVote;
+    use crate::net::rpc::grpc::PreVoteResponse;
     use crate::net::rpc::grpc::AppendEntries;
     use crate::net::rpc::grpc::AppendEntriesResponse;
+    use crate::net::rpc::grpc::InstallSnapshot;
+    use crate::net::rpc::grpc::TimeoutNow;
+    use crate::net::rpc::grpc::Command;
 
     #[tokio::test]
     async fn request_vote_client_with_connection_error() {
@@ -86,6 +153,9 @@ mod tests {
                 term: 1,
                 replica_id: 10,
                 correlation_id: 10,
+                last_log_index: None,
+                last_log_term: None,
+                leadership_transfer: false,
             }
         );
         let address = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 7080);
@@ -116,6 +186,46 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[tokio::test]
+    async fn pre_vote_client_with_connection_error() {
+        let client = PreVoteClient{};
+        let request = Request::new(
+            PreVote {
+                term: 1,
+                replica_id: 10,
+                correlation_id: 10,
+                last_log_index: None,
+                last_log_term: None,
+            }
+        );
+        let address = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 7080);
+
+        let result = client.call(request, address).await;
+        assert!(result.is_err());
+
+        let result = result.unwrap_err().downcast::<tonic::transport::Error>();
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn pre_vote_response_client_with_connection_error() {
+        let client = PreVoteResponseClient{};
+        let request = Request::new(
+            PreVoteResponse {
+                term: 1,
+                voted: true,
+                correlation_id: 10,
+            }
+        );
+        let address = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 7080);
+
+        let result = client.call(request, address).await;
+        assert!(result.is_err());
+
+        let result = result.unwrap_err().downcast::<tonic::transport::Error>();
+        assert!(result.is_ok());
+    }
+
     #[tokio::test]
     async fn append_entries_client_with_connection_error() {
         let client = HeartbeatServiceClient {};
@@ -124,7 +234,7 @@ mod tests {
                 term: 1,
                 leader_id: 10,
                 correlation_id: 10,
-                entry: None,
+                entries: vec![],
                 previous_log_index: None,
                 previous_log_term: None,
                 leader_commit_index: None,
@@ -149,7 +259,7 @@ mod tests {
                 correlation_id: 10,
                 previous_log_index: None,
                 previous_log_term: None,
-                entry: None,
+                entries: vec![],
                 leader_commit_index: None,
             }
         );
@@ -171,6 +281,51 @@ mod tests {
                 success: true,
                 correlation_id: 10,
                 log_entry_index: None,
+                conflict_term: None,
+                conflict_index: None,
+            }
+        );
+        let address = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 7080);
+
+        let result = client.call(request, address).await;
+        assert!(result.is_err());
+
+        let result = result.unwrap_err().downcast::<tonic::transport::Error>();
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn install_snapshot_client_with_connection_error() {
+        let client = InstallSnapshotClient {};
+        let request = Request::new(
+            InstallSnapshot {
+                term: 1,
+                leader_id: 30,
+                correlation_id: 10,
+                last_included_index: 5,
+                last_included_term: 1,
+                data: Vec::new(),
+                client_sessions: Vec::new(),
+                membership: Vec::new(),
+            }
+        );
+        let address = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 7080);
+
+        let result = client.call(request, address).await;
+        assert!(result.is_err());
+
+        let result = result.unwrap_err().downcast::<tonic::transport::Error>();
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn timeout_now_client_with_connection_error() {
+        let client = TimeoutNowClient {};
+        let request = Request::new(
+            TimeoutNow {
+                term: 1,
+                leader_id: 30,
+                correlation_id: 10,
             }
         );
         let address = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 7080);
@@ -181,4 +336,23 @@ mod tests {
         let result = result.unwrap_err().downcast::<tonic::transport::Error>();
         assert!(result.is_ok());
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn execute_client_with_connection_error() {
+        let client = ExecuteClient {};
+        let request = Request::new(
+            Command {
+                command: "Content".as_bytes().to_vec(),
+                client_id: None,
+                sequence: 0,
+            }
+        );
+        let address = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 7080);
+
+        let result = client.call(request, address).await;
+        assert!(result.is_err());
+
+        let result = result.unwrap_err().downcast::<tonic::transport::Error>();
+        assert!(result.is_ok());
+    }
+}
@@ -3,22 +3,35 @@ use replicate::net::connect::random_correlation_id_generator::RandomCorrelationI
 use replicate::net::connect::service_client::ServiceRequest;
 use replicate::net::replica::ReplicaId;
 
-use crate::net::factory::client_provider::{HeartbeatServiceClient, ReplicateLogClient, ReplicateLogResponseClient, RequestVoteClient, RequestVoteResponseClient};
+use crate::net::factory::client_provider::{HeartbeatServiceClient, InstallSnapshotClient, PreVoteClient, PreVoteResponseClient, ReplicateLogClient, ReplicateLogResponseClient, RequestVoteClient, RequestVoteResponseClient, TimeoutNowClient, ExecuteClient};
 use crate::net::rpc::grpc::AppendEntries;
 use crate::net::rpc::grpc::AppendEntriesResponse;
+use crate::net::rpc::grpc::Command;
+use crate::net::rpc::grpc::CommandResponse;
 use crate::net::rpc::grpc::Entry;
+use crate::net::rpc::grpc::InstallSnapshot;
+use crate::net::rpc::grpc::InstallSnapshotResponse;
+use crate::net::rpc::grpc::PreVote;
+use crate::net::rpc::grpc::PreVoteResponse;
 use crate::net::rpc::grpc::RequestVote;
 use crate::net::rpc::grpc::RequestVoteResponse;
+use crate::net::rpc::grpc::TimeoutNow;
+use crate::replicated_log::ReplicatedLog;
+use crate::storage::snapshot_storage::Snapshot;
 
 pub(crate) trait ServiceRequestFactory: Send + Sync {
-    fn request_vote(&self, replica_id: ReplicaId, term: u64) -> ServiceRequest<RequestVote, ()> {
+    fn request_vote(&self, replica_id: ReplicaId, term: u64, replicated_log: &ReplicatedLog, leadership_transfer: bool) -> ServiceRequest<RequestVote, ()> {
         let correlation_id_generator = RandomCorrelationIdGenerator::new();
         let correlation_id = correlation_id_generator.generate();
+        let (last_log_index, last_log_term) = replicated_log.get_last_log_index_term();
         return ServiceRequest::new(
             RequestVote {
                 replica_id,
                 term,
                 correlation_id,
+                last_log_index,
+                last_log_term,
+                leadership_transfer,
             },
             Box::new(RequestVoteClient {}),
             correlation_id,
@@ -37,6 +50,35 @@ pub(crate) trait ServiceRequestFactory: Send + Sync {
         );
     }
 
+    fn pre_vote(&self, replica_id: ReplicaId, term: u64, replicated_log: &ReplicatedLog) -> ServiceRequest<PreVote, ()> {
+        let correlation_id_generator = RandomCorrelationIdGenerator::new();
+        let correlation_id = correlation_id_generator.generate();
+        let (last_log_index, last_log_term) = replicated_log.get_last_log_index_term();
+        return ServiceRequest::new(
+            PreVote {
+                replica_id,
+                term,
+                correlation_id,
+                last_log_index,
+                last_log_term,
+            },
+            Box::new(PreVoteClient {}),
+            correlation_id,
+        );
+    }
+
+    fn pre_vote_response(&self, term: u64, voted: bool, correlation_id: CorrelationId) -> ServiceRequest<PreVoteResponse, ()> {
+        return ServiceRequest::new(
+            PreVoteResponse {
+                term,
+                voted,
+                correlation_id,
+            },
+            Box::new(PreVoteResponseClient {}),
+            correlation_id,
+        );
+    }
+
     fn heartbeat(&self, term: u64, leader_id: ReplicaId) -> ServiceRequest<AppendEntries, AppendEntriesResponse> {
         let correlation_id_generator = RandomCorrelationIdGenerator::new();
         let correlation_id = correlation_id_generator.generate();
@@ -46,7 +88,7 @@ pub(crate) trait ServiceRequestFactory: Send + Sync {
                 term,
                 leader_id,
                 correlation_id,
-                entry: None,
+                entries: vec![],
                 previous_log_index: None,
                 previous_log_term: None,
                 leader_commit_index: None,
@@ -62,7 +104,7 @@ pub(crate) trait ServiceRequestFactory: Send + Sync {
                      previous_log_index: Option<u64>,
                      previous_log_term: Option<u64>,
                      leader_commit_index: Option<u64>,
-                     entry: Option<Entry>,
+                     entries: Vec<Entry>,
     ) -> ServiceRequest<AppendEntries, ()> {
         let correlation_id_generator = RandomCorrelationIdGenerator::new();
         let correlation_id = correlation_id_generator.generate();
@@ -72,7 +114,7 @@ pub(crate) trait ServiceRequestFactory: Send + Sync {
                 term,
                 leader_id,
                 correlation_id,
-                entry,
+                entries,
                 previous_log_index,
                 previous_log_term,
                 leader_commit_index,
@@ -82,18 +124,74 @@ pub(crate) trait ServiceRequestFactory: Send + Sync {
         );
     }
 
-    fn replicate_log_response(&self, term: u64, success: bool, log_entry_index: Option<u64>, correlation_id: CorrelationId) -> ServiceRequest<AppendEntriesResponse, ()> {
+    fn replicate_log_response(
+        &self,
+        term: u64,
+        success: bool,
+        log_entry_index: Option<u64>,
+        conflict_term: Option<u64>,
+        conflict_index: Option<u64>,
+        correlation_id: CorrelationId,
+    ) -> ServiceRequest<AppendEntriesResponse, ()> {
         return ServiceRequest::new(
             AppendEntriesResponse {
                 term,
                 success,
                 log_entry_index,
+                conflict_term,
+                conflict_index,
                 correlation_id
             },
             Box::new(ReplicateLogResponseClient {}),
             correlation_id,
         );
     }
+
+    fn install_snapshot(&self, term: u64, leader_id: ReplicaId, snapshot: &Snapshot) -> ServiceRequest<InstallSnapshot, InstallSnapshotResponse> {
+        let correlation_id_generator = RandomCorrelationIdGenerator::new();
+        let correlation_id = correlation_id_generator.generate();
+
+        return ServiceRequest::new(
+            InstallSnapshot {
+                term,
+                leader_id,
+                correlation_id,
+                last_included_index: snapshot.get_last_included_index(),
+                last_included_term: snapshot.get_last_included_term(),
+                data: snapshot.get_data_as_vec(),
+                client_sessions: snapshot.get_client_sessions_as_vec(),
+                membership: snapshot.get_membership_as_vec(),
+            },
+            Box::new(InstallSnapshotClient {}),
+            correlation_id,
+        );
+    }
+
+    fn timeout_now(&self, term: u64, leader_id: ReplicaId) -> ServiceRequest<TimeoutNow, ()> {
+        let correlation_id_generator = RandomCorrelationIdGenerator::new();
+        let correlation_id = correlation_id_generator.generate();
+
+        return ServiceRequest::new(
+            TimeoutNow {
+                term,
+                leader_id,
+                correlation_id,
+            },
+            Box::new(TimeoutNowClient {}),
+            correlation_id,
+        );
+    }
+
+    fn execute(&self, command: Command) -> ServiceRequest<Command, CommandResponse> {
+        let correlation_id_generator = RandomCorrelationIdGenerator::new();
+        let correlation_id = correlation_id_generator.generate();
+
+        return ServiceRequest::new(
+            command,
+            Box::new(ExecuteClient {}),
+            correlation_id,
+        );
+    }
 }
 
 pub(crate) struct BuiltInServiceRequestFactory {}
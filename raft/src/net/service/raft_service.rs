@@ -1,46 +1,306 @@
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
 
+use bytes::Bytes;
+use prost::Message;
 use tokio::sync::mpsc;
+use tokio::time::Instant;
 use tonic::{Request, Response};
 
 use replicate::callback::quorum_completion_response::QuorumCompletionResponse;
 use replicate::callback::single_response_completion_callback::SingleResponseCompletionCallback;
 use replicate::clock::clock::Clock;
 use replicate::net::connect::async_network::AsyncNetwork;
+use replicate::net::connect::host_and_port::HostAndPort;
 use replicate::net::connect::host_port_extractor::HostAndPortExtractor;
+use replicate::net::replica::ReplicaId;
 use replicate::net::request_waiting_list::request_waiting_list::RequestWaitingList;
 use replicate::net::request_waiting_list::request_waiting_list_config::RequestWaitingListConfig;
 
+use crate::client_sessions::ClientSessionError;
+use crate::election::election::Election;
 use crate::follower_state::FollowerState;
+use crate::log_entry::{LogEntry, LogEntryType};
+use crate::membership::Membership;
 use crate::net::factory::service_request::{BuiltInServiceRequestFactory, ServiceRequestFactory};
-use crate::net::rpc::grpc::{AppendEntries, AppendEntriesResponse, Command, RequestVote, RequestVoteResponse};
+use crate::net::rpc::grpc::{AppendEntries, AppendEntriesResponse, Command, CommandResponse, InstallSnapshot, InstallSnapshotResponse, Member, NotLeader, PreVote, PreVoteResponse, Query, QueryResponse, RequestVote, RequestVoteResponse, TimeoutNow, TransferLeadership};
 use crate::net::rpc::grpc::raft_server::Raft;
+use crate::replication_config::ReplicationConfig;
 use crate::state::{ReplicaRole, State};
+use crate::storage::snapshot_storage::Snapshot;
+
+/// The output of applying a committed command, along with the term of its entry.
+/// The waiter of a command matches the entry by its index, the term tells whether the entry is still the command.
+#[derive(Debug)]
+struct AppliedCommand {
+    term: u64,
+    result: Vec<u8>,
+}
 
 pub struct RaftService {
     state: Arc<State>,
     service_request_factory: Arc<dyn ServiceRequestFactory>,
     follower_state: Arc<FollowerState>,
     pending_committed_log_entries: Arc<RequestWaitingList>,
+    leadership_transfer_in_progress: AtomicBool,
+    forward_to_leader: bool,
 }
 
 impl RaftService {
+    /// A service configured by the `RaftConfig` that the state was built with.
     pub fn new(state: Arc<State>, clock: Arc<dyn Clock>) -> Self {
+        let replication_config = state.get_raft_config().get_replication_config();
+        let request_waiting_list_config = state.get_raft_config().get_request_waiting_list_config();
+        return Self::new_with(state, clock, replication_config, request_waiting_list_config);
+    }
+
+    fn new_with(state: Arc<State>,
+                clock: Arc<dyn Clock>,
+                replication_config: ReplicationConfig,
+                request_waiting_list_config: RequestWaitingListConfig) -> Self {
         let inner_state = state.clone();
         let service_request_factory = Arc::new(BuiltInServiceRequestFactory::new());
         let inner_service_request_factory = service_request_factory.clone();
+        let forward_to_leader = replication_config.is_forwarding_to_leader_enabled();
+        let follower_state = Arc::new(FollowerState::new(inner_state, inner_service_request_factory, replication_config));
+
+        //a new leader replicates its no-op entry right away
+        let leader_follower_state = Arc::downgrade(&follower_state);
+        state.on_leader_elected(move || {
+            if let Some(follower_state) = leader_follower_state.upgrade() {
+                follower_state.reset_progress();
+                follower_state.replicate_log();
+            }
+        });
 
         return RaftService {
             state,
             service_request_factory,
-            follower_state: Arc::new(FollowerState::new(inner_state, inner_service_request_factory)),
+            follower_state,
             pending_committed_log_entries: Arc::new(
                 RequestWaitingList::new(
                     clock,
-                    RequestWaitingListConfig::default(),
+                    request_waiting_list_config,
                 )
             ),
+            leadership_transfer_in_progress: AtomicBool::new(false),
+            forward_to_leader,
+        };
+    }
+
+    const LEADERSHIP_TRANSFER_POLL_INTERVAL: Duration = Duration::from_millis(5);
+    const READ_INDEX_POLL_INTERVAL: Duration = Duration::from_millis(5);
+    const MEMBERSHIP_CHANGE_POLL_INTERVAL: Duration = Duration::from_millis(5);
+    const MEMBERSHIP_CHANGE_TIMEOUT_IN_HEARTBEAT_TIMEOUTS: u32 = 10;
+
+    /// Brings the target up to date and asks it to start an election, all within a heartbeat timeout (the election timeout).
+    async fn transfer_leadership_to(&self, target: HostAndPort) -> Result<(), tonic::Status> {
+        let timeout = self.state.get_heartbeat_config().get_heartbeat_timeout();
+        let replication_interval = self.state.get_heartbeat_config().get_heartbeat_interval();
+        let started_at = Instant::now();
+
+        let mut last_replicated_at: Option<Instant> = None;
+        while !self.follower_state.is_caught_up(&target) {
+            if self.state.get_role() != ReplicaRole::Leader {
+                return Err(tonic::Status::failed_precondition("lost the leadership while transferring it"));
+            }
+            if started_at.elapsed() >= timeout {
+                return Err(tonic::Status::deadline_exceeded(format!("{:?} did not catch up with the leader's log within {:?}", target, timeout)));
+            }
+            let replication_due = match last_replicated_at {
+                None => true,
+                Some(replicated_at) => replicated_at.elapsed() >= replication_interval
+            };
+            if replication_due {
+                self.follower_state.replicate_log_to_peer(&target);
+                last_replicated_at = Some(Instant::now());
+            }
+            tokio::time::sleep(Self::LEADERSHIP_TRANSFER_POLL_INTERVAL).await;
+        }
+
+        let send_result = AsyncNetwork::send_with_source_footprint(
+            self.service_request_factory.timeout_now(self.state.get_term(), self.state.get_replica_reference().get_id()),
+            self.state.get_replica_reference().get_self_address(),
+            target,
+        ).await;
+        if send_result.is_err() {
+            return Err(tonic::Status::unavailable(format!("failed to send timeout_now to {:?}", target)));
+        }
+
+        while self.state.get_role() == ReplicaRole::Leader {
+            if started_at.elapsed() >= timeout {
+                return Err(tonic::Status::deadline_exceeded(format!("{:?} did not take over the leadership within {:?}", target, timeout)));
+            }
+            tokio::time::sleep(Self::LEADERSHIP_TRANSFER_POLL_INTERVAL).await;
+        }
+        return Ok(());
+    }
+
+    /// Appends the next membership on the leader, one membership change at a time, and waits for it to be committed.
+    /// A joint membership (C_old,new) is done once the membership it moves to (C_new) is committed.
+    /// The status of a rejected change is boxed, `tonic::Status` is too large to pass around in a `Result`.
+    async fn change_membership<F>(&self, next_membership_for: F) -> Result<(), tonic::Status>
+        where F: FnOnce(&Membership) -> Result<Membership, Box<tonic::Status>> + Send + 'static {
+        let state = self.state.clone();
+        let follower_state = self.follower_state.clone();
+
+        let (sender, mut receiver) = mpsc::channel(1);
+        let handler = async move {
+            let result = Self::membership_change_precondition(&state)
+                .and_then(|membership| next_membership_for(&membership))
+                .map(|next_membership| {
+                    let index = state.get_replicated_log().append_membership(&next_membership, state.get_term());
+                    state.refresh_membership();
+                    follower_state.replicate_log();
+                    index
+                });
+            let _ = sender.send(result).await;
+        };
+        let _ = self.state.get_replica_reference().add_async_to_queue(handler).await;
+        let next_membership_index = match receiver.recv().await {
+            None => return Err(tonic::Status::unknown("failed receiving the index of the next membership from the async handler")),
+            Some(result) => result.map_err(|status| *status)?
+        };
+
+        let timeout = self.state.get_heartbeat_config().get_heartbeat_timeout() * Self::MEMBERSHIP_CHANGE_TIMEOUT_IN_HEARTBEAT_TIMEOUTS;
+        let replication_interval = self.state.get_heartbeat_config().get_heartbeat_interval();
+        let started_at = Instant::now();
+        let mut last_replicated_at = Instant::now();
+        loop {
+            let replicated_log = self.state.get_replicated_log();
+            if let Some((index, membership)) = replicated_log.get_membership() {
+                let committed = replicated_log.get_commit_index().is_some_and(|commit_index| commit_index >= index);
+                if index >= next_membership_index && !membership.is_joint() && committed {
+                    return Ok(());
+                }
+            }
+            if self.state.get_role() != ReplicaRole::Leader {
+                return Err(tonic::Status::unavailable("lost the leadership while changing the membership"));
+            }
+            if started_at.elapsed() >= timeout {
+                return Err(tonic::Status::deadline_exceeded(format!("membership change was not committed within {:?}", timeout)));
+            }
+            //lagging voters catch up only when the log is replicated
+            if last_replicated_at.elapsed() >= replication_interval {
+                self.follower_state.replicate_log();
+                last_replicated_at = Instant::now();
+            }
+            tokio::time::sleep(Self::MEMBERSHIP_CHANGE_POLL_INTERVAL).await;
+        }
+    }
+
+    fn membership_change_precondition(state: &Arc<State>) -> Result<Membership, Box<tonic::Status>> {
+        if state.get_role() != ReplicaRole::Leader {
+            return Err(Box::new(tonic::Status::failed_precondition("membership can only be changed by the leader")));
+        }
+        let replicated_log = state.get_replicated_log();
+        if let Some((index, membership)) = replicated_log.get_membership() {
+            let committed = replicated_log.get_commit_index().is_some_and(|commit_index| commit_index >= index);
+            if membership.is_joint() || !committed {
+                return Err(Box::new(tonic::Status::failed_precondition("membership change is already in progress")));
+            }
+        }
+        return Ok(state.get_membership());
+    }
+
+    /// Once the joint membership (C_old,new) is committed, the leader appends the membership it moves to (C_new).
+    /// Once that is committed, a leader that is not one of its voters steps down.
+    fn advance_membership_change(state: &Arc<State>, follower_state: &Arc<FollowerState>) {
+        let replicated_log = state.get_replicated_log();
+        let (index, membership) = match replicated_log.get_membership() {
+            None => return,
+            Some(index_membership) => index_membership
+        };
+        let committed = replicated_log.get_commit_index().is_some_and(|commit_index| commit_index >= index);
+        if !committed || state.get_role() != ReplicaRole::Leader {
+            return;
+        }
+        if membership.is_joint() {
+            replicated_log.append_membership(&membership.complete(), state.get_term());
+            state.refresh_membership();
+            follower_state.replicate_log();
+            return;
+        }
+        if !state.is_voter() {
+            println!("stepping down, the leader is not a voter of the committed membership");
+            state.clone().change_to_follower(state.get_term());
+        }
+    }
+
+    fn voter_address(member: Member) -> Result<HostAndPort, Box<tonic::Status>> {
+        return match u16::try_from(member.port) {
+            Err(_) => Err(Box::new(tonic::Status::invalid_argument(format!("invalid port {}", member.port)))),
+            Ok(port) => HostAndPort::try_new(&member.host, port)
+                .map_err(|_| Box::new(tonic::Status::invalid_argument(format!("invalid host {}", member.host))))
+        };
+    }
+
+    /// A command that its client session can not answer fails with `OutOfRange` if the client has moved past the command's sequence,
+    /// and with `NotFound` if the session has expired, the client opens a new session with sequence 0.
+    fn client_session_status(err: ClientSessionError) -> tonic::Status {
+        return match err {
+            ClientSessionError::StaleSequence(..) => tonic::Status::out_of_range(err.to_string()),
+            ClientSessionError::SessionExpired(..) => tonic::Status::not_found(err.to_string()),
+        };
+    }
+
+    /// Forwards the command to the leader and relays its result back, if forwarding is enabled and the leader is known.
+    /// Otherwise, rejects the command with the leader's address.
+    /// A forwarded command carries the footprint of the forwarding replica, and is never forwarded again:
+    /// replicas with stale views of the leader would otherwise bounce it between themselves.
+    async fn forward_or_reject(&self, command: Command, forwarded: bool) -> Result<Response<CommandResponse>, tonic::Status> {
+        let self_address = self.state.get_replica_reference().get_self_address();
+        let leader_address = self.state.get_leader_address().filter(|leader_address| *leader_address != self_address);
+        if let (true, false, Some(leader_address)) = (self.forward_to_leader, forwarded, leader_address) {
+            println!("forwarding command to the leader {:?}", leader_address);
+            return match AsyncNetwork::send_with_source_footprint(self.service_request_factory.execute(command), self_address, leader_address).await {
+                Ok(command_response) => Ok(Response::new(command_response)),
+                Err(err) => match err.downcast::<tonic::Status>() {
+                    Ok(status) => Err(*status),
+                    Err(err) => Err(tonic::Status::unavailable(format!("failed forwarding the command to the leader {:?}: {}", leader_address, err)))
+                }
+            };
+        }
+        return Err(NotLeader::new(self.state.get_leader_id(), self.state.get_leader_address()).into_status());
+    }
+
+    /// Waits (at most a heartbeat timeout) for the leader to commit an entry of its current term, its no-op entry at the latest.
+    /// Until then, the leader's commit index may be behind the cluster's and a read at it may miss committed writes.
+    async fn wait_until_committed_in_current_term(&self) -> Result<(), tonic::Status> {
+        let timeout = self.state.get_heartbeat_config().get_heartbeat_timeout();
+        let started_at = Instant::now();
+        loop {
+            let term = self.state.get_term();
+            if self.state.get_replicated_log().is_committed_in_term(term) {
+                return Ok(());
+            }
+            if started_at.elapsed() >= timeout {
+                return Err(tonic::Status::unavailable(format!("leader has not committed an entry of its term {} within {:?}", term, timeout)));
+            }
+            tokio::time::sleep(Self::READ_INDEX_POLL_INTERVAL).await;
+        }
+    }
+
+    /// Waits (at most a heartbeat timeout) for the state machine to apply all the entries up to the read index.
+    async fn wait_until_applied(&self, read_index: Option<u64>) -> Result<(), tonic::Status> {
+        let read_index = match read_index {
+            None => return Ok(()),
+            Some(read_index) => read_index
         };
+        let timeout = self.state.get_heartbeat_config().get_heartbeat_timeout();
+        let started_at = Instant::now();
+        loop {
+            if let Some(last_applied) = self.state.get_last_applied() {
+                if last_applied >= read_index {
+                    return Ok(());
+                }
+            }
+            if started_at.elapsed() >= timeout {
+                return Err(tonic::Status::deadline_exceeded(format!("log entries up to the read index {} were not applied within {:?}", read_index, timeout)));
+            }
+            tokio::time::sleep(Self::READ_INDEX_POLL_INTERVAL).await;
+        }
     }
 }
 
@@ -60,17 +320,33 @@ impl Raft for RaftService {
         let handler = async move {
             let term = state.get_term();
             let role = state.get_role();
-            let voted: bool = if request.term > term && role != ReplicaRole::Leader && state.has_not_voted_for_or_matches(request.replica_id) {
+            //a replica that has recently heard from the leader ignores the candidate (thesis §4.2.3), a removed voter or a partitioned replica
+            //can not disrupt the leader with its higher term. The leader itself asks for the election in a leadership transfer,
+            //and the leader that runs another election itself has nothing to be protected from.
+            let has_live_leader = !request.leadership_transfer
+                && state.get_leader_id() != Some(request.replica_id)
+                && state.has_recently_heard_from_leader();
+            if request.term > term && role != ReplicaRole::Leader && !has_live_leader {
+                state.clone().change_to_follower(request.term);
+            }
+            let voted: bool = if request.term > term
+                && role != ReplicaRole::Leader
+                && role != ReplicaRole::Learner
+                && !has_live_leader
+                && state.has_not_voted_for_or_matches(request.replica_id)
+                && state.get_replicated_log().is_not_more_up_to_date_than(request.last_log_index, request.last_log_term) {
                 true
             } else {
                 false
             };
             if voted {
+                state.clone().change_to_follower(request.term);
                 state.voted_for(request.replica_id);
+                state.mark_heartbeat_received();
             }
 
             let send_result = AsyncNetwork::send_with_source_footprint(
-                service_request_factory.request_vote_response(term, voted, correlation_id),
+                service_request_factory.request_vote_response(state.get_term(), voted, correlation_id),
                 source_address,
                 originating_host_port,
             ).await;
@@ -92,11 +368,55 @@ impl Raft for RaftService {
         return Ok(Response::new(()));
     }
 
+    async fn acknowledge_pre_vote(&self, request: Request<PreVote>) -> Result<Response<()>, tonic::Status> {
+        let originating_host_port = request.try_referral_host_port()?;
+
+        let state = self.state.clone();
+        let request = request.into_inner();
+        let correlation_id = request.correlation_id;
+        let replica = self.state.get_replica();
+        let source_address = replica.get_self_address();
+        let service_request_factory = self.service_request_factory.clone();
+
+        println!("received PreVote with term {}", request.term);
+        let handler = async move {
+            let term = state.get_term();
+            let role = state.get_role();
+            let voted: bool = request.term > term
+                && role != ReplicaRole::Leader
+                && role != ReplicaRole::Learner
+                && !state.has_live_leader()
+                && state.get_replicated_log().is_not_more_up_to_date_than(request.last_log_index, request.last_log_term);
+
+            let send_result = AsyncNetwork::send_with_source_footprint(
+                service_request_factory.pre_vote_response(term, voted, correlation_id),
+                source_address,
+                originating_host_port,
+            ).await;
+
+            if send_result.is_err() {
+                eprintln!("failed to send pre_vote_response to {:?}", originating_host_port);
+            }
+        };
+        let _ = replica.add_async_to_queue(handler).await;
+        return Ok(Response::new(()));
+    }
+
+    async fn finish_pre_vote(&self, request: Request<PreVoteResponse>) -> Result<Response<()>, tonic::Status> {
+        let originating_host_port = request.try_referral_host_port()?;
+        let response = request.into_inner();
+        println!("received PreVoteResponse with voted? {}", response.voted);
+
+        let _ = &self.state.get_replica_reference().register_response(response.correlation_id, originating_host_port, Ok(Box::new(response)));
+        return Ok(Response::new(()));
+    }
+
     async fn acknowledge_heartbeat(&self, request: Request<AppendEntries>) -> Result<Response<AppendEntriesResponse>, tonic::Status> {
         println!("received heartbeat on {:?}", self.state.get_replica_reference().get_self_address());
         let state = self.state.clone();
         let replica = self.state.get_replica_reference();
 
+        let leader_address = request.try_referral_host_port().ok();
         let append_entries = request.into_inner();
 
         let (sender, mut receiver) = mpsc::channel::<AppendEntriesResponse>(1);
@@ -104,20 +424,26 @@ impl Raft for RaftService {
             state.mark_heartbeat_received();
             let term = state.get_term();
             if append_entries.term > term {
-                state.change_to_follower(append_entries.term);
+                state.clone().change_to_follower(append_entries.term);
+                state.mark_leader(append_entries.leader_id, leader_address);
                 let _ = sender.send(AppendEntriesResponse {
                     success: true,
                     term: append_entries.term,
                     correlation_id: append_entries.correlation_id,
                     log_entry_index: None,
+                    conflict_term: None,
+                    conflict_index: None,
                 }).await;
             }
             if append_entries.term == term {
+                state.mark_leader(append_entries.leader_id, leader_address);
                 let _ = sender.send(AppendEntriesResponse {
                     success: true,
                     term,
                     correlation_id: append_entries.correlation_id,
                     log_entry_index: None,
+                    conflict_term: None,
+                    conflict_index: None,
                 }).await;
             }
             let _ = sender.send(AppendEntriesResponse {
@@ -125,6 +451,8 @@ impl Raft for RaftService {
                 term,
                 correlation_id: append_entries.correlation_id,
                 log_entry_index: None,
+                conflict_term: None,
+                conflict_index: None,
             }).await;
         };
 
@@ -154,6 +482,9 @@ impl Raft for RaftService {
             if append_entries.term > term {
                 state.clone().change_to_follower(append_entries.term);
             }
+            if append_entries.term >= term {
+                state.mark_leader(append_entries.leader_id, Some(originating_host_port));
+            }
             let success;
             if term > append_entries.term {
                 success = false;
@@ -165,19 +496,43 @@ impl Raft for RaftService {
                 success = true;
             };
 
-            let log_entry_index = if success {
+            let (log_entry_index, conflict_term, conflict_index) = if success {
                 let replicated_log = state.get_replicated_log();
-                let entry = append_entries.entry.unwrap();
-                let command = entry.command.unwrap();
-
-                replicated_log.append_command(&command, append_entries.term);
-                replicated_log.maybe_advance_commit_index_to(append_entries.leader_commit_index);
-                Some(entry.index)
+                let last_log_entry_index = append_entries.entries
+                    .last()
+                    .map(|entry| entry.index)
+                    .or(append_entries.previous_log_index);
+
+                let log_entries = append_entries.entries
+                    .into_iter()
+                    .map(|entry| {
+                        LogEntry::new(entry.term, entry.index, &entry.command.unwrap())
+                            .with_entry_type(LogEntryType::from(entry.entry_type))
+                            .with_time(entry.time)
+                    })
+                    .collect();
+
+                replicated_log.append_log_entries(log_entries);
+                state.refresh_membership();
+                replicated_log.maybe_advance_commit_index_to(append_entries.leader_commit_index, last_log_entry_index);
+                state.apply_committed_log_entries(|_, _, _| {});
+                (last_log_entry_index, None, None)
+            } else if term > append_entries.term {
+                (None, None, None)
             } else {
-                None
+                let previous_log_index = append_entries.previous_log_index.unwrap();
+                let (conflict_term, conflict_index) = state.get_replicated_log().conflict_term_index_at(previous_log_index);
+                (None, conflict_term, Some(conflict_index))
             };
             let send_result = AsyncNetwork::send_with_source_footprint(
-                service_request_factory.replicate_log_response(term, success, log_entry_index, append_entries.correlation_id),
+                service_request_factory.replicate_log_response(
+                    term,
+                    success,
+                    log_entry_index,
+                    conflict_term,
+                    conflict_index,
+                    append_entries.correlation_id,
+                ),
                 state.get_replica_reference().get_self_address(),
                 originating_host_port,
             ).await;
@@ -207,20 +562,24 @@ impl Raft for RaftService {
             }
             let replica_role = state.get_role();
             if replica_role == ReplicaRole::Leader {
-                if response.success {
-                    let replicated_log = state.get_replicated_log();
-                    let log_entry_index = response.log_entry_index.unwrap() as usize;
-
-                    replicated_log.acknowledge_log_entry_at(log_entry_index);
-                    if replicated_log.is_entry_replicated(log_entry_index) {
-                        replicated_log.commit(|commit_index| {
+                if response.success && response.log_entry_index.is_some() {
+                    follower_state.clone().register(response, originating_host_port);
+                    //only an entry of the current term is committed by counting its replicas, the earlier entries are committed along with it
+                    if let Some(majority_match_index) = follower_state.majority_match_index() {
+                        state.get_replicated_log().commit(majority_match_index, state.get_term());
+                        state.apply_committed_log_entries(|applied_index, applied_term, output| {
                             pending_committed_log_entries.handle_response(
-                                commit_index,
+                                applied_index,
                                 state.get_replica_reference().get_self_address(),
-                                Ok(Box::new(()))
+                                match output {
+                                    Ok(result) => Ok(Box::new(AppliedCommand { term: applied_term, result })),
+                                    Err(err) => Err(Box::new(Self::client_session_status(err))),
+                                }
                             );
                         });
+                        Self::advance_membership_change(&state, &follower_state);
                     }
+                    return;
                 }
                 follower_state.register(response, originating_host_port);
             }
@@ -230,58 +589,303 @@ impl Raft for RaftService {
         return Ok(Response::new(()));
     }
 
-    async fn execute(&self, request: Request<Command>) -> Result<Response<()>, tonic::Status> {
+    async fn install_snapshot(&self, request: Request<InstallSnapshot>) -> Result<Response<InstallSnapshotResponse>, tonic::Status> {
+        println!("received install_snapshot on {:?}", self.state.get_replica_reference().get_self_address());
+        let state = self.state.clone();
+        let replica = self.state.get_replica_reference();
+
+        let leader_address = request.try_referral_host_port().ok();
+        let install_snapshot = request.into_inner();
+        let snapshot = Snapshot::new(install_snapshot.last_included_index, install_snapshot.last_included_term, install_snapshot.data)
+            .with_client_sessions(install_snapshot.client_sessions)
+            .with_encoded_membership(&install_snapshot.membership)
+            .ok_or_else(|| tonic::Status::invalid_argument("snapshot membership can not be decoded"))?;
+
+        let (sender, mut receiver) = mpsc::channel::<InstallSnapshotResponse>(1);
+        let handler = async move {
+            let term = state.get_term();
+            if term > install_snapshot.term {
+                let _ = sender.send(InstallSnapshotResponse {
+                    term,
+                    success: false,
+                    correlation_id: install_snapshot.correlation_id,
+                }).await;
+                return;
+            }
+
+            state.mark_heartbeat_received();
+            if install_snapshot.term > term {
+                state.clone().change_to_follower(install_snapshot.term);
+            }
+            state.mark_leader(install_snapshot.leader_id, leader_address);
+            state.install_snapshot(snapshot);
+            state.refresh_membership();
+            let _ = sender.send(InstallSnapshotResponse {
+                term: install_snapshot.term,
+                success: true,
+                correlation_id: install_snapshot.correlation_id,
+            }).await;
+        };
+
+        let _ = replica.add_async_to_queue(handler).await;
+        return match receiver.recv().await {
+            Some(install_snapshot_response) =>
+                Ok(Response::new(install_snapshot_response)),
+            None =>
+                Err(tonic::Status::unknown("failed receiving InstallSnapshotResponse from the async handler"))
+        };
+    }
+
+    async fn execute(&self, request: Request<Command>) -> Result<Response<CommandResponse>, tonic::Status> {
         println!("received command on {:?}", self.state.get_replica_reference().get_self_address());
+        if self.leadership_transfer_in_progress.load(Ordering::SeqCst) {
+            return Err(tonic::Status::unavailable("leadership transfer is in progress, not accepting commands"));
+        }
         let state = self.state.clone();
         let replica = self.state.get_replica_reference();
+        let forwarded = request.try_referral_host_port().is_ok();
         let command = request.into_inner();
+        let inner_command = command.clone();
         let follower_state = self.follower_state.clone();
 
         let (sender, mut receiver) = mpsc::channel(1);
         let handler = async move {
+            if state.get_role() != ReplicaRole::Leader {
+                let _ = sender.send(None).await;
+                return;
+            }
             let term: u64 = state.get_term();
-            let index = state.get_replicated_log().append_command(&command, term);
-            let _ = follower_state.replicate_log();
-            let _ = sender.send(index).await;
+            let index = state.get_replicated_log().append_command_at(&inner_command, term, state.get_log_time());
+            follower_state.replicate_log();
+            let _ = sender.send(Some((index, term))).await;
         };
 
         let _ = replica.add_async_to_queue(handler).await;
-        let entry_index = receiver.recv().await.unwrap();
-        let response_callback = SingleResponseCompletionCallback::<()>::new();
+        let (entry_index, entry_term) = match receiver.recv().await {
+            None => return Err(tonic::Status::unknown("failed receiving the index of the command from the async handler")),
+            Some(None) => return self.forward_or_reject(command, forwarded).await,
+            Some(Some(entry_index_term)) => entry_index_term
+        };
+        let response_callback = SingleResponseCompletionCallback::<AppliedCommand>::new();
 
         self.pending_committed_log_entries.add(entry_index,
                                                self.state.get_replica_reference().get_self_address(),
                                                response_callback.clone());
 
         return match response_callback.handle().await {
-            QuorumCompletionResponse::Success(_) =>
-                Ok(Response::new(())),
+            QuorumCompletionResponse::Success(applied_commands) => {
+                let applied_command = applied_commands.into_values().next().unwrap();
+                //a later leader has replaced the entry of the command at its index, the command itself was never committed
+                if applied_command.term != entry_term {
+                    return Err(NotLeader::new(self.state.get_leader_id(), self.state.get_leader_address()).into_status());
+                }
+                Ok(Response::new(CommandResponse { result: applied_command.result }))
+            }
+            QuorumCompletionResponse::Error(errors) => match errors.into_values().next().map(|err| err.downcast::<tonic::Status>()) {
+                Some(Ok(status)) => Err(*status),
+                _ => Err(tonic::Status::unknown(format!("failed receiving the response of command execution for raft log entry index {}", entry_index))),
+            },
             _ =>
                 Err(tonic::Status::unknown(format!("failed receiving the response of command execution for raft log entry index {}", entry_index))),
         };
     }
+
+    async fn read_index(&self, request: Request<Query>) -> Result<Response<QueryResponse>, tonic::Status> {
+        println!("received read_index on {:?}", self.state.get_replica_reference().get_self_address());
+        if self.state.get_role() != ReplicaRole::Leader {
+            return Err(tonic::Status::failed_precondition("reads are served only by the leader"));
+        }
+        let query = request.into_inner();
+        self.wait_until_committed_in_current_term().await?;
+        let read_index = self.state.get_replicated_log().get_commit_index();
+        if !self.state.clone().confirm_leadership().await {
+            return Err(tonic::Status::unavailable("failed to confirm the leadership with a majority of the replicas"));
+        }
+        self.wait_until_applied(read_index).await?;
+
+        return Ok(Response::new(QueryResponse { result: self.state.read_state_machine(&query.query) }));
+    }
+
+    async fn lease_read(&self, request: Request<Query>) -> Result<Response<QueryResponse>, tonic::Status> {
+        println!("received lease_read on {:?}", self.state.get_replica_reference().get_self_address());
+        if !self.state.is_leader_lease_enabled() {
+            return Err(tonic::Status::failed_precondition("leader lease is not enabled"));
+        }
+        if self.state.get_role() != ReplicaRole::Leader {
+            return Err(tonic::Status::failed_precondition("reads are served only by the leader"));
+        }
+        if !self.state.has_leader_lease() {
+            return Err(tonic::Status::unavailable("leader does not hold the lease"));
+        }
+        let query = request.into_inner();
+        self.wait_until_committed_in_current_term().await?;
+        let read_index = self.state.get_replicated_log().get_commit_index();
+        self.wait_until_applied(read_index).await?;
+        //the lease may have expired while waiting
+        if !self.state.has_leader_lease() {
+            return Err(tonic::Status::unavailable("leader lease expired before the read"));
+        }
+
+        return Ok(Response::new(QueryResponse { result: self.state.read_state_machine(&query.query) }));
+    }
+
+    async fn transfer_leadership(&self, request: Request<TransferLeadership>) -> Result<Response<()>, tonic::Status> {
+        let request = request.into_inner();
+        let target = match u16::try_from(request.target_port) {
+            Err(_) => return Err(tonic::Status::invalid_argument(format!("invalid target port {}", request.target_port))),
+            Ok(target_port) => HostAndPort::try_new(&request.target_host, target_port)
+                .map_err(|_| tonic::Status::invalid_argument(format!("invalid target host {}", request.target_host)))?
+        };
+        println!("received transfer_leadership to {:?}", target);
+
+        if self.state.get_role() != ReplicaRole::Leader {
+            return Err(tonic::Status::failed_precondition("leadership can only be transferred by the leader"));
+        }
+        if !self.state.get_replica_reference().get_peers().contains(&target) {
+            return Err(tonic::Status::invalid_argument(format!("{:?} is not a peer", target)));
+        }
+        if !self.state.get_membership().is_voter(&target) {
+            return Err(tonic::Status::invalid_argument(format!("{:?} is not a voter", target)));
+        }
+        if self.leadership_transfer_in_progress.swap(true, Ordering::SeqCst) {
+            return Err(tonic::Status::failed_precondition("leadership transfer is already in progress"));
+        }
+
+        let result = self.transfer_leadership_to(target).await;
+        self.leadership_transfer_in_progress.store(false, Ordering::SeqCst);
+        return result.map(|_| Response::new(()));
+    }
+
+    async fn timeout_now(&self, request: Request<TimeoutNow>) -> Result<Response<()>, tonic::Status> {
+        println!("received timeout_now on {:?}", self.state.get_replica_reference().get_self_address());
+        let state = self.state.clone();
+        let replica = self.state.get_replica_reference();
+        let timeout_now = request.into_inner();
+
+        let handler = async move {
+            if timeout_now.term >= state.get_term() && state.get_role() != ReplicaRole::Leader {
+                Election::new(state).start_for_leadership_transfer();
+            }
+        };
+        let _ = replica.add_async_to_queue(handler).await;
+        return Ok(Response::new(()));
+    }
+
+    async fn add_voter(&self, request: Request<Member>) -> Result<Response<()>, tonic::Status> {
+        let voter = Self::voter_address(request.into_inner()).map_err(|status| *status)?;
+        println!("received add_voter {:?}", voter);
+
+        return self.change_membership(move |membership| {
+            if membership.is_voter(&voter) {
+                return Err(Box::new(tonic::Status::already_exists(format!("{:?} is already a voter", voter))));
+            }
+            return Ok(membership.with_voter(voter));
+        }).await.map(|_| Response::new(()));
+    }
+
+    async fn remove_voter(&self, request: Request<Member>) -> Result<Response<()>, tonic::Status> {
+        let voter = Self::voter_address(request.into_inner()).map_err(|status| *status)?;
+        println!("received remove_voter {:?}", voter);
+
+        return self.change_membership(move |membership| {
+            if !membership.is_voter(&voter) {
+                return Err(Box::new(tonic::Status::not_found(format!("{:?} is not a voter", voter))));
+            }
+            if membership.get_voters().len() == 1 {
+                return Err(Box::new(tonic::Status::failed_precondition("the last voter can not be removed")));
+            }
+            return Ok(membership.without_voter(&voter));
+        }).await.map(|_| Response::new(()));
+    }
+
+    async fn add_learner(&self, request: Request<Member>) -> Result<Response<()>, tonic::Status> {
+        let learner = Self::voter_address(request.into_inner()).map_err(|status| *status)?;
+        println!("received add_learner {:?}", learner);
+
+        return self.change_membership(move |membership| {
+            if membership.is_voter(&learner) || membership.is_learner(&learner) {
+                return Err(Box::new(tonic::Status::already_exists(format!("{:?} is already a member", learner))));
+            }
+            return Ok(membership.with_learner(learner));
+        }).await.map(|_| Response::new(()));
+    }
+
+    async fn promote_learner(&self, request: Request<Member>) -> Result<Response<()>, tonic::Status> {
+        let learner = Self::voter_address(request.into_inner()).map_err(|status| *status)?;
+        println!("received promote_learner {:?}", learner);
+
+        let follower_state = self.follower_state.clone();
+        return self.change_membership(move |membership| {
+            if !membership.is_learner(&learner) {
+                return Err(Box::new(tonic::Status::not_found(format!("{:?} is not a learner", learner))));
+            }
+            if !follower_state.is_caught_up(&learner) {
+                return Err(Box::new(tonic::Status::failed_precondition(format!("{:?} has not caught up with the leader's log", learner))));
+            }
+            return Ok(membership.promoting(&learner));
+        }).await.map(|_| Response::new(()));
+    }
+}
+
+impl NotLeader {
+    pub(crate) fn new(leader_id: Option<ReplicaId>, leader_address: Option<HostAndPort>) -> Self {
+        return NotLeader {
+            leader_id,
+            leader_host: leader_address.map(|leader_address| leader_address.host_as_string()),
+            leader_port: leader_address.map(|leader_address| leader_address.port() as u32),
+        };
+    }
+
+    /// The NotLeader details of a status returned by a replica that is not the leader.
+    pub fn from_status(status: &tonic::Status) -> Option<Self> {
+        if status.code() != tonic::Code::FailedPrecondition {
+            return None;
+        }
+        return NotLeader::decode(status.details()).ok();
+    }
+
+    pub fn get_leader_address(&self) -> Option<HostAndPort> {
+        return match (&self.leader_host, self.leader_port) {
+            (Some(leader_host), Some(leader_port)) => {
+                let leader_port = u16::try_from(leader_port).ok()?;
+                HostAndPort::try_new(leader_host, leader_port).ok()
+            }
+            _ => None
+        };
+    }
+
+    pub(crate) fn into_status(self) -> tonic::Status {
+        return tonic::Status::with_details(
+            tonic::Code::FailedPrecondition,
+            "not the leader",
+            Bytes::from(self.encode_to_vec()),
+        );
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use std::net::{IpAddr, Ipv4Addr};
     use std::sync::Arc;
+    use std::sync::atomic::Ordering;
     use std::thread;
-    use std::time::Duration;
+    use std::time::{Duration, Instant};
 
     use tokio::runtime::Builder;
-    use tonic::{Request, Response};
+    use tonic::{Code, Request, Response};
 
     use replicate::clock::clock::SystemClock;
     use replicate::net::connect::host_and_port::HostAndPort;
     use replicate::net::connect::host_port_extractor::HostAndPortHeaderAdder;
     use replicate::net::replica::Replica;
 
-    use crate::heartbeat_config::HeartbeatConfig;
-    use crate::net::rpc::grpc::{AppendEntries, AppendEntriesResponse, Command, Entry, RequestVote};
+    use crate::membership::Membership;
     use crate::net::rpc::grpc::raft_server::Raft;
-    use crate::net::service::raft_service::RaftService;
+    use crate::net::rpc::grpc::{AppendEntries, AppendEntriesResponse, Command, Entry, EntryType, InstallSnapshot, InstallSnapshotResponse, Member, NotLeader, Query, RequestVote, TimeoutNow, TransferLeadership};
+    use crate::net::service::raft_service::{AppliedCommand, RaftService};
+    use crate::raft_config::RaftConfig;
     use crate::state::{ReplicaRole, State};
+    use crate::test_support::ControllableClock;
 
     #[test]
     fn acknowledge_request_vote_successfully_voted() {
@@ -297,14 +901,14 @@ mod tests {
         );
 
         let state = runtime.block_on(async move {
-            return State::new(Arc::new(replica), HeartbeatConfig::default());
+            return State::new(Arc::new(replica), RaftConfig::default());
         });
 
         let inner_state = state.clone();
         let _ = runtime.block_on(async move {
             let raft_service = RaftService::new(inner_state.clone(), Arc::new(SystemClock::new()));
 
-            let mut request = Request::new(RequestVote { term: 10, replica_id: 30, correlation_id: 20 });
+            let mut request = Request::new(RequestVote { term: 10, replica_id: 30, correlation_id: 20, last_log_index: None, last_log_term: None, leadership_transfer: false });
             request.add_host_port(self_host_and_port);
 
             let _ = raft_service.acknowledge_request_vote(request).await;
@@ -314,6 +918,38 @@ mod tests {
         assert_eq!(Some(30), state.get_voted_for());
     }
 
+    #[test]
+    fn acknowledge_request_vote_do_not_vote_given_replica_is_a_learner() {
+        let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2060);
+        let peers = vec![HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2061)];
+
+        let runtime = Builder::new_multi_thread().worker_threads(4).enable_all().build().unwrap();
+        let replica = Replica::new(
+            30,
+            self_host_and_port.clone(),
+            peers,
+            Arc::new(SystemClock::new()),
+        );
+
+        let state = runtime.block_on(async move {
+            return State::new_learner(Arc::new(replica), RaftConfig::default());
+        });
+
+        let inner_state = state.clone();
+        let _ = runtime.block_on(async move {
+            let raft_service = RaftService::new(inner_state.clone(), Arc::new(SystemClock::new()));
+
+            let mut request = Request::new(RequestVote { term: 10, replica_id: 40, correlation_id: 20, last_log_index: None, last_log_term: None, leadership_transfer: false });
+            request.add_host_port(self_host_and_port);
+
+            let _ = raft_service.acknowledge_request_vote(request).await;
+        });
+
+        thread::sleep(Duration::from_millis(5));
+        assert_eq!(None, state.get_voted_for());
+        assert_eq!(ReplicaRole::Learner, state.get_role());
+    }
+
     #[test]
     fn acknowledge_request_vote_do_not_vote_given_replica_is_the_leader() {
         let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2060);
@@ -328,7 +964,7 @@ mod tests {
         );
 
         let state = runtime.block_on(async move {
-            let state = State::new(Arc::new(replica), HeartbeatConfig::default());
+            let state = State::new(Arc::new(replica), RaftConfig::default());
             let state_clone = state.clone();
 
             state_clone.change_to_leader();
@@ -339,7 +975,7 @@ mod tests {
         let _ = runtime.block_on(async move {
             let raft_service = RaftService::new(inner_state.clone(), Arc::new(SystemClock::new()));
 
-            let mut request = Request::new(RequestVote { term: 10, replica_id: 30, correlation_id: 20 });
+            let mut request = Request::new(RequestVote { term: 10, replica_id: 30, correlation_id: 20, last_log_index: None, last_log_term: None, leadership_transfer: false });
             request.add_host_port(self_host_and_port);
 
             let _ = raft_service.acknowledge_request_vote(request).await;
@@ -350,7 +986,7 @@ mod tests {
     }
 
     #[test]
-    fn acknowledge_request_vote_do_not_vote_given_replica_has_already_voted() {
+    fn acknowledge_request_vote_successfully_voted_given_replica_has_voted_in_an_earlier_term() {
         let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2060);
         let peers = vec![HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2061)];
 
@@ -363,7 +999,7 @@ mod tests {
         );
 
         let state = runtime.block_on(async move {
-            let state = State::new(Arc::new(replica), HeartbeatConfig::default());
+            let state = State::new(Arc::new(replica), RaftConfig::default());
             let state_clone = state.clone();
 
             state_clone.voted_for(20);
@@ -374,18 +1010,19 @@ mod tests {
         let _ = runtime.block_on(async move {
             let raft_service = RaftService::new(inner_state.clone(), Arc::new(SystemClock::new()));
 
-            let mut request = Request::new(RequestVote { term: 10, replica_id: 30, correlation_id: 20 });
+            let mut request = Request::new(RequestVote { term: 10, replica_id: 30, correlation_id: 20, last_log_index: None, last_log_term: None, leadership_transfer: false });
             request.add_host_port(self_host_and_port);
 
             let _ = raft_service.acknowledge_request_vote(request).await;
         });
 
         thread::sleep(Duration::from_millis(5));
-        assert_eq!(Some(20), state.get_voted_for());
+        assert_eq!(Some(30), state.get_voted_for());
+        assert_eq!(10, state.get_term());
     }
 
     #[test]
-    fn acknowledge_request_vote_do_not_vote_given_the_request_term_not_higher() {
+    fn acknowledge_request_vote_do_not_vote_within_the_leader_lease() {
         let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2060);
         let peers = vec![HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2061)];
 
@@ -398,14 +1035,17 @@ mod tests {
         );
 
         let state = runtime.block_on(async move {
-            return State::new(Arc::new(replica), HeartbeatConfig::default());
+            let state = State::new(Arc::new(replica), RaftConfig::builder().with_leader_lease(Duration::from_millis(10)).build().unwrap());
+            state.mark_heartbeat_received();
+            state.mark_leader(40, None);
+            return state;
         });
 
         let inner_state = state.clone();
         let _ = runtime.block_on(async move {
             let raft_service = RaftService::new(inner_state.clone(), Arc::new(SystemClock::new()));
 
-            let mut request = Request::new(RequestVote { term: 0, replica_id: 30, correlation_id: 20 });
+            let mut request = Request::new(RequestVote { term: 10, replica_id: 20, correlation_id: 20, last_log_index: None, last_log_term: None, leadership_transfer: false });
             request.add_host_port(self_host_and_port);
 
             let _ = raft_service.acknowledge_request_vote(request).await;
@@ -413,12 +1053,15 @@ mod tests {
 
         thread::sleep(Duration::from_millis(5));
         assert_eq!(None, state.get_voted_for());
+        assert_eq!(0, state.get_term());
     }
 
     #[test]
-    fn acknowledge_heartbeat_mark_heartbeat_received() {
+    fn acknowledge_request_vote_do_not_vote_given_the_replica_has_recently_heard_from_the_leader() {
         let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2060);
         let peers = vec![HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2061)];
+
+        let runtime = Builder::new_multi_thread().worker_threads(4).enable_all().build().unwrap();
         let replica = Replica::new(
             30,
             self_host_and_port.clone(),
@@ -426,36 +1069,34 @@ mod tests {
             Arc::new(SystemClock::new()),
         );
 
-        let runtime = Builder::new_current_thread().enable_all().build().unwrap();
         let state = runtime.block_on(async move {
-            return State::new(Arc::new(replica), HeartbeatConfig::default());
+            let state = State::new(Arc::new(replica), RaftConfig::default());
+            state.mark_heartbeat_received();
+            state.mark_leader(40, None);
+            return state;
         });
 
         let inner_state = state.clone();
         let _ = runtime.block_on(async move {
             let raft_service = RaftService::new(inner_state.clone(), Arc::new(SystemClock::new()));
-            let _ = raft_service.acknowledge_heartbeat(
-                Request::new(
-                    AppendEntries {
-                        term: 1,
-                        leader_id: 10,
-                        correlation_id: 20,
-                        entry: None,
-                        previous_log_index: None,
-                        previous_log_term: None,
-                        leader_commit_index: None,
-                    }
-                )
-            ).await;
 
-            assert!(inner_state.get_heartbeat_received_time().is_some());
+            let mut request = Request::new(RequestVote { term: 10, replica_id: 20, correlation_id: 20, last_log_index: None, last_log_term: None, leadership_transfer: false });
+            request.add_host_port(self_host_and_port);
+
+            let _ = raft_service.acknowledge_request_vote(request).await;
         });
+
+        thread::sleep(Duration::from_millis(5));
+        assert_eq!(None, state.get_voted_for());
+        assert_eq!(0, state.get_term());
     }
 
     #[test]
-    fn acknowledge_heartbeat_with_request_containing_higher_term() {
+    fn acknowledge_request_vote_of_the_leader_the_replica_has_recently_heard_from() {
         let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2060);
         let peers = vec![HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2061)];
+
+        let runtime = Builder::new_multi_thread().worker_threads(4).enable_all().build().unwrap();
         let replica = Replica::new(
             30,
             self_host_and_port.clone(),
@@ -463,122 +1104,1332 @@ mod tests {
             Arc::new(SystemClock::new()),
         );
 
-        let runtime = Builder::new_current_thread().enable_all().build().unwrap();
         let state = runtime.block_on(async move {
-            return State::new(Arc::new(replica), HeartbeatConfig::default());
+            let state = State::new(Arc::new(replica), RaftConfig::default());
+            state.mark_heartbeat_received();
+            state.mark_leader(20, None);
+            return state;
         });
 
         let inner_state = state.clone();
         let _ = runtime.block_on(async move {
             let raft_service = RaftService::new(inner_state.clone(), Arc::new(SystemClock::new()));
-            let result: Result<Response<AppendEntriesResponse>, tonic::Status> = raft_service.acknowledge_heartbeat(
-                Request::new(
-                    AppendEntries {
-                        term: 1,
-                        leader_id: 10,
-                        correlation_id: 20,
-                        entry: None,
-                        previous_log_index: None,
-                        previous_log_term: None,
-                        leader_commit_index: None,
-                    }
-                )
-            ).await;
 
-            let response = result.unwrap().into_inner();
-            assert_eq!(true, response.success);
-            assert_eq!(1, response.term);
-            assert_eq!(1, inner_state.get_term());
+            let mut request = Request::new(RequestVote { term: 10, replica_id: 20, correlation_id: 20, last_log_index: None, last_log_term: None, leadership_transfer: false });
+            request.add_host_port(self_host_and_port);
+
+            let _ = raft_service.acknowledge_request_vote(request).await;
+        });
+
+        thread::sleep(Duration::from_millis(5));
+        assert_eq!(Some(20), state.get_voted_for());
+        assert_eq!(10, state.get_term());
+    }
+
+    #[test]
+    fn acknowledge_request_vote_for_a_leadership_transfer_within_the_leader_lease() {
+        let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2060);
+        let peers = vec![HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2061)];
+
+        let runtime = Builder::new_multi_thread().worker_threads(4).enable_all().build().unwrap();
+        let replica = Replica::new(
+            30,
+            self_host_and_port.clone(),
+            peers,
+            Arc::new(SystemClock::new()),
+        );
+
+        let state = runtime.block_on(async move {
+            let state = State::new(Arc::new(replica), RaftConfig::builder().with_leader_lease(Duration::from_millis(10)).build().unwrap());
+            state.mark_heartbeat_received();
+            state.mark_leader(40, None);
+            return state;
+        });
+
+        let inner_state = state.clone();
+        let _ = runtime.block_on(async move {
+            let raft_service = RaftService::new(inner_state.clone(), Arc::new(SystemClock::new()));
+
+            let mut request = Request::new(RequestVote { term: 10, replica_id: 20, correlation_id: 20, last_log_index: None, last_log_term: None, leadership_transfer: true });
+            request.add_host_port(self_host_and_port);
+
+            let _ = raft_service.acknowledge_request_vote(request).await;
+        });
+
+        thread::sleep(Duration::from_millis(5));
+        assert_eq!(Some(20), state.get_voted_for());
+        assert_eq!(10, state.get_term());
+    }
+
+    #[test]
+    fn acknowledge_request_vote_do_not_vote_given_the_request_term_not_higher() {
+        let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2060);
+        let peers = vec![HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2061)];
+
+        let runtime = Builder::new_multi_thread().worker_threads(4).enable_all().build().unwrap();
+        let replica = Replica::new(
+            30,
+            self_host_and_port.clone(),
+            peers,
+            Arc::new(SystemClock::new()),
+        );
+
+        let state = runtime.block_on(async move {
+            return State::new(Arc::new(replica), RaftConfig::default());
+        });
+
+        let inner_state = state.clone();
+        let _ = runtime.block_on(async move {
+            let raft_service = RaftService::new(inner_state.clone(), Arc::new(SystemClock::new()));
+
+            let mut request = Request::new(RequestVote { term: 0, replica_id: 30, correlation_id: 20, last_log_index: None, last_log_term: None, leadership_transfer: false });
+            request.add_host_port(self_host_and_port);
+
+            let _ = raft_service.acknowledge_request_vote(request).await;
+        });
+
+        thread::sleep(Duration::from_millis(5));
+        assert_eq!(None, state.get_voted_for());
+    }
+
+    #[test]
+    fn acknowledge_request_vote_do_not_vote_given_the_candidate_log_has_a_lower_last_term() {
+        let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2060);
+        let peers = vec![HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2061)];
+
+        let runtime = Builder::new_multi_thread().worker_threads(4).enable_all().build().unwrap();
+        let replica = Replica::new(
+            30,
+            self_host_and_port.clone(),
+            peers,
+            Arc::new(SystemClock::new()),
+        );
+
+        let state = runtime.block_on(async move {
+            let state = State::new(Arc::new(replica), RaftConfig::default());
+            let command = Command { command: String::from("Content").as_bytes().to_vec(), client_id: None, sequence: 0 };
+            state.get_replicated_log().append_command(&command, 1);
+            state.get_replicated_log().append_command(&command, 2);
+            return state;
+        });
+
+        let inner_state = state.clone();
+        let _ = runtime.block_on(async move {
+            let raft_service = RaftService::new(inner_state.clone(), Arc::new(SystemClock::new()));
+
+            let mut request = Request::new(RequestVote { term: 10, replica_id: 20, correlation_id: 20, last_log_index: Some(5), last_log_term: Some(1), leadership_transfer: false });
+            request.add_host_port(self_host_and_port);
+
+            let _ = raft_service.acknowledge_request_vote(request).await;
+        });
+
+        thread::sleep(Duration::from_millis(5));
+        assert_eq!(None, state.get_voted_for());
+    }
+
+    #[test]
+    fn acknowledge_request_vote_do_not_vote_given_the_candidate_log_is_shorter() {
+        let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2060);
+        let peers = vec![HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2061)];
+
+        let runtime = Builder::new_multi_thread().worker_threads(4).enable_all().build().unwrap();
+        let replica = Replica::new(
+            30,
+            self_host_and_port.clone(),
+            peers,
+            Arc::new(SystemClock::new()),
+        );
+
+        let state = runtime.block_on(async move {
+            let state = State::new(Arc::new(replica), RaftConfig::default());
+            let command = Command { command: String::from("Content").as_bytes().to_vec(), client_id: None, sequence: 0 };
+            state.get_replicated_log().append_command(&command, 1);
+            state.get_replicated_log().append_command(&command, 2);
+            return state;
+        });
+
+        let inner_state = state.clone();
+        let _ = runtime.block_on(async move {
+            let raft_service = RaftService::new(inner_state.clone(), Arc::new(SystemClock::new()));
+
+            let mut request = Request::new(RequestVote { term: 10, replica_id: 20, correlation_id: 20, last_log_index: Some(0), last_log_term: Some(2), leadership_transfer: false });
+            request.add_host_port(self_host_and_port);
+
+            let _ = raft_service.acknowledge_request_vote(request).await;
+        });
+
+        thread::sleep(Duration::from_millis(5));
+        assert_eq!(None, state.get_voted_for());
+    }
+
+    #[test]
+    fn acknowledge_request_vote_do_not_vote_given_the_candidate_log_is_empty() {
+        let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2060);
+        let peers = vec![HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2061)];
+
+        let runtime = Builder::new_multi_thread().worker_threads(4).enable_all().build().unwrap();
+        let replica = Replica::new(
+            30,
+            self_host_and_port.clone(),
+            peers,
+            Arc::new(SystemClock::new()),
+        );
+
+        let state = runtime.block_on(async move {
+            let state = State::new(Arc::new(replica), RaftConfig::default());
+            let command = Command { command: String::from("Content").as_bytes().to_vec(), client_id: None, sequence: 0 };
+            state.get_replicated_log().append_command(&command, 1);
+            state.get_replicated_log().append_command(&command, 2);
+            return state;
+        });
+
+        let inner_state = state.clone();
+        let _ = runtime.block_on(async move {
+            let raft_service = RaftService::new(inner_state.clone(), Arc::new(SystemClock::new()));
+
+            let mut request = Request::new(RequestVote { term: 10, replica_id: 20, correlation_id: 20, last_log_index: None, last_log_term: None, leadership_transfer: false });
+            request.add_host_port(self_host_and_port);
+
+            let _ = raft_service.acknowledge_request_vote(request).await;
+        });
+
+        thread::sleep(Duration::from_millis(5));
+        assert_eq!(None, state.get_voted_for());
+    }
+
+    #[test]
+    fn acknowledge_request_vote_successfully_voted_given_the_candidate_log_has_a_higher_last_term() {
+        let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2060);
+        let peers = vec![HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2061)];
+
+        let runtime = Builder::new_multi_thread().worker_threads(4).enable_all().build().unwrap();
+        let replica = Replica::new(
+            30,
+            self_host_and_port.clone(),
+            peers,
+            Arc::new(SystemClock::new()),
+        );
+
+        let state = runtime.block_on(async move {
+            let state = State::new(Arc::new(replica), RaftConfig::default());
+            let command = Command { command: String::from("Content").as_bytes().to_vec(), client_id: None, sequence: 0 };
+            state.get_replicated_log().append_command(&command, 1);
+            state.get_replicated_log().append_command(&command, 2);
+            return state;
+        });
+
+        let inner_state = state.clone();
+        let _ = runtime.block_on(async move {
+            let raft_service = RaftService::new(inner_state.clone(), Arc::new(SystemClock::new()));
+
+            let mut request = Request::new(RequestVote { term: 10, replica_id: 20, correlation_id: 20, last_log_index: Some(0), last_log_term: Some(3), leadership_transfer: false });
+            request.add_host_port(self_host_and_port);
+
+            let _ = raft_service.acknowledge_request_vote(request).await;
+        });
+
+        thread::sleep(Duration::from_millis(5));
+        assert_eq!(Some(20), state.get_voted_for());
+    }
+
+    #[test]
+    fn acknowledge_heartbeat_mark_heartbeat_received() {
+        let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2060);
+        let peers = vec![HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2061)];
+        let replica = Replica::new(
+            30,
+            self_host_and_port.clone(),
+            peers,
+            Arc::new(SystemClock::new()),
+        );
+
+        let runtime = Builder::new_current_thread().enable_all().build().unwrap();
+        let state = runtime.block_on(async move {
+            return State::new(Arc::new(replica), RaftConfig::default());
+        });
+
+        let inner_state = state.clone();
+        let _ = runtime.block_on(async move {
+            let raft_service = RaftService::new(inner_state.clone(), Arc::new(SystemClock::new()));
+            let _ = raft_service.acknowledge_heartbeat(
+                Request::new(
+                    AppendEntries {
+                        term: 1,
+                        leader_id: 10,
+                        correlation_id: 20,
+                        entries: vec![],
+                        previous_log_index: None,
+                        previous_log_term: None,
+                        leader_commit_index: None,
+                    }
+                )
+            ).await;
+
+            assert!(inner_state.get_heartbeat_received_time().is_some());
+        });
+    }
+
+    #[test]
+    fn acknowledge_heartbeat_with_request_containing_higher_term() {
+        let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2060);
+        let peers = vec![HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2061)];
+        let replica = Replica::new(
+            30,
+            self_host_and_port.clone(),
+            peers,
+            Arc::new(SystemClock::new()),
+        );
+
+        let runtime = Builder::new_current_thread().enable_all().build().unwrap();
+        let state = runtime.block_on(async move {
+            return State::new(Arc::new(replica), RaftConfig::default());
+        });
+
+        let inner_state = state.clone();
+        let _ = runtime.block_on(async move {
+            let raft_service = RaftService::new(inner_state.clone(), Arc::new(SystemClock::new()));
+            let result: Result<Response<AppendEntriesResponse>, tonic::Status> = raft_service.acknowledge_heartbeat(
+                Request::new(
+                    AppendEntries {
+                        term: 1,
+                        leader_id: 10,
+                        correlation_id: 20,
+                        entries: vec![],
+                        previous_log_index: None,
+                        previous_log_term: None,
+                        leader_commit_index: None,
+                    }
+                )
+            ).await;
+
+            let response = result.unwrap().into_inner();
+            assert_eq!(true, response.success);
+            assert_eq!(1, response.term);
+            assert_eq!(1, inner_state.get_term());
+        });
+    }
+
+    #[test]
+    fn acknowledge_heartbeat_with_request_containing_same_term() {
+        let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2069);
+        let peers = vec![HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2061)];
+        let replica = Replica::new(
+            30,
+            self_host_and_port.clone(),
+            peers,
+            Arc::new(SystemClock::new()),
+        );
+
+        let runtime = Builder::new_current_thread().enable_all().build().unwrap();
+        let state = runtime.block_on(async move {
+            return State::new(Arc::new(replica), RaftConfig::default());
+        });
+
+        let inner_state = state.clone();
+        let _ = runtime.block_on(async move {
+            let raft_service = RaftService::new(inner_state.clone(), Arc::new(SystemClock::new()));
+
+            let result: Result<Response<AppendEntriesResponse>, tonic::Status> = raft_service.acknowledge_heartbeat(
+                Request::new(
+                    AppendEntries {
+                        term: 0,
+                        leader_id: 10,
+                        correlation_id: 20,
+                        entries: vec![],
+                        previous_log_index: None,
+                        previous_log_term: None,
+                        leader_commit_index: None,
+                    }
+                )
+            ).await;
+
+            let response = result.unwrap().into_inner();
+
+            assert_eq!(true, response.success);
+            assert_eq!(0, response.term);
+            assert_eq!(0, inner_state.get_term());
+        });
+    }
+
+    #[test]
+    fn acknowledge_heartbeat_with_request_containing_smaller_term() {
+        let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2068);
+        let peers = vec![HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2061)];
+        let replica = Replica::new(
+            30,
+            self_host_and_port.clone(),
+            peers,
+            Arc::new(SystemClock::new()),
+        );
+
+        let runtime = Builder::new_current_thread().enable_all().build().unwrap();
+        let state = runtime.block_on(async move {
+            let state = State::new(Arc::new(replica), RaftConfig::default());
+            state.change_to_candidate();
+            return state;
+        });
+
+        let inner_state = state.clone();
+        let _ = runtime.block_on(async move {
+            let raft_service = RaftService::new(inner_state.clone(), Arc::new(SystemClock::new()));
+            let result: Result<Response<AppendEntriesResponse>, tonic::Status> = raft_service.acknowledge_heartbeat(
+                Request::new(
+                    AppendEntries {
+                        term: 0,
+                        leader_id: 10,
+                        correlation_id: 20,
+                        entries: vec![],
+                        previous_log_index: None,
+                        previous_log_term: None,
+                        leader_commit_index: None,
+                    }
+                )
+            ).await;
+
+            let response = result.unwrap().into_inner();
+
+            assert_eq!(false, response.success);
+            assert_eq!(1, response.term);
+            assert_eq!(1, inner_state.get_term());
+        });
+    }
+
+    #[test]
+    fn install_snapshot_with_request_containing_higher_term() {
+        let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2069);
+        let peers = vec![HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2061)];
+        let replica = Replica::new(
+            30,
+            self_host_and_port.clone(),
+            peers,
+            Arc::new(SystemClock::new()),
+        );
+
+        let runtime = Builder::new_current_thread().enable_all().build().unwrap();
+        let state = runtime.block_on(async move {
+            return State::new(Arc::new(replica), RaftConfig::default());
+        });
+
+        let inner_state = state.clone();
+        let _ = runtime.block_on(async move {
+            let raft_service = RaftService::new(inner_state.clone(), Arc::new(SystemClock::new()));
+
+            let result: Result<Response<InstallSnapshotResponse>, tonic::Status> = raft_service.install_snapshot(
+                Request::new(
+                    InstallSnapshot {
+                        term: 2,
+                        leader_id: 10,
+                        correlation_id: 20,
+                        last_included_index: 4,
+                        last_included_term: 1,
+                        data: "state".as_bytes().to_vec(),
+                        client_sessions: Vec::new(),
+                        membership: Vec::new(),
+                    }
+                )
+            ).await;
+
+            let response = result.unwrap().into_inner();
+
+            assert_eq!(true, response.success);
+            assert_eq!(2, response.term);
+            assert_eq!(2, inner_state.get_term());
+            assert_eq!(ReplicaRole::Follower, inner_state.get_role());
+
+            let snapshot = inner_state.get_replicated_log().get_snapshot().unwrap();
+            assert_eq!(4, snapshot.get_last_included_index());
+            assert_eq!(1, snapshot.get_last_included_term());
+            assert_eq!("state".as_bytes().to_vec(), snapshot.get_data_as_vec());
+            assert_eq!(Some(4), inner_state.get_replicated_log().get_commit_index());
+            assert_eq!(5, inner_state.get_replicated_log().total_log_entries());
+        });
+    }
+
+    #[test]
+    fn install_snapshot_with_a_membership() {
+        let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2069);
+        let peer_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2061);
+        let new_voter_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2062);
+        let replica = Replica::new(
+            30,
+            self_host_and_port.clone(),
+            vec![peer_host_and_port],
+            Arc::new(SystemClock::new()),
+        );
+
+        let runtime = Builder::new_current_thread().enable_all().build().unwrap();
+        let state = runtime.block_on(async move {
+            return State::new(Arc::new(replica), RaftConfig::default());
+        });
+
+        let membership = Membership::new(vec![peer_host_and_port, self_host_and_port])
+            .with_learner(new_voter_host_and_port)
+            .promoting(&new_voter_host_and_port);
+
+        let inner_state = state.clone();
+        let inner_membership = membership.clone();
+        let _ = runtime.block_on(async move {
+            let raft_service = RaftService::new(inner_state.clone(), Arc::new(SystemClock::new()));
+
+            let result: Result<Response<InstallSnapshotResponse>, tonic::Status> = raft_service.install_snapshot(
+                Request::new(
+                    InstallSnapshot {
+                        term: 2,
+                        leader_id: 10,
+                        correlation_id: 20,
+                        last_included_index: 4,
+                        last_included_term: 1,
+                        data: "state".as_bytes().to_vec(),
+                        client_sessions: Vec::new(),
+                        membership: inner_membership.encode(),
+                    }
+                )
+            ).await;
+
+            assert_eq!(true, result.unwrap().into_inner().success);
+        });
+
+        assert_eq!(Some(&membership), state.get_replicated_log().get_snapshot().unwrap().get_membership());
+        assert_eq!(membership, state.get_membership());
+        assert!(state.get_replica().get_peers().contains(&new_voter_host_and_port));
+    }
+
+    #[test]
+    fn install_snapshot_with_request_containing_smaller_term() {
+        let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2069);
+        let peers = vec![HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2061)];
+        let replica = Replica::new(
+            30,
+            self_host_and_port.clone(),
+            peers,
+            Arc::new(SystemClock::new()),
+        );
+
+        let runtime = Builder::new_current_thread().enable_all().build().unwrap();
+        let state = runtime.block_on(async move {
+            let state = State::new(Arc::new(replica), RaftConfig::default());
+            state.change_to_candidate();
+            state.change_to_candidate();
+            return state;
+        });
+
+        let inner_state = state.clone();
+        let _ = runtime.block_on(async move {
+            let raft_service = RaftService::new(inner_state.clone(), Arc::new(SystemClock::new()));
+
+            let result: Result<Response<InstallSnapshotResponse>, tonic::Status> = raft_service.install_snapshot(
+                Request::new(
+                    InstallSnapshot {
+                        term: 1,
+                        leader_id: 10,
+                        correlation_id: 20,
+                        last_included_index: 4,
+                        last_included_term: 1,
+                        data: Vec::new(),
+                        client_sessions: Vec::new(),
+                        membership: Vec::new(),
+                    }
+                )
+            ).await;
+
+            let response = result.unwrap().into_inner();
+
+            assert_eq!(false, response.success);
+            assert_eq!(2, response.term);
+            assert_eq!(None, inner_state.get_replicated_log().get_snapshot());
+        });
+    }
+
+    #[test]
+    fn execute_command() {
+        let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2060);
+        let peers = vec![HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2061)];
+
+        let runtime = Builder::new_multi_thread().worker_threads(4).enable_all().build().unwrap();
+        let replica = Replica::new(
+            30,
+            self_host_and_port.clone(),
+            peers,
+            Arc::new(SystemClock::new()),
+        );
+
+        let state = runtime.block_on(async move {
+            let state = State::new(Arc::new(replica), RaftConfig::default());
+            state.change_to_candidate();
+            state.clone().change_to_leader();
+            return state;
+        });
+
+        let inner_state = state.clone();
+        let raft_service = Arc::new(
+            RaftService::new(inner_state.clone(), Arc::new(SystemClock::new()))
+        );
+        let inner_raft_service = raft_service.clone();
+        let _ = runtime.spawn(async move {
+            let content = String::from("Content");
+            let command = Command { command: content.as_bytes().to_vec(), client_id: None, sequence: 0 };
+
+            let mut request = Request::new(command);
+            request.add_host_port(self_host_and_port);
+
+            let _ = inner_raft_service.execute(request).await;
+        });
+
+        runtime.block_on(async {
+            raft_service.pending_committed_log_entries.handle_response(
+                1,
+                HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2060),
+                Ok(Box::new(AppliedCommand { term: 1, result: "Output".as_bytes().to_vec() }))
+            );
+        });
+
+        thread::sleep(Duration::from_millis(5));
+        let log_entry = state.get_replicated_log().get_log_entry_at(1).unwrap();
+
+        assert_eq!(1, log_entry.get_term());
+        assert_eq!(String::from("Content").as_bytes().to_vec(), log_entry.get_bytes_as_vec());
+    }
+
+    #[test]
+    fn fail_command_given_its_entry_was_replaced_by_an_entry_of_another_term() {
+        let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2060);
+        let peers = vec![HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2061)];
+
+        let runtime = Builder::new_multi_thread().worker_threads(4).enable_all().build().unwrap();
+        let replica = Replica::new(
+            30,
+            self_host_and_port.clone(),
+            peers,
+            Arc::new(SystemClock::new()),
+        );
+
+        let state = runtime.block_on(async move {
+            let state = State::new(Arc::new(replica), RaftConfig::default());
+            state.change_to_candidate();
+            state.clone().change_to_leader();
+            return state;
+        });
+
+        let raft_service = Arc::new(
+            RaftService::new(state.clone(), Arc::new(SystemClock::new()))
+        );
+        let inner_raft_service = raft_service.clone();
+        let execution = runtime.spawn(async move {
+            let command = Command { command: String::from("Content").as_bytes().to_vec(), client_id: None, sequence: 0 };
+            return inner_raft_service.execute(Request::new(command)).await;
+        });
+
+        thread::sleep(Duration::from_millis(5));
+        let result = runtime.block_on(async {
+            //the entry at the index of the command has the term of a later leader
+            raft_service.pending_committed_log_entries.handle_response(
+                1,
+                self_host_and_port,
+                Ok(Box::new(AppliedCommand { term: 2, result: "Output of another command".as_bytes().to_vec() }))
+            );
+            return execution.await.unwrap();
+        });
+
+        let status = result.unwrap_err();
+        assert_eq!(Code::FailedPrecondition, status.code());
+        assert!(NotLeader::from_status(&status).is_some());
+    }
+
+    #[test]
+    fn do_not_execute_command_given_replica_is_not_the_leader() {
+        let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2060);
+        let leader_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2061);
+
+        let runtime = Builder::new_multi_thread().worker_threads(4).enable_all().build().unwrap();
+        let replica = Replica::new(
+            30,
+            self_host_and_port.clone(),
+            vec![leader_host_and_port],
+            Arc::new(SystemClock::new()),
+        );
+
+        let state = runtime.block_on(async move {
+            return State::new(Arc::new(replica), RaftConfig::default());
+        });
+
+        let inner_state = state.clone();
+        let result = runtime.block_on(async move {
+            let raft_service = RaftService::new(inner_state.clone(), Arc::new(SystemClock::new()));
+
+            let mut request = Request::new(AppendEntries {
+                term: 1,
+                leader_id: 10,
+                correlation_id: 20,
+                entries: vec![],
+                previous_log_index: None,
+                previous_log_term: None,
+                leader_commit_index: None,
+            });
+            request.add_host_port(leader_host_and_port);
+            let _ = raft_service.acknowledge_heartbeat(request).await;
+
+            let command = Command { command: String::from("Content").as_bytes().to_vec(), client_id: None, sequence: 0 };
+            return raft_service.execute(Request::new(command)).await;
+        });
+
+        let status = result.unwrap_err();
+        assert_eq!(Code::FailedPrecondition, status.code());
+
+        let not_leader = NotLeader::from_status(&status).unwrap();
+        assert_eq!(Some(10), not_leader.leader_id);
+        assert_eq!(Some(leader_host_and_port), not_leader.get_leader_address());
+        assert_eq!(Some(10), state.get_leader_id());
+        assert_eq!(0, state.get_replicated_log().total_log_entries());
+    }
+
+    #[test]
+    fn do_not_forward_a_forwarded_command_again() {
+        let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2060);
+        let leader_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2061);
+        let forwarding_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2062);
+
+        let runtime = Builder::new_multi_thread().worker_threads(4).enable_all().build().unwrap();
+        let replica = Replica::new(
+            30,
+            self_host_and_port.clone(),
+            vec![leader_host_and_port, forwarding_host_and_port],
+            Arc::new(SystemClock::new()),
+        );
+
+        let state = runtime.block_on(async move {
+            return State::new(Arc::new(replica), RaftConfig::builder().with_forwarding_to_leader().build().unwrap());
+        });
+
+        let result = runtime.block_on(async move {
+            let raft_service = RaftService::new(state, Arc::new(SystemClock::new()));
+
+            let mut request = Request::new(AppendEntries {
+                term: 1,
+                leader_id: 10,
+                correlation_id: 20,
+                entries: vec![],
+                previous_log_index: None,
+                previous_log_term: None,
+                leader_commit_index: None,
+            });
+            request.add_host_port(leader_host_and_port);
+            let _ = raft_service.acknowledge_heartbeat(request).await;
+
+            let command = Command { command: String::from("Content").as_bytes().to_vec(), client_id: None, sequence: 0 };
+            let mut request = Request::new(command);
+            request.add_host_port(forwarding_host_and_port);
+            return raft_service.execute(request).await;
+        });
+
+        let not_leader = NotLeader::from_status(&result.unwrap_err()).unwrap();
+        assert_eq!(Some(10), not_leader.leader_id);
+        assert_eq!(Some(leader_host_and_port), not_leader.get_leader_address());
+    }
+
+    #[test]
+    fn raft_service_with_the_replication_config_of_the_raft_config() {
+        let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2060);
+        let peers = vec![HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2061)];
+
+        let runtime = Builder::new_multi_thread().worker_threads(4).enable_all().build().unwrap();
+        let replica = Replica::new(
+            30,
+            self_host_and_port.clone(),
+            peers,
+            Arc::new(SystemClock::new()),
+        );
+
+        let raft_config = RaftConfig::builder()
+            .with_max_entries_per_append(8)
+            .with_max_bytes_per_append(512)
+            .with_max_in_flight_appends(2)
+            .with_forwarding_to_leader()
+            .build()
+            .unwrap();
+
+        let state = runtime.block_on(async move {
+            return State::new(Arc::new(replica), raft_config);
+        });
+
+        let raft_service = runtime.block_on(async move {
+            return RaftService::new(state, Arc::new(SystemClock::new()));
+        });
+
+        let replication_config = raft_service.follower_state.get_replication_config();
+        assert_eq!(8, replication_config.get_max_entries_per_request());
+        assert_eq!(512, replication_config.get_max_bytes_per_request());
+        assert_eq!(2, replication_config.get_max_in_flight_requests());
+        assert!(replication_config.is_forwarding_to_leader_enabled());
+        assert!(raft_service.forward_to_leader);
+    }
+
+    #[test]
+    fn do_not_execute_command_given_the_leader_is_unknown() {
+        let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2060);
+        let peers = vec![HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2061)];
+
+        let runtime = Builder::new_multi_thread().worker_threads(4).enable_all().build().unwrap();
+        let replica = Replica::new(
+            30,
+            self_host_and_port.clone(),
+            peers,
+            Arc::new(SystemClock::new()),
+        );
+
+        let state = runtime.block_on(async move {
+            return State::new(Arc::new(replica), RaftConfig::builder().with_forwarding_to_leader().build().unwrap());
+        });
+
+        let result = runtime.block_on(async move {
+            let raft_service = RaftService::new(state, Arc::new(SystemClock::new()));
+            let command = Command { command: String::from("Content").as_bytes().to_vec(), client_id: None, sequence: 0 };
+            return raft_service.execute(Request::new(command)).await;
+        });
+
+        let not_leader = NotLeader::from_status(&result.unwrap_err()).unwrap();
+        assert_eq!(None, not_leader.leader_id);
+        assert_eq!(None, not_leader.get_leader_address());
+    }
+
+    #[test]
+    fn do_not_execute_command_during_leadership_transfer() {
+        let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2060);
+        let peers = vec![HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2061)];
+
+        let runtime = Builder::new_multi_thread().worker_threads(4).enable_all().build().unwrap();
+        let replica = Replica::new(
+            30,
+            self_host_and_port.clone(),
+            peers,
+            Arc::new(SystemClock::new()),
+        );
+
+        let state = runtime.block_on(async move {
+            return State::new(Arc::new(replica), RaftConfig::default());
+        });
+
+        let inner_state = state.clone();
+        let result = runtime.block_on(async move {
+            let raft_service = RaftService::new(inner_state.clone(), Arc::new(SystemClock::new()));
+            raft_service.leadership_transfer_in_progress.store(true, Ordering::SeqCst);
+
+            let command = Command { command: String::from("Content").as_bytes().to_vec(), client_id: None, sequence: 0 };
+            return raft_service.execute(Request::new(command)).await;
+        });
+
+        assert_eq!(Code::Unavailable, result.unwrap_err().code());
+        assert_eq!(0, state.get_replicated_log().total_log_entries());
+    }
+
+    #[test]
+    fn read_index_on_a_single_replica() {
+        let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2060);
+
+        let runtime = Builder::new_multi_thread().worker_threads(4).enable_all().build().unwrap();
+        let replica = Replica::new(
+            30,
+            self_host_and_port.clone(),
+            vec![],
+            Arc::new(SystemClock::new()),
+        );
+
+        let state = runtime.block_on(async move {
+            let state = State::new(Arc::new(replica), RaftConfig::default());
+            let term = state.change_to_candidate();
+            state.clone().change_to_leader();
+            //the leader's no-op entry at index 0, the test commits it as there are no peers to acknowledge it
+            state.get_replicated_log().commit(0, term);
+            state.apply_committed_log_entries(|_, _, _| {});
+            return state;
+        });
+
+        let result = runtime.block_on(async move {
+            let raft_service = RaftService::new(state, Arc::new(SystemClock::new()));
+            return raft_service.read_index(Request::new(Query { query: "Content".as_bytes().to_vec() })).await;
+        });
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn do_not_read_index_given_the_leader_has_not_committed_an_entry_of_its_term() {
+        let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2060);
+
+        let runtime = Builder::new_multi_thread().worker_threads(4).enable_all().build().unwrap();
+        let replica = Replica::new(
+            30,
+            self_host_and_port.clone(),
+            vec![],
+            Arc::new(SystemClock::new()),
+        );
+
+        let state = runtime.block_on(async move {
+            let state = State::new(Arc::new(replica), RaftConfig::default());
+            let command = Command { command: "Content".as_bytes().to_vec(), client_id: None, sequence: 0 };
+            state.get_replicated_log().append_command(&command, 0);
+            state.get_replicated_log().commit(0, 0);
+
+            state.change_to_candidate();
+            state.clone().change_to_leader();
+            return state;
+        });
+
+        let inner_state = state.clone();
+        let result = runtime.block_on(async move {
+            let raft_service = RaftService::new(inner_state, Arc::new(SystemClock::new()));
+            return raft_service.read_index(Request::new(Query { query: "Content".as_bytes().to_vec() })).await;
+        });
+
+        assert_eq!(Code::Unavailable, result.unwrap_err().code());
+        assert_eq!(Some(0), state.get_replicated_log().get_commit_index());
+        assert_eq!(2, state.get_replicated_log().total_log_entries());
+    }
+
+    #[test]
+    fn do_not_read_index_given_replica_is_not_the_leader() {
+        let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2060);
+        let peers = vec![HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2061)];
+
+        let runtime = Builder::new_multi_thread().worker_threads(4).enable_all().build().unwrap();
+        let replica = Replica::new(
+            30,
+            self_host_and_port.clone(),
+            peers,
+            Arc::new(SystemClock::new()),
+        );
+
+        let state = runtime.block_on(async move {
+            return State::new(Arc::new(replica), RaftConfig::default());
+        });
+
+        let result = runtime.block_on(async move {
+            let raft_service = RaftService::new(state, Arc::new(SystemClock::new()));
+            return raft_service.read_index(Request::new(Query { query: "Content".as_bytes().to_vec() })).await;
+        });
+
+        assert_eq!(Code::FailedPrecondition, result.unwrap_err().code());
+    }
+
+    #[test]
+    fn lease_read_within_the_leader_lease_and_refuse_once_it_expires() {
+        let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2060);
+        let clock = Arc::new(ControllableClock::new());
+
+        let runtime = Builder::new_multi_thread().worker_threads(4).enable_all().build().unwrap();
+        let replica = Replica::new(
+            30,
+            self_host_and_port.clone(),
+            vec![],
+            clock.clone(),
+        );
+
+        let state = runtime.block_on(async move {
+            let state = State::new(Arc::new(replica), RaftConfig::builder().with_leader_lease(Duration::from_millis(10)).build().unwrap());
+            let term = state.change_to_candidate();
+            state.clone().change_to_leader();
+            //the lease is extended only by the heartbeat sent below, so advancing the clock expires it
+            state.stop_heartbeats();
+            state.get_replicated_log().commit(0, term);
+            state.apply_committed_log_entries(|_, _, _| {});
+            let _ = state.clone().get_heartbeat_sender().await;
+            return state;
+        });
+
+        let inner_clock = clock.clone();
+        let inner_state = state.clone();
+        runtime.block_on(async move {
+            let raft_service = RaftService::new(inner_state, inner_clock.clone());
+            let result = raft_service.lease_read(Request::new(Query { query: "Content".as_bytes().to_vec() })).await;
+            assert!(result.is_ok());
+
+            inner_clock.advance_by(Duration::from_millis(140));
+            let result = raft_service.lease_read(Request::new(Query { query: "Content".as_bytes().to_vec() })).await;
+            assert_eq!(Code::Unavailable, result.unwrap_err().code());
+        });
+    }
+
+    #[test]
+    fn do_not_lease_read_given_the_leader_has_not_committed_an_entry_of_its_term() {
+        let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2060);
+        let clock = Arc::new(ControllableClock::new());
+
+        let runtime = Builder::new_multi_thread().worker_threads(4).enable_all().build().unwrap();
+        let replica = Replica::new(
+            30,
+            self_host_and_port.clone(),
+            vec![],
+            clock.clone(),
+        );
+
+        let state = runtime.block_on(async move {
+            let state = State::new(Arc::new(replica), RaftConfig::builder().with_leader_lease(Duration::from_millis(10)).build().unwrap());
+            state.change_to_candidate();
+            state.clone().change_to_leader();
+            let _ = state.clone().get_heartbeat_sender().await;
+            return state;
+        });
+
+        let inner_clock = clock.clone();
+        runtime.block_on(async move {
+            assert!(state.has_leader_lease());
+            let raft_service = RaftService::new(state, inner_clock);
+            let result = raft_service.lease_read(Request::new(Query { query: "Content".as_bytes().to_vec() })).await;
+            assert_eq!(Code::Unavailable, result.unwrap_err().code());
+        });
+    }
+
+    #[test]
+    fn do_not_lease_read_given_the_leader_lease_expires_while_waiting_for_an_entry_of_its_term() {
+        let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2060);
+        let clock = Arc::new(ControllableClock::new());
+
+        let runtime = Builder::new_multi_thread().worker_threads(4).enable_all().build().unwrap();
+        let replica = Replica::new(
+            30,
+            self_host_and_port.clone(),
+            vec![],
+            clock.clone(),
+        );
+
+        let state = runtime.block_on(async move {
+            let state = State::new(Arc::new(replica), RaftConfig::builder().with_leader_lease(Duration::from_millis(10)).build().unwrap());
+            state.change_to_candidate();
+            state.clone().change_to_leader();
+            state.stop_heartbeats();
+            let _ = state.clone().get_heartbeat_sender().await;
+            return state;
+        });
+        assert!(state.has_leader_lease());
+
+        let raft_service = Arc::new(RaftService::new(state.clone(), clock.clone()));
+        let inner_raft_service = raft_service.clone();
+        let read = runtime.spawn(async move {
+            return inner_raft_service.lease_read(Request::new(Query { query: "Content".as_bytes().to_vec() })).await;
+        });
+
+        thread::sleep(Duration::from_millis(20));
+        clock.advance_by(Duration::from_millis(140));
+        state.get_replicated_log().commit(0, state.get_term());
+        state.apply_committed_log_entries(|_, _, _| {});
+
+        let result = runtime.block_on(async move {
+            return read.await.unwrap();
+        });
+        assert_eq!(Code::Unavailable, result.unwrap_err().code());
+    }
+
+    #[test]
+    fn do_not_lease_read_given_leader_lease_is_not_enabled() {
+        let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2060);
+
+        let runtime = Builder::new_multi_thread().worker_threads(4).enable_all().build().unwrap();
+        let replica = Replica::new(
+            30,
+            self_host_and_port.clone(),
+            vec![],
+            Arc::new(SystemClock::new()),
+        );
+
+        let state = runtime.block_on(async move {
+            let state = State::new(Arc::new(replica), RaftConfig::default());
+            state.change_to_candidate();
+            state.clone().change_to_leader();
+            return state;
+        });
+
+        let result = runtime.block_on(async move {
+            let raft_service = RaftService::new(state, Arc::new(SystemClock::new()));
+            return raft_service.lease_read(Request::new(Query { query: "Content".as_bytes().to_vec() })).await;
+        });
+
+        assert_eq!(Code::FailedPrecondition, result.unwrap_err().code());
+    }
+
+    #[test]
+    fn do_not_transfer_leadership_given_replica_is_not_the_leader() {
+        let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2060);
+        let peer = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2061);
+
+        let runtime = Builder::new_multi_thread().worker_threads(4).enable_all().build().unwrap();
+        let replica = Replica::new(
+            30,
+            self_host_and_port.clone(),
+            vec![peer],
+            Arc::new(SystemClock::new()),
+        );
+
+        let state = runtime.block_on(async move {
+            return State::new(Arc::new(replica), RaftConfig::default());
+        });
+
+        let result = runtime.block_on(async move {
+            let raft_service = RaftService::new(state, Arc::new(SystemClock::new()));
+            return raft_service.transfer_leadership(Request::new(TransferLeadership {
+                target_host: peer.host_as_string(),
+                target_port: peer.port() as u32,
+            })).await;
+        });
+
+        assert_eq!(Code::FailedPrecondition, result.unwrap_err().code());
+    }
+
+    #[test]
+    fn do_not_transfer_leadership_to_a_replica_that_is_not_a_peer() {
+        let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2060);
+        let peer = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2061);
+
+        let runtime = Builder::new_multi_thread().worker_threads(4).enable_all().build().unwrap();
+        let replica = Replica::new(
+            30,
+            self_host_and_port.clone(),
+            vec![peer],
+            Arc::new(SystemClock::new()),
+        );
+
+        let state = runtime.block_on(async move {
+            let state = State::new(Arc::new(replica), RaftConfig::default());
+            state.change_to_candidate();
+            state.clone().change_to_leader();
+            return state;
+        });
+
+        let result = runtime.block_on(async move {
+            let raft_service = RaftService::new(state, Arc::new(SystemClock::new()));
+            return raft_service.transfer_leadership(Request::new(TransferLeadership {
+                target_host: String::from("127.0.0.1"),
+                target_port: 2069,
+            })).await;
+        });
+
+        assert_eq!(Code::InvalidArgument, result.unwrap_err().code());
+    }
+
+    #[test]
+    fn do_not_transfer_leadership_to_a_learner() {
+        let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2060);
+        let peer = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2061);
+        let learner = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2062);
+
+        let runtime = Builder::new_multi_thread().worker_threads(4).enable_all().build().unwrap();
+        let replica = Replica::new(
+            30,
+            self_host_and_port.clone(),
+            vec![peer],
+            Arc::new(SystemClock::new()),
+        );
+
+        let state = runtime.block_on(async move {
+            let state = State::new(Arc::new(replica), RaftConfig::default());
+            state.change_to_candidate();
+            state.clone().change_to_leader();
+            return state;
+        });
+        state.get_replicated_log().append_membership(&state.get_membership().with_learner(learner), state.get_term());
+        state.refresh_membership();
+        assert!(state.get_replica_reference().get_peers().contains(&learner));
+
+        let inner_state = state.clone();
+        let result = runtime.block_on(async move {
+            let raft_service = RaftService::new(inner_state, Arc::new(SystemClock::new()));
+            return raft_service.transfer_leadership(Request::new(TransferLeadership {
+                target_host: learner.host_as_string(),
+                target_port: learner.port() as u32,
+            })).await;
+        });
+
+        assert_eq!(Code::InvalidArgument, result.unwrap_err().code());
+    }
+
+    #[test]
+    fn do_not_add_voter_given_replica_is_not_the_leader() {
+        let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2060);
+        let peer = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2061);
+
+        let runtime = Builder::new_multi_thread().worker_threads(4).enable_all().build().unwrap();
+        let replica = Replica::new(
+            30,
+            self_host_and_port.clone(),
+            vec![peer],
+            Arc::new(SystemClock::new()),
+        );
+
+        let state = runtime.block_on(async move {
+            return State::new(Arc::new(replica), RaftConfig::default());
+        });
+
+        let result = runtime.block_on(async move {
+            let raft_service = RaftService::new(state, Arc::new(SystemClock::new()));
+            return raft_service.add_voter(Request::new(Member {
+                host: String::from("127.0.0.1"),
+                port: 2062,
+            })).await;
+        });
+
+        assert_eq!(Code::FailedPrecondition, result.unwrap_err().code());
+    }
+
+    #[test]
+    fn do_not_add_a_voter_that_is_already_a_voter() {
+        let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2060);
+        let peer = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2061);
+
+        let runtime = Builder::new_multi_thread().worker_threads(4).enable_all().build().unwrap();
+        let replica = Replica::new(
+            30,
+            self_host_and_port.clone(),
+            vec![peer],
+            Arc::new(SystemClock::new()),
+        );
+
+        let state = runtime.block_on(async move {
+            let state = State::new(Arc::new(replica), RaftConfig::default());
+            state.change_to_candidate();
+            state.clone().change_to_leader();
+            return state;
+        });
+
+        let result = runtime.block_on(async move {
+            let raft_service = RaftService::new(state, Arc::new(SystemClock::new()));
+            return raft_service.add_voter(Request::new(Member {
+                host: peer.host_as_string(),
+                port: peer.port() as u32,
+            })).await;
+        });
+
+        assert_eq!(Code::AlreadyExists, result.unwrap_err().code());
+    }
+
+    #[test]
+    fn do_not_remove_a_replica_that_is_not_a_voter() {
+        let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2060);
+        let peer = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2061);
+
+        let runtime = Builder::new_multi_thread().worker_threads(4).enable_all().build().unwrap();
+        let replica = Replica::new(
+            30,
+            self_host_and_port.clone(),
+            vec![peer],
+            Arc::new(SystemClock::new()),
+        );
+
+        let state = runtime.block_on(async move {
+            let state = State::new(Arc::new(replica), RaftConfig::default());
+            state.change_to_candidate();
+            state.clone().change_to_leader();
+            return state;
+        });
+
+        let result = runtime.block_on(async move {
+            let raft_service = RaftService::new(state, Arc::new(SystemClock::new()));
+            return raft_service.remove_voter(Request::new(Member {
+                host: String::from("127.0.0.1"),
+                port: 2069,
+            })).await;
         });
+
+        assert_eq!(Code::NotFound, result.unwrap_err().code());
     }
 
     #[test]
-    fn acknowledge_heartbeat_with_request_containing_same_term() {
-        let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2069);
-        let peers = vec![HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2061)];
+    fn do_not_promote_a_replica_that_is_not_a_learner() {
+        let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2060);
+        let peer = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2061);
+
+        let runtime = Builder::new_multi_thread().worker_threads(4).enable_all().build().unwrap();
         let replica = Replica::new(
             30,
             self_host_and_port.clone(),
-            peers,
+            vec![peer],
             Arc::new(SystemClock::new()),
         );
 
-        let runtime = Builder::new_current_thread().enable_all().build().unwrap();
         let state = runtime.block_on(async move {
-            return State::new(Arc::new(replica), HeartbeatConfig::default());
+            let state = State::new(Arc::new(replica), RaftConfig::default());
+            state.change_to_candidate();
+            state.clone().change_to_leader();
+            return state;
         });
 
-        let inner_state = state.clone();
-        let _ = runtime.block_on(async move {
-            let raft_service = RaftService::new(inner_state.clone(), Arc::new(SystemClock::new()));
+        let result = runtime.block_on(async move {
+            let raft_service = RaftService::new(state, Arc::new(SystemClock::new()));
+            return raft_service.promote_learner(Request::new(Member {
+                host: String::from("127.0.0.1"),
+                port: 2061,
+            })).await;
+        });
 
-            let result: Result<Response<AppendEntriesResponse>, tonic::Status> = raft_service.acknowledge_heartbeat(
-                Request::new(
-                    AppendEntries {
-                        term: 0,
-                        leader_id: 10,
-                        correlation_id: 20,
-                        entry: None,
-                        previous_log_index: None,
-                        previous_log_term: None,
-                        leader_commit_index: None,
-                    }
-                )
-            ).await;
+        assert_eq!(Code::NotFound, result.unwrap_err().code());
+    }
 
-            let response = result.unwrap().into_inner();
+    #[test]
+    fn do_not_remove_the_last_voter() {
+        let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2060);
 
-            assert_eq!(true, response.success);
-            assert_eq!(0, response.term);
-            assert_eq!(0, inner_state.get_term());
+        let runtime = Builder::new_multi_thread().worker_threads(4).enable_all().build().unwrap();
+        let replica = Replica::new(
+            30,
+            self_host_and_port.clone(),
+            vec![],
+            Arc::new(SystemClock::new()),
+        );
+
+        let state = runtime.block_on(async move {
+            let state = State::new(Arc::new(replica), RaftConfig::default());
+            state.change_to_candidate();
+            state.clone().change_to_leader();
+            return state;
+        });
+
+        let result = runtime.block_on(async move {
+            let raft_service = RaftService::new(state, Arc::new(SystemClock::new()));
+            return raft_service.remove_voter(Request::new(Member {
+                host: self_host_and_port.host_as_string(),
+                port: self_host_and_port.port() as u32,
+            })).await;
         });
+
+        assert_eq!(Code::FailedPrecondition, result.unwrap_err().code());
     }
 
     #[test]
-    fn acknowledge_heartbeat_with_request_containing_smaller_term() {
-        let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2068);
-        let peers = vec![HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2061)];
+    fn do_not_change_the_membership_given_a_membership_change_is_in_progress() {
+        let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2060);
+        let peer = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2061);
+        let other_peer = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2062);
+
+        let runtime = Builder::new_multi_thread().worker_threads(4).enable_all().build().unwrap();
         let replica = Replica::new(
             30,
             self_host_and_port.clone(),
-            peers,
+            vec![peer],
             Arc::new(SystemClock::new()),
         );
 
-        let runtime = Builder::new_current_thread().enable_all().build().unwrap();
         let state = runtime.block_on(async move {
-            let state = State::new(Arc::new(replica), HeartbeatConfig::default());
+            let state = State::new(Arc::new(replica), RaftConfig::default());
             state.change_to_candidate();
+            state.clone().change_to_leader();
             return state;
         });
 
-        let inner_state = state.clone();
-        let _ = runtime.block_on(async move {
-            let raft_service = RaftService::new(inner_state.clone(), Arc::new(SystemClock::new()));
-            let result: Result<Response<AppendEntriesResponse>, tonic::Status> = raft_service.acknowledge_heartbeat(
-                Request::new(
-                    AppendEntries {
-                        term: 0,
-                        leader_id: 10,
-                        correlation_id: 20,
-                        entry: None,
-                        previous_log_index: None,
-                        previous_log_term: None,
-                        leader_commit_index: None,
-                    }
-                )
-            ).await;
-
-            let response = result.unwrap().into_inner();
+        let joint_membership = state.get_membership().with_voter(other_peer);
+        state.get_replicated_log().append_membership(&joint_membership, state.get_term());
 
-            assert_eq!(false, response.success);
-            assert_eq!(1, response.term);
-            assert_eq!(1, inner_state.get_term());
+        let inner_state = state.clone();
+        let result = runtime.block_on(async move {
+            let raft_service = RaftService::new(inner_state, Arc::new(SystemClock::new()));
+            return raft_service.remove_voter(Request::new(Member {
+                host: peer.host_as_string(),
+                port: peer.port() as u32,
+            })).await;
         });
+
+        assert_eq!(Code::FailedPrecondition, result.unwrap_err().code());
+        assert_eq!(joint_membership, state.get_membership());
     }
 
     #[test]
-    fn execute_command() {
+    fn start_election_on_timeout_now() {
         let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2060);
         let peers = vec![HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2061)];
 
@@ -591,37 +2442,21 @@ mod tests {
         );
 
         let state = runtime.block_on(async move {
-            return State::new(Arc::new(replica), HeartbeatConfig::default());
+            return State::new(Arc::new(replica), RaftConfig::builder().with_pre_vote().build().unwrap());
         });
 
         let inner_state = state.clone();
-        let raft_service = Arc::new(
-            RaftService::new(inner_state.clone(), Arc::new(SystemClock::new()))
-        );
-        let inner_raft_service = raft_service.clone();
-        let _ = runtime.spawn(async move {
-            let content = String::from("Content");
-            let command = Command { command: content.as_bytes().to_vec() };
-
-            let mut request = Request::new(command);
-            request.add_host_port(self_host_and_port);
-
-            let _ = inner_raft_service.execute(request).await;
-        });
-
-        runtime.block_on(async {
-            raft_service.pending_committed_log_entries.handle_response(
-                0,
-                HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2060),
-                Ok(Box::new(()))
-            );
+        let _ = runtime.block_on(async move {
+            let raft_service = RaftService::new(inner_state, Arc::new(SystemClock::new()));
+            let _ = raft_service.timeout_now(Request::new(TimeoutNow { term: 0, leader_id: 10, correlation_id: 10 })).await;
         });
 
-        thread::sleep(Duration::from_millis(5));
-        let log_entry = state.get_replicated_log().get_log_entry_at(0).unwrap();
-
-        assert_eq!(0, log_entry.get_term());
-        assert_eq!(String::from("Content").as_bytes().to_vec(), log_entry.get_bytes_as_vec());
+        let started_at = Instant::now();
+        while state.get_term() != 1 && started_at.elapsed() < Duration::from_millis(500) {
+            thread::sleep(Duration::from_millis(5));
+        }
+        assert_eq!(1, state.get_term());
+        assert_eq!(Some(30), state.get_voted_for());
     }
 
     #[test]
@@ -638,7 +2473,7 @@ mod tests {
         );
 
         let state = runtime.block_on(async move {
-            let state = State::new(Arc::new(replica), HeartbeatConfig::default());
+            let state = State::new(Arc::new(replica), RaftConfig::default());
             state.change_to_candidate();
 
             return state;
@@ -648,17 +2483,19 @@ mod tests {
         let _ = runtime.block_on(async move {
             let raft_service = RaftService::new(inner_state.clone(), Arc::new(SystemClock::new()));
             let content = String::from("Content");
-            let command = Command { command: content.as_bytes().to_vec() };
+            let command = Command { command: content.as_bytes().to_vec(), client_id: None, sequence: 0 };
 
             let mut request = Request::new(AppendEntries {
                 term: 0,
                 leader_id: 30,
                 correlation_id: 10,
-                entry: Some(Entry {
+                entries: vec![Entry {
                     term: 0,
                     index: 1,
                     command: Some(command),
-                }),
+                    entry_type: EntryType::Command as i32,
+                    time: 0,
+                }],
                 previous_log_index: None,
                 previous_log_term: None,
                 leader_commit_index: None,
@@ -686,7 +2523,7 @@ mod tests {
         );
 
         let state = runtime.block_on(async move {
-            let state = State::new(Arc::new(replica), HeartbeatConfig::default());
+            let state = State::new(Arc::new(replica), RaftConfig::default());
             state.change_to_candidate();
 
             return state;
@@ -696,17 +2533,19 @@ mod tests {
         let _ = runtime.block_on(async move {
             let raft_service = RaftService::new(inner_state.clone(), Arc::new(SystemClock::new()));
             let content = String::from("Content");
-            let command = Command { command: content.as_bytes().to_vec() };
+            let command = Command { command: content.as_bytes().to_vec(), client_id: None, sequence: 0 };
 
             let mut request = Request::new(AppendEntries {
                 term: 3,
                 leader_id: 30,
                 correlation_id: 10,
-                entry: Some(Entry {
+                entries: vec![Entry {
                     term: 3,
                     index: 1,
                     command: Some(command),
-                }),
+                    entry_type: EntryType::Command as i32,
+                    time: 0,
+                }],
                 previous_log_index: None,
                 previous_log_term: None,
                 leader_commit_index: None,
@@ -734,24 +2573,26 @@ mod tests {
         );
 
         let state = runtime.block_on(async move {
-            return State::new(Arc::new(replica), HeartbeatConfig::default());
+            return State::new(Arc::new(replica), RaftConfig::default());
         });
 
         let inner_state = state.clone();
         let _ = runtime.block_on(async move {
             let raft_service = RaftService::new(inner_state.clone(), Arc::new(SystemClock::new()));
             let content = String::from("Content");
-            let command = Command { command: content.as_bytes().to_vec() };
+            let command = Command { command: content.as_bytes().to_vec(), client_id: None, sequence: 0 };
 
             let mut request = Request::new(AppendEntries {
                 term: 1,
                 leader_id: 30,
                 correlation_id: 10,
-                entry: Some(Entry {
+                entries: vec![Entry {
                     term: 1,
                     index: 1,
                     command: Some(command),
-                }),
+                    entry_type: EntryType::Command as i32,
+                    time: 0,
+                }],
                 previous_log_index: Some(0),
                 previous_log_term: Some(0),
                 leader_commit_index: None,
@@ -779,9 +2620,9 @@ mod tests {
         );
 
         let state = runtime.block_on(async move {
-            let state = State::new(Arc::new(replica), HeartbeatConfig::default());
+            let state = State::new(Arc::new(replica), RaftConfig::default());
             let content = String::from("anything");
-            let command = Command { command: content.as_bytes().to_vec() };
+            let command = Command { command: content.as_bytes().to_vec(), client_id: None, sequence: 0 };
             let term = state.get_term();
 
             state.get_replicated_log().append_command(&command, term);
@@ -792,17 +2633,19 @@ mod tests {
         let _ = runtime.block_on(async move {
             let raft_service = RaftService::new(inner_state.clone(), Arc::new(SystemClock::new()));
             let content = String::from("Content");
-            let command = Command { command: content.as_bytes().to_vec() };
+            let command = Command { command: content.as_bytes().to_vec(), client_id: None, sequence: 0 };
 
             let mut request = Request::new(AppendEntries {
                 term: 1,
                 leader_id: 30,
                 correlation_id: 10,
-                entry: Some(Entry {
+                entries: vec![Entry {
                     term: 1,
                     index: 1,
                     command: Some(command),
-                }),
+                    entry_type: EntryType::Command as i32,
+                    time: 0,
+                }],
                 previous_log_index: Some(0),
                 previous_log_term: Some(0),
                 leader_commit_index: None,
@@ -836,9 +2679,9 @@ mod tests {
         );
 
         let state = runtime.block_on(async move {
-            let state = State::new(Arc::new(replica), HeartbeatConfig::default());
+            let state = State::new(Arc::new(replica), RaftConfig::default());
             let content = String::from("anything");
-            let command = Command { command: content.as_bytes().to_vec() };
+            let command = Command { command: content.as_bytes().to_vec(), client_id: None, sequence: 0 };
             let term = state.get_term();
 
             state.get_replicated_log().append_command(&command, term);
@@ -849,17 +2692,19 @@ mod tests {
         let _ = runtime.block_on(async move {
             let raft_service = RaftService::new(inner_state.clone(), Arc::new(SystemClock::new()));
             let content = String::from("Content");
-            let command = Command { command: content.as_bytes().to_vec() };
+            let command = Command { command: content.as_bytes().to_vec(), client_id: None, sequence: 0 };
 
             let mut request = Request::new(AppendEntries {
                 term: 1,
                 leader_id: 30,
                 correlation_id: 10,
-                entry: Some(Entry {
+                entries: vec![Entry {
                     term: 1,
                     index: 1,
                     command: Some(command),
-                }),
+                    entry_type: EntryType::Command as i32,
+                    time: 0,
+                }],
                 previous_log_index: Some(0),
                 previous_log_term: Some(0),
                 leader_commit_index: Some(0),
@@ -880,6 +2725,239 @@ mod tests {
         assert_eq!(Some(0), state.get_replicated_log().get_commit_index());
     }
 
+    #[test]
+    fn acknowledge_replicate_log_with_multiple_entries() {
+        let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2060);
+        let peers = vec![HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2061)];
+
+        let runtime = Builder::new_multi_thread().worker_threads(4).enable_all().build().unwrap();
+        let replica = Replica::new(
+            30,
+            self_host_and_port.clone(),
+            peers,
+            Arc::new(SystemClock::new()),
+        );
+
+        let state = runtime.block_on(async move {
+            return State::new(Arc::new(replica), RaftConfig::default());
+        });
+
+        let inner_state = state.clone();
+        let _ = runtime.block_on(async move {
+            let raft_service = RaftService::new(inner_state.clone(), Arc::new(SystemClock::new()));
+            let entries = vec!["replicate", "raft", "log"].into_iter().enumerate().map(|(index, content)| Entry {
+                term: 1,
+                index: index as u64,
+                command: Some(Command { command: content.as_bytes().to_vec(), client_id: None, sequence: 0 }),
+                entry_type: EntryType::Command as i32,
+                time: 0,
+            }).collect();
+
+            let mut request = Request::new(AppendEntries {
+                term: 1,
+                leader_id: 30,
+                correlation_id: 10,
+                entries,
+                previous_log_index: None,
+                previous_log_term: None,
+                leader_commit_index: None,
+            });
+            request.add_host_port(self_host_and_port);
+
+            let _ = raft_service.acknowledge_replicate_log(request).await;
+        });
+
+        thread::sleep(Duration::from_millis(20));
+
+        let replicated_log = state.get_replicated_log();
+        assert_eq!(3, replicated_log.total_log_entries());
+        assert_eq!(String::from("replicate").as_bytes().to_vec(), replicated_log.get_log_entry_at(0).unwrap().get_bytes_as_vec());
+        assert_eq!(String::from("log").as_bytes().to_vec(), replicated_log.get_log_entry_at(2).unwrap().get_bytes_as_vec());
+        assert_eq!(1, replicated_log.get_log_entry_at(2).unwrap().get_term());
+    }
+
+    #[test]
+    fn acknowledge_replicate_log_and_discard_the_uncommitted_entries_of_a_deposed_leader() {
+        let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2060);
+        let peers = vec![HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2061)];
+
+        let runtime = Builder::new_multi_thread().worker_threads(4).enable_all().build().unwrap();
+        let replica = Replica::new(
+            30,
+            self_host_and_port.clone(),
+            peers,
+            Arc::new(SystemClock::new()),
+        );
+
+        let state = runtime.block_on(async move {
+            let state = State::new(Arc::new(replica), RaftConfig::default());
+            state.change_to_candidate();
+            state.clone().change_to_leader();
+
+            let command = Command { command: String::from("uncommitted").as_bytes().to_vec(), client_id: None, sequence: 0 };
+            for _count in 1..=3 {
+                state.get_replicated_log().append_command(&command, 1);
+            }
+            return state;
+        });
+
+        let inner_state = state.clone();
+        let _ = runtime.block_on(async move {
+            let raft_service = RaftService::new(inner_state.clone(), Arc::new(SystemClock::new()));
+            let content = String::from("Content");
+            let command = Command { command: content.as_bytes().to_vec(), client_id: None, sequence: 0 };
+
+            let mut request = Request::new(AppendEntries {
+                term: 2,
+                leader_id: 10,
+                correlation_id: 10,
+                entries: vec![Entry {
+                    term: 2,
+                    index: 1,
+                    command: Some(command),
+                    entry_type: EntryType::Command as i32,
+                    time: 0,
+                }],
+                previous_log_index: Some(0),
+                previous_log_term: Some(1),
+                leader_commit_index: Some(1),
+            });
+            request.add_host_port(self_host_and_port);
+
+            let _ = raft_service.acknowledge_replicate_log(request).await;
+        });
+
+        thread::sleep(Duration::from_millis(20));
+
+        assert_eq!(ReplicaRole::Follower, state.get_role());
+        assert_eq!(2, state.get_replicated_log().total_log_entries());
+        let log_entry = state.get_replicated_log().get_log_entry_at(1).unwrap();
+
+        assert_eq!(2, log_entry.get_term());
+        assert_eq!(String::from("Content").as_bytes().to_vec(), log_entry.get_bytes_as_vec());
+        assert_eq!(None, state.get_replicated_log().get_log_entry_at(2));
+        assert_eq!(Some(1), state.get_replicated_log().get_commit_index());
+    }
+
+    #[test]
+    fn acknowledge_replicate_log_and_never_apply_an_entry_that_is_later_overwritten() {
+        let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2060);
+        let peers = vec![HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2061)];
+
+        let runtime = Builder::new_multi_thread().worker_threads(4).enable_all().build().unwrap();
+        let replica = Replica::new(
+            30,
+            self_host_and_port.clone(),
+            peers,
+            Arc::new(SystemClock::new()),
+        );
+
+        let state = runtime.block_on(async move {
+            let state = State::new(Arc::new(replica), RaftConfig::default());
+            let command = Command { command: String::from("term-1").as_bytes().to_vec(), client_id: None, sequence: 0 };
+            for _count in 1..=4 {
+                state.get_replicated_log().append_command(&command, 1);
+            }
+            return state;
+        });
+
+        let append_entries = |entries: Vec<(u64, &str)>, previous_log_index: u64, previous_log_term: u64, correlation_id: u64| {
+            let entries = entries.into_iter().map(|(index, content)| Entry {
+                term: 2,
+                index,
+                command: Some(Command { command: content.as_bytes().to_vec(), client_id: None, sequence: 0 }),
+                entry_type: EntryType::Command as i32,
+                time: 0,
+            }).collect();
+            let mut request = Request::new(AppendEntries {
+                term: 2,
+                leader_id: 10,
+                correlation_id,
+                entries,
+                previous_log_index: Some(previous_log_index),
+                previous_log_term: Some(previous_log_term),
+                leader_commit_index: Some(3),
+            });
+            request.add_host_port(self_host_and_port);
+            return request;
+        };
+
+        //the leader of term 2 has the entries 0..=1 of term 1 and the entries 2..=3 of its own term, all of them committed.
+        //a partial batch carries no entries after the previous entry 1, the follower's entries 2 and 3 are stale entries of term 1
+        let inner_state = state.clone();
+        let request = append_entries(vec![], 1, 1, 10);
+        runtime.block_on(async move {
+            let raft_service = RaftService::new(inner_state, Arc::new(SystemClock::new()));
+            let _ = raft_service.acknowledge_replicate_log(request).await;
+        });
+        thread::sleep(Duration::from_millis(20));
+
+        assert_eq!(Some(1), state.get_replicated_log().get_commit_index());
+        assert_eq!(Some(1), state.get_last_applied());
+
+        let inner_state = state.clone();
+        let request = append_entries(vec![(2, "term-2"), (3, "term-2")], 1, 1, 20);
+        runtime.block_on(async move {
+            let raft_service = RaftService::new(inner_state, Arc::new(SystemClock::new()));
+            let _ = raft_service.acknowledge_replicate_log(request).await;
+        });
+        thread::sleep(Duration::from_millis(20));
+
+        assert_eq!(Some(3), state.get_replicated_log().get_commit_index());
+        assert_eq!(Some(3), state.get_last_applied());
+        assert_eq!(2, state.get_replicated_log().get_log_entry_at(2).unwrap().get_term());
+        assert_eq!(2, state.get_replicated_log().get_log_entry_at(3).unwrap().get_term());
+    }
+
+    #[test]
+    fn acknowledge_replicate_log_with_a_duplicate_entry() {
+        let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2060);
+        let peers = vec![HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2061)];
+
+        let runtime = Builder::new_multi_thread().worker_threads(4).enable_all().build().unwrap();
+        let replica = Replica::new(
+            30,
+            self_host_and_port.clone(),
+            peers,
+            Arc::new(SystemClock::new()),
+        );
+
+        let state = runtime.block_on(async move {
+            return State::new(Arc::new(replica), RaftConfig::default());
+        });
+
+        let inner_state = state.clone();
+        let _ = runtime.block_on(async move {
+            let raft_service = RaftService::new(inner_state.clone(), Arc::new(SystemClock::new()));
+            for _count in 1..=2 {
+                let content = String::from("Content");
+                let command = Command { command: content.as_bytes().to_vec(), client_id: None, sequence: 0 };
+
+                let mut request = Request::new(AppendEntries {
+                    term: 0,
+                    leader_id: 10,
+                    correlation_id: 10,
+                    entries: vec![Entry {
+                        term: 0,
+                        index: 0,
+                        command: Some(command),
+                        entry_type: EntryType::Command as i32,
+                        time: 0,
+                    }],
+                    previous_log_index: None,
+                    previous_log_term: None,
+                    leader_commit_index: None,
+                });
+                request.add_host_port(self_host_and_port);
+
+                let _ = raft_service.acknowledge_replicate_log(request).await;
+            }
+        });
+
+        thread::sleep(Duration::from_millis(20));
+        assert_eq!(1, state.get_replicated_log().total_log_entries());
+    }
+
     #[test]
     fn finish_replicate_log_and_leader_steps_down() {
         let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2060);
@@ -894,7 +2972,7 @@ mod tests {
         );
 
         let state = runtime.block_on(async move {
-            let state = State::new(Arc::new(replica), HeartbeatConfig::default());
+            let state = State::new(Arc::new(replica), RaftConfig::default());
             state.clone().change_to_leader();
             return state;
         });
@@ -906,6 +2984,8 @@ mod tests {
                 term: 3,
                 success: false,
                 log_entry_index: Some(0),
+                conflict_term: None,
+                conflict_index: None,
                 correlation_id: 10,
             });
             response_from_peer_1.add_host_port(self_host_and_port);
@@ -932,9 +3012,9 @@ mod tests {
         );
 
         let state = runtime.block_on(async move {
-            let state = State::new(Arc::new(replica), HeartbeatConfig::default());
+            let state = State::new(Arc::new(replica), RaftConfig::default());
             let content = String::from("anything");
-            let command = Command { command: content.as_bytes().to_vec() };
+            let command = Command { command: content.as_bytes().to_vec(), client_id: None, sequence: 0 };
             let term = state.get_term();
             state.get_replicated_log().append_command(&command, term);
 
@@ -949,6 +3029,8 @@ mod tests {
                 term: 3,
                 success: false,
                 log_entry_index: Some(0),
+                conflict_term: None,
+                conflict_index: None,
                 correlation_id: 10,
             });
             response_from_peer_1.add_host_port(peer_host_and_port);
@@ -958,6 +3040,8 @@ mod tests {
                 term: 0,
                 success: true,
                 log_entry_index: Some(0),
+                conflict_term: None,
+                conflict_index: None,
                 correlation_id: 10,
             });
             response_from_peer_2.add_host_port(peer_host_and_port);
@@ -967,6 +3051,8 @@ mod tests {
                 term: 0,
                 success: true,
                 log_entry_index: Some(0),
+                conflict_term: None,
+                conflict_index: None,
                 correlation_id: 10,
             });
             response_from_peer_3.add_host_port(peer_host_and_port);
@@ -993,7 +3079,7 @@ mod tests {
         );
 
         let state = runtime.block_on(async move {
-            let state = State::new(Arc::new(replica), HeartbeatConfig::default());
+            let state = State::new(Arc::new(replica), RaftConfig::default());
             state.clone().change_to_leader();
             return state;
         });
@@ -1005,6 +3091,8 @@ mod tests {
                 term: 0,
                 success: true,
                 log_entry_index: Some(0),
+                conflict_term: None,
+                conflict_index: None,
                 correlation_id: 10,
             });
             response_from_peer_1.add_host_port(self_host_and_port);
@@ -1030,9 +3118,9 @@ mod tests {
         );
 
         let state = runtime.block_on(async move {
-            let state = State::new(Arc::new(replica), HeartbeatConfig::default());
+            let state = State::new(Arc::new(replica), RaftConfig::default());
             let content = String::from("anything");
-            let command = Command { command: content.as_bytes().to_vec() };
+            let command = Command { command: content.as_bytes().to_vec(), client_id: None, sequence: 0 };
             let term = state.get_term();
 
             state.get_replicated_log().append_command(&command, term);
@@ -1047,6 +3135,8 @@ mod tests {
                 term: 1,
                 success: true,
                 log_entry_index: Some(0),
+                conflict_term: None,
+                conflict_index: None,
                 correlation_id: 10,
             });
             response_from_peer_1.add_host_port(self_host_and_port);
@@ -1060,7 +3150,9 @@ mod tests {
     #[test]
     fn finish_replicate_log_and_commit() {
         let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2060);
-        let peers = vec![HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2061)];
+        let peer_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2061);
+        let peer_other_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 2062);
+        let peers = vec![peer_host_and_port, peer_other_host_and_port];
 
         let runtime = Builder::new_multi_thread().worker_threads(4).enable_all().build().unwrap();
         let replica = Replica::new(
@@ -1071,9 +3163,9 @@ mod tests {
         );
 
         let state = runtime.block_on(async move {
-            let state = State::new(Arc::new(replica), HeartbeatConfig::default());
+            let state = State::new(Arc::new(replica), RaftConfig::default());
             let content = String::from("anything");
-            let command = Command { command: content.as_bytes().to_vec() };
+            let command = Command { command: content.as_bytes().to_vec(), client_id: None, sequence: 0 };
             let term = state.get_term();
 
             state.get_replicated_log().append_command(&command, term);
@@ -1088,18 +3180,22 @@ mod tests {
                 term: 0,
                 success: true,
                 log_entry_index: Some(0),
+                conflict_term: None,
+                conflict_index: None,
                 correlation_id: 10,
             });
-            response_from_peer_1.add_host_port(self_host_and_port);
+            response_from_peer_1.add_host_port(peer_host_and_port);
             let _ = raft_service.finish_replicate_log(response_from_peer_1).await;
 
             let mut response_from_peer_2 = Request::new(AppendEntriesResponse {
                 term: 0,
                 success: true,
                 log_entry_index: Some(0),
+                conflict_term: None,
+                conflict_index: None,
                 correlation_id: 10,
             });
-            response_from_peer_2.add_host_port(self_host_and_port);
+            response_from_peer_2.add_host_port(peer_other_host_and_port);
             let _ = raft_service.finish_replicate_log(response_from_peer_2).await;
         });
 
@@ -1122,9 +3218,9 @@ mod tests {
         );
 
         let state = runtime.block_on(async move {
-            let state = State::new(Arc::new(replica), HeartbeatConfig::default());
+            let state = State::new(Arc::new(replica), RaftConfig::default());
             let content = String::from("anything");
-            let command = Command { command: content.as_bytes().to_vec() };
+            let command = Command { command: content.as_bytes().to_vec(), client_id: None, sequence: 0 };
             let term = state.get_term();
 
             state.get_replicated_log().append_command(&command, term);
@@ -1139,6 +3235,8 @@ mod tests {
                 term: 0,
                 success: false,
                 log_entry_index: Some(0),
+                conflict_term: None,
+                conflict_index: None,
                 correlation_id: 10,
             });
             response_from_peer_1.add_host_port(peer_host_and_port);
@@ -1148,6 +3246,8 @@ mod tests {
                 term: 0,
                 success: true,
                 log_entry_index: Some(0),
+                conflict_term: None,
+                conflict_index: None,
                 correlation_id: 10,
             });
             response_from_peer_2.add_host_port(peer_host_and_port);
@@ -1157,6 +3257,8 @@ mod tests {
                 term: 0,
                 success: true,
                 log_entry_index: Some(0),
+                conflict_term: None,
+                conflict_index: None,
                 correlation_id: 10,
             });
             response_from_peer_3.add_host_port(peer_host_and_port);
@@ -1166,4 +3268,5 @@ mod tests {
         thread::sleep(Duration::from_millis(20));
         assert_eq!(0, state.get_replicated_log().get_commit_index().unwrap());
     }
-}
\ No newline at end of file
+
+}
@@ -0,0 +1,424 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::ops::RangeInclusive;
+use std::time::Duration;
+
+use replicate::net::request_waiting_list::request_waiting_list_config::RequestWaitingListConfig;
+
+use crate::replication_config::ReplicationConfig;
+
+/// The configuration of a replica, built (and validated) by `RaftConfigBuilder`.
+#[derive(Clone, Debug)]
+pub struct RaftConfig {
+    heartbeat_interval: Duration,
+    election_timeout_range: RangeInclusive<Duration>,
+    random_seed: Option<u64>,
+    max_entries_per_append: usize,
+    max_bytes_per_append: usize,
+    max_in_flight_appends: usize,
+    forward_to_leader: bool,
+    request_expiry: Duration,
+    pre_vote: bool,
+    check_quorum: bool,
+    leader_lease_clock_drift_bound: Option<Duration>,
+    client_session_timeout: Duration,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum RaftConfigError {
+    ZeroHeartbeatInterval,
+    EmptyElectionTimeoutRange(RangeInclusive<Duration>),
+    ElectionTimeoutNotAboveHeartbeatInterval(Duration, Duration),
+    ZeroMaxEntriesPerAppend,
+    ZeroMaxBytesPerAppend,
+    ZeroMaxInFlightAppends,
+    ZeroRequestExpiry,
+    ClockDriftBoundNotBelowElectionTimeout(Duration, Duration),
+    ZeroClientSessionTimeout,
+}
+
+impl Display for RaftConfigError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RaftConfigError::ZeroHeartbeatInterval =>
+                write!(f, "heartbeat interval must be greater than zero"),
+            RaftConfigError::EmptyElectionTimeoutRange(range) =>
+                write!(f, "election timeout range {:?} is empty", range),
+            RaftConfigError::ElectionTimeoutNotAboveHeartbeatInterval(minimum_election_timeout, heartbeat_interval) =>
+                write!(f, "minimum election timeout {:?} must be greater than the heartbeat interval {:?}", minimum_election_timeout, heartbeat_interval),
+            RaftConfigError::ZeroMaxEntriesPerAppend =>
+                write!(f, "max entries per append must be greater than zero"),
+            RaftConfigError::ZeroMaxBytesPerAppend =>
+                write!(f, "max bytes per append must be greater than zero"),
+            RaftConfigError::ZeroMaxInFlightAppends =>
+                write!(f, "max in-flight appends must be greater than zero"),
+            RaftConfigError::ZeroRequestExpiry =>
+                write!(f, "request expiry must be greater than zero"),
+            RaftConfigError::ClockDriftBoundNotBelowElectionTimeout(clock_drift_bound, minimum_election_timeout) =>
+                write!(f, "clock drift bound {:?} must be less than the minimum election timeout {:?}", clock_drift_bound, minimum_election_timeout),
+            RaftConfigError::ZeroClientSessionTimeout =>
+                write!(f, "client session timeout must be greater than zero"),
+        }
+    }
+}
+
+impl Error for RaftConfigError {}
+
+impl RaftConfig {
+    const DEFAULT_HEARTBEAT_INTERVAL: Duration = Duration::from_millis(50);
+    const DEFAULT_ELECTION_TIMEOUT_RANGE_MS: RangeInclusive<u64> = 150..=300;
+    const DEFAULT_MAX_ENTRIES_PER_APPEND: usize = 64;
+    const DEFAULT_MAX_BYTES_PER_APPEND: usize = 1024 * 1024;
+    const DEFAULT_MAX_IN_FLIGHT_APPENDS: usize = 4;
+    const DEFAULT_REQUEST_EXPIRY: Duration = Duration::from_secs(3);
+    const DEFAULT_CLIENT_SESSION_TIMEOUT: Duration = Duration::from_secs(60 * 60);
+
+    pub fn default() -> Self {
+        return RaftConfig::builder().build().unwrap();
+    }
+
+    pub fn builder() -> RaftConfigBuilder {
+        return RaftConfigBuilder {
+            config: RaftConfig {
+                heartbeat_interval: Self::DEFAULT_HEARTBEAT_INTERVAL,
+                election_timeout_range: Duration::from_millis(*Self::DEFAULT_ELECTION_TIMEOUT_RANGE_MS.start())..=
+                    Duration::from_millis(*Self::DEFAULT_ELECTION_TIMEOUT_RANGE_MS.end()),
+                random_seed: None,
+                max_entries_per_append: Self::DEFAULT_MAX_ENTRIES_PER_APPEND,
+                max_bytes_per_append: Self::DEFAULT_MAX_BYTES_PER_APPEND,
+                max_in_flight_appends: Self::DEFAULT_MAX_IN_FLIGHT_APPENDS,
+                forward_to_leader: false,
+                request_expiry: Self::DEFAULT_REQUEST_EXPIRY,
+                pre_vote: false,
+                check_quorum: true,
+                leader_lease_clock_drift_bound: None,
+                client_session_timeout: Self::DEFAULT_CLIENT_SESSION_TIMEOUT,
+            }
+        };
+    }
+
+    pub fn get_heartbeat_interval(&self) -> Duration {
+        return self.heartbeat_interval;
+    }
+
+    pub fn get_election_timeout_range(&self) -> &RangeInclusive<Duration> {
+        return &self.election_timeout_range;
+    }
+
+    pub fn get_random_seed(&self) -> Option<u64> {
+        return self.random_seed;
+    }
+
+    pub fn get_max_entries_per_append(&self) -> usize {
+        return self.max_entries_per_append;
+    }
+
+    pub fn get_max_bytes_per_append(&self) -> usize {
+        return self.max_bytes_per_append;
+    }
+
+    pub fn get_max_in_flight_appends(&self) -> usize {
+        return self.max_in_flight_appends;
+    }
+
+    pub fn is_forwarding_to_leader_enabled(&self) -> bool {
+        return self.forward_to_leader;
+    }
+
+    pub fn get_request_expiry(&self) -> Duration {
+        return self.request_expiry;
+    }
+
+    pub fn is_pre_vote_enabled(&self) -> bool {
+        return self.pre_vote;
+    }
+
+    pub fn is_check_quorum_enabled(&self) -> bool {
+        return self.check_quorum;
+    }
+
+    pub fn get_leader_lease_clock_drift_bound(&self) -> Option<Duration> {
+        return self.leader_lease_clock_drift_bound;
+    }
+
+    pub fn get_client_session_timeout(&self) -> Duration {
+        return self.client_session_timeout;
+    }
+
+    /// The waiting list config for the requests of the replica, requests without a response expire after the request expiry.
+    pub fn get_request_waiting_list_config(&self) -> RequestWaitingListConfig {
+        let pause_request_expiry_checker = RequestWaitingListConfig::default().get_pause_request_expiry_checker();
+        return RequestWaitingListConfig::new(self.request_expiry, pause_request_expiry_checker.min(self.request_expiry));
+    }
+
+    pub(crate) fn get_replication_config(&self) -> ReplicationConfig {
+        return ReplicationConfig::new(self);
+    }
+}
+
+pub struct RaftConfigBuilder {
+    config: RaftConfig,
+}
+
+impl RaftConfigBuilder {
+    /// The interval of the leader's heartbeats, there is no upper bound, so the interval can fit slow (WAN) links.
+    pub fn with_heartbeat_interval(mut self, heartbeat_interval: Duration) -> Self {
+        self.config.heartbeat_interval = heartbeat_interval;
+        return self;
+    }
+
+    /// The range that the election timeout is drawn from, a new timeout is drawn for every election round.
+    pub fn with_election_timeout_range(mut self, election_timeout_range: RangeInclusive<Duration>) -> Self {
+        self.config.election_timeout_range = election_timeout_range;
+        return self;
+    }
+
+    /// Seeds the random draws of the election timeout, the same seed draws the same sequence of timeouts.
+    pub fn with_random_seed(mut self, random_seed: u64) -> Self {
+        self.config.random_seed = Some(random_seed);
+        return self;
+    }
+
+    /// The maximum number of log entries that the leader sends to a peer in a single append entries request.
+    pub fn with_max_entries_per_append(mut self, max_entries_per_append: usize) -> Self {
+        self.config.max_entries_per_append = max_entries_per_append;
+        return self;
+    }
+
+    /// The maximum size of the log entries that the leader sends to a peer in a single append entries request.
+    /// A request always carries at least one entry, even if that entry alone exceeds the size.
+    pub fn with_max_bytes_per_append(mut self, max_bytes_per_append: usize) -> Self {
+        self.config.max_bytes_per_append = max_bytes_per_append;
+        return self;
+    }
+
+    /// The number of append entries requests that the leader pipelines to a peer without waiting for their responses.
+    pub fn with_max_in_flight_appends(mut self, max_in_flight_appends: usize) -> Self {
+        self.config.max_in_flight_appends = max_in_flight_appends;
+        return self;
+    }
+
+    /// Lets a follower forward the commands it receives to the leader and relay the result back,
+    /// instead of rejecting them with the leader's address.
+    pub fn with_forwarding_to_leader(mut self) -> Self {
+        self.config.forward_to_leader = true;
+        return self;
+    }
+
+    /// The duration after which a request without a response expires.
+    pub fn with_request_expiry(mut self, request_expiry: Duration) -> Self {
+        self.config.request_expiry = request_expiry;
+        return self;
+    }
+
+    /// Runs a pre-vote round before every election, the term is incremented only if a majority would grant the vote.
+    pub fn with_pre_vote(mut self) -> Self {
+        self.config.pre_vote = true;
+        return self;
+    }
+
+    /// Keeps a leader that has not heard from a majority within an election timeout from stepping down.
+    pub fn without_check_quorum(mut self) -> Self {
+        self.config.check_quorum = false;
+        return self;
+    }
+
+    /// Lets the leader serve reads locally while it holds a lease. The lease starts when a majority acknowledges a heartbeat round
+    /// and lasts the minimum election timeout less the clock drift bound, followers do not vote while they hear from a live leader.
+    pub fn with_leader_lease(mut self, clock_drift_bound: Duration) -> Self {
+        self.config.leader_lease_clock_drift_bound = Some(clock_drift_bound);
+        return self;
+    }
+
+    /// The log time after which the session of an inactive client expires, along with the response of its last sequence.
+    pub fn with_client_session_timeout(mut self, client_session_timeout: Duration) -> Self {
+        self.config.client_session_timeout = client_session_timeout;
+        return self;
+    }
+
+    pub fn build(self) -> Result<RaftConfig, RaftConfigError> {
+        let config = self.config;
+        if config.heartbeat_interval.is_zero() {
+            return Err(RaftConfigError::ZeroHeartbeatInterval);
+        }
+        if config.election_timeout_range.is_empty() {
+            return Err(RaftConfigError::EmptyElectionTimeoutRange(config.election_timeout_range));
+        }
+        let minimum_election_timeout = *config.election_timeout_range.start();
+        if minimum_election_timeout.le(&config.heartbeat_interval) {
+            return Err(RaftConfigError::ElectionTimeoutNotAboveHeartbeatInterval(minimum_election_timeout, config.heartbeat_interval));
+        }
+        if config.max_entries_per_append == 0 {
+            return Err(RaftConfigError::ZeroMaxEntriesPerAppend);
+        }
+        if config.max_bytes_per_append == 0 {
+            return Err(RaftConfigError::ZeroMaxBytesPerAppend);
+        }
+        if config.max_in_flight_appends == 0 {
+            return Err(RaftConfigError::ZeroMaxInFlightAppends);
+        }
+        if config.request_expiry.is_zero() {
+            return Err(RaftConfigError::ZeroRequestExpiry);
+        }
+        if let Some(clock_drift_bound) = config.leader_lease_clock_drift_bound {
+            if clock_drift_bound.ge(&minimum_election_timeout) {
+                return Err(RaftConfigError::ClockDriftBoundNotBelowElectionTimeout(clock_drift_bound, minimum_election_timeout));
+            }
+        }
+        if config.client_session_timeout.is_zero() {
+            return Err(RaftConfigError::ZeroClientSessionTimeout);
+        }
+        return Ok(config);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::raft_config::{RaftConfig, RaftConfigError};
+
+    #[test]
+    fn default_raft_config() {
+        let raft_config = RaftConfig::default();
+
+        assert_eq!(Duration::from_millis(50), raft_config.get_heartbeat_interval());
+        assert_eq!(&(Duration::from_millis(150)..=Duration::from_millis(300)), raft_config.get_election_timeout_range());
+        assert_eq!(None, raft_config.get_random_seed());
+        assert_eq!(64, raft_config.get_max_entries_per_append());
+        assert_eq!(1024 * 1024, raft_config.get_max_bytes_per_append());
+        assert_eq!(4, raft_config.get_max_in_flight_appends());
+        assert_eq!(false, raft_config.is_forwarding_to_leader_enabled());
+        assert_eq!(Duration::from_secs(3), raft_config.get_request_expiry());
+        assert_eq!(false, raft_config.is_pre_vote_enabled());
+        assert!(raft_config.is_check_quorum_enabled());
+        assert_eq!(None, raft_config.get_leader_lease_clock_drift_bound());
+        assert_eq!(Duration::from_secs(60 * 60), raft_config.get_client_session_timeout());
+    }
+
+    #[test]
+    fn raft_config_for_slow_links() {
+        let raft_config = RaftConfig::builder()
+            .with_heartbeat_interval(Duration::from_millis(500))
+            .with_election_timeout_range(Duration::from_secs(2)..=Duration::from_secs(4))
+            .with_random_seed(7)
+            .with_max_entries_per_append(256)
+            .with_request_expiry(Duration::from_secs(10))
+            .with_client_session_timeout(Duration::from_secs(10 * 60))
+            .with_pre_vote()
+            .without_check_quorum()
+            .build()
+            .unwrap();
+
+        assert_eq!(Duration::from_millis(500), raft_config.get_heartbeat_interval());
+        assert_eq!(&(Duration::from_secs(2)..=Duration::from_secs(4)), raft_config.get_election_timeout_range());
+        assert_eq!(Some(7), raft_config.get_random_seed());
+        assert_eq!(256, raft_config.get_max_entries_per_append());
+        assert_eq!(Duration::from_secs(10), raft_config.get_request_expiry());
+        assert_eq!(Duration::from_secs(10 * 60), raft_config.get_client_session_timeout());
+        assert!(raft_config.is_pre_vote_enabled());
+        assert_eq!(false, raft_config.is_check_quorum_enabled());
+    }
+
+    #[test]
+    fn raft_config_with_zero_heartbeat_interval() {
+        let result = RaftConfig::builder().with_heartbeat_interval(Duration::ZERO).build();
+
+        assert_eq!(RaftConfigError::ZeroHeartbeatInterval, result.unwrap_err());
+    }
+
+    #[test]
+    fn raft_config_with_empty_election_timeout_range() {
+        let result = RaftConfig::builder()
+            .with_election_timeout_range(Duration::from_millis(300)..=Duration::from_millis(150))
+            .build();
+
+        assert_eq!(
+            RaftConfigError::EmptyElectionTimeoutRange(Duration::from_millis(300)..=Duration::from_millis(150)),
+            result.unwrap_err()
+        );
+    }
+
+    #[test]
+    fn raft_config_with_election_timeout_not_above_heartbeat_interval() {
+        let result = RaftConfig::builder()
+            .with_heartbeat_interval(Duration::from_millis(200))
+            .build();
+
+        assert_eq!(
+            RaftConfigError::ElectionTimeoutNotAboveHeartbeatInterval(Duration::from_millis(150), Duration::from_millis(200)),
+            result.unwrap_err()
+        );
+    }
+
+    #[test]
+    fn raft_config_with_zero_max_entries_per_append() {
+        let result = RaftConfig::builder().with_max_entries_per_append(0).build();
+
+        assert_eq!(RaftConfigError::ZeroMaxEntriesPerAppend, result.unwrap_err());
+    }
+
+    #[test]
+    fn raft_config_with_zero_max_bytes_per_append() {
+        let result = RaftConfig::builder().with_max_bytes_per_append(0).build();
+
+        assert_eq!(RaftConfigError::ZeroMaxBytesPerAppend, result.unwrap_err());
+    }
+
+    #[test]
+    fn raft_config_with_zero_max_in_flight_appends() {
+        let result = RaftConfig::builder().with_max_in_flight_appends(0).build();
+
+        assert_eq!(RaftConfigError::ZeroMaxInFlightAppends, result.unwrap_err());
+    }
+
+    #[test]
+    fn raft_config_with_zero_request_expiry() {
+        let result = RaftConfig::builder().with_request_expiry(Duration::ZERO).build();
+
+        assert_eq!(RaftConfigError::ZeroRequestExpiry, result.unwrap_err());
+    }
+
+    #[test]
+    fn raft_config_with_leader_lease_clock_drift_bound_not_below_minimum_election_timeout() {
+        let result = RaftConfig::builder().with_leader_lease(Duration::from_millis(150)).build();
+
+        assert_eq!(
+            RaftConfigError::ClockDriftBoundNotBelowElectionTimeout(Duration::from_millis(150), Duration::from_millis(150)),
+            result.unwrap_err()
+        );
+    }
+
+    #[test]
+    fn raft_config_with_zero_client_session_timeout() {
+        let result = RaftConfig::builder().with_client_session_timeout(Duration::ZERO).build();
+
+        assert_eq!(RaftConfigError::ZeroClientSessionTimeout, result.unwrap_err());
+    }
+
+    #[test]
+    fn raft_config_request_waiting_list_config() {
+        let raft_config = RaftConfig::builder().with_request_expiry(Duration::from_millis(500)).build().unwrap();
+        let request_waiting_list_config = raft_config.get_request_waiting_list_config();
+
+        assert_eq!(Duration::from_millis(500), request_waiting_list_config.get_request_expiry_after());
+        assert_eq!(Duration::from_millis(500), request_waiting_list_config.get_pause_request_expiry_checker());
+    }
+
+    #[test]
+    fn raft_config_replication_config() {
+        let raft_config = RaftConfig::builder()
+            .with_max_entries_per_append(16)
+            .with_max_bytes_per_append(2048)
+            .with_max_in_flight_appends(2)
+            .with_forwarding_to_leader()
+            .build()
+            .unwrap();
+        let replication_config = raft_config.get_replication_config();
+
+        assert_eq!(16, replication_config.get_max_entries_per_request());
+        assert_eq!(2048, replication_config.get_max_bytes_per_request());
+        assert_eq!(2, replication_config.get_max_in_flight_requests());
+        assert!(replication_config.is_forwarding_to_leader_enabled());
+    }
+}
@@ -0,0 +1,191 @@
+use std::sync::Arc;
+
+use tonic::Request;
+
+use replicate::clock::clock::SystemClock;
+use replicate::net::connect::host_and_port::HostAndPort;
+use replicate::net::connect::service_registration::{AllServicesShutdownHandle, ServiceRegistration};
+use replicate::net::replica::{Replica, ReplicaId};
+
+use crate::net::rpc::grpc::{Command, Query};
+use crate::net::rpc::grpc::raft_server::{Raft, RaftServer};
+use crate::net::service::raft_service::RaftService;
+use crate::raft_config::RaftConfig;
+use crate::state::{ReplicaRole, State};
+use crate::state_machine::StateMachine;
+use crate::storage::hard_state_storage::{HardStateStorage, InMemoryHardStateStorage};
+use crate::storage::log_store::{InMemoryLogStore, LogStore};
+use crate::storage::snapshot_storage::{InMemorySnapshotStorage, SnapshotStorage};
+
+/// The identity of a node, the addresses of its peers and the raft config it runs with.
+pub struct RaftNodeConfig {
+    replica_id: ReplicaId,
+    self_address: HostAndPort,
+    peers: Vec<HostAndPort>,
+    raft_config: RaftConfig,
+}
+
+impl RaftNodeConfig {
+    pub fn new(replica_id: ReplicaId, self_address: HostAndPort, peers: Vec<HostAndPort>) -> Self {
+        return RaftNodeConfig { replica_id, self_address, peers, raft_config: RaftConfig::default() };
+    }
+
+    pub fn with_raft_config(mut self, raft_config: RaftConfig) -> Self {
+        self.raft_config = raft_config;
+        return self;
+    }
+
+    pub fn get_replica_id(&self) -> ReplicaId {
+        return self.replica_id;
+    }
+
+    pub fn get_self_address(&self) -> HostAndPort {
+        return self.self_address;
+    }
+
+    pub fn get_peers(&self) -> &Vec<HostAndPort> {
+        return &self.peers;
+    }
+
+    pub fn get_raft_config(&self) -> &RaftConfig {
+        return &self.raft_config;
+    }
+}
+
+/// A point-in-time view of a node.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RaftNodeStatus {
+    replica_id: ReplicaId,
+    role: ReplicaRole,
+    term: u64,
+    leader_id: Option<ReplicaId>,
+    leader_address: Option<HostAndPort>,
+    commit_index: Option<u64>,
+    last_applied: Option<u64>,
+}
+
+impl RaftNodeStatus {
+    pub fn get_replica_id(&self) -> ReplicaId {
+        return self.replica_id;
+    }
+
+    pub fn get_role(&self) -> ReplicaRole {
+        return self.role;
+    }
+
+    pub fn is_leader(&self) -> bool {
+        return self.role == ReplicaRole::Leader;
+    }
+
+    pub fn get_term(&self) -> u64 {
+        return self.term;
+    }
+
+    pub fn get_leader_id(&self) -> Option<ReplicaId> {
+        return self.leader_id;
+    }
+
+    pub fn get_leader_address(&self) -> Option<HostAndPort> {
+        return self.leader_address;
+    }
+
+    pub fn get_commit_index(&self) -> Option<u64> {
+        return self.commit_index;
+    }
+
+    pub fn get_last_applied(&self) -> Option<u64> {
+        return self.last_applied;
+    }
+}
+
+/// A single raft node: owns the replica, its state, the raft service and the server that serves the service on the node's address.
+/// Proposals and reads are served only by the leader, others fail with the `NotLeader` details (see `NotLeader::from_status`).
+pub struct RaftNode {
+    state: Arc<State>,
+    raft_service: Arc<RaftService>,
+    all_services_shutdown_handle: AllServicesShutdownHandle,
+}
+
+impl RaftNode {
+    /// Starts a node with in-memory storage. The node joins an election once it does not hear from a leader within its election timeout.
+    pub async fn start(config: RaftNodeConfig, state_machine: Arc<dyn StateMachine>) -> RaftNode {
+        return Self::start_with_storage(
+            config,
+            Arc::new(InMemoryHardStateStorage::new()),
+            Box::new(InMemoryLogStore::new()),
+            Box::new(InMemorySnapshotStorage::new()),
+            state_machine,
+        ).await;
+    }
+
+    pub async fn start_with_storage(config: RaftNodeConfig,
+                                    hard_state_storage: Arc<dyn HardStateStorage>,
+                                    log_store: Box<dyn LogStore>,
+                                    snapshot_storage: Box<dyn SnapshotStorage>,
+                                    state_machine: Arc<dyn StateMachine>) -> RaftNode {
+        let clock = Arc::new(SystemClock::new());
+        let replica = Replica::new_with_waiting_list_config(
+            config.replica_id,
+            config.self_address,
+            config.peers,
+            clock.clone(),
+            config.raft_config.get_request_waiting_list_config(),
+        );
+        let state = State::new_with_storage(
+            Arc::new(replica),
+            config.raft_config,
+            hard_state_storage,
+            log_store,
+            snapshot_storage,
+            state_machine,
+        );
+        let raft_service = Arc::new(RaftService::new(state.clone(), clock));
+
+        let (all_services_shutdown_handle, all_services_shutdown_receiver) = AllServicesShutdownHandle::new();
+        let self_address = config.self_address;
+        let inner_raft_service = raft_service.clone();
+        tokio::spawn(async move {
+            ServiceRegistration::register_services_on(
+                &self_address,
+                RaftServer::from_arc(inner_raft_service),
+                all_services_shutdown_receiver,
+            ).await;
+        });
+
+        return RaftNode { state, raft_service, all_services_shutdown_handle };
+    }
+
+    /// Appends the command to the log and returns the output of applying it, once it is committed.
+    pub async fn propose(&self, command: Vec<u8>) -> Result<Vec<u8>, tonic::Status> {
+        let response = self.raft_service.execute(Request::new(Command { command, client_id: None, sequence: 0 })).await?;
+        return Ok(response.into_inner().result);
+    }
+
+    /// A linearizable read of the state machine (read index).
+    pub async fn read(&self, query: Vec<u8>) -> Result<Vec<u8>, tonic::Status> {
+        let response = self.raft_service.read_index(Request::new(Query { query })).await?;
+        return Ok(response.into_inner().result);
+    }
+
+    pub fn status(&self) -> RaftNodeStatus {
+        return RaftNodeStatus {
+            replica_id: self.state.get_replica_reference().get_id(),
+            role: self.state.get_role(),
+            term: self.state.get_term(),
+            leader_id: self.state.get_leader_id(),
+            leader_address: self.state.get_leader_address(),
+            commit_index: self.state.get_replicated_log().get_commit_index(),
+            last_applied: self.state.get_last_applied(),
+        };
+    }
+
+    /// Stops serving the raft service, and stops the heartbeats and the elections of the node.
+    pub async fn shutdown(&self) {
+        let _ = self.all_services_shutdown_handle.shutdown().await;
+        self.state.stop_heartbeats();
+    }
+
+    pub fn get_state(&self) -> Arc<State> {
+        return self.state.clone();
+    }
+}
@@ -1,32 +1,82 @@
 use std::sync::RwLock;
 
-use crate::log_entry::LogEntry;
+use crate::log_entry::{LogEntry, LogEntryType};
+use crate::membership::Membership;
 use crate::net::rpc::grpc::Command;
+use crate::storage::log_store::LogStore;
+use crate::storage::snapshot_storage::{Snapshot, SnapshotStorage};
 
 pub struct ReplicatedLog {
-    majority_quorum: usize,
     replicated_log_state: RwLock<ReplicatedLogState>,
 }
 
 struct ReplicatedLogState {
-    log_entries: Vec<LogEntry>,
+    log_store: Box<dyn LogStore>,
+    snapshot_storage: Box<dyn SnapshotStorage>,
+    snapshot: Option<Snapshot>,
     commit_index: Option<u64>,
+    memberships: Vec<(u64, Membership)>,
 }
 
 impl ReplicatedLog {
-    pub(crate) fn new(majority_quorum: usize) -> Self {
+    #[cfg(test)]
+    pub(crate) fn new() -> Self {
+        return Self::new_with_storage(
+            Box::new(crate::storage::log_store::InMemoryLogStore::new()),
+            Box::new(crate::storage::snapshot_storage::InMemorySnapshotStorage::new()),
+        );
+    }
+
+    pub(crate) fn new_with_storage(mut log_store: Box<dyn LogStore>, snapshot_storage: Box<dyn SnapshotStorage>) -> Self {
+        let snapshot = snapshot_storage.load().expect("failed to load the snapshot");
+        if let Some(snapshot) = &snapshot {
+            let log_store_behind_snapshot = match log_store.last_index() {
+                None => false,
+                Some(last_index) => last_index < snapshot.get_last_included_index()
+            };
+            if log_store_behind_snapshot {
+                log_store.truncate_suffix(0).expect("failed to discard the log entries behind the snapshot");
+            }
+        }
+        let commit_index = snapshot.as_ref().map(|snapshot| snapshot.get_last_included_index());
+        let memberships = Self::memberships_in(&*log_store);
+
+        let mut replicated_log_state = ReplicatedLogState {
+            log_store,
+            snapshot_storage,
+            snapshot,
+            commit_index,
+            memberships,
+        };
+        replicated_log_state.restore_snapshot_membership();
         return ReplicatedLog {
-            majority_quorum,
-            replicated_log_state: RwLock::new(ReplicatedLogState {
-                log_entries: Vec::new(),
-                commit_index: None,
-            }),
+            replicated_log_state: RwLock::new(replicated_log_state),
         };
     }
 
+    fn memberships_in(log_store: &dyn LogStore) -> Vec<(u64, Membership)> {
+        let (first_index, last_index) = match (log_store.first_index(), log_store.last_index()) {
+            (Some(first_index), Some(last_index)) => (first_index, last_index),
+            _ => return Vec::new()
+        };
+        return log_store
+            .read_range(first_index, last_index + 1)
+            .expect("failed to read the log entries from the log store")
+            .iter()
+            .filter(|log_entry| log_entry.is_configuration())
+            .filter_map(|log_entry| Membership::decode(&log_entry.get_bytes_as_vec()).map(|membership| (log_entry.get_index(), membership)))
+            .collect();
+    }
+
     pub(crate) fn matches_log_entry_term_at(&self, index: usize, term: u64) -> bool {
         let guard = self.replicated_log_state.read().unwrap();
-        return match (*guard).log_entries.get(index) {
+        if let Some(snapshot) = &(*guard).snapshot {
+            if snapshot.includes(index as u64) {
+                //entries covered by the snapshot are committed, and committed entries match the leader's log
+                return index as u64 != snapshot.get_last_included_index() || snapshot.get_last_included_term() == term;
+            }
+        }
+        return match (*guard).read_log_entry(index as u64) {
             None => false,
             Some(log_entry) => log_entry.matches_term(term)
         };
@@ -34,108 +84,385 @@ impl ReplicatedLog {
 
     pub(crate) fn get_log_term_at(&self, index: usize) -> Option<u64> {
         let guard = self.replicated_log_state.read().unwrap();
-        return match (*guard).log_entries.get(index) {
+        if let Some(snapshot) = &(*guard).snapshot {
+            if index as u64 == snapshot.get_last_included_index() {
+                return Some(snapshot.get_last_included_term());
+            }
+        }
+        return match (*guard).read_log_entry(index as u64) {
             None => None,
             Some(log_entry) => Some(log_entry.get_term())
         };
     }
 
-    pub(crate) fn acknowledge_log_entry_at(&self, index: usize) {
+    /// The hint for a leader whose previous log entry at `index` does not match this log: the term of the conflicting entry
+    /// along with the first index of that term, or no term along with the length of this log if it ends before `index`.
+    pub(crate) fn conflict_term_index_at(&self, index: u64) -> (Option<u64>, u64) {
+        let guard = self.replicated_log_state.read().unwrap();
+        let replicated_log_state = &*guard;
+        let conflict_term = match replicated_log_state.read_log_entry(index) {
+            None => return (None, (replicated_log_state.total_log_entries() as u64).min(index)),
+            Some(log_entry) => log_entry.get_term()
+        };
+        let mut conflict_index = index;
+        while conflict_index > 0 {
+            match replicated_log_state.read_log_entry(conflict_index - 1) {
+                Some(log_entry) if log_entry.matches_term(conflict_term) => conflict_index = conflict_index - 1,
+                _ => break
+            }
+        }
+        return (Some(conflict_term), conflict_index);
+    }
+
+    /// The index of the last entry of `term` in this log, none if the log has no entry of the term.
+    pub(crate) fn last_index_of_term(&self, term: u64) -> Option<u64> {
+        let guard = self.replicated_log_state.read().unwrap();
+        let replicated_log_state = &*guard;
+        let mut index = match replicated_log_state.last_log_index_term() {
+            (Some(last_log_index), _) => last_log_index,
+            (None, _) => return None
+        };
+        loop {
+            match replicated_log_state.read_log_entry(index) {
+                None => {
+                    return match &replicated_log_state.snapshot {
+                        Some(snapshot) if snapshot.get_last_included_index() == index && snapshot.get_last_included_term() == term => Some(index),
+                        _ => None
+                    };
+                }
+                Some(log_entry) => {
+                    if log_entry.matches_term(term) {
+                        return Some(index);
+                    }
+                    //terms never decrease along the log
+                    if log_entry.get_term() < term || index == 0 {
+                        return None;
+                    }
+                }
+            }
+            index = index - 1;
+        }
+    }
+
+    pub(crate) fn is_compacted(&self, index: u64) -> bool {
+        let guard = self.replicated_log_state.read().unwrap();
+        return (*guard).is_compacted(index);
+    }
+
+    pub fn compact(&self, last_included_index: u64, data: Vec<u8>) -> Option<Snapshot> {
+        return self.compact_with_client_sessions(last_included_index, data, Vec::new());
+    }
+
+    pub(crate) fn compact_with_client_sessions(&self, last_included_index: u64, data: Vec<u8>, client_sessions: Vec<u8>) -> Option<Snapshot> {
         let mut write_guard = self.replicated_log_state.write().unwrap();
         let replicated_log_state = &mut *write_guard;
 
-        let log_entry = &mut replicated_log_state.log_entries[index];
-        log_entry.acknowledge();
+        let committed = match replicated_log_state.commit_index {
+            None => false,
+            Some(commit_index) => last_included_index <= commit_index
+        };
+        if !committed || replicated_log_state.is_compacted(last_included_index) {
+            return None;
+        }
+        let last_included_term = match replicated_log_state.read_log_entry(last_included_index) {
+            None => return None,
+            Some(log_entry) => log_entry.get_term()
+        };
+
+        let mut snapshot = Snapshot::new(last_included_index, last_included_term, data).with_client_sessions(client_sessions);
+        if let Some((_, membership)) = replicated_log_state.memberships.iter().rev().find(|(index, _)| *index <= last_included_index) {
+            snapshot = snapshot.with_membership(membership.clone());
+        }
+        replicated_log_state.save_snapshot(&snapshot);
+        replicated_log_state.log_store
+            .truncate_prefix(last_included_index)
+            .expect("failed to compact the log store");
+        replicated_log_state.restore_snapshot_membership();
+        return Some(snapshot);
     }
 
-    pub(crate) fn is_entry_replicated(&self, index: usize) -> bool {
+    pub(crate) fn install_snapshot(&self, snapshot: Snapshot) -> bool {
+        let mut write_guard = self.replicated_log_state.write().unwrap();
+        let replicated_log_state = &mut *write_guard;
+
+        let last_included_index = snapshot.get_last_included_index();
+        if replicated_log_state.is_compacted(last_included_index) {
+            return false;
+        }
+        let retain_log_suffix = match replicated_log_state.read_log_entry(last_included_index) {
+            None => false,
+            Some(log_entry) => log_entry.matches_term(snapshot.get_last_included_term())
+        };
+
+        replicated_log_state.save_snapshot(&snapshot);
+        if retain_log_suffix {
+            replicated_log_state.log_store.truncate_prefix(last_included_index).expect("failed to compact the log store");
+        } else {
+            replicated_log_state.truncate_suffix(0);
+        }
+        replicated_log_state.restore_snapshot_membership();
+        let commit_index = replicated_log_state.commit_index;
+        if commit_index.is_none() || commit_index.unwrap() < last_included_index {
+            replicated_log_state.commit_index = Some(last_included_index);
+        }
+        return true;
+    }
+
+    pub fn get_snapshot(&self) -> Option<Snapshot> {
         let guard = self.replicated_log_state.read().unwrap();
-        let entry = &(*guard).log_entries[index];
-        return entry.is_replicated(self.majority_quorum);
+        return (*guard).snapshot.clone();
     }
 
-    pub(crate) fn commit<F>(&self, commit_execution_block: F)
-        where F: Fn(u64) -> () {
+    /// Commits all the entries up to and including `index` if the entry at `index` is of `term`, the leader's current term.
+    /// The entries of the previous terms are never committed by counting their replicas, they are committed along with an entry of the current term.
+    /// The caller has established that the entry at `index` is replicated on a majority.
+    pub(crate) fn commit(&self, index: u64, term: u64) {
         let mut write_guard = self.replicated_log_state.write().unwrap();
         let replicated_log_state = &mut *write_guard;
-        let starting_commit_index: usize = match replicated_log_state.commit_index {
-            None => 0,
-            Some(commit_index) => (commit_index + 1) as usize
+        let of_term = match replicated_log_state.read_log_entry(index) {
+            None => false,
+            Some(log_entry) => log_entry.matches_term(term)
         };
-        for commit_index in starting_commit_index..replicated_log_state.log_entries.len() {
-            if self._is_entry_replicated(commit_index, replicated_log_state) {
-                let index = commit_index as u64;
-                replicated_log_state.commit_index = Some(index);
-                commit_execution_block(index);
-            }
+        if !of_term {
+            return;
+        }
+        match replicated_log_state.commit_index {
+            Some(commit_index) if commit_index >= index => {}
+            _ => replicated_log_state.commit_index = Some(index)
         }
     }
 
-    //TODO: Handle the gap in log entries
-    pub(crate) fn maybe_advance_commit_index_to(&self, requested_commit_index: Option<u64>) {
-        if let Some(commit_index) = requested_commit_index {
-            let mut write_guard = self.replicated_log_state.write().unwrap();
-            let replicated_log_state = &mut *write_guard;
-
-            let self_commit_index = match replicated_log_state.commit_index {
-                None => 0,
-                Some(commit_index) => commit_index
-            };
+    /// Advances the commit index of a follower to the leader's commit index, limited to `last_new_entry_index`, the last entry
+    /// covered by the request (the previous log index for a request without entries). The entries after it may be stale entries
+    /// of an earlier term that the leader is yet to overwrite. The commit index never moves backwards.
+    pub(crate) fn maybe_advance_commit_index_to(&self, leader_commit_index: Option<u64>, last_new_entry_index: Option<u64>) {
+        let commit_index = match (leader_commit_index, last_new_entry_index) {
+            (Some(leader_commit_index), Some(last_new_entry_index)) => leader_commit_index.min(last_new_entry_index),
+            _ => return
+        };
+        let mut write_guard = self.replicated_log_state.write().unwrap();
+        let replicated_log_state = &mut *write_guard;
 
-            if commit_index >= self_commit_index {
-                replicated_log_state.commit_index = requested_commit_index;
-            }
+        if replicated_log_state.commit_index.is_none_or(|self_commit_index| commit_index > self_commit_index) {
+            replicated_log_state.commit_index = Some(commit_index);
         }
     }
 
+    /// Whether the entry at the commit index is of `term`. A new leader knows the commit index of the cluster only once it commits an entry of its own term.
+    pub(crate) fn is_committed_in_term(&self, term: u64) -> bool {
+        return match self.get_commit_index() {
+            None => false,
+            Some(commit_index) => self.matches_log_entry_term_at(commit_index as usize, term)
+        };
+    }
+
     pub fn get_commit_index(&self) -> Option<u64> {
         let guard = self.replicated_log_state.read().unwrap();
         return (*guard).commit_index;
     }
 
     pub fn append_command(&self, command: &Command, term: u64) -> u64 {
+        return self.append_command_at(command, term, 0);
+    }
+
+    /// Appends the command stamped with the log time, the leader's clock in milliseconds since the unix epoch.
+    pub fn append_command_at(&self, command: &Command, term: u64, time: u64) -> u64 {
         let mut write_guard = self.replicated_log_state.write().unwrap();
         let replicated_log_state = &mut *write_guard;
-        let log_entries_size = replicated_log_state.log_entries.len();
+        let log_entries_size = replicated_log_state.total_log_entries();
 
-        let log_entry = LogEntry::new(term, log_entries_size as u64, command);
-        replicated_log_state.log_entries.push(log_entry);
+        let log_entry = LogEntry::new(term, log_entries_size as u64, command).with_time(time);
+        replicated_log_state.log_store
+            .append(vec![log_entry])
+            .expect("failed to append the log entry to the log store");
 
         return log_entries_size as u64;
     }
 
+    /// Appends a membership change, the membership takes effect as soon as it is appended (it need not be committed).
+    pub fn append_membership(&self, membership: &Membership, term: u64) -> u64 {
+        let mut write_guard = self.replicated_log_state.write().unwrap();
+        let replicated_log_state = &mut *write_guard;
+        let log_entries_size = replicated_log_state.total_log_entries();
+
+        let command = Command { command: membership.encode(), client_id: None, sequence: 0 };
+        let log_entry = LogEntry::new(term, log_entries_size as u64, &command).with_entry_type(LogEntryType::Configuration);
+        replicated_log_state.log_store
+            .append(vec![log_entry])
+            .expect("failed to append the log entry to the log store");
+        replicated_log_state.memberships.push((log_entries_size as u64, membership.clone()));
+
+        return log_entries_size as u64;
+    }
+
+    /// Appends a no-op entry, a new leader appends one in its term to commit the entries of the earlier terms.
+    pub(crate) fn append_no_op(&self, term: u64) -> u64 {
+        let mut write_guard = self.replicated_log_state.write().unwrap();
+        let replicated_log_state = &mut *write_guard;
+        let log_entries_size = replicated_log_state.total_log_entries();
+
+        let command = Command { command: Vec::new(), client_id: None, sequence: 0 };
+        let log_entry = LogEntry::new(term, log_entries_size as u64, &command).with_entry_type(LogEntryType::NoOp);
+        replicated_log_state.log_store
+            .append(vec![log_entry])
+            .expect("failed to append the log entry to the log store");
+
+        return log_entries_size as u64;
+    }
+
+    /// Returns the latest membership in the log along with its index, none if the membership has never changed.
+    pub fn get_membership(&self) -> Option<(u64, Membership)> {
+        let guard = self.replicated_log_state.read().unwrap();
+        return (*guard).memberships.last().cloned();
+    }
+
+    /// Appends the entries received from the leader in a single write, skipping the entries that are already present.
+    /// An existing entry with the same index but a different term conflicts with the leader's log,
+    /// so the conflicting entry and all the entries following it are removed before appending.
+    pub(crate) fn append_log_entries(&self, log_entries: Vec<LogEntry>) {
+        let mut write_guard = self.replicated_log_state.write().unwrap();
+        let replicated_log_state = &mut *write_guard;
+
+        let mut new_log_entries = Vec::new();
+        for log_entry in log_entries {
+            let index = log_entry.get_index();
+            if replicated_log_state.is_compacted(index) {
+                continue;
+            }
+            if new_log_entries.is_empty() {
+                match replicated_log_state.read_log_entry(index) {
+                    None => {}
+                    Some(existing_log_entry) if existing_log_entry.matches_term(log_entry.get_term()) => continue,
+                    Some(_) => replicated_log_state.truncate_suffix(index),
+                }
+            }
+            new_log_entries.push(log_entry);
+        }
+
+        if !new_log_entries.is_empty() {
+            let new_memberships: Vec<(u64, Membership)> = new_log_entries
+                .iter()
+                .filter(|log_entry| log_entry.is_configuration())
+                .filter_map(|log_entry| Membership::decode(&log_entry.get_bytes_as_vec()).map(|membership| (log_entry.get_index(), membership)))
+                .collect();
+            replicated_log_state.log_store
+                .append(new_log_entries)
+                .expect("failed to append the log entries to the log store");
+            replicated_log_state.memberships.extend(new_memberships);
+        }
+    }
+
+    pub(crate) fn get_last_log_index_term(&self) -> (Option<u64>, Option<u64>) {
+        let guard = self.replicated_log_state.read().unwrap();
+        return (*guard).last_log_index_term();
+    }
+
+    /// Returns true if a log ending at `last_log_index` and `last_log_term` is at least as up to date as this log:
+    /// a higher last term wins, and for the same last term a longer log wins.
+    pub(crate) fn is_not_more_up_to_date_than(&self, last_log_index: Option<u64>, last_log_term: Option<u64>) -> bool {
+        let guard = self.replicated_log_state.read().unwrap();
+        let (self_last_log_index, self_last_log_term) = (*guard).last_log_index_term();
+        return (last_log_term, last_log_index) >= (self_last_log_term, self_last_log_index);
+    }
+
     pub fn total_log_entries(&self) -> usize {
         let guard = self.replicated_log_state.read().unwrap();
-        return (*guard).log_entries.len();
+        return (*guard).total_log_entries();
     }
 
     pub fn get_log_entry_at(&self, index: usize) -> Option<LogEntry> {
         let guard = self.replicated_log_state.read().unwrap();
-        return match (*guard).log_entries.get(index) {
-            None => None,
-            Some(entry) => Some(LogEntry::from(entry))
+        let replicated_log_state = &*guard;
+        return replicated_log_state.read_log_entry(index as u64);
+    }
+
+    pub(crate) fn get_log_entries_between(&self, from_index: usize, to_index: usize) -> Vec<LogEntry> {
+        let guard = self.replicated_log_state.read().unwrap();
+        let replicated_log_state = &*guard;
+        if replicated_log_state.is_compacted(from_index as u64) {
+            return Vec::new();
+        }
+        return replicated_log_state.log_store
+            .read_range(from_index as u64, to_index as u64)
+            .expect("failed to read the log entries from the log store");
+    }
+}
+
+impl ReplicatedLogState {
+    fn read_log_entry(&self, index: u64) -> Option<LogEntry> {
+        if self.is_compacted(index) {
+            return None;
+        }
+        return self.log_store.read(index).expect("failed to read the log entry from the log store");
+    }
+
+    fn total_log_entries(&self) -> usize {
+        return match (self.log_store.last_index(), &self.snapshot) {
+            (Some(last_index), _) => (last_index + 1) as usize,
+            (None, Some(snapshot)) => (snapshot.get_last_included_index() + 1) as usize,
+            (None, None) => 0
         };
     }
 
-    fn _is_entry_replicated(&self, index: usize, replicated_log_state: &ReplicatedLogState) -> bool {
-        let entry = &replicated_log_state.log_entries[index];
-        return entry.is_replicated(self.majority_quorum);
+    fn last_log_index_term(&self) -> (Option<u64>, Option<u64>) {
+        return match (self.log_store.last_index(), &self.snapshot) {
+            (Some(last_index), _) => (Some(last_index), self.log_store.last_term()),
+            (None, Some(snapshot)) => (Some(snapshot.get_last_included_index()), Some(snapshot.get_last_included_term())),
+            (None, None) => (None, None)
+        };
     }
+
+    fn is_compacted(&self, index: u64) -> bool {
+        return match &self.snapshot {
+            None => false,
+            Some(snapshot) => snapshot.includes(index)
+        };
+    }
+
+    fn save_snapshot(&mut self, snapshot: &Snapshot) {
+        self.snapshot_storage.save(snapshot).expect("failed to save the snapshot");
+        self.snapshot = Some(snapshot.clone());
+    }
+
+    /// Replaces the memberships covered by the snapshot with the snapshot's membership, it remains in effect until a later one is appended.
+    fn restore_snapshot_membership(&mut self) {
+        let snapshot = match &self.snapshot {
+            None => return,
+            Some(snapshot) => snapshot
+        };
+        self.memberships.retain(|(index, _)| !snapshot.includes(*index));
+        if let Some(membership) = snapshot.get_membership() {
+            self.memberships.insert(0, (snapshot.get_last_included_index(), membership.clone()));
+        }
+    }
+
+    fn truncate_suffix(&mut self, from_index: u64) {
+        self.log_store.truncate_suffix(from_index).expect("failed to truncate the log store");
+        self.memberships.retain(|(index, _)| *index < from_index);
+    }
+
 }
 
 
 #[cfg(test)]
 mod tests {
-    use std::sync::{Arc, Mutex};
-    use crate::log_entry::LogEntry;
+    use std::net::{IpAddr, Ipv4Addr};
+
+    use replicate::net::connect::host_and_port::HostAndPort;
+
+    use crate::log_entry::{LogEntry, LogEntryType};
+    use crate::membership::Membership;
     use crate::net::rpc::grpc::Command;
     use crate::replicated_log::ReplicatedLog;
+    use crate::storage::file_snapshot_storage::FileSnapshotStorage;
+    use crate::storage::segmented_log_store::SegmentedLogStore;
+    use crate::storage::snapshot_storage::{InMemorySnapshotStorage, Snapshot};
 
     #[test]
     fn append_command() {
-        let replicated_log = ReplicatedLog::new(2);
+        let replicated_log = ReplicatedLog::new();
         let content = String::from("Content");
-        let command = Command { command: content.as_bytes().to_vec() };
+        let command = Command { command: content.as_bytes().to_vec(), client_id: None, sequence: 0 };
 
         let index = replicated_log.append_command(&command, 1);
 
@@ -148,10 +475,10 @@ mod tests {
 
     #[test]
     fn append_multiple_commands() {
-        let replicated_log = ReplicatedLog::new(2);
+        let replicated_log = ReplicatedLog::new();
         for count in 1..=3 {
             let content = String::from("Content");
-            let command = Command { command: content.as_bytes().to_vec() };
+            let command = Command { command: content.as_bytes().to_vec(), client_id: None, sequence: 0 };
 
             let index = replicated_log.append_command(&command, 1);
             let log_entry = replicated_log.get_log_entry_at(0).unwrap();
@@ -164,224 +491,692 @@ mod tests {
     }
 
     #[test]
-    fn get_log_term_at_non_existing_index() {
-        let replicated_log = ReplicatedLog::new(2);
-        assert_eq!(None, replicated_log.get_log_term_at(99));
+    fn append_log_entries() {
+        let replicated_log = ReplicatedLog::new();
+        let command = Command { command: "Content".as_bytes().to_vec(), client_id: None, sequence: 0 };
+        replicated_log.append_log_entries(vec![
+            LogEntry::new(1, 0, &command),
+            LogEntry::new(1, 1, &command),
+            LogEntry::new(2, 2, &command),
+        ]);
+
+        assert_eq!(3, replicated_log.total_log_entries());
+        assert_eq!(Some(2), replicated_log.get_log_term_at(2));
     }
 
     #[test]
-    fn get_log_term_at_an_existing_index() {
-        let replicated_log = ReplicatedLog::new(2);
-        let content = String::from("Content");
-        let command = Command { command: content.as_bytes().to_vec() };
-
+    fn append_log_entries_skipping_the_existing_entries() {
+        let replicated_log = ReplicatedLog::new();
+        let command = Command { command: "Content".as_bytes().to_vec(), client_id: None, sequence: 0 };
         replicated_log.append_command(&command, 1);
-        assert_eq!(Some(1), replicated_log.get_log_term_at(0));
+        replicated_log.append_command(&command, 1);
+
+        replicated_log.append_log_entries(vec![
+            LogEntry::new(1, 1, &command),
+            LogEntry::new(2, 2, &command),
+        ]);
+
+        assert_eq!(3, replicated_log.total_log_entries());
+        assert_eq!(Some(1), replicated_log.get_log_term_at(1));
+        assert_eq!(Some(2), replicated_log.get_log_term_at(2));
     }
 
     #[test]
-    fn get_log_entry_at_non_existing_index() {
-        let replicated_log = ReplicatedLog::new(2);
-        assert_eq!(None, replicated_log.get_log_entry_at(99));
+    fn append_log_entries_truncating_a_conflicting_suffix() {
+        let replicated_log = ReplicatedLog::new();
+        let command = Command { command: "Content".as_bytes().to_vec(), client_id: None, sequence: 0 };
+        for _count in 1..=3 {
+            replicated_log.append_command(&command, 1);
+        }
+
+        let leader_command = Command { command: "Leader".as_bytes().to_vec(), client_id: None, sequence: 0 };
+        replicated_log.append_log_entries(vec![
+            LogEntry::new(1, 0, &leader_command),
+            LogEntry::new(2, 1, &leader_command),
+        ]);
+
+        assert_eq!(2, replicated_log.total_log_entries());
+        assert_eq!(command.command, replicated_log.get_log_entry_at(0).unwrap().get_bytes_as_vec());
+        assert_eq!(Some(2), replicated_log.get_log_term_at(1));
+        assert_eq!(leader_command.command, replicated_log.get_log_entry_at(1).unwrap().get_bytes_as_vec());
+        assert_eq!(None, replicated_log.get_log_entry_at(2));
+
     }
 
     #[test]
-    fn get_log_entry_at_an_existing_index() {
-        let replicated_log = ReplicatedLog::new(2);
-        let content = String::from("Content");
-        let command = Command { command: content.as_bytes().to_vec() };
+    fn append_log_entries_retaining_the_matching_entries_beyond_the_request() {
+        let replicated_log = ReplicatedLog::new();
+        let command = Command { command: "Content".as_bytes().to_vec(), client_id: None, sequence: 0 };
+        for _count in 1..=3 {
+            replicated_log.append_command(&command, 1);
+        }
+
+        replicated_log.append_log_entries(vec![LogEntry::new(1, 1, &command)]);
+
+        assert_eq!(3, replicated_log.total_log_entries());
+    }
+
+    #[test]
+    fn append_log_entries_after_a_snapshot() {
+        let replicated_log = ReplicatedLog::new();
+        let command = Command { command: "Content".as_bytes().to_vec(), client_id: None, sequence: 0 };
+        replicated_log.install_snapshot(Snapshot::new(1, 1, Vec::new()));
+
+        replicated_log.append_log_entries(vec![
+            LogEntry::new(1, 1, &command),
+            LogEntry::new(2, 2, &command),
+        ]);
+
+        assert_eq!(3, replicated_log.total_log_entries());
+        assert_eq!(Some(2), replicated_log.get_log_term_at(2));
+    }
+
+    #[test]
+    fn last_log_index_term_of_an_empty_log() {
+        let replicated_log = ReplicatedLog::new();
+        assert_eq!((None, None), replicated_log.get_last_log_index_term());
+    }
+
+    #[test]
+    fn last_log_index_term() {
+        let replicated_log = ReplicatedLog::new();
+        let command = Command { command: "Content".as_bytes().to_vec(), client_id: None, sequence: 0 };
         replicated_log.append_command(&command, 1);
+        replicated_log.append_command(&command, 3);
 
-        assert_eq!(
-            Some(LogEntry::new(1, 0, &command)),
-            replicated_log.get_log_entry_at(0)
-        );
+        assert_eq!((Some(1), Some(3)), replicated_log.get_last_log_index_term());
     }
 
     #[test]
-    fn acknowledge_log_entry() {
-        let replicated_log = ReplicatedLog::new(2);
-        let content = String::from("Content");
-        let command = Command { command: content.as_bytes().to_vec() };
+    fn last_log_index_term_after_compacting_the_entire_log() {
+        let replicated_log = ReplicatedLog::new();
+        replicated_log.install_snapshot(Snapshot::new(4, 2, Vec::new()));
+
+        assert_eq!((Some(4), Some(2)), replicated_log.get_last_log_index_term());
+    }
+
+    #[test]
+    fn log_up_to_date_comparison() {
+        let replicated_log = ReplicatedLog::new();
+        assert!(replicated_log.is_not_more_up_to_date_than(None, None));
+
+        let command = Command { command: "Content".as_bytes().to_vec(), client_id: None, sequence: 0 };
         replicated_log.append_command(&command, 1);
+        replicated_log.append_command(&command, 2);
+
+        assert!(replicated_log.is_not_more_up_to_date_than(Some(1), Some(2)));
+        assert!(replicated_log.is_not_more_up_to_date_than(Some(5), Some(2)));
+        assert!(replicated_log.is_not_more_up_to_date_than(Some(0), Some(3)));
+        assert_eq!(false, replicated_log.is_not_more_up_to_date_than(Some(0), Some(2)));
+        assert_eq!(false, replicated_log.is_not_more_up_to_date_than(Some(9), Some(1)));
+        assert_eq!(false, replicated_log.is_not_more_up_to_date_than(None, None));
+    }
 
-        replicated_log.acknowledge_log_entry_at(0);
-        assert_eq!(1, replicated_log.get_log_entry_at(0).unwrap().get_acknowledgements());
-        assert_eq!(1, replicated_log.total_log_entries());
+    #[test]
+    fn get_log_entries_between() {
+        let replicated_log = ReplicatedLog::new();
+        for term in 1..=3 {
+            let command = Command { command: "Content".as_bytes().to_vec(), client_id: None, sequence: 0 };
+            replicated_log.append_command(&command, term);
+        }
+
+        let log_entries = replicated_log.get_log_entries_between(1, 5);
+        assert_eq!(vec![1, 2], log_entries.iter().map(|log_entry| log_entry.get_index()).collect::<Vec<u64>>());
     }
 
     #[test]
-    fn multiple_acknowledge_log_entry() {
-        let replicated_log = ReplicatedLog::new(2);
-        let content = String::from("Content");
-        let command = Command { command: content.as_bytes().to_vec() };
+    fn get_log_entries_between_a_compacted_range() {
+        let replicated_log = ReplicatedLog::new();
+        let command = Command { command: "Content".as_bytes().to_vec(), client_id: None, sequence: 0 };
+        replicated_log.append_command(&command, 1);
         replicated_log.append_command(&command, 1);
+        replicated_log.commit(0, 1);
+        replicated_log.compact(0, Vec::new()).unwrap();
 
-        replicated_log.acknowledge_log_entry_at(0);
-        replicated_log.acknowledge_log_entry_at(0);
+        assert!(replicated_log.get_log_entries_between(0, 2).is_empty());
+    }
+
+    #[test]
+    fn conflict_term_index_at_the_first_index_of_the_conflicting_term() {
+        let replicated_log = ReplicatedLog::new();
+        for term in vec![1, 2, 2, 2] {
+            let command = Command { command: "Content".as_bytes().to_vec(), client_id: None, sequence: 0 };
+            replicated_log.append_command(&command, term);
+        }
 
-        assert_eq!(2, replicated_log.get_log_entry_at(0).unwrap().get_acknowledgements());
-        assert_eq!(1, replicated_log.total_log_entries());
+        assert_eq!((Some(2), 1), replicated_log.conflict_term_index_at(3));
+        assert_eq!((Some(1), 0), replicated_log.conflict_term_index_at(0));
     }
 
     #[test]
-    fn is_entry_replicated() {
-        let replicated_log = ReplicatedLog::new(2);
-        let content = String::from("Content");
-        let command = Command { command: content.as_bytes().to_vec() };
+    fn conflict_term_index_beyond_the_end_of_the_log() {
+        let replicated_log = ReplicatedLog::new();
+        let command = Command { command: "Content".as_bytes().to_vec(), client_id: None, sequence: 0 };
         replicated_log.append_command(&command, 1);
 
-        replicated_log.acknowledge_log_entry_at(0);
-        replicated_log.acknowledge_log_entry_at(0);
+        assert_eq!((None, 1), replicated_log.conflict_term_index_at(7));
+    }
+
+    #[test]
+    fn last_index_of_term() {
+        let replicated_log = ReplicatedLog::new();
+        for term in vec![1, 1, 3, 3, 4] {
+            let command = Command { command: "Content".as_bytes().to_vec(), client_id: None, sequence: 0 };
+            replicated_log.append_command(&command, term);
+        }
+
+        assert_eq!(Some(1), replicated_log.last_index_of_term(1));
+        assert_eq!(Some(3), replicated_log.last_index_of_term(3));
+        assert_eq!(None, replicated_log.last_index_of_term(2));
+        assert_eq!(None, replicated_log.last_index_of_term(5));
+    }
+
+    #[test]
+    fn last_index_of_term_at_the_last_included_index_of_snapshot() {
+        let replicated_log = ReplicatedLog::new();
+        replicated_log.install_snapshot(Snapshot::new(4, 2, Vec::new()));
 
-        assert!(replicated_log.is_entry_replicated(0));
+        assert_eq!(Some(4), replicated_log.last_index_of_term(2));
+        assert_eq!(None, replicated_log.last_index_of_term(1));
     }
 
     #[test]
-    fn is_entry_not_replicated() {
-        let replicated_log = ReplicatedLog::new(2);
+    fn get_log_term_at_non_existing_index() {
+        let replicated_log = ReplicatedLog::new();
+        assert_eq!(None, replicated_log.get_log_term_at(99));
+    }
+
+    #[test]
+    fn get_log_term_at_an_existing_index() {
+        let replicated_log = ReplicatedLog::new();
         let content = String::from("Content");
-        let command = Command { command: content.as_bytes().to_vec() };
+        let command = Command { command: content.as_bytes().to_vec(), client_id: None, sequence: 0 };
+
         replicated_log.append_command(&command, 1);
+        assert_eq!(Some(1), replicated_log.get_log_term_at(0));
+    }
 
-        replicated_log.acknowledge_log_entry_at(0);
+    #[test]
+    fn get_log_entry_at_non_existing_index() {
+        let replicated_log = ReplicatedLog::new();
+        assert_eq!(None, replicated_log.get_log_entry_at(99));
+    }
 
-        assert_eq!(false, replicated_log.is_entry_replicated(0));
+    #[test]
+    fn get_log_entry_at_an_existing_index() {
+        let replicated_log = ReplicatedLog::new();
+        let content = String::from("Content");
+        let command = Command { command: content.as_bytes().to_vec(), client_id: None, sequence: 0 };
+        replicated_log.append_command(&command, 1);
+
+        assert_eq!(
+            Some(LogEntry::new(1, 0, &command)),
+            replicated_log.get_log_entry_at(0)
+        );
     }
 
     #[test]
     fn initial_commit_index() {
-        let replicated_log = ReplicatedLog::new(2);
+        let replicated_log = ReplicatedLog::new();
 
         assert_eq!(None, replicated_log.get_commit_index())
     }
 
     #[test]
     fn commit_index_for_first_entry() {
-        let replicated_log = ReplicatedLog::new(1);
+        let replicated_log = ReplicatedLog::new();
         let content = String::from("Content");
-        let command = Command { command: content.as_bytes().to_vec() };
+        let command = Command { command: content.as_bytes().to_vec(), client_id: None, sequence: 0 };
         replicated_log.append_command(&command, 1);
 
-        replicated_log.acknowledge_log_entry_at(0);
 
-        replicated_log.commit(|_| {});
+        replicated_log.commit(0, 1);
         assert_eq!(Some(0), replicated_log.get_commit_index())
     }
 
     #[test]
     fn commit_index_for_few_entries() {
-        let replicated_log = ReplicatedLog::new(1);
+        let replicated_log = ReplicatedLog::new();
 
         for _count in 1..=3 {
             let content = String::from("Content");
-            let command = Command { command: content.as_bytes().to_vec() };
+            let command = Command { command: content.as_bytes().to_vec(), client_id: None, sequence: 0 };
             replicated_log.append_command(&command, 1);
         }
 
-        replicated_log.acknowledge_log_entry_at(0);
-        replicated_log.acknowledge_log_entry_at(1);
-        replicated_log.acknowledge_log_entry_at(2);
 
-        replicated_log.commit(|_| {});
+        replicated_log.commit(2, 1);
         assert_eq!(Some(2), replicated_log.get_commit_index())
     }
 
     #[test]
-    fn commit_index_for_few_entries_with_execution_block() {
-        let replicated_log = ReplicatedLog::new(1);
+    fn commit_index_with_a_non_replicated_entry() {
+        let replicated_log = ReplicatedLog::new();
 
         for _count in 1..=3 {
             let content = String::from("Content");
-            let command = Command { command: content.as_bytes().to_vec() };
+            let command = Command { command: content.as_bytes().to_vec(), client_id: None, sequence: 0 };
             replicated_log.append_command(&command, 1);
         }
 
-        replicated_log.acknowledge_log_entry_at(0);
-        replicated_log.acknowledge_log_entry_at(1);
-        replicated_log.acknowledge_log_entry_at(2);
-
-        let commit_count = Arc::new(Mutex::new(0));
-        replicated_log.commit(|_commit_index| {
-            let mut guard = commit_count.lock().unwrap();
-            *guard = *guard + 1;
-        });
-
-        let count = commit_count.lock().unwrap();
-        assert_eq!(3, *count);
-        assert_eq!(Some(2), replicated_log.get_commit_index());
+        replicated_log.commit(1, 1);
+        assert_eq!(Some(1), replicated_log.get_commit_index())
     }
 
     #[test]
-    fn commit_index_with_a_non_replicated_entry() {
-        let replicated_log = ReplicatedLog::new(1);
+    fn do_not_commit_an_entry_of_a_previous_term() {
+        let replicated_log = ReplicatedLog::new();
+        let command = Command { command: "Content".as_bytes().to_vec(), client_id: None, sequence: 0 };
 
-        for _count in 1..=3 {
-            let content = String::from("Content");
-            let command = Command { command: content.as_bytes().to_vec() };
-            replicated_log.append_command(&command, 1);
-        }
+        replicated_log.append_command(&command, 1);
+        replicated_log.append_command(&command, 2);
 
-        replicated_log.acknowledge_log_entry_at(0);
-        replicated_log.acknowledge_log_entry_at(1);
+        replicated_log.commit(0, 2);
+        assert_eq!(None, replicated_log.get_commit_index());
 
-        replicated_log.commit(|_| {});
-        assert_eq!(Some(1), replicated_log.get_commit_index())
+        replicated_log.commit(1, 2);
+        assert_eq!(Some(1), replicated_log.get_commit_index());
     }
 
     #[test]
     fn do_not_advance_commit_index() {
-        let replicated_log = ReplicatedLog::new(1);
+        let replicated_log = ReplicatedLog::new();
         let content = String::from("Content");
-        let command = Command { command: content.as_bytes().to_vec() };
+        let command = Command { command: content.as_bytes().to_vec(), client_id: None, sequence: 0 };
 
         replicated_log.append_command(&command, 1);
-        replicated_log.acknowledge_log_entry_at(0);
-        replicated_log.commit(|_| {});
+        replicated_log.commit(0, 1);
 
-        replicated_log.maybe_advance_commit_index_to(None);
+        replicated_log.maybe_advance_commit_index_to(None, Some(0));
         assert_eq!(Some(0), replicated_log.get_commit_index())
     }
 
     #[test]
     fn do_not_advance_commit_index_as_the_requested_commit_index_is_smaller() {
-        let replicated_log = ReplicatedLog::new(1);
+        let replicated_log = ReplicatedLog::new();
         for _count in 1..=2 {
             let content = String::from("Content");
-            let command = Command { command: content.as_bytes().to_vec() };
+            let command = Command { command: content.as_bytes().to_vec(), client_id: None, sequence: 0 };
             replicated_log.append_command(&command, 1);
         }
 
-        replicated_log.acknowledge_log_entry_at(0);
-        replicated_log.acknowledge_log_entry_at(1);
-        replicated_log.commit(|_| {});
+        replicated_log.commit(1, 1);
 
-        replicated_log.maybe_advance_commit_index_to(Some(0));
+        replicated_log.maybe_advance_commit_index_to(Some(0), Some(1));
         assert_eq!(Some(1), replicated_log.get_commit_index())
     }
 
     #[test]
     fn advance_commit_index_as_requested_commit_index_is_the_first() {
-        let replicated_log = ReplicatedLog::new(1);
+        let replicated_log = ReplicatedLog::new();
+        let command = Command { command: "Content".as_bytes().to_vec(), client_id: None, sequence: 0 };
+        replicated_log.append_command(&command, 1);
 
-        replicated_log.maybe_advance_commit_index_to(Some(0));
+        replicated_log.maybe_advance_commit_index_to(Some(0), Some(0));
         assert_eq!(Some(0), replicated_log.get_commit_index())
     }
 
     #[test]
     fn advance_commit_index() {
-        let replicated_log = ReplicatedLog::new(1);
+        let replicated_log = ReplicatedLog::new();
         for _count in 1..=2 {
             let content = String::from("Content");
-            let command = Command { command: content.as_bytes().to_vec() };
+            let command = Command { command: content.as_bytes().to_vec(), client_id: None, sequence: 0 };
             replicated_log.append_command(&command, 1);
         }
 
-        replicated_log.acknowledge_log_entry_at(0);
-        replicated_log.acknowledge_log_entry_at(1);
-        replicated_log.commit(|_| {});
+        replicated_log.commit(1, 1);
 
-        replicated_log.maybe_advance_commit_index_to(Some(2));
+        replicated_log.maybe_advance_commit_index_to(Some(2), Some(2));
         assert_eq!(Some(2), replicated_log.get_commit_index())
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn do_not_advance_commit_index_without_a_last_new_entry() {
+        let replicated_log = ReplicatedLog::new();
+
+        replicated_log.maybe_advance_commit_index_to(Some(3), None);
+        assert_eq!(None, replicated_log.get_commit_index())
+    }
+
+    #[test]
+    fn advance_commit_index_up_to_the_last_new_entry_of_a_partial_batch_over_a_stale_suffix() {
+        let replicated_log = ReplicatedLog::new();
+        let command = Command { command: "Content".as_bytes().to_vec(), client_id: None, sequence: 0 };
+        for _count in 1..=6 {
+            replicated_log.append_command(&command, 1);
+        }
+        replicated_log.maybe_advance_commit_index_to(Some(1), Some(1));
+
+        //the leader has the entries 0..=3 of term 1 and the entries 4..=5 of term 2, its commit index is 5.
+        //a partial batch of the entries 2..=3 matches the follower's log, the follower's entries 4 and 5 are stale entries of term 1
+        replicated_log.append_log_entries(vec![
+            LogEntry::new(1, 2, &command),
+            LogEntry::new(1, 3, &command),
+        ]);
+        replicated_log.maybe_advance_commit_index_to(Some(5), Some(3));
+        assert_eq!(Some(3), replicated_log.get_commit_index());
+        assert_eq!(6, replicated_log.total_log_entries());
+
+        replicated_log.maybe_advance_commit_index_to(Some(5), Some(2));
+        assert_eq!(Some(3), replicated_log.get_commit_index());
+    }
+
+    #[test]
+    fn recover_log_entries_from_the_log_store() {
+        let directory = tempfile::tempdir().unwrap();
+        let content = String::from("Content");
+        let command = Command { command: content.as_bytes().to_vec(), client_id: None, sequence: 0 };
+        {
+            let replicated_log = ReplicatedLog::new_with_storage(Box::new(SegmentedLogStore::new(directory.path()).unwrap()), Box::new(InMemorySnapshotStorage::new()));
+            replicated_log.append_command(&command, 1);
+            replicated_log.append_command(&command, 2);
+        }
+
+        let replicated_log = ReplicatedLog::new_with_storage(Box::new(SegmentedLogStore::new(directory.path()).unwrap()), Box::new(InMemorySnapshotStorage::new()));
+        assert_eq!(2, replicated_log.total_log_entries());
+        assert_eq!(Some(2), replicated_log.get_log_term_at(1));
+        assert_eq!(2, replicated_log.append_command(&command, 2));
+    }
+
+    #[test]
+    fn compact_committed_log_entries() {
+        let replicated_log = ReplicatedLog::new();
+        for _count in 0..3 {
+            let command = Command { command: "Content".as_bytes().to_vec(), client_id: None, sequence: 0 };
+            replicated_log.append_command(&command, 1);
+        }
+        replicated_log.commit(2, 1);
+
+        let snapshot = replicated_log.compact(1, "state".as_bytes().to_vec()).unwrap();
+
+        assert_eq!(Snapshot::new(1, 1, "state".as_bytes().to_vec()), snapshot);
+        assert_eq!(Some(snapshot), replicated_log.get_snapshot());
+        assert_eq!(None, replicated_log.get_log_entry_at(1));
+        assert!(replicated_log.get_log_entry_at(2).is_some());
+        assert!(replicated_log.is_compacted(1));
+        assert_eq!(false, replicated_log.is_compacted(2));
+        assert_eq!(3, replicated_log.total_log_entries());
+    }
+
+    #[test]
+    fn do_not_compact_uncommitted_log_entries() {
+        let replicated_log = ReplicatedLog::new();
+        let command = Command { command: "Content".as_bytes().to_vec(), client_id: None, sequence: 0 };
+        replicated_log.append_command(&command, 1);
+
+        assert_eq!(None, replicated_log.compact(0, Vec::new()));
+        assert_eq!(None, replicated_log.get_snapshot());
+    }
+
+    #[test]
+    fn do_not_compact_an_already_compacted_prefix() {
+        let replicated_log = ReplicatedLog::new();
+        for _count in 0..3 {
+            let command = Command { command: "Content".as_bytes().to_vec(), client_id: None, sequence: 0 };
+            replicated_log.append_command(&command, 1);
+        }
+        replicated_log.commit(2, 1);
+        replicated_log.compact(1, Vec::new()).unwrap();
+
+        assert_eq!(None, replicated_log.compact(0, Vec::new()));
+        assert_eq!(1, replicated_log.get_snapshot().unwrap().get_last_included_index());
+    }
+
+    #[test]
+    fn get_log_term_at_the_last_included_index_of_snapshot() {
+        let replicated_log = ReplicatedLog::new();
+        for _count in 0..2 {
+            let command = Command { command: "Content".as_bytes().to_vec(), client_id: None, sequence: 0 };
+            replicated_log.append_command(&command, 2);
+        }
+        replicated_log.commit(1, 2);
+        replicated_log.compact(1, Vec::new()).unwrap();
+
+        assert_eq!(Some(2), replicated_log.get_log_term_at(1));
+        assert_eq!(None, replicated_log.get_log_term_at(0));
+        assert!(replicated_log.matches_log_entry_term_at(1, 2));
+        assert!(replicated_log.matches_log_entry_term_at(0, 1));
+        assert_eq!(false, replicated_log.matches_log_entry_term_at(1, 1));
+    }
+
+    #[test]
+    fn append_command_after_compacting_the_entire_log() {
+        let replicated_log = ReplicatedLog::new();
+        let command = Command { command: "Content".as_bytes().to_vec(), client_id: None, sequence: 0 };
+        replicated_log.append_command(&command, 1);
+        replicated_log.commit(0, 1);
+        replicated_log.compact(0, Vec::new()).unwrap();
+
+        assert_eq!(1, replicated_log.append_command(&command, 1));
+        assert_eq!(1, replicated_log.get_log_entry_at(1).unwrap().get_index());
+    }
+
+    #[test]
+    fn install_snapshot_discards_a_conflicting_log() {
+        let replicated_log = ReplicatedLog::new();
+        let command = Command { command: "Content".as_bytes().to_vec(), client_id: None, sequence: 0 };
+        replicated_log.append_command(&command, 1);
+        replicated_log.append_command(&command, 1);
+
+        assert!(replicated_log.install_snapshot(Snapshot::new(4, 2, "state".as_bytes().to_vec())));
+        assert_eq!(None, replicated_log.get_log_entry_at(1));
+        assert_eq!(5, replicated_log.total_log_entries());
+        assert_eq!(Some(4), replicated_log.get_commit_index());
+        assert_eq!(5, replicated_log.append_command(&command, 2));
+    }
+
+    #[test]
+    fn install_snapshot_retains_the_matching_log_suffix() {
+        let replicated_log = ReplicatedLog::new();
+        let command = Command { command: "Content".as_bytes().to_vec(), client_id: None, sequence: 0 };
+        for _count in 1..=3 {
+            replicated_log.append_command(&command, 1);
+        }
+
+        assert!(replicated_log.install_snapshot(Snapshot::new(1, 1, Vec::new())));
+        assert_eq!(None, replicated_log.get_log_entry_at(1));
+        assert!(replicated_log.get_log_entry_at(2).is_some());
+        assert_eq!(3, replicated_log.total_log_entries());
+    }
+
+    #[test]
+    fn do_not_install_an_older_snapshot() {
+        let replicated_log = ReplicatedLog::new();
+        replicated_log.install_snapshot(Snapshot::new(4, 2, Vec::new()));
+
+        assert_eq!(false, replicated_log.install_snapshot(Snapshot::new(3, 2, Vec::new())));
+        assert_eq!(4, replicated_log.get_snapshot().unwrap().get_last_included_index());
+    }
+
+    #[test]
+    fn recover_snapshot_and_log_entries_from_storage() {
+        let directory = tempfile::tempdir().unwrap();
+        let command = Command { command: "Content".as_bytes().to_vec(), client_id: None, sequence: 0 };
+        {
+            let replicated_log = ReplicatedLog::new_with_storage(
+                Box::new(SegmentedLogStore::new(directory.path()).unwrap()),
+                Box::new(FileSnapshotStorage::new(directory.path()).unwrap()),
+            );
+            for _count in 0..3 {
+                replicated_log.append_command(&command, 1);
+            }
+            replicated_log.commit(2, 1);
+            replicated_log.compact(1, "state".as_bytes().to_vec()).unwrap();
+        }
+
+        let replicated_log = ReplicatedLog::new_with_storage(
+            Box::new(SegmentedLogStore::new(directory.path()).unwrap()),
+            Box::new(FileSnapshotStorage::new(directory.path()).unwrap()),
+        );
+        assert_eq!(Some(Snapshot::new(1, 1, "state".as_bytes().to_vec())), replicated_log.get_snapshot());
+        assert_eq!(Some(1), replicated_log.get_commit_index());
+        assert_eq!(None, replicated_log.get_log_entry_at(0));
+        assert!(replicated_log.get_log_entry_at(2).is_some());
+        assert_eq!(3, replicated_log.total_log_entries());
+    }
+
+    fn voter(port: u16) -> HostAndPort {
+        return HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), port);
+    }
+
+    #[test]
+    fn no_membership_given_the_membership_has_never_changed() {
+        let replicated_log = ReplicatedLog::new();
+        replicated_log.append_command(&Command { command: "Content".as_bytes().to_vec(), client_id: None, sequence: 0 }, 1);
+
+        assert_eq!(None, replicated_log.get_membership());
+    }
+
+    #[test]
+    fn append_membership() {
+        let replicated_log = ReplicatedLog::new();
+        replicated_log.append_command(&Command { command: "Content".as_bytes().to_vec(), client_id: None, sequence: 0 }, 1);
+
+        let membership = Membership::new(vec![voter(2010), voter(2011)]).with_voter(voter(2012));
+        let index = replicated_log.append_membership(&membership, 1);
+
+        assert_eq!(1, index);
+        assert_eq!(Some((1, membership)), replicated_log.get_membership());
+        assert_eq!(LogEntryType::Configuration, replicated_log.get_log_entry_at(1).unwrap().get_entry_type());
+    }
+
+    #[test]
+    fn append_no_op() {
+        let replicated_log = ReplicatedLog::new();
+        replicated_log.append_command(&Command { command: "Content".as_bytes().to_vec(), client_id: None, sequence: 0 }, 1);
+
+        let index = replicated_log.append_no_op(2);
+
+        assert_eq!(1, index);
+        let log_entry = replicated_log.get_log_entry_at(1).unwrap();
+        assert_eq!(LogEntryType::NoOp, log_entry.get_entry_type());
+        assert_eq!(2, log_entry.get_term());
+        assert!(log_entry.get_bytes_as_vec().is_empty());
+    }
+
+    #[test]
+    fn append_log_entries_with_a_membership() {
+        let replicated_log = ReplicatedLog::new();
+        let membership = Membership::new(vec![voter(2010), voter(2011)]).with_voter(voter(2012));
+
+        replicated_log.append_log_entries(vec![
+            LogEntry::new(1, 0, &Command { command: "Content".as_bytes().to_vec(), client_id: None, sequence: 0 }),
+            LogEntry::new(1, 1, &Command { command: membership.encode(), client_id: None, sequence: 0 }).with_entry_type(LogEntryType::Configuration),
+        ]);
+
+        assert_eq!(Some((1, membership)), replicated_log.get_membership());
+    }
+
+    #[test]
+    fn discard_the_membership_in_a_conflicting_suffix() {
+        let replicated_log = ReplicatedLog::new();
+        let command = Command { command: "Content".as_bytes().to_vec(), client_id: None, sequence: 0 };
+        let membership = Membership::new(vec![voter(2010), voter(2011)]);
+        let joint_membership = membership.with_voter(voter(2012));
+
+        replicated_log.append_membership(&membership, 1);
+        replicated_log.append_membership(&joint_membership, 1);
+        replicated_log.append_log_entries(vec![
+            LogEntry::new(2, 1, &command),
+        ]);
+
+        assert_eq!(Some((0, membership)), replicated_log.get_membership());
+    }
+
+    #[test]
+    fn membership_after_reopening_the_log() {
+        let directory = tempfile::tempdir().unwrap();
+        let membership = Membership::new(vec![voter(2010), voter(2011)]).without_voter(&voter(2011));
+        {
+            let replicated_log = ReplicatedLog::new_with_storage(Box::new(SegmentedLogStore::new(directory.path()).unwrap()), Box::new(InMemorySnapshotStorage::new()));
+            replicated_log.append_command(&Command { command: "Content".as_bytes().to_vec(), client_id: None, sequence: 0 }, 1);
+            replicated_log.append_membership(&membership, 1);
+        }
+
+        let replicated_log = ReplicatedLog::new_with_storage(Box::new(SegmentedLogStore::new(directory.path()).unwrap()), Box::new(InMemorySnapshotStorage::new()));
+        assert_eq!(Some((1, membership)), replicated_log.get_membership());
+    }
+
+    #[test]
+    fn retain_the_latest_compacted_membership() {
+        let replicated_log = ReplicatedLog::new();
+        let membership = Membership::new(vec![voter(2010), voter(2011)]);
+        let joint_membership = membership.with_voter(voter(2012));
+
+        replicated_log.append_membership(&joint_membership, 1);
+        replicated_log.append_membership(&joint_membership.complete(), 1);
+        replicated_log.append_command(&Command { command: "Content".as_bytes().to_vec(), client_id: None, sequence: 0 }, 1);
+        replicated_log.commit(2, 1);
+
+        let snapshot = replicated_log.compact(2, "state".as_bytes().to_vec()).unwrap();
+        assert_eq!(Some(&joint_membership.complete()), snapshot.get_membership());
+        assert_eq!(Some((2, joint_membership.complete())), replicated_log.get_membership());
+    }
+
+    #[test]
+    fn membership_after_reopening_a_compacted_log() {
+        let directory = tempfile::tempdir().unwrap();
+        let membership = Membership::new(vec![voter(2010), voter(2011)])
+            .with_learner(voter(2012))
+            .with_voter(voter(2013));
+        {
+            let replicated_log = ReplicatedLog::new_with_storage(
+                Box::new(SegmentedLogStore::new(directory.path()).unwrap()),
+                Box::new(FileSnapshotStorage::new(directory.path()).unwrap()),
+            );
+            replicated_log.append_membership(&membership, 1);
+            replicated_log.append_command(&Command { command: "Content".as_bytes().to_vec(), client_id: None, sequence: 0 }, 1);
+            replicated_log.commit(1, 1);
+            replicated_log.compact(1, "state".as_bytes().to_vec()).unwrap();
+        }
+
+        let replicated_log = ReplicatedLog::new_with_storage(
+            Box::new(SegmentedLogStore::new(directory.path()).unwrap()),
+            Box::new(FileSnapshotStorage::new(directory.path()).unwrap()),
+        );
+        assert_eq!(Some((1, membership)), replicated_log.get_membership());
+    }
+
+    #[test]
+    fn install_snapshot_with_a_membership() {
+        let replicated_log = ReplicatedLog::new();
+        let command = Command { command: "Content".as_bytes().to_vec(), client_id: None, sequence: 0 };
+        replicated_log.append_membership(&Membership::new(vec![voter(2010), voter(2011)]), 1);
+        replicated_log.append_command(&command, 1);
+
+        let membership = Membership::new(vec![voter(2010), voter(2011)]).with_learner(voter(2012)).promoting(&voter(2012));
+        replicated_log.install_snapshot(Snapshot::new(4, 2, Vec::new()).with_membership(membership.clone()));
+
+        assert_eq!(Some((4, membership)), replicated_log.get_membership());
+    }
+
+    #[test]
+    fn install_snapshot_retaining_the_membership_after_the_snapshot() {
+        let replicated_log = ReplicatedLog::new();
+        let command = Command { command: "Content".as_bytes().to_vec(), client_id: None, sequence: 0 };
+        let membership = Membership::new(vec![voter(2010), voter(2011)]);
+        let joint_membership = membership.with_voter(voter(2012));
+        replicated_log.append_membership(&membership, 1);
+        replicated_log.append_command(&command, 1);
+        replicated_log.append_membership(&joint_membership, 1);
+
+        replicated_log.install_snapshot(Snapshot::new(1, 1, Vec::new()).with_membership(membership));
+
+        assert_eq!(Some((2, joint_membership)), replicated_log.get_membership());
+    }
+
+    #[test]
+    fn commit_up_to_an_index() {
+        let replicated_log = ReplicatedLog::new();
+        for _count in 1..=3 {
+            replicated_log.append_command(&Command { command: "Content".as_bytes().to_vec(), client_id: None, sequence: 0 }, 1);
+        }
+
+        replicated_log.commit(1, 1);
+        assert_eq!(Some(1), replicated_log.get_commit_index());
+
+        replicated_log.commit(0, 1);
+        assert_eq!(Some(1), replicated_log.get_commit_index());
+    }
+}
@@ -0,0 +1,59 @@
+use crate::raft_config::RaftConfig;
+
+/// The replication limits and the forwarding of a `RaftConfig`, validated when the `RaftConfig` is built.
+pub(crate) struct ReplicationConfig {
+    max_entries_per_request: usize,
+    max_bytes_per_request: usize,
+    max_in_flight_requests: usize,
+    forward_to_leader: bool,
+}
+
+impl ReplicationConfig {
+    pub(crate) fn new(raft_config: &RaftConfig) -> Self {
+        return ReplicationConfig {
+            max_entries_per_request: raft_config.get_max_entries_per_append(),
+            max_bytes_per_request: raft_config.get_max_bytes_per_append(),
+            max_in_flight_requests: raft_config.get_max_in_flight_appends(),
+            forward_to_leader: raft_config.is_forwarding_to_leader_enabled(),
+        };
+    }
+
+    pub(crate) fn get_max_entries_per_request(&self) -> usize {
+        return self.max_entries_per_request;
+    }
+
+    pub(crate) fn get_max_bytes_per_request(&self) -> usize {
+        return self.max_bytes_per_request;
+    }
+
+    pub(crate) fn get_max_in_flight_requests(&self) -> usize {
+        return self.max_in_flight_requests;
+    }
+
+    pub(crate) fn is_forwarding_to_leader_enabled(&self) -> bool {
+        return self.forward_to_leader;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::raft_config::RaftConfig;
+    use crate::replication_config::ReplicationConfig;
+
+    #[test]
+    fn replication_config_of_the_default_raft_config() {
+        let replication_config = ReplicationConfig::new(&RaftConfig::default());
+
+        assert_eq!(64, replication_config.get_max_entries_per_request());
+        assert_eq!(1024 * 1024, replication_config.get_max_bytes_per_request());
+        assert_eq!(4, replication_config.get_max_in_flight_requests());
+        assert_eq!(false, replication_config.is_forwarding_to_leader_enabled());
+    }
+
+    #[test]
+    fn replication_config_with_forwarding_to_leader() {
+        let replication_config = ReplicationConfig::new(&RaftConfig::builder().with_forwarding_to_leader().build().unwrap());
+
+        assert!(replication_config.is_forwarding_to_leader_enabled());
+    }
+}
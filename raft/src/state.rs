@@ -1,34 +1,61 @@
+use std::any::Any;
+use std::collections::HashMap;
+use std::fmt::Debug;
 use std::future::Future;
-use std::sync::{Arc, RwLock};
-use std::time::{Duration, SystemTime};
+use std::sync::{Arc, Mutex, RwLock, RwLockWriteGuard};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
+use replicate::callback::async_quorum_callback::{AsyncQuorumCallback, StopCondition, SuccessCondition};
 use replicate::clock::clock::Clock;
 use replicate::heartbeat::heartbeat_scheduler::SingleThreadedHeartbeatScheduler;
+use replicate::net::connect::async_network::AsyncNetwork;
+use replicate::net::connect::correlation_id::RESERVED_CORRELATION_ID;
 use replicate::net::connect::error::{AnyError, ServiceResponseError};
+use replicate::net::connect::host_and_port::HostAndPort;
 use replicate::net::replica::{Replica, ReplicaId};
+use replicate::net::request_waiting_list::response_callback::ResponseCallback;
 
+use crate::client_sessions::{ClientSessionError, ClientSessions};
 use crate::election::election::Election;
 use crate::heartbeat_config::HeartbeatConfig;
+use crate::membership::Membership;
 use crate::net::factory::service_request::{BuiltInServiceRequestFactory, ServiceRequestFactory};
 use crate::net::rpc::grpc::AppendEntriesResponse;
+use crate::raft_config::RaftConfig;
 use crate::replicated_log::ReplicatedLog;
+use crate::state_machine::{NoOpStateMachine, StateMachine};
+use crate::storage::hard_state_storage::{HardState, HardStateStorage, InMemoryHardStateStorage};
+use crate::storage::log_store::{InMemoryLogStore, LogStore};
+use crate::storage::snapshot_storage::{InMemorySnapshotStorage, Snapshot, SnapshotStorage};
 
 pub struct State {
     consensus_state: RwLock<ConsensusState>,
     replica: Arc<Replica>,
     clock: Arc<dyn Clock>,
     heartbeat_config: HeartbeatConfig,
+    raft_config: RaftConfig,
     heartbeat_send_scheduler: SingleThreadedHeartbeatScheduler,
     heartbeat_check_scheduler: SingleThreadedHeartbeatScheduler,
     service_request_factory: Arc<dyn ServiceRequestFactory>,
     replicated_log: ReplicatedLog,
+    hard_state_storage: Arc<dyn HardStateStorage>,
+    state_machine: Arc<dyn StateMachine>,
+    last_applied: Mutex<Option<u64>>,
+    client_sessions: Mutex<ClientSessions>,
+    bootstrap_membership: Membership,
+    leader_elected_listener: Mutex<Option<Box<dyn Fn() + Send + Sync>>>,
 }
 
 struct ConsensusState {
     term: u64,
     role: ReplicaRole,
     voted_for: Option<u64>,
+    leader_id: Option<ReplicaId>,
+    leader_address: Option<HostAndPort>,
     heartbeat_received_time: Option<SystemTime>,
+    leader_lease_start_time: Option<SystemTime>,
+    heartbeat_response_time_by_peer: HashMap<HostAndPort, SystemTime>,
+    election_start_time: Option<SystemTime>,
     creation_time: SystemTime,
 }
 
@@ -37,39 +64,132 @@ pub enum ReplicaRole {
     Leader,
     Follower,
     Candidate,
+    Learner,
 }
 
 impl State {
-    pub fn new(replica: Arc<Replica>, heartbeat_config: HeartbeatConfig) -> Arc<State> {
-        return Self::new_with(replica, heartbeat_config, Arc::new(BuiltInServiceRequestFactory::new()));
+    pub fn new(replica: Arc<Replica>, raft_config: RaftConfig) -> Arc<State> {
+        return Self::new_with_storage(
+            replica,
+            raft_config,
+            Arc::new(InMemoryHardStateStorage::new()),
+            Box::new(InMemoryLogStore::new()),
+            Box::new(InMemorySnapshotStorage::new()),
+            Arc::new(NoOpStateMachine::new()),
+        );
+    }
+
+    /// A replica that joins the cluster (the replica's peers) as a learner: it receives the log,
+    /// but neither votes nor starts an election until it is promoted to a voter.
+    pub fn new_learner(replica: Arc<Replica>, raft_config: RaftConfig) -> Arc<State> {
+        let bootstrap_membership = Membership::new(replica.get_peers()).with_learner(replica.get_self_address());
+        return Self::new_with_bootstrap_membership(
+            replica,
+            raft_config,
+            Arc::new(BuiltInServiceRequestFactory::new()),
+            Arc::new(InMemoryHardStateStorage::new()),
+            ReplicatedLog::new_with_storage(Box::new(InMemoryLogStore::new()), Box::new(InMemorySnapshotStorage::new())),
+            Arc::new(NoOpStateMachine::new()),
+            bootstrap_membership,
+        );
+    }
+
+    pub fn new_with_storage(replica: Arc<Replica>,
+                            raft_config: RaftConfig,
+                            hard_state_storage: Arc<dyn HardStateStorage>,
+                            log_store: Box<dyn LogStore>,
+                            snapshot_storage: Box<dyn SnapshotStorage>,
+                            state_machine: Arc<dyn StateMachine>) -> Arc<State> {
+        return Self::new_with(
+            replica,
+            raft_config,
+            Arc::new(BuiltInServiceRequestFactory::new()),
+            hard_state_storage,
+            log_store,
+            snapshot_storage,
+            state_machine,
+        );
+    }
+
+    fn new_with(replica: Arc<Replica>,
+                raft_config: RaftConfig,
+                service_request_factory: Arc<dyn ServiceRequestFactory>,
+                hard_state_storage: Arc<dyn HardStateStorage>,
+                log_store: Box<dyn LogStore>,
+                snapshot_storage: Box<dyn SnapshotStorage>,
+                state_machine: Arc<dyn StateMachine>) -> Arc<State> {
+        let mut bootstrap_voters = vec![replica.get_self_address()];
+        bootstrap_voters.extend(replica.get_peers());
+        return Self::new_with_bootstrap_membership(
+            replica,
+            raft_config,
+            service_request_factory,
+            hard_state_storage,
+            ReplicatedLog::new_with_storage(log_store, snapshot_storage),
+            state_machine,
+            Membership::new(bootstrap_voters),
+        );
     }
 
-    fn new_with(replica: Arc<Replica>, heartbeat_config: HeartbeatConfig, service_request_factory: Arc<dyn ServiceRequestFactory>) -> Arc<State> {
+    fn new_with_bootstrap_membership(replica: Arc<Replica>,
+                                     raft_config: RaftConfig,
+                                     service_request_factory: Arc<dyn ServiceRequestFactory>,
+                                     hard_state_storage: Arc<dyn HardStateStorage>,
+                                     replicated_log: ReplicatedLog,
+                                     state_machine: Arc<dyn StateMachine>,
+                                     bootstrap_membership: Membership) -> Arc<State> {
         let clock = replica.get_clock();
-        let heartbeat_config = heartbeat_config;
+        let heartbeat_config = HeartbeatConfig::new(&raft_config);
         let heartbeat_interval = heartbeat_config.get_heartbeat_interval();
-        let heartbeat_timeout = heartbeat_config.get_heartbeat_timeout();
+        let heartbeat_check_interval = heartbeat_config.get_heartbeat_check_interval();
+
+        let hard_state = hard_state_storage
+            .load()
+            .expect("failed to load the hard state (term and voted_for)")
+            .unwrap_or(HardState::new(0, None));
+
+        let mut client_sessions = ClientSessions::new(raft_config.get_client_session_timeout());
+        let last_applied = match replicated_log.get_snapshot() {
+            None => None,
+            Some(snapshot) => {
+                state_machine.restore(&snapshot);
+                client_sessions.restore(&snapshot.get_client_sessions_as_vec());
+                Some(snapshot.get_last_included_index())
+            }
+        };
 
-        let majority_quorum = (replica.cluster_size() / 2) + 1;
         let state = State {
             consensus_state: RwLock::new(ConsensusState {
-                term: 0,
+                term: hard_state.get_term(),
                 role: ReplicaRole::Follower,
-                voted_for: None,
+                voted_for: hard_state.get_voted_for(),
+                leader_id: None,
+                leader_address: None,
                 heartbeat_received_time: None,
+                leader_lease_start_time: None,
+                heartbeat_response_time_by_peer: HashMap::new(),
+                election_start_time: None,
                 creation_time: clock.now(),
             }),
             replica,
             clock,
             heartbeat_config,
+            raft_config,
             heartbeat_send_scheduler: SingleThreadedHeartbeatScheduler::new(heartbeat_interval),
-            heartbeat_check_scheduler: SingleThreadedHeartbeatScheduler::new(heartbeat_timeout),
+            heartbeat_check_scheduler: SingleThreadedHeartbeatScheduler::new(heartbeat_check_interval),
             service_request_factory,
-            replicated_log: ReplicatedLog::new(majority_quorum),
+            replicated_log,
+            hard_state_storage,
+            state_machine,
+            last_applied: Mutex::new(last_applied),
+            client_sessions: Mutex::new(client_sessions),
+            bootstrap_membership,
+            leader_elected_listener: Mutex::new(None),
         };
+        state.refresh_membership();
 
         let state = Arc::new(state);
-        state.clone().change_to_follower(0);
+        state.clone().change_to_follower(hard_state.get_term());
         return state;
     }
 
@@ -79,12 +199,34 @@ impl State {
         consensus_state.heartbeat_received_time = Some(self.clock.now());
     }
 
+    /// Records the leader of the current term, a request without the leader's address keeps the address known for the same leader.
+    pub(crate) fn mark_leader(&self, leader_id: ReplicaId, leader_address: Option<HostAndPort>) {
+        let mut write_guard = self.consensus_state.write().unwrap();
+        let consensus_state = &mut *write_guard;
+        if consensus_state.leader_id != Some(leader_id) {
+            consensus_state.leader_id = Some(leader_id);
+            consensus_state.leader_address = None;
+        }
+        if leader_address.is_some() {
+            consensus_state.leader_address = leader_address;
+        }
+    }
+
+    /// Stops sending heartbeats and checking the heartbeat timeout, used when the node shuts down.
+    pub(crate) fn stop_heartbeats(&self) {
+        self.heartbeat_send_scheduler.stop();
+        self.heartbeat_check_scheduler.stop();
+    }
+
     pub(crate) fn change_to_candidate(&self) -> u64 {
         let mut write_guard = self.consensus_state.write().unwrap();
         let mut consensus_state = &mut *write_guard;
         consensus_state.term = consensus_state.term + 1;
         consensus_state.role = ReplicaRole::Candidate;
         consensus_state.voted_for = Some(self.replica.get_id());
+        consensus_state.leader_id = None;
+        consensus_state.leader_address = None;
+        self.persist_hard_state(consensus_state);
 
         self.heartbeat_send_scheduler.stop();
         self.heartbeat_check_scheduler.stop();
@@ -93,30 +235,95 @@ impl State {
     }
 
     pub(crate) fn change_to_follower(self: Arc<State>, term: u64) {
-        let mut write_guard = self.consensus_state.write().unwrap();
+        let write_guard = self.consensus_state.write().unwrap();
+        self.become_follower(write_guard, term);
+    }
+
+    /// Steps down after losing the election of `term`, to the highest term received if it is past the current term,
+    /// or to a follower of `term` if the replica is still its candidate.
+    /// A replica that has moved past the election in the meantime (say, voted in a higher term) keeps its term and its vote.
+    pub(crate) fn change_to_follower_after_lost_election(self: Arc<State>, term: u64, highest_term: u64) {
+        let write_guard = self.consensus_state.write().unwrap();
+        if highest_term > write_guard.term {
+            self.become_follower(write_guard, highest_term);
+        } else if write_guard.role == ReplicaRole::Candidate && write_guard.term == term {
+            self.become_follower(write_guard, term);
+        }
+    }
+
+    fn become_follower(self: &Arc<State>, mut write_guard: RwLockWriteGuard<ConsensusState>, term: u64) {
         let mut consensus_state = &mut *write_guard;
-        consensus_state.role = ReplicaRole::Follower;
-        consensus_state.term = term;
-        consensus_state.voted_for = None;
+        consensus_state.role = if self.is_learner() { ReplicaRole::Learner } else { ReplicaRole::Follower };
+        if consensus_state.term != term {
+            consensus_state.term = term;
+            consensus_state.voted_for = None;
+            consensus_state.leader_id = None;
+            consensus_state.leader_address = None;
+        }
+        self.persist_hard_state(consensus_state);
 
         self.heartbeat_send_scheduler.stop();
         Self::restart_heartbeat_checker(self.clone(), &self.heartbeat_check_scheduler);
     }
 
+    /// A new leader appends a no-op entry in its term, the entries of the earlier terms are committed once the no-op is committed.
+    #[cfg(test)]
     pub(crate) fn change_to_leader(self: Arc<State>) {
-        let mut write_guard = self.consensus_state.write().unwrap();
+        let write_guard = self.consensus_state.write().unwrap();
+        self.become_leader(write_guard);
+    }
+
+    /// Becomes the leader only if the replica is still the candidate of `term`, the term it has won the election in.
+    pub(crate) fn change_to_leader_if_candidate_in(self: Arc<State>, term: u64) -> bool {
+        let write_guard = self.consensus_state.write().unwrap();
+        if write_guard.role != ReplicaRole::Candidate || write_guard.term != term {
+            return false;
+        }
+        self.become_leader(write_guard);
+        return true;
+    }
+
+    fn become_leader(self: &Arc<State>, mut write_guard: RwLockWriteGuard<ConsensusState>) {
         let mut consensus_state = &mut *write_guard;
         consensus_state.role = ReplicaRole::Leader;
+        consensus_state.leader_lease_start_time = None;
+        //a new leader has a heartbeat timeout to hear from a majority
+        let now = self.clock.now();
+        consensus_state.heartbeat_response_time_by_peer = self.replica.get_peers().into_iter().map(|peer| (peer, now)).collect();
+        consensus_state.leader_id = Some(self.replica.get_id());
+        consensus_state.leader_address = Some(self.replica.get_self_address());
+        self.replicated_log.append_no_op(consensus_state.term);
 
         self.heartbeat_check_scheduler.stop();
         Self::restart_heartbeat_sender(self.clone(), &self.heartbeat_send_scheduler);
+        drop(write_guard);
+
+        if let Some(listener) = &*self.leader_elected_listener.lock().unwrap() {
+            listener();
+        }
+    }
+
+    /// Registers the listener that is invoked after the replica becomes the leader (and has appended the no-op entry).
+    pub(crate) fn on_leader_elected<F>(&self, listener: F)
+        where F: Fn() + Send + Sync + 'static {
+        *self.leader_elected_listener.lock().unwrap() = Some(Box::new(listener));
     }
 
-    pub(crate) fn get_heartbeat_response_handler(self: Arc<State>, append_entry_response: AppendEntriesResponse) -> impl Future<Output=()> {
+    pub(crate) fn get_heartbeat_response_handler(self: Arc<State>,
+                                                 append_entry_response: AppendEntriesResponse,
+                                                 from: HostAndPort,
+                                                 heartbeat_round: Arc<HeartbeatRound>) -> impl Future<Output=()> {
         let inner_state = self.clone();
         return async move {
-            if !append_entry_response.success {
+            if !append_entry_response.success && append_entry_response.term > inner_state.get_term() {
                 inner_state.change_to_follower(append_entry_response.term);
+                return;
+            }
+            if append_entry_response.success && append_entry_response.term == heartbeat_round.term {
+                inner_state.mark_heartbeat_response_from(from.clone());
+                if inner_state.get_membership().has_majority_of(&heartbeat_round.acknowledge(from)) {
+                    inner_state.extend_leader_lease(heartbeat_round.term, heartbeat_round.sent_time);
+                }
             }
         };
     }
@@ -127,24 +334,137 @@ impl State {
         let clock = self.clock.clone();
 
         return async move {
-            let write_guard = inner_self.consensus_state.write().unwrap();
-            let consensus_state = &*write_guard;
-            match consensus_state.heartbeat_received_time {
-                Some(last_heartbeat_time) => {
-                    if clock.duration_since(last_heartbeat_time).ge(&heartbeat_timeout) {
-                        election_starter(inner_self.clone());
-                    }
-                }
-                None => {
-                    if clock.duration_since(consensus_state.creation_time).ge(&heartbeat_timeout) {
-                        election_starter(inner_self.clone());
-                    }
-                }
+            let mut write_guard = inner_self.consensus_state.write().unwrap();
+            let consensus_state = &mut *write_guard;
+            let mut timeout_start_time = match consensus_state.heartbeat_received_time {
+                Some(last_heartbeat_time) => last_heartbeat_time,
+                None => consensus_state.creation_time,
+            };
+            //an election round that did not elect a leader waits for a whole (newly drawn) timeout before the next round
+            if let Some(election_start_time) = consensus_state.election_start_time {
+                timeout_start_time = timeout_start_time.max(election_start_time);
+            }
+            if clock.duration_since(timeout_start_time).ge(&heartbeat_timeout) {
+                consensus_state.election_start_time = Some(clock.now());
+                inner_self.heartbeat_config.next_heartbeat_timeout();
+                election_starter(inner_self.clone());
             }
             return Ok(());
         };
     }
 
+    /// The leader holds a lease if a majority has acknowledged one of its heartbeat rounds within the lease duration.
+    /// No other leader can be elected while the lease holds, so reads can be served locally.
+    pub(crate) fn has_leader_lease(&self) -> bool {
+        let leader_lease_duration = match self.heartbeat_config.get_leader_lease_duration() {
+            None => return false,
+            Some(leader_lease_duration) => leader_lease_duration
+        };
+        let guard = self.consensus_state.read().unwrap();
+        if (*guard).role != ReplicaRole::Leader {
+            return false;
+        }
+        return match (*guard).leader_lease_start_time {
+            None => false,
+            Some(leader_lease_start_time) =>
+                self.clock.duration_since(leader_lease_start_time).lt(&leader_lease_duration)
+        };
+    }
+
+    pub(crate) fn is_leader_lease_enabled(&self) -> bool {
+        return self.heartbeat_config.is_leader_lease_enabled();
+    }
+
+    pub(crate) fn get_heartbeat_config(&self) -> &HeartbeatConfig {
+        return &self.heartbeat_config;
+    }
+
+    pub(crate) fn get_raft_config(&self) -> &RaftConfig {
+        return &self.raft_config;
+    }
+
+    pub(crate) fn is_pre_vote_enabled(&self) -> bool {
+        return self.heartbeat_config.is_pre_vote_enabled();
+    }
+
+    /// A replica that has received a heartbeat within its heartbeat timeout still follows a live leader.
+    pub(crate) fn has_live_leader(&self) -> bool {
+        let guard = self.consensus_state.read().unwrap();
+        return match (*guard).heartbeat_received_time {
+            None => false,
+            Some(heartbeat_received_time) =>
+                self.clock.duration_since(heartbeat_received_time).lt(&self.heartbeat_config.get_heartbeat_timeout())
+        };
+    }
+
+    /// A follower that has heard from the leader of its term within the minimum heartbeat (election) timeout believes the leader is alive,
+    /// whatever the timeout drawn for its own next election.
+    pub(crate) fn has_recently_heard_from_leader(&self) -> bool {
+        let guard = self.consensus_state.read().unwrap();
+        if (*guard).role == ReplicaRole::Leader || (*guard).leader_id.is_none() {
+            return false;
+        }
+        return match (*guard).heartbeat_received_time {
+            None => false,
+            Some(heartbeat_received_time) =>
+                self.clock.duration_since(heartbeat_received_time).lt(&self.heartbeat_config.minimum_heartbeat_timeout())
+        };
+    }
+
+    /// The latest membership in the log, or the replica and its peers if the membership has never changed.
+    pub fn get_membership(&self) -> Membership {
+        return match self.replicated_log.get_membership() {
+            None => self.bootstrap_membership.clone(),
+            Some((_, membership)) => membership
+        };
+    }
+
+    pub(crate) fn is_voter(&self) -> bool {
+        return self.get_membership().is_voter(&self.replica.get_self_address());
+    }
+
+    pub(crate) fn is_learner(&self) -> bool {
+        return self.get_membership().is_learner(&self.replica.get_self_address());
+    }
+
+    /// Points the replica to the members of the latest membership, to be called after the log changes.
+    /// A follower that became a learner (or a learner that was promoted) switches its role.
+    pub(crate) fn refresh_membership(&self) {
+        let membership = self.get_membership();
+        self.replica.update_peers(membership.get_members());
+
+        let mut write_guard = self.consensus_state.write().unwrap();
+        let consensus_state = &mut *write_guard;
+        let is_learner = membership.is_learner(&self.replica.get_self_address());
+        if is_learner && consensus_state.role == ReplicaRole::Follower {
+            consensus_state.role = ReplicaRole::Learner;
+        } else if !is_learner && consensus_state.role == ReplicaRole::Learner {
+            consensus_state.role = ReplicaRole::Follower;
+        }
+    }
+
+    /// A callback that completes once the responses meeting the success condition (including the replica's own response) form a majority of the membership.
+    pub(crate) fn quorum_callback<Response: Any + Send + Sync + Debug>(&self, success_condition: SuccessCondition<Response>) -> Arc<AsyncQuorumCallback<Response>> {
+        return self.quorum_callback_with_stop_condition(success_condition, None);
+    }
+
+    /// Same as `quorum_callback`, but completes without success as soon as any response meets the stop condition.
+    pub(crate) fn quorum_callback_with_stop_condition<Response: Any + Send + Sync + Debug>(&self,
+                                                                                          success_condition: SuccessCondition<Response>,
+                                                                                          stop_condition: Option<StopCondition<Response>>) -> Arc<AsyncQuorumCallback<Response>> {
+        let cluster_size = self.replica.cluster_size();
+        if self.replicated_log.get_membership().is_none() {
+            return AsyncQuorumCallback::<Response>::new_with_stop_condition(cluster_size, cluster_size, success_condition, stop_condition);
+        }
+        let membership = self.get_membership();
+        return AsyncQuorumCallback::<Response>::new_with_quorum_and_stop_condition(
+            cluster_size,
+            success_condition,
+            Box::new(move |hosts| membership.has_majority_of(hosts)),
+            stop_condition,
+        );
+    }
+
     pub(crate) fn get_replica(&self) -> Arc<Replica> {
         return self.replica.clone();
     }
@@ -157,6 +477,7 @@ impl State {
         let mut write_guard = self.consensus_state.write().unwrap();
         let mut consensus_state = &mut *write_guard;
         consensus_state.voted_for = Some(replica_id);
+        self.persist_hard_state(consensus_state);
     }
 
     pub(crate) fn has_not_voted_for_or_matches(&self, replica_id: ReplicaId) -> bool {
@@ -169,11 +490,90 @@ impl State {
         return true;
     }
 
-    pub(crate) fn get_voted_for(&self) -> Option<ReplicaId> {
+    pub fn get_voted_for(&self) -> Option<ReplicaId> {
         let guard = self.consensus_state.read().unwrap();
         return (*guard).voted_for;
     }
 
+    /// Applies the committed entries that are not applied yet, in log order.
+    /// `applied_execution_block` receives the index and the term of every applied entry along with its output.
+    pub(crate) fn apply_committed_log_entries<F>(&self, applied_execution_block: F)
+        where F: Fn(u64, u64, Result<Vec<u8>, ClientSessionError>) -> () {
+        let mut last_applied = self.last_applied.lock().unwrap();
+        let commit_index = match self.replicated_log.get_commit_index() {
+            None => return,
+            Some(commit_index) => commit_index
+        };
+        let starting_index = match *last_applied {
+            None => 0,
+            Some(last_applied) => last_applied + 1
+        };
+        let mut client_sessions = self.client_sessions.lock().unwrap();
+        for index in starting_index..=commit_index {
+            match self.replicated_log.get_log_entry_at(index as usize) {
+                None => break,
+                Some(log_entry) => {
+                    let output = if log_entry.is_configuration() || log_entry.is_no_op() {
+                        Ok(Vec::new())
+                    } else {
+                        client_sessions.apply(
+                            log_entry.get_client_id(),
+                            log_entry.get_sequence(),
+                            log_entry.get_time(),
+                            || self.state_machine.apply(&log_entry),
+                        )
+                    };
+                    *last_applied = Some(index);
+                    applied_execution_block(index, log_entry.get_term(), output);
+                }
+            }
+        }
+    }
+
+    pub(crate) fn install_snapshot(&self, snapshot: Snapshot) {
+        let mut last_applied = self.last_applied.lock().unwrap();
+        let last_included_index = snapshot.get_last_included_index();
+        if !self.replicated_log.install_snapshot(snapshot.clone()) {
+            return;
+        }
+        let already_applied = match *last_applied {
+            None => false,
+            Some(last_applied) => last_applied >= last_included_index
+        };
+        if !already_applied {
+            self.state_machine.restore(&snapshot);
+            self.client_sessions.lock().unwrap().restore(&snapshot.get_client_sessions_as_vec());
+            *last_applied = Some(last_included_index);
+        }
+    }
+
+    /// Compacts the log up to the last included index into a snapshot of the state machine's data and the client sessions.
+    /// The client sessions are known only as of the last applied index, so the snapshot is taken only if it is the last included index.
+    pub fn compact(&self, last_included_index: u64, data: Vec<u8>) -> Option<Snapshot> {
+        let last_applied = self.last_applied.lock().unwrap();
+        if *last_applied != Some(last_included_index) {
+            return None;
+        }
+        let client_sessions = self.client_sessions.lock().unwrap().encode();
+        return self.replicated_log.compact_with_client_sessions(last_included_index, data, client_sessions);
+    }
+
+    /// The leader's clock in milliseconds since the unix epoch, the log time stamped on the commands it appends.
+    pub(crate) fn get_log_time(&self) -> u64 {
+        return self.clock.now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_millis() as u64);
+    }
+
+    pub(crate) fn read_state_machine(&self, query: &[u8]) -> Vec<u8> {
+        return self.state_machine.read(query);
+    }
+
+    pub fn get_last_applied(&self) -> Option<u64> {
+        let guard = self.last_applied.lock().unwrap();
+        return *guard;
+    }
+
     pub fn get_replicated_log(&self) -> &ReplicatedLog {
         return &self.replicated_log;
     }
@@ -188,6 +588,16 @@ impl State {
         return (*guard).role;
     }
 
+    pub fn get_leader_id(&self) -> Option<ReplicaId> {
+        let guard = self.consensus_state.read().unwrap();
+        return (*guard).leader_id;
+    }
+
+    pub fn get_leader_address(&self) -> Option<HostAndPort> {
+        let guard = self.consensus_state.read().unwrap();
+        return (*guard).leader_address;
+    }
+
     pub fn get_heartbeat_received_time(&self) -> Option<SystemTime> {
         let guard = self.consensus_state.read().unwrap();
         return (*guard).heartbeat_received_time;
@@ -200,14 +610,28 @@ impl State {
         let service_request_factory = self.service_request_factory.clone();
 
         return async move {
+            //a round generated just as the leader became a candidate must not carry the candidate's term
+            if self.get_role() != ReplicaRole::Leader {
+                return Ok(());
+            }
+            if self.has_lost_contact_with_majority(term) {
+                println!("stepping down, the leader has not heard from a majority within the heartbeat timeout");
+                self.clone().change_to_follower(term);
+                return Ok(());
+            }
+            let self_address = self.replica.get_self_address();
+            let heartbeat_round = Arc::new(HeartbeatRound::new(term, self_address, self.clock.now()));
+            if self.get_membership().has_majority_of(&vec![self_address]) {
+                self.extend_leader_lease(term, heartbeat_round.sent_time);
+            }
             let service_request_constructor = || {
                 service_request_factory.heartbeat(term, leader_id)
             };
 
             let response_handler_generator =
-                move |response: Result<AppendEntriesResponse, ServiceResponseError>| {
+                move |from: HostAndPort, response: Result<AppendEntriesResponse, ServiceResponseError>| {
                     match response {
-                        Ok(response) => Some(self.clone().get_heartbeat_response_handler(response)),
+                        Ok(response) => Some(self.clone().get_heartbeat_response_handler(response, from, heartbeat_round.clone())),
                         Err(_) => None
                     }
                 };
@@ -217,7 +641,88 @@ impl State {
         };
     }
 
+    fn mark_heartbeat_response_from(&self, peer: HostAndPort) {
+        let mut write_guard = self.consensus_state.write().unwrap();
+        let consensus_state = &mut *write_guard;
+        consensus_state.heartbeat_response_time_by_peer.insert(peer, self.clock.now());
+    }
+
+    /// Check-quorum (unless disabled): the leader (of `term`) has lost contact with a majority if a majority of the voters, the leader included,
+    /// has not answered its heartbeats within the heartbeat timeout. Such a leader can not commit and steps down.
+    fn has_lost_contact_with_majority(&self, term: u64) -> bool {
+        let membership = self.get_membership();
+        let self_address = self.replica.get_self_address();
+        let heartbeat_timeout = self.heartbeat_config.get_heartbeat_timeout();
+
+        let guard = self.consensus_state.read().unwrap();
+        let consensus_state = &*guard;
+        if !self.heartbeat_config.is_check_quorum_enabled() || consensus_state.role != ReplicaRole::Leader || consensus_state.term != term {
+            return false;
+        }
+        return !membership.has_majority(|voter| {
+            if *voter == self_address {
+                return true;
+            }
+            return match consensus_state.heartbeat_response_time_by_peer.get(voter) {
+                None => false,
+                Some(heartbeat_response_time) => self.clock.duration_since(*heartbeat_response_time).lt(&heartbeat_timeout)
+            };
+        });
+    }
+
+    /// Sends a round of heartbeats and returns true if a majority, including the replica itself, acknowledges its term.
+    pub(crate) async fn confirm_leadership(self: Arc<State>) -> bool {
+        let term = self.get_term();
+        let leader_id = self.replica.get_id();
+
+        let success_condition = Box::new(move |response: &AppendEntriesResponse| response.success && response.term == term);
+        let async_quorum_callback = self.quorum_callback::<AppendEntriesResponse>(success_condition);
+        for peer in self.replica.get_peers() {
+            let service_request = self.service_request_factory.heartbeat(term, leader_id);
+            let inner_async_quorum_callback = async_quorum_callback.clone();
+            tokio::spawn(async move {
+                match AsyncNetwork::send_without_source_footprint(service_request, peer).await {
+                    Ok(response) => inner_async_quorum_callback.on_response(peer, Ok(Box::new(response))),
+                    Err(err) => inner_async_quorum_callback.on_response(peer, Err(err)),
+                }
+            });
+        }
+        async_quorum_callback.on_response(self.replica.get_self_address(), Ok(Box::new(AppendEntriesResponse {
+            term,
+            success: true,
+            log_entry_index: None,
+            conflict_term: None,
+            conflict_index: None,
+            correlation_id: RESERVED_CORRELATION_ID,
+        })));
+
+        let confirmed = async_quorum_callback.handle().await.is_success();
+        return confirmed && self.get_role() == ReplicaRole::Leader && self.get_term() == term;
+    }
+
+    fn extend_leader_lease(&self, term: u64, heartbeat_sent_time: SystemTime) {
+        let mut write_guard = self.consensus_state.write().unwrap();
+        let consensus_state = &mut *write_guard;
+        if consensus_state.role != ReplicaRole::Leader || consensus_state.term != term {
+            return;
+        }
+        let extended = match consensus_state.leader_lease_start_time {
+            None => true,
+            Some(leader_lease_start_time) => heartbeat_sent_time > leader_lease_start_time
+        };
+        if extended {
+            consensus_state.leader_lease_start_time = Some(heartbeat_sent_time);
+        }
+    }
+
+    fn persist_hard_state(&self, consensus_state: &ConsensusState) {
+        self.hard_state_storage
+            .save(HardState::new(consensus_state.term, consensus_state.voted_for))
+            .expect("failed to persist the hard state (term and voted_for)");
+    }
+
     fn restart_heartbeat_checker(state: Arc<State>, heartbeat_check_scheduler: &SingleThreadedHeartbeatScheduler) {
+        state.heartbeat_config.next_heartbeat_timeout();
         heartbeat_check_scheduler.restart_with(move || {
             let inner_state = state.clone();
             let heartbeat_timeout = inner_state.heartbeat_config.get_heartbeat_timeout();
@@ -237,11 +742,32 @@ impl State {
     }
 }
 
+/// A round of heartbeats sent by the leader, recording the replicas (including the leader) that acknowledged its term.
+pub(crate) struct HeartbeatRound {
+    term: u64,
+    sent_time: SystemTime,
+    acknowledged_by: Mutex<Vec<HostAndPort>>,
+}
+
+impl HeartbeatRound {
+    fn new(term: u64, leader: HostAndPort, sent_time: SystemTime) -> Self {
+        return HeartbeatRound { term, sent_time, acknowledged_by: Mutex::new(vec![leader]) };
+    }
+
+    fn acknowledge(&self, from: HostAndPort) -> Vec<HostAndPort> {
+        let mut acknowledged_by = self.acknowledged_by.lock().unwrap();
+        if !acknowledged_by.contains(&from) {
+            acknowledged_by.push(from);
+        }
+        return acknowledged_by.clone();
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::net::{IpAddr, Ipv4Addr};
-    use std::sync::{Arc, RwLock};
-    use std::sync::atomic::AtomicU64;
+    use std::sync::{Arc, Mutex, RwLock};
+    use std::sync::atomic::{AtomicU64, Ordering};
     use std::thread;
     use std::time::Duration;
 
@@ -251,9 +777,16 @@ mod tests {
     use replicate::net::connect::host_and_port::HostAndPort;
     use replicate::net::replica::Replica;
 
-    use crate::heartbeat_config::HeartbeatConfig;
+    use crate::log_entry::LogEntryType;
+    use crate::net::rpc::grpc::Command;
+    use crate::raft_config::RaftConfig;
+    use crate::state::tests::setup::{HeartbeatResponseClientType, IncrementingCorrelationIdServiceRequestFactory, RecordingStateMachine};
     use crate::state::{ReplicaRole, State};
-    use crate::state::tests::setup::{HeartbeatResponseClientType, IncrementingCorrelationIdServiceRequestFactory};
+    use crate::state_machine::NoOpStateMachine;
+    use crate::storage::hard_state_storage::{HardState, HardStateStorage, InMemoryHardStateStorage};
+    use crate::storage::log_store::InMemoryLogStore;
+    use crate::storage::snapshot_storage::{InMemorySnapshotStorage, Snapshot, SnapshotStorage};
+    use crate::test_support::ControllableClock;
 
     #[tokio::test(flavor = "multi_thread")]
     async fn change_to_candidate() {
@@ -266,7 +799,7 @@ mod tests {
             Arc::new(SystemClock::new()),
         );
 
-        let state = State::new(Arc::new(some_replica), HeartbeatConfig::default());
+        let state = State::new(Arc::new(some_replica), RaftConfig::default());
         state.change_to_candidate();
 
         assert_eq!(1, state.get_term());
@@ -285,7 +818,7 @@ mod tests {
             Arc::new(SystemClock::new()),
         );
 
-        let state = State::new(Arc::new(some_replica), HeartbeatConfig::default());
+        let state = State::new(Arc::new(some_replica), RaftConfig::default());
         let clone = state.clone();
         clone.change_to_candidate();
         clone.change_to_leader();
@@ -296,7 +829,7 @@ mod tests {
     }
 
     #[tokio::test(flavor = "multi_thread")]
-    async fn change_to_follower() {
+    async fn keep_the_term_and_the_vote_of_a_higher_term_after_losing_an_election() {
         let some_replica = Replica::new(
             10,
             HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1971),
@@ -306,18 +839,23 @@ mod tests {
             Arc::new(SystemClock::new()),
         );
 
-        let state = State::new(Arc::new(some_replica), HeartbeatConfig::default());
-        let clone = state.clone();
-        clone.change_to_candidate();
-        clone.change_to_follower(2);
+        let state = State::new(Arc::new(some_replica), RaftConfig::default());
+        let term = state.change_to_candidate();
+        state.clone().change_to_follower(term + 1);
+        state.voted_for(20);
 
-        assert_eq!(2, state.get_term());
+        state.clone().change_to_follower_after_lost_election(term, term);
         assert_eq!(ReplicaRole::Follower, state.get_role());
-        assert_eq!(None, state.get_voted_for());
+        assert_eq!(term + 1, state.get_term());
+        assert_eq!(Some(20), state.get_voted_for());
+
+        assert_eq!(false, state.clone().change_to_leader_if_candidate_in(term));
+        assert_eq!(ReplicaRole::Follower, state.get_role());
+        state.stop_heartbeats();
     }
 
     #[tokio::test(flavor = "multi_thread")]
-    async fn get_voted_for_none() {
+    async fn step_down_after_losing_an_election_to_the_highest_term() {
         let some_replica = Replica::new(
             10,
             HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1971),
@@ -327,13 +865,23 @@ mod tests {
             Arc::new(SystemClock::new()),
         );
 
-        let state = State::new(Arc::new(some_replica), HeartbeatConfig::default());
+        let state = State::new(Arc::new(some_replica), RaftConfig::default());
+        let term = state.change_to_candidate();
+        state.clone().change_to_follower_after_lost_election(term, term);
+        assert_eq!(ReplicaRole::Follower, state.get_role());
+        assert_eq!(term, state.get_term());
+        assert_eq!(Some(10), state.get_voted_for());
 
+        let term = state.change_to_candidate();
+        state.clone().change_to_follower_after_lost_election(term, term + 3);
+        assert_eq!(ReplicaRole::Follower, state.get_role());
+        assert_eq!(term + 3, state.get_term());
         assert_eq!(None, state.get_voted_for());
+        state.stop_heartbeats();
     }
 
     #[tokio::test(flavor = "multi_thread")]
-    async fn has_not_voted() {
+    async fn change_to_leader_given_the_replica_is_still_the_candidate_of_the_term() {
         let some_replica = Replica::new(
             10,
             HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1971),
@@ -343,13 +891,19 @@ mod tests {
             Arc::new(SystemClock::new()),
         );
 
-        let state = State::new(Arc::new(some_replica), HeartbeatConfig::default());
+        let state = State::new(Arc::new(some_replica), RaftConfig::default());
+        let term = state.change_to_candidate();
+        let next_term = state.change_to_candidate();
 
-        assert_eq!(true, state.has_not_voted_for_or_matches(10));
+        assert_eq!(false, state.clone().change_to_leader_if_candidate_in(term));
+        assert_eq!(ReplicaRole::Candidate, state.get_role());
+        assert!(state.clone().change_to_leader_if_candidate_in(next_term));
+        assert_eq!(ReplicaRole::Leader, state.get_role());
+        state.stop_heartbeats();
     }
 
     #[tokio::test(flavor = "multi_thread")]
-    async fn has_voted_for_the_replica_id() {
+    async fn change_to_leader_appends_a_no_op_entry() {
         let some_replica = Replica::new(
             10,
             HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1971),
@@ -359,14 +913,28 @@ mod tests {
             Arc::new(SystemClock::new()),
         );
 
-        let state = State::new(Arc::new(some_replica), HeartbeatConfig::default());
-        state.voted_for(15);
+        let state = State::new(Arc::new(some_replica), RaftConfig::default());
+        let command = Command { command: "Content".as_bytes().to_vec(), client_id: None, sequence: 0 };
+        state.get_replicated_log().append_command(&command, 0);
 
-        assert_eq!(true, state.has_not_voted_for_or_matches(15));
+        let leader_elected = Arc::new(AtomicU64::new(0));
+        let inner_leader_elected = leader_elected.clone();
+        state.on_leader_elected(move || {
+            inner_leader_elected.fetch_add(1, Ordering::SeqCst);
+        });
+
+        let clone = state.clone();
+        clone.change_to_candidate();
+        clone.change_to_leader();
+
+        let log_entry = state.get_replicated_log().get_log_entry_at(1).unwrap();
+        assert_eq!(LogEntryType::NoOp, log_entry.get_entry_type());
+        assert_eq!(1, log_entry.get_term());
+        assert_eq!(1, leader_elected.load(Ordering::SeqCst));
     }
 
     #[tokio::test(flavor = "multi_thread")]
-    async fn does_not_match_the_replica_id_voted_for() {
+    async fn change_to_follower() {
         let some_replica = Replica::new(
             10,
             HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1971),
@@ -376,14 +944,18 @@ mod tests {
             Arc::new(SystemClock::new()),
         );
 
-        let state = State::new(Arc::new(some_replica), HeartbeatConfig::default());
-        state.voted_for(10);
+        let state = State::new(Arc::new(some_replica), RaftConfig::default());
+        let clone = state.clone();
+        clone.change_to_candidate();
+        clone.change_to_follower(2);
 
-        assert_eq!(false, state.has_not_voted_for_or_matches(15));
+        assert_eq!(2, state.get_term());
+        assert_eq!(ReplicaRole::Follower, state.get_role());
+        assert_eq!(None, state.get_voted_for());
     }
 
     #[tokio::test(flavor = "multi_thread")]
-    async fn heartbeat_timeout() {
+    async fn change_to_follower_with_the_same_term_retains_voted_for() {
         let some_replica = Replica::new(
             10,
             HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1971),
@@ -393,29 +965,18 @@ mod tests {
             Arc::new(SystemClock::new()),
         );
 
-        let state = State::new(Arc::new(some_replica), HeartbeatConfig::default());
-        state.mark_heartbeat_received();
-
-        let heartbeat_timeout = Duration::from_millis(0);
-        let count = Arc::new(RwLock::new(0));
-        let cloned = count.clone();
-
-        let election_starter = move |_state| {
-            let mut write_guard = cloned.write().unwrap();
-            *write_guard = *write_guard + 1;
-        };
-
-        let handle = tokio::spawn(state.get_heartbeat_checker(
-            heartbeat_timeout,
-            election_starter,
-        ));
+        let state = State::new(Arc::new(some_replica), RaftConfig::default());
+        let clone = state.clone();
+        let term = clone.change_to_candidate();
+        clone.change_to_follower(term);
 
-        let _ = handle.await;
-        assert_eq!(1, *(count.read().unwrap()));
+        assert_eq!(1, state.get_term());
+        assert_eq!(ReplicaRole::Follower, state.get_role());
+        assert_eq!(Some(10), state.get_voted_for());
     }
 
     #[tokio::test(flavor = "multi_thread")]
-    async fn never_received_heartbeat_times_out() {
+    async fn change_to_candidate_persists_hard_state() {
         let some_replica = Replica::new(
             10,
             HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1971),
@@ -425,28 +986,48 @@ mod tests {
             Arc::new(SystemClock::new()),
         );
 
-        let state = State::new(Arc::new(some_replica), HeartbeatConfig::default());
-        let heartbeat_timeout = Duration::from_millis(5);
-        let count = Arc::new(RwLock::new(0));
-        let cloned = count.clone();
+        let hard_state_storage = Arc::new(InMemoryHardStateStorage::new());
+        let state = State::new_with_storage(
+            Arc::new(some_replica),
+            RaftConfig::default(),
+            hard_state_storage.clone(),
+            Box::new(InMemoryLogStore::new()),
+            Box::new(InMemorySnapshotStorage::new()),
+            Arc::new(NoOpStateMachine::new()),
+        );
+        state.change_to_candidate();
 
-        let election_starter = move |_state| {
-            let mut write_guard = cloned.write().unwrap();
-            *write_guard = *write_guard + 1;
-        };
+        assert_eq!(Some(HardState::new(1, Some(10))), hard_state_storage.load().unwrap());
+    }
 
-        thread::sleep(Duration::from_millis(5));
-        let handle = tokio::spawn(state.get_heartbeat_checker(
-            heartbeat_timeout,
-            election_starter,
-        ));
+    #[tokio::test(flavor = "multi_thread")]
+    async fn learner_stays_a_learner_until_promoted() {
+        let self_host = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1971);
+        let peer_host = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1297);
+        let some_replica = Replica::new(
+            10,
+            self_host,
+            vec![peer_host],
+            Arc::new(SystemClock::new()),
+        );
 
-        let _ = handle.await;
-        assert_eq!(1, *(count.read().unwrap()));
+        let state = State::new_learner(Arc::new(some_replica), RaftConfig::default());
+        assert_eq!(ReplicaRole::Learner, state.get_role());
+        assert_eq!(false, state.is_voter());
+
+        state.clone().change_to_follower(2);
+        assert_eq!(ReplicaRole::Learner, state.get_role());
+
+        let membership = state.get_membership().promoting(&self_host);
+        state.get_replicated_log().append_membership(&membership, 2);
+        state.refresh_membership();
+
+        assert_eq!(ReplicaRole::Follower, state.get_role());
+        assert!(state.is_voter());
     }
 
     #[tokio::test(flavor = "multi_thread")]
-    async fn never_received_heartbeat_but_yet_to_time_out() {
+    async fn voted_for_persists_hard_state() {
         let some_replica = Replica::new(
             10,
             HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1971),
@@ -456,29 +1037,613 @@ mod tests {
             Arc::new(SystemClock::new()),
         );
 
-        let state = State::new(Arc::new(some_replica), HeartbeatConfig::default());
-        let heartbeat_timeout = Duration::from_millis(10);
-        let count = Arc::new(RwLock::new(0));
-        let cloned = count.clone();
+        let hard_state_storage = Arc::new(InMemoryHardStateStorage::new());
+        let state = State::new_with_storage(
+            Arc::new(some_replica),
+            RaftConfig::default(),
+            hard_state_storage.clone(),
+            Box::new(InMemoryLogStore::new()),
+            Box::new(InMemorySnapshotStorage::new()),
+            Arc::new(NoOpStateMachine::new()),
+        );
+        state.voted_for(20);
 
-        let election_starter = move |_state| {
-            let mut write_guard = cloned.write().unwrap();
-            *write_guard = *write_guard + 1;
-        };
+        assert_eq!(Some(HardState::new(0, Some(20))), hard_state_storage.load().unwrap());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn recover_hard_state() {
+        let some_replica = Replica::new(
+            10,
+            HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1971),
+            vec![
+                HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1297),
+            ],
+            Arc::new(SystemClock::new()),
+        );
+
+        let hard_state_storage = Arc::new(InMemoryHardStateStorage::new());
+        hard_state_storage.save(HardState::new(3, Some(20))).unwrap();
+
+        let state = State::new_with_storage(
+            Arc::new(some_replica),
+            RaftConfig::default(),
+            hard_state_storage,
+            Box::new(InMemoryLogStore::new()),
+            Box::new(InMemorySnapshotStorage::new()),
+            Arc::new(NoOpStateMachine::new()),
+        );
+
+        assert_eq!(3, state.get_term());
+        assert_eq!(ReplicaRole::Follower, state.get_role());
+        assert_eq!(Some(20), state.get_voted_for());
+        assert_eq!(false, state.has_not_voted_for_or_matches(30));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn apply_committed_log_entries_in_log_order() {
+        let some_replica = Replica::new(
+            10,
+            HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1971),
+            vec![
+                HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1297),
+            ],
+            Arc::new(SystemClock::new()),
+        );
+
+        let state_machine = Arc::new(RecordingStateMachine::new());
+        let state = State::new_with_storage(
+            Arc::new(some_replica),
+            RaftConfig::default(),
+            Arc::new(InMemoryHardStateStorage::new()),
+            Box::new(InMemoryLogStore::new()),
+            Box::new(InMemorySnapshotStorage::new()),
+            state_machine.clone(),
+        );
+        for count in 0..3 {
+            let command = Command { command: format!("Content-{}", count).as_bytes().to_vec(), client_id: None, sequence: 0 };
+            state.get_replicated_log().append_command(&command, 1);
+        }
+
+        let applied_indices = Mutex::new(Vec::new());
+        state.get_replicated_log().maybe_advance_commit_index_to(Some(1), Some(1));
+        state.apply_committed_log_entries(|index, _, _| applied_indices.lock().unwrap().push(index));
+
+        assert_eq!(vec![0, 1], *applied_indices.lock().unwrap());
+        assert_eq!(vec!["Content-0".as_bytes().to_vec(), "Content-1".as_bytes().to_vec()], state_machine.get_applied_commands());
+        assert_eq!(Some(1), state.get_last_applied());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn apply_committed_log_entries_exactly_once() {
+        let some_replica = Replica::new(
+            10,
+            HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1971),
+            vec![
+                HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1297),
+            ],
+            Arc::new(SystemClock::new()),
+        );
+
+        let state_machine = Arc::new(RecordingStateMachine::new());
+        let state = State::new_with_storage(
+            Arc::new(some_replica),
+            RaftConfig::default(),
+            Arc::new(InMemoryHardStateStorage::new()),
+            Box::new(InMemoryLogStore::new()),
+            Box::new(InMemorySnapshotStorage::new()),
+            state_machine.clone(),
+        );
+        for count in 0..2 {
+            let command = Command { command: format!("Content-{}", count).as_bytes().to_vec(), client_id: None, sequence: 0 };
+            state.get_replicated_log().append_command(&command, 1);
+        }
+
+        state.get_replicated_log().maybe_advance_commit_index_to(Some(0), Some(0));
+        state.apply_committed_log_entries(|_, _, _| {});
+        state.apply_committed_log_entries(|_, _, _| {});
+
+        state.get_replicated_log().maybe_advance_commit_index_to(Some(1), Some(1));
+        state.apply_committed_log_entries(|_, _, _| {});
+
+        assert_eq!(vec!["Content-0".as_bytes().to_vec(), "Content-1".as_bytes().to_vec()], state_machine.get_applied_commands());
+        assert_eq!(Some(1), state.get_last_applied());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn apply_committed_log_entries_up_to_the_last_log_entry() {
+        let some_replica = Replica::new(
+            10,
+            HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1971),
+            vec![
+                HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1297),
+            ],
+            Arc::new(SystemClock::new()),
+        );
+
+        let state_machine = Arc::new(RecordingStateMachine::new());
+        let state = State::new_with_storage(
+            Arc::new(some_replica),
+            RaftConfig::default(),
+            Arc::new(InMemoryHardStateStorage::new()),
+            Box::new(InMemoryLogStore::new()),
+            Box::new(InMemorySnapshotStorage::new()),
+            state_machine.clone(),
+        );
+        let command = Command { command: "Content".as_bytes().to_vec(), client_id: None, sequence: 0 };
+        state.get_replicated_log().append_command(&command, 1);
+
+        state.get_replicated_log().maybe_advance_commit_index_to(Some(3), Some(3));
+        state.apply_committed_log_entries(|_, _, _| {});
+
+        assert_eq!(vec!["Content".as_bytes().to_vec()], state_machine.get_applied_commands());
+        assert_eq!(Some(0), state.get_last_applied());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn apply_a_duplicate_client_command_once() {
+        let some_replica = Replica::new(
+            10,
+            HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1971),
+            vec![
+                HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1297),
+            ],
+            Arc::new(SystemClock::new()),
+        );
+
+        let state_machine = Arc::new(RecordingStateMachine::new());
+        let state = State::new_with_storage(
+            Arc::new(some_replica),
+            RaftConfig::default(),
+            Arc::new(InMemoryHardStateStorage::new()),
+            Box::new(InMemoryLogStore::new()),
+            Box::new(InMemorySnapshotStorage::new()),
+            state_machine.clone(),
+        );
+        let command = Command { command: "Content".as_bytes().to_vec(), client_id: Some(100), sequence: 0 };
+        state.get_replicated_log().append_command_at(&command, 1, 10);
+        state.get_replicated_log().append_command_at(&command, 1, 20);
+
+        let outputs = Mutex::new(Vec::new());
+        state.get_replicated_log().maybe_advance_commit_index_to(Some(1), Some(1));
+        state.apply_committed_log_entries(|index, _, output| outputs.lock().unwrap().push((index, output)));
+
+        assert_eq!(vec!["Content".as_bytes().to_vec()], state_machine.get_applied_commands());
+        assert_eq!(vec![(0, Ok(Vec::new())), (1, Ok(Vec::new()))], *outputs.lock().unwrap());
+        assert_eq!(Some(1), state.get_last_applied());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn drop_a_duplicate_client_command_after_restoring_the_snapshot() {
+        let some_replica = || Replica::new(
+            10,
+            HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1971),
+            vec![
+                HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1297),
+            ],
+            Arc::new(SystemClock::new()),
+        );
+
+        let state = State::new_with_storage(
+            Arc::new(some_replica()),
+            RaftConfig::default(),
+            Arc::new(InMemoryHardStateStorage::new()),
+            Box::new(InMemoryLogStore::new()),
+            Box::new(InMemorySnapshotStorage::new()),
+            Arc::new(RecordingStateMachine::new()),
+        );
+        let command = Command { command: "Content".as_bytes().to_vec(), client_id: Some(100), sequence: 0 };
+        state.get_replicated_log().append_command_at(&command, 1, 10);
+        state.get_replicated_log().maybe_advance_commit_index_to(Some(0), Some(0));
+        state.apply_committed_log_entries(|_, _, _| {});
+
+        let snapshot = state.compact(0, "state".as_bytes().to_vec()).unwrap();
+        assert_eq!(false, snapshot.get_client_sessions_as_vec().is_empty());
+
+        let snapshot_storage = InMemorySnapshotStorage::new();
+        snapshot_storage.save(&snapshot).unwrap();
+
+        let state_machine = Arc::new(RecordingStateMachine::new());
+        let restored_state = State::new_with_storage(
+            Arc::new(some_replica()),
+            RaftConfig::default(),
+            Arc::new(InMemoryHardStateStorage::new()),
+            Box::new(InMemoryLogStore::new()),
+            Box::new(snapshot_storage),
+            state_machine.clone(),
+        );
+        restored_state.get_replicated_log().append_command_at(&command, 1, 20);
+        restored_state.get_replicated_log().maybe_advance_commit_index_to(Some(1), Some(1));
+        restored_state.apply_committed_log_entries(|_, _, _| {});
+
+        assert!(state_machine.get_applied_commands().is_empty());
+        assert_eq!(Some(1), restored_state.get_last_applied());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn do_not_compact_beyond_the_last_applied_index() {
+        let some_replica = Replica::new(
+            10,
+            HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1971),
+            vec![
+                HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1297),
+            ],
+            Arc::new(SystemClock::new()),
+        );
+
+        let state = State::new_with_storage(
+            Arc::new(some_replica),
+            RaftConfig::default(),
+            Arc::new(InMemoryHardStateStorage::new()),
+            Box::new(InMemoryLogStore::new()),
+            Box::new(InMemorySnapshotStorage::new()),
+            Arc::new(RecordingStateMachine::new()),
+        );
+        for count in 0..2 {
+            let command = Command { command: format!("Content-{}", count).as_bytes().to_vec(), client_id: None, sequence: 0 };
+            state.get_replicated_log().append_command(&command, 1);
+        }
+        state.get_replicated_log().maybe_advance_commit_index_to(Some(1), Some(1));
+
+        assert!(state.compact(1, "state".as_bytes().to_vec()).is_none());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn restore_the_state_machine_from_the_snapshot() {
+        let some_replica = Replica::new(
+            10,
+            HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1971),
+            vec![
+                HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1297),
+            ],
+            Arc::new(SystemClock::new()),
+        );
+
+        let snapshot_storage = InMemorySnapshotStorage::new();
+        snapshot_storage.save(&Snapshot::new(4, 1, "state".as_bytes().to_vec())).unwrap();
+
+        let state_machine = Arc::new(RecordingStateMachine::new());
+        let state = State::new_with_storage(
+            Arc::new(some_replica),
+            RaftConfig::default(),
+            Arc::new(InMemoryHardStateStorage::new()),
+            Box::new(InMemoryLogStore::new()),
+            Box::new(snapshot_storage),
+            state_machine.clone(),
+        );
+
+        assert_eq!(Some("state".as_bytes().to_vec()), state_machine.get_restored_data());
+        assert_eq!(Some(4), state.get_last_applied());
+
+        let command = Command { command: "Content".as_bytes().to_vec(), client_id: None, sequence: 0 };
+        state.get_replicated_log().append_command(&command, 1);
+        state.get_replicated_log().maybe_advance_commit_index_to(Some(5), Some(5));
+        state.apply_committed_log_entries(|_, _, _| {});
+
+        assert_eq!(vec!["Content".as_bytes().to_vec()], state_machine.get_applied_commands());
+        assert_eq!(Some(5), state.get_last_applied());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn install_snapshot_restores_the_state_machine() {
+        let some_replica = Replica::new(
+            10,
+            HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1971),
+            vec![
+                HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1297),
+            ],
+            Arc::new(SystemClock::new()),
+        );
+
+        let state_machine = Arc::new(RecordingStateMachine::new());
+        let state = State::new_with_storage(
+            Arc::new(some_replica),
+            RaftConfig::default(),
+            Arc::new(InMemoryHardStateStorage::new()),
+            Box::new(InMemoryLogStore::new()),
+            Box::new(InMemorySnapshotStorage::new()),
+            state_machine.clone(),
+        );
+        state.install_snapshot(Snapshot::new(4, 1, "state".as_bytes().to_vec()));
+
+        assert_eq!(Some("state".as_bytes().to_vec()), state_machine.get_restored_data());
+        assert_eq!(Some(4), state.get_last_applied());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn get_voted_for_none() {
+        let some_replica = Replica::new(
+            10,
+            HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1971),
+            vec![
+                HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1297),
+            ],
+            Arc::new(SystemClock::new()),
+        );
+
+        let state = State::new(Arc::new(some_replica), RaftConfig::default());
+
+        assert_eq!(None, state.get_voted_for());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn has_not_voted() {
+        let some_replica = Replica::new(
+            10,
+            HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1971),
+            vec![
+                HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1297),
+            ],
+            Arc::new(SystemClock::new()),
+        );
+
+        let state = State::new(Arc::new(some_replica), RaftConfig::default());
+
+        assert_eq!(true, state.has_not_voted_for_or_matches(10));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn has_voted_for_the_replica_id() {
+        let some_replica = Replica::new(
+            10,
+            HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1971),
+            vec![
+                HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1297),
+            ],
+            Arc::new(SystemClock::new()),
+        );
+
+        let state = State::new(Arc::new(some_replica), RaftConfig::default());
+        state.voted_for(15);
+
+        assert_eq!(true, state.has_not_voted_for_or_matches(15));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn does_not_match_the_replica_id_voted_for() {
+        let some_replica = Replica::new(
+            10,
+            HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1971),
+            vec![
+                HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1297),
+            ],
+            Arc::new(SystemClock::new()),
+        );
+
+        let state = State::new(Arc::new(some_replica), RaftConfig::default());
+        state.voted_for(10);
+
+        assert_eq!(false, state.has_not_voted_for_or_matches(15));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn no_live_leader_without_a_heartbeat() {
+        let some_replica = Replica::new(
+            10,
+            HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1971),
+            vec![
+                HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1297),
+            ],
+            Arc::new(SystemClock::new()),
+        );
+
+        let state = State::new(Arc::new(some_replica), RaftConfig::default());
+        assert_eq!(false, state.has_live_leader());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn live_leader_after_a_heartbeat() {
+        let some_replica = Replica::new(
+            10,
+            HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1971),
+            vec![
+                HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1297),
+            ],
+            Arc::new(SystemClock::new()),
+        );
+
+        let state = State::new(Arc::new(some_replica), RaftConfig::default());
+        state.mark_heartbeat_received();
+
+        assert!(state.has_live_leader());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn recently_heard_from_the_leader_after_its_heartbeat() {
+        let some_replica = Replica::new(
+            10,
+            HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1971),
+            vec![
+                HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1297),
+            ],
+            Arc::new(SystemClock::new()),
+        );
+
+        let state = State::new(Arc::new(some_replica), RaftConfig::default());
+        state.mark_heartbeat_received();
+        state.mark_leader(20, None);
+
+        assert!(state.has_recently_heard_from_leader());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn not_recently_heard_from_the_leader_without_a_leader_of_the_term() {
+        let some_replica = Replica::new(
+            10,
+            HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1971),
+            vec![
+                HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1297),
+            ],
+            Arc::new(SystemClock::new()),
+        );
+
+        //a replica that has granted its vote restarts its election timeout, but has not heard from a leader
+        let state = State::new(Arc::new(some_replica), RaftConfig::default());
+        state.mark_heartbeat_received();
+
+        assert_eq!(false, state.has_recently_heard_from_leader());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn confirm_leadership_of_a_single_replica() {
+        let some_replica = Replica::new(
+            10,
+            HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1971),
+            vec![],
+            Arc::new(SystemClock::new()),
+        );
+
+        let state = State::new(Arc::new(some_replica), RaftConfig::default());
+        state.change_to_candidate();
+        state.clone().change_to_leader();
+
+        assert!(state.clone().confirm_leadership().await);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn do_not_confirm_leadership_given_the_peers_are_unreachable() {
+        let some_replica = Replica::new(
+            10,
+            HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1971),
+            vec![
+                HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1297),
+                HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1298),
+            ],
+            Arc::new(SystemClock::new()),
+        );
+
+        let state = State::new(Arc::new(some_replica), RaftConfig::default());
+        state.change_to_candidate();
+        state.clone().change_to_leader();
+
+        assert_eq!(false, state.clone().confirm_leadership().await);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn heartbeat_timeout() {
+        let some_replica = Replica::new(
+            10,
+            HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1971),
+            vec![
+                HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1297),
+            ],
+            Arc::new(SystemClock::new()),
+        );
+
+        let state = State::new(Arc::new(some_replica), RaftConfig::default());
+        state.mark_heartbeat_received();
+
+        let heartbeat_timeout = Duration::from_millis(0);
+        let count = Arc::new(RwLock::new(0));
+        let cloned = count.clone();
+
+        let election_starter = move |_state| {
+            let mut write_guard = cloned.write().unwrap();
+            *write_guard = *write_guard + 1;
+        };
+
+        let handle = tokio::spawn(state.get_heartbeat_checker(
+            heartbeat_timeout,
+            election_starter,
+        ));
+
+        let _ = handle.await;
+        assert_eq!(1, *(count.read().unwrap()));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn never_received_heartbeat_times_out() {
+        let some_replica = Replica::new(
+            10,
+            HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1971),
+            vec![
+                HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1297),
+            ],
+            Arc::new(SystemClock::new()),
+        );
+
+        let state = State::new(Arc::new(some_replica), RaftConfig::default());
+        let heartbeat_timeout = Duration::from_millis(5);
+        let count = Arc::new(RwLock::new(0));
+        let cloned = count.clone();
+
+        let election_starter = move |_state| {
+            let mut write_guard = cloned.write().unwrap();
+            *write_guard = *write_guard + 1;
+        };
+
+        thread::sleep(Duration::from_millis(5));
+        let handle = tokio::spawn(state.get_heartbeat_checker(
+            heartbeat_timeout,
+            election_starter,
+        ));
+
+        let _ = handle.await;
+        assert_eq!(1, *(count.read().unwrap()));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn never_received_heartbeat_but_yet_to_time_out() {
+        let some_replica = Replica::new(
+            10,
+            HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1971),
+            vec![
+                HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1297),
+            ],
+            Arc::new(SystemClock::new()),
+        );
+
+        let state = State::new(Arc::new(some_replica), RaftConfig::default());
+        let heartbeat_timeout = Duration::from_millis(10);
+        let count = Arc::new(RwLock::new(0));
+        let cloned = count.clone();
+
+        let election_starter = move |_state| {
+            let mut write_guard = cloned.write().unwrap();
+            *write_guard = *write_guard + 1;
+        };
+
+        thread::sleep(Duration::from_millis(2));
+        {
+            let mut guard = state.consensus_state.write().unwrap();
+            let mut consensus_state = &mut *guard;
+            consensus_state.creation_time = SystemClock::new().now();
+        }
+        let handle = tokio::spawn(state.clone().get_heartbeat_checker(
+            heartbeat_timeout,
+            election_starter,
+        ));
+
+        let _ = handle.await;
+        assert_eq!(0, *(count.read().unwrap()));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn election_round_waits_for_a_whole_heartbeat_timeout_before_the_next_round() {
+        let some_replica = Replica::new(
+            10,
+            HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1971),
+            vec![
+                HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1297),
+            ],
+            Arc::new(SystemClock::new()),
+        );
 
-        thread::sleep(Duration::from_millis(2));
-        {
-            let mut guard = state.consensus_state.write().unwrap();
-            let mut consensus_state = &mut *guard;
-            consensus_state.creation_time = SystemClock::new().now();
+        let state = State::new(Arc::new(some_replica), RaftConfig::builder().with_random_seed(7).build().unwrap());
+        let heartbeat_timeout = Duration::from_millis(50);
+        let count = Arc::new(RwLock::new(0));
+
+        thread::sleep(Duration::from_millis(55));
+        for _round in 1..=2 {
+            let cloned = count.clone();
+            let election_starter = move |_state| {
+                let mut write_guard = cloned.write().unwrap();
+                *write_guard = *write_guard + 1;
+            };
+            let _ = tokio::spawn(state.clone().get_heartbeat_checker(heartbeat_timeout, election_starter)).await;
         }
-        let handle = tokio::spawn(state.clone().get_heartbeat_checker(
-            heartbeat_timeout,
-            election_starter,
-        ));
 
-        let _ = handle.await;
-        assert_eq!(0, *(count.read().unwrap()));
+        assert_eq!(1, *(count.read().unwrap()));
     }
 
     #[tokio::test(flavor = "multi_thread")]
@@ -492,7 +1657,7 @@ mod tests {
             Arc::new(SystemClock::new()),
         );
 
-        let state = State::new(Arc::new(some_replica), HeartbeatConfig::default());
+        let state = State::new(Arc::new(some_replica), RaftConfig::default());
         state.mark_heartbeat_received();
 
         let heartbeat_timeout = Duration::from_secs(100);
@@ -531,11 +1696,15 @@ mod tests {
         let state = blocking_runtime.block_on(async move {
             return State::new_with(
                 inner_replica,
-                HeartbeatConfig::default(),
+                RaftConfig::default(),
                 Arc::new(IncrementingCorrelationIdServiceRequestFactory {
                     base_correlation_id: RwLock::new(AtomicU64::new(0)),
                     heartbeat_response_client_type: HeartbeatResponseClientType::Success,
                 }),
+                Arc::new(InMemoryHardStateStorage::new()),
+                Box::new(InMemoryLogStore::new()),
+                Box::new(InMemorySnapshotStorage::new()),
+                Arc::new(NoOpStateMachine::new()),
             );
         });
 
@@ -564,12 +1733,18 @@ mod tests {
         let state = blocking_runtime.block_on(async move {
             let state = State::new_with(
                 inner_replica,
-                HeartbeatConfig::default(),
+                RaftConfig::default(),
                 Arc::new(IncrementingCorrelationIdServiceRequestFactory {
                     base_correlation_id: RwLock::new(AtomicU64::new(0)),
                     heartbeat_response_client_type: HeartbeatResponseClientType::Success,
                 }),
+                Arc::new(InMemoryHardStateStorage::new()),
+                Box::new(InMemoryLogStore::new()),
+                Box::new(InMemorySnapshotStorage::new()),
+                Arc::new(NoOpStateMachine::new()),
             );
+            //the heartbeat responses are of the leader's term, the leader hears from a majority
+            state.change_to_candidate();
             state.clone().change_to_leader();
             return state;
         });
@@ -602,11 +1777,15 @@ mod tests {
         let state = blocking_runtime.block_on(async move {
             let state = State::new_with(
                 inner_replica,
-                HeartbeatConfig::default(),
+                RaftConfig::default(),
                 Arc::new(IncrementingCorrelationIdServiceRequestFactory {
                     base_correlation_id: RwLock::new(AtomicU64::new(0)),
                     heartbeat_response_client_type: HeartbeatResponseClientType::Failure,
                 }),
+                Arc::new(InMemoryHardStateStorage::new()),
+                Box::new(InMemoryLogStore::new()),
+                Box::new(InMemorySnapshotStorage::new()),
+                Arc::new(NoOpStateMachine::new()),
             );
             state.clone().change_to_leader();
             state.heartbeat_check_scheduler.stop();
@@ -626,6 +1805,306 @@ mod tests {
         });
     }
 
+    #[test]
+    fn do_not_switch_to_follower_on_failed_heartbeat_response_with_the_same_term() {
+        let some_replica = Replica::new(
+            10,
+            HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1971),
+            vec![
+                HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1297),
+            ],
+            Arc::new(SystemClock::new()),
+        );
+
+        let some_replica = Arc::new(some_replica);
+        let inner_replica = some_replica.clone();
+
+        let hard_state_storage = Arc::new(InMemoryHardStateStorage::new());
+        hard_state_storage.save(HardState::new(5, None)).unwrap();
+
+        let blocking_runtime = Builder::new_multi_thread().worker_threads(2).enable_all().build().unwrap();
+        let state = blocking_runtime.block_on(async move {
+            let state = State::new_with(
+                inner_replica,
+                RaftConfig::default(),
+                Arc::new(IncrementingCorrelationIdServiceRequestFactory {
+                    base_correlation_id: RwLock::new(AtomicU64::new(0)),
+                    heartbeat_response_client_type: HeartbeatResponseClientType::Failure,
+                }),
+                hard_state_storage,
+                Box::new(InMemoryLogStore::new()),
+                Box::new(InMemorySnapshotStorage::new()),
+                Arc::new(NoOpStateMachine::new()),
+            );
+            state.clone().change_to_leader();
+            state.heartbeat_check_scheduler.stop();
+            state.heartbeat_send_scheduler.stop();
+            return state;
+        });
+
+        let inner_state = state.clone();
+        blocking_runtime.block_on(async move {
+            let cloned = inner_state.clone();
+            let _ = inner_state.get_heartbeat_sender().await;
+
+            thread::sleep(Duration::from_millis(15));
+
+            assert_eq!(ReplicaRole::Leader, cloned.get_role());
+            assert_eq!(5, cloned.get_term());
+        });
+    }
+
+    #[test]
+    fn leader_lease_after_a_majority_acknowledges_the_heartbeats() {
+        let clock = Arc::new(ControllableClock::new());
+        let some_replica = Replica::new(
+            10,
+            HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1971),
+            vec![
+                HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1297),
+            ],
+            clock.clone(),
+        );
+
+        let blocking_runtime = Builder::new_multi_thread().worker_threads(2).enable_all().build().unwrap();
+        let state = blocking_runtime.block_on(async move {
+            let state = State::new_with(
+                Arc::new(some_replica),
+                RaftConfig::builder().with_leader_lease(Duration::from_millis(10)).build().unwrap(),
+                Arc::new(IncrementingCorrelationIdServiceRequestFactory {
+                    base_correlation_id: RwLock::new(AtomicU64::new(0)),
+                    heartbeat_response_client_type: HeartbeatResponseClientType::Success,
+                }),
+                Arc::new(InMemoryHardStateStorage::new()),
+                Box::new(InMemoryLogStore::new()),
+                Box::new(InMemorySnapshotStorage::new()),
+                Arc::new(NoOpStateMachine::new()),
+            );
+            state.change_to_candidate();
+            state.clone().change_to_leader();
+            state.heartbeat_check_scheduler.stop();
+            state.heartbeat_send_scheduler.stop();
+            return state;
+        });
+        assert_eq!(false, state.has_leader_lease());
+
+        let inner_state = state.clone();
+        blocking_runtime.block_on(async move {
+            let _ = inner_state.get_heartbeat_sender().await;
+        });
+        thread::sleep(Duration::from_millis(15));
+        assert!(state.has_leader_lease());
+
+        clock.advance_by(Duration::from_millis(139));
+        assert!(state.has_leader_lease());
+
+        clock.advance_by(Duration::from_millis(1));
+        assert_eq!(false, state.has_leader_lease());
+    }
+
+    #[test]
+    fn leader_steps_down_without_heartbeat_responses_from_a_majority() {
+        let clock = Arc::new(ControllableClock::new());
+        let some_replica = Replica::new(
+            10,
+            HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1971),
+            vec![
+                HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1297),
+            ],
+            clock.clone(),
+        );
+
+        let blocking_runtime = Builder::new_multi_thread().worker_threads(2).enable_all().build().unwrap();
+        let state = blocking_runtime.block_on(async move {
+            let state = State::new_with(
+                Arc::new(some_replica),
+                RaftConfig::default(),
+                Arc::new(IncrementingCorrelationIdServiceRequestFactory {
+                    base_correlation_id: RwLock::new(AtomicU64::new(0)),
+                    heartbeat_response_client_type: HeartbeatResponseClientType::Unreachable,
+                }),
+                Arc::new(InMemoryHardStateStorage::new()),
+                Box::new(InMemoryLogStore::new()),
+                Box::new(InMemorySnapshotStorage::new()),
+                Arc::new(NoOpStateMachine::new()),
+            );
+            state.change_to_candidate();
+            state.clone().change_to_leader();
+            state.heartbeat_check_scheduler.stop();
+            state.heartbeat_send_scheduler.stop();
+            return state;
+        });
+
+        let inner_state = state.clone();
+        blocking_runtime.block_on(async move {
+            let _ = inner_state.get_heartbeat_sender().await;
+        });
+        assert_eq!(ReplicaRole::Leader, state.get_role());
+
+        clock.advance_by(Duration::from_millis(300));
+        let inner_state = state.clone();
+        blocking_runtime.block_on(async move {
+            let _ = inner_state.get_heartbeat_sender().await;
+        });
+        assert_eq!(ReplicaRole::Follower, state.get_role());
+        assert_eq!(1, state.get_term());
+    }
+
+    #[test]
+    fn leader_stays_without_heartbeat_responses_from_a_majority_given_check_quorum_is_disabled() {
+        let clock = Arc::new(ControllableClock::new());
+        let some_replica = Replica::new(
+            10,
+            HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1971),
+            vec![
+                HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1297),
+            ],
+            clock.clone(),
+        );
+
+        let blocking_runtime = Builder::new_multi_thread().worker_threads(2).enable_all().build().unwrap();
+        let state = blocking_runtime.block_on(async move {
+            let state = State::new_with(
+                Arc::new(some_replica),
+                RaftConfig::builder().without_check_quorum().build().unwrap(),
+                Arc::new(IncrementingCorrelationIdServiceRequestFactory {
+                    base_correlation_id: RwLock::new(AtomicU64::new(0)),
+                    heartbeat_response_client_type: HeartbeatResponseClientType::Unreachable,
+                }),
+                Arc::new(InMemoryHardStateStorage::new()),
+                Box::new(InMemoryLogStore::new()),
+                Box::new(InMemorySnapshotStorage::new()),
+                Arc::new(NoOpStateMachine::new()),
+            );
+            state.change_to_candidate();
+            state.clone().change_to_leader();
+            state.heartbeat_check_scheduler.stop();
+            state.heartbeat_send_scheduler.stop();
+            return state;
+        });
+
+        let inner_state = state.clone();
+        blocking_runtime.block_on(async move {
+            let _ = inner_state.get_heartbeat_sender().await;
+        });
+        assert_eq!(ReplicaRole::Leader, state.get_role());
+
+        clock.advance_by(Duration::from_millis(300));
+        let inner_state = state.clone();
+        blocking_runtime.block_on(async move {
+            let _ = inner_state.get_heartbeat_sender().await;
+        });
+        assert_eq!(ReplicaRole::Leader, state.get_role());
+    }
+
+    #[test]
+    fn leader_stays_with_heartbeat_responses_from_a_majority() {
+        let clock = Arc::new(ControllableClock::new());
+        let some_replica = Replica::new(
+            10,
+            HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1971),
+            vec![
+                HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1297),
+            ],
+            clock.clone(),
+        );
+
+        let blocking_runtime = Builder::new_multi_thread().worker_threads(2).enable_all().build().unwrap();
+        let state = blocking_runtime.block_on(async move {
+            let state = State::new_with(
+                Arc::new(some_replica),
+                RaftConfig::default(),
+                Arc::new(IncrementingCorrelationIdServiceRequestFactory {
+                    base_correlation_id: RwLock::new(AtomicU64::new(0)),
+                    heartbeat_response_client_type: HeartbeatResponseClientType::Success,
+                }),
+                Arc::new(InMemoryHardStateStorage::new()),
+                Box::new(InMemoryLogStore::new()),
+                Box::new(InMemorySnapshotStorage::new()),
+                Arc::new(NoOpStateMachine::new()),
+            );
+            state.change_to_candidate();
+            state.clone().change_to_leader();
+            state.heartbeat_check_scheduler.stop();
+            state.heartbeat_send_scheduler.stop();
+            return state;
+        });
+
+        clock.advance_by(Duration::from_millis(100));
+        let inner_state = state.clone();
+        blocking_runtime.block_on(async move {
+            let _ = inner_state.get_heartbeat_sender().await;
+        });
+        thread::sleep(Duration::from_millis(15));
+
+        clock.advance_by(Duration::from_millis(100));
+        let inner_state = state.clone();
+        blocking_runtime.block_on(async move {
+            let _ = inner_state.get_heartbeat_sender().await;
+        });
+        assert_eq!(ReplicaRole::Leader, state.get_role());
+    }
+
+    #[test]
+    fn no_leader_lease_given_the_heartbeats_are_not_acknowledged() {
+        let clock = Arc::new(ControllableClock::new());
+        let some_replica = Replica::new(
+            10,
+            HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1971),
+            vec![
+                HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1297),
+            ],
+            clock.clone(),
+        );
+
+        let hard_state_storage = Arc::new(InMemoryHardStateStorage::new());
+        hard_state_storage.save(HardState::new(5, None)).unwrap();
+
+        let blocking_runtime = Builder::new_multi_thread().worker_threads(2).enable_all().build().unwrap();
+        let state = blocking_runtime.block_on(async move {
+            let state = State::new_with(
+                Arc::new(some_replica),
+                RaftConfig::builder().with_leader_lease(Duration::from_millis(10)).build().unwrap(),
+                Arc::new(IncrementingCorrelationIdServiceRequestFactory {
+                    base_correlation_id: RwLock::new(AtomicU64::new(0)),
+                    heartbeat_response_client_type: HeartbeatResponseClientType::Failure,
+                }),
+                hard_state_storage,
+                Box::new(InMemoryLogStore::new()),
+                Box::new(InMemorySnapshotStorage::new()),
+                Arc::new(NoOpStateMachine::new()),
+            );
+            state.clone().change_to_leader();
+            state.heartbeat_check_scheduler.stop();
+            state.heartbeat_send_scheduler.stop();
+            return state;
+        });
+
+        let inner_state = state.clone();
+        blocking_runtime.block_on(async move {
+            let _ = inner_state.get_heartbeat_sender().await;
+        });
+        thread::sleep(Duration::from_millis(15));
+        assert_eq!(false, state.has_leader_lease());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn no_leader_lease_given_leader_lease_is_not_enabled() {
+        let some_replica = Replica::new(
+            10,
+            HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1971),
+            vec![],
+            Arc::new(SystemClock::new()),
+        );
+
+        let state = State::new(Arc::new(some_replica), RaftConfig::default());
+        state.change_to_candidate();
+        state.clone().change_to_leader();
+        let _ = state.clone().get_heartbeat_sender().await;
+
+        assert_eq!(false, state.has_leader_lease());
+    }
+
     mod setup {
         use std::sync::atomic::{AtomicU64, Ordering};
         use std::sync::RwLock;
@@ -639,14 +2118,18 @@ mod tests {
         use replicate::net::connect::service_client::{ServiceClientProvider, ServiceRequest};
         use replicate::net::replica::ReplicaId;
 
+        use crate::log_entry::LogEntry;
         use crate::net::factory::service_request::ServiceRequestFactory;
         use crate::net::rpc::grpc::AppendEntries;
         use crate::net::rpc::grpc::AppendEntriesResponse;
+        use crate::state_machine::StateMachine;
+        use crate::storage::snapshot_storage::Snapshot;
 
         #[derive(PartialEq)]
         pub(crate) enum HeartbeatResponseClientType {
             Success,
             Failure,
+            Unreachable,
         }
 
         pub(crate) struct IncrementingCorrelationIdServiceRequestFactory {
@@ -664,10 +2147,10 @@ mod tests {
                 let guard = self.base_correlation_id.read().unwrap();
                 let correlation_id: CorrelationId = guard.load(Ordering::SeqCst);
 
-                let client: Box<dyn ServiceClientProvider<AppendEntries, AppendEntriesResponse>> = if self.heartbeat_response_client_type == HeartbeatResponseClientType::Success {
-                    Box::new(TestHeartbeatSuccessClient {})
-                } else {
-                    Box::new(TestHeartbeatFailureClient {})
+                let client: Box<dyn ServiceClientProvider<AppendEntries, AppendEntriesResponse>> = match self.heartbeat_response_client_type {
+                    HeartbeatResponseClientType::Success => Box::new(TestHeartbeatSuccessClient {}),
+                    HeartbeatResponseClientType::Failure => Box::new(TestHeartbeatFailureClient {}),
+                    HeartbeatResponseClientType::Unreachable => Box::new(TestHeartbeatUnreachableClient {}),
                 };
 
                 return ServiceRequest::new(
@@ -675,7 +2158,7 @@ mod tests {
                         term,
                         leader_id,
                         correlation_id,
-                        entry: None,
+                        entries: vec![],
                         previous_log_index: None,
                         previous_log_term: None,
                         leader_commit_index: None,
@@ -692,7 +2175,7 @@ mod tests {
         impl ServiceClientProvider<AppendEntries, AppendEntriesResponse> for TestHeartbeatSuccessClient {
             async fn call(&self, _: Request<AppendEntries>, _: HostAndPort) -> Result<Response<AppendEntriesResponse>, ServiceResponseError> {
                 return Ok(
-                    Response::new(AppendEntriesResponse { term: 1, success: true, correlation_id: 10, log_entry_index: None })
+                    Response::new(AppendEntriesResponse { term: 1, success: true, correlation_id: 10, log_entry_index: None, conflict_term: None, conflict_index: None })
                 );
             }
         }
@@ -703,9 +2186,52 @@ mod tests {
         impl ServiceClientProvider<AppendEntries, AppendEntriesResponse> for TestHeartbeatFailureClient {
             async fn call(&self, _: Request<AppendEntries>, _: HostAndPort) -> Result<Response<AppendEntriesResponse>, ServiceResponseError> {
                 return Ok(
-                    Response::new(AppendEntriesResponse { term: 5, success: false, correlation_id: 20, log_entry_index: None })
+                    Response::new(AppendEntriesResponse { term: 5, success: false, correlation_id: 20, log_entry_index: None, conflict_term: None, conflict_index: None })
                 );
             }
         }
+
+        struct TestHeartbeatUnreachableClient {}
+
+        #[async_trait]
+        impl ServiceClientProvider<AppendEntries, AppendEntriesResponse> for TestHeartbeatUnreachableClient {
+            async fn call(&self, _: Request<AppendEntries>, _: HostAndPort) -> Result<Response<AppendEntriesResponse>, ServiceResponseError> {
+                Err(Box::new(tonic::Status::unavailable("peer is unreachable")))
+            }
+        }
+
+        pub(crate) struct RecordingStateMachine {
+            applied_commands: RwLock<Vec<Vec<u8>>>,
+            restored_data: RwLock<Option<Vec<u8>>>,
+        }
+
+        impl RecordingStateMachine {
+            pub(crate) fn new() -> Self {
+                return RecordingStateMachine { applied_commands: RwLock::new(Vec::new()), restored_data: RwLock::new(None) };
+            }
+
+            pub(crate) fn get_applied_commands(&self) -> Vec<Vec<u8>> {
+                return self.applied_commands.read().unwrap().clone();
+            }
+
+            pub(crate) fn get_restored_data(&self) -> Option<Vec<u8>> {
+                return self.restored_data.read().unwrap().clone();
+            }
+        }
+
+        impl StateMachine for RecordingStateMachine {
+            fn apply(&self, log_entry: &LogEntry) -> Vec<u8> {
+                self.applied_commands.write().unwrap().push(log_entry.get_bytes_as_vec());
+                return Vec::new();
+            }
+
+            fn read(&self, _: &[u8]) -> Vec<u8> {
+                return self.applied_commands.read().unwrap().len().to_string().as_bytes().to_vec();
+            }
+
+            fn restore(&self, snapshot: &Snapshot) {
+                *self.restored_data.write().unwrap() = Some(snapshot.get_data_as_vec());
+            }
+        }
     }
 }
\ No newline at end of file
@@ -0,0 +1,35 @@
+use crate::log_entry::LogEntry;
+use crate::storage::snapshot_storage::Snapshot;
+
+/// The replicated application state. Committed log entries are applied in log order, each index exactly once.
+pub trait StateMachine: Send + Sync {
+    /// Returns the output of the command, which is sent back to the client that executed it.
+    fn apply(&self, log_entry: &LogEntry) -> Vec<u8>;
+
+    /// Returns the result of a read-only query against the state, the state is not changed.
+    fn read(&self, query: &[u8]) -> Vec<u8>;
+
+    /// Replaces the entire state with the one captured in the snapshot.
+    fn restore(&self, snapshot: &Snapshot);
+}
+
+#[derive(Default)]
+pub struct NoOpStateMachine {}
+
+impl NoOpStateMachine {
+    pub fn new() -> Self {
+        return NoOpStateMachine {};
+    }
+}
+
+impl StateMachine for NoOpStateMachine {
+    fn apply(&self, _: &LogEntry) -> Vec<u8> {
+        return Vec::new();
+    }
+
+    fn read(&self, _: &[u8]) -> Vec<u8> {
+        return Vec::new();
+    }
+
+    fn restore(&self, _: &Snapshot) {}
+}
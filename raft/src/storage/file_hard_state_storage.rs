@@ -0,0 +1,147 @@
+use std::fs;
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::io::{ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::storage::hard_state_storage::{HardState, HardStateStorage};
+
+const HARD_STATE_FILE_NAME: &str = "hard_state";
+const HARD_STATE_TEMPORARY_FILE_NAME: &str = "hard_state.tmp";
+const ENCODED_HARD_STATE_SIZE: usize = 17;
+
+pub struct FileHardStateStorage {
+    directory: PathBuf,
+    write_lock: Mutex<()>,
+}
+
+impl FileHardStateStorage {
+    pub fn new<P: AsRef<Path>>(directory: P) -> io::Result<Self> {
+        let directory = directory.as_ref().to_path_buf();
+        fs::create_dir_all(&directory)?;
+
+        return Ok(FileHardStateStorage { directory, write_lock: Mutex::new(()) });
+    }
+
+    fn encode(hard_state: &HardState) -> [u8; ENCODED_HARD_STATE_SIZE] {
+        let mut bytes = [0; ENCODED_HARD_STATE_SIZE];
+        bytes[0..8].copy_from_slice(&hard_state.get_term().to_be_bytes());
+        if let Some(voted_for) = hard_state.get_voted_for() {
+            bytes[8] = 1;
+            bytes[9..17].copy_from_slice(&voted_for.to_be_bytes());
+        }
+        return bytes;
+    }
+
+    fn decode(bytes: &[u8]) -> io::Result<HardState> {
+        if bytes.len() != ENCODED_HARD_STATE_SIZE {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!("hard state file is expected to be {} bytes, found {} bytes", ENCODED_HARD_STATE_SIZE, bytes.len()),
+            ));
+        }
+        let term = u64::from_be_bytes(bytes[0..8].try_into().unwrap());
+        let voted_for = match bytes[8] {
+            0 => None,
+            1 => Some(u64::from_be_bytes(bytes[9..17].try_into().unwrap())),
+            marker => return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!("unexpected voted_for marker {} in hard state file", marker),
+            )),
+        };
+        return Ok(HardState::new(term, voted_for));
+    }
+}
+
+impl HardStateStorage for FileHardStateStorage {
+    fn save(&self, hard_state: HardState) -> io::Result<()> {
+        let _guard = self.write_lock.lock().unwrap();
+        let temporary_path = self.directory.join(HARD_STATE_TEMPORARY_FILE_NAME);
+
+        let mut file = OpenOptions::new().create(true).write(true).truncate(true).open(&temporary_path)?;
+        file.write_all(&Self::encode(&hard_state))?;
+        file.sync_all()?;
+
+        fs::rename(&temporary_path, self.directory.join(HARD_STATE_FILE_NAME))?;
+        return File::open(&self.directory)?.sync_all();
+    }
+
+    fn load(&self) -> io::Result<Option<HardState>> {
+        let mut file = match File::open(self.directory.join(HARD_STATE_FILE_NAME)) {
+            Ok(file) => file,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err),
+        };
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)?;
+        return Ok(Some(Self::decode(&bytes)?));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::io::ErrorKind;
+
+    use crate::storage::file_hard_state_storage::{FileHardStateStorage, HARD_STATE_FILE_NAME};
+    use crate::storage::hard_state_storage::{HardState, HardStateStorage};
+
+    #[test]
+    fn load_without_save() {
+        let directory = tempfile::tempdir().unwrap();
+        let storage = FileHardStateStorage::new(directory.path()).unwrap();
+
+        assert_eq!(None, storage.load().unwrap());
+    }
+
+    #[test]
+    fn save_and_load() {
+        let directory = tempfile::tempdir().unwrap();
+        let storage = FileHardStateStorage::new(directory.path()).unwrap();
+        storage.save(HardState::new(3, Some(20))).unwrap();
+
+        assert_eq!(Some(HardState::new(3, Some(20))), storage.load().unwrap());
+    }
+
+    #[test]
+    fn save_and_load_without_voted_for() {
+        let directory = tempfile::tempdir().unwrap();
+        let storage = FileHardStateStorage::new(directory.path()).unwrap();
+        storage.save(HardState::new(3, None)).unwrap();
+
+        assert_eq!(Some(HardState::new(3, None)), storage.load().unwrap());
+    }
+
+    #[test]
+    fn save_overwrites_the_previous_hard_state() {
+        let directory = tempfile::tempdir().unwrap();
+        let storage = FileHardStateStorage::new(directory.path()).unwrap();
+        storage.save(HardState::new(3, Some(20))).unwrap();
+        storage.save(HardState::new(4, Some(30))).unwrap();
+
+        assert_eq!(Some(HardState::new(4, Some(30))), storage.load().unwrap());
+    }
+
+    #[test]
+    fn load_after_reopen() {
+        let directory = tempfile::tempdir().unwrap();
+        {
+            let storage = FileHardStateStorage::new(directory.path()).unwrap();
+            storage.save(HardState::new(7, Some(10))).unwrap();
+        }
+
+        let storage = FileHardStateStorage::new(directory.path()).unwrap();
+        assert_eq!(Some(HardState::new(7, Some(10))), storage.load().unwrap());
+    }
+
+    #[test]
+    fn load_a_corrupted_hard_state() {
+        let directory = tempfile::tempdir().unwrap();
+        let storage = FileHardStateStorage::new(directory.path()).unwrap();
+        fs::write(directory.path().join(HARD_STATE_FILE_NAME), [1, 2, 3]).unwrap();
+
+        let result = storage.load();
+        assert_eq!(ErrorKind::InvalidData, result.unwrap_err().kind());
+    }
+}
@@ -0,0 +1,204 @@
+use std::fs;
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::io::{ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::storage::snapshot_storage::{Snapshot, SnapshotStorage};
+
+const SNAPSHOT_FILE_NAME: &str = "snapshot";
+const SNAPSHOT_TEMPORARY_FILE_NAME: &str = "snapshot.tmp";
+const SNAPSHOT_HEADER_SIZE: usize = 24;
+
+/// Stores the snapshot as `[last_included_index: u64][last_included_term: u64][crc32 of the rest: u32][client sessions length: u32][client sessions][membership length: u32][membership][data]`.
+/// A new snapshot is written to a temporary file which replaces the previous snapshot only after it is fsynced.
+pub struct FileSnapshotStorage {
+    directory: PathBuf,
+    write_lock: Mutex<()>,
+}
+
+impl FileSnapshotStorage {
+    pub fn new<P: AsRef<Path>>(directory: P) -> io::Result<Self> {
+        let directory = directory.as_ref().to_path_buf();
+        fs::create_dir_all(&directory)?;
+
+        return Ok(FileSnapshotStorage { directory, write_lock: Mutex::new(()) });
+    }
+
+    fn encode(snapshot: &Snapshot) -> Vec<u8> {
+        let client_sessions = snapshot.get_client_sessions_as_vec();
+        let membership = snapshot.get_membership_as_vec();
+        let data = snapshot.get_data_as_vec();
+        let mut checksummed = Vec::with_capacity(8 + client_sessions.len() + membership.len() + data.len());
+        checksummed.extend_from_slice(&(client_sessions.len() as u32).to_be_bytes());
+        checksummed.extend_from_slice(&client_sessions);
+        checksummed.extend_from_slice(&(membership.len() as u32).to_be_bytes());
+        checksummed.extend_from_slice(&membership);
+        checksummed.extend_from_slice(&data);
+
+        let mut bytes = Vec::with_capacity(20 + checksummed.len());
+        bytes.extend_from_slice(&snapshot.get_last_included_index().to_be_bytes());
+        bytes.extend_from_slice(&snapshot.get_last_included_term().to_be_bytes());
+        bytes.extend_from_slice(&crc32fast::hash(&checksummed).to_be_bytes());
+        bytes.extend_from_slice(&checksummed);
+        return bytes;
+    }
+
+    fn decode(bytes: Vec<u8>) -> io::Result<Snapshot> {
+        if bytes.len() < SNAPSHOT_HEADER_SIZE {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!("snapshot file is expected to be at least {} bytes, found {} bytes", SNAPSHOT_HEADER_SIZE, bytes.len()),
+            ));
+        }
+        let last_included_index = u64::from_be_bytes(bytes[0..8].try_into().unwrap());
+        let last_included_term = u64::from_be_bytes(bytes[8..16].try_into().unwrap());
+        let checksum = u32::from_be_bytes(bytes[16..20].try_into().unwrap());
+        if crc32fast::hash(&bytes[20..]) != checksum {
+            return Err(io::Error::new(ErrorKind::InvalidData, "snapshot checksum mismatch"));
+        }
+
+        let client_sessions_length = u32::from_be_bytes(bytes[20..24].try_into().unwrap()) as usize;
+        if bytes.len() < SNAPSHOT_HEADER_SIZE + client_sessions_length {
+            return Err(io::Error::new(ErrorKind::InvalidData, "snapshot file is shorter than its client sessions"));
+        }
+        let client_sessions = bytes[SNAPSHOT_HEADER_SIZE..SNAPSHOT_HEADER_SIZE + client_sessions_length].to_vec();
+
+        let membership_offset = SNAPSHOT_HEADER_SIZE + client_sessions_length;
+        if bytes.len() < membership_offset + 4 {
+            return Err(io::Error::new(ErrorKind::InvalidData, "snapshot file is shorter than its membership length"));
+        }
+        let membership_length = u32::from_be_bytes(bytes[membership_offset..membership_offset + 4].try_into().unwrap()) as usize;
+        if bytes.len() < membership_offset + 4 + membership_length {
+            return Err(io::Error::new(ErrorKind::InvalidData, "snapshot file is shorter than its membership"));
+        }
+        let membership = &bytes[membership_offset + 4..membership_offset + 4 + membership_length];
+        let data = bytes[membership_offset + 4 + membership_length..].to_vec();
+
+        return Snapshot::new(last_included_index, last_included_term, data)
+            .with_client_sessions(client_sessions)
+            .with_encoded_membership(membership)
+            .ok_or_else(|| io::Error::new(ErrorKind::InvalidData, "snapshot membership can not be decoded"));
+    }
+}
+
+impl SnapshotStorage for FileSnapshotStorage {
+    fn save(&self, snapshot: &Snapshot) -> io::Result<()> {
+        let _guard = self.write_lock.lock().unwrap();
+        let temporary_path = self.directory.join(SNAPSHOT_TEMPORARY_FILE_NAME);
+
+        let mut file = OpenOptions::new().create(true).write(true).truncate(true).open(&temporary_path)?;
+        file.write_all(&Self::encode(snapshot))?;
+        file.sync_all()?;
+
+        fs::rename(&temporary_path, self.directory.join(SNAPSHOT_FILE_NAME))?;
+        return File::open(&self.directory)?.sync_all();
+    }
+
+    fn load(&self) -> io::Result<Option<Snapshot>> {
+        let mut file = match File::open(self.directory.join(SNAPSHOT_FILE_NAME)) {
+            Ok(file) => file,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err),
+        };
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)?;
+        return Ok(Some(Self::decode(bytes)?));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::io::ErrorKind;
+    use std::net::{IpAddr, Ipv4Addr};
+
+    use replicate::net::connect::host_and_port::HostAndPort;
+
+    use crate::membership::Membership;
+    use crate::storage::file_snapshot_storage::{FileSnapshotStorage, SNAPSHOT_FILE_NAME};
+    use crate::storage::snapshot_storage::{Snapshot, SnapshotStorage};
+
+    fn voter(port: u16) -> HostAndPort {
+        return HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), port);
+    }
+
+    #[test]
+    fn load_without_save() {
+        let directory = tempfile::tempdir().unwrap();
+        let storage = FileSnapshotStorage::new(directory.path()).unwrap();
+
+        assert_eq!(None, storage.load().unwrap());
+    }
+
+    #[test]
+    fn save_and_load_after_reopen() {
+        let directory = tempfile::tempdir().unwrap();
+        {
+            let storage = FileSnapshotStorage::new(directory.path()).unwrap();
+            storage.save(&Snapshot::new(10, 3, "state".as_bytes().to_vec())).unwrap();
+        }
+
+        let storage = FileSnapshotStorage::new(directory.path()).unwrap();
+        assert_eq!(Some(Snapshot::new(10, 3, "state".as_bytes().to_vec())), storage.load().unwrap());
+    }
+
+    #[test]
+    fn save_and_load_the_client_sessions_after_reopen() {
+        let directory = tempfile::tempdir().unwrap();
+        let snapshot = Snapshot::new(10, 3, "state".as_bytes().to_vec()).with_client_sessions("sessions".as_bytes().to_vec());
+        {
+            let storage = FileSnapshotStorage::new(directory.path()).unwrap();
+            storage.save(&snapshot).unwrap();
+        }
+
+        let storage = FileSnapshotStorage::new(directory.path()).unwrap();
+        let loaded = storage.load().unwrap().unwrap();
+        assert_eq!("sessions".as_bytes().to_vec(), loaded.get_client_sessions_as_vec());
+        assert_eq!("state".as_bytes().to_vec(), loaded.get_data_as_vec());
+    }
+
+    #[test]
+    fn save_and_load_the_membership_after_reopen() {
+        let directory = tempfile::tempdir().unwrap();
+        let membership = Membership::new(vec![voter(2010), voter(2011)])
+            .with_learner(voter(2012))
+            .with_voter(voter(2013));
+        let snapshot = Snapshot::new(10, 3, "state".as_bytes().to_vec()).with_membership(membership.clone());
+        {
+            let storage = FileSnapshotStorage::new(directory.path()).unwrap();
+            storage.save(&snapshot).unwrap();
+        }
+
+        let storage = FileSnapshotStorage::new(directory.path()).unwrap();
+        let loaded = storage.load().unwrap().unwrap();
+        assert_eq!(Some(&membership), loaded.get_membership());
+        assert_eq!("state".as_bytes().to_vec(), loaded.get_data_as_vec());
+    }
+
+    #[test]
+    fn save_overwrites_the_previous_snapshot() {
+        let directory = tempfile::tempdir().unwrap();
+        let storage = FileSnapshotStorage::new(directory.path()).unwrap();
+        storage.save(&Snapshot::new(10, 3, "state".as_bytes().to_vec())).unwrap();
+        storage.save(&Snapshot::new(20, 4, "new state".as_bytes().to_vec())).unwrap();
+
+        assert_eq!(Some(Snapshot::new(20, 4, "new state".as_bytes().to_vec())), storage.load().unwrap());
+    }
+
+    #[test]
+    fn load_a_corrupted_snapshot() {
+        let directory = tempfile::tempdir().unwrap();
+        let storage = FileSnapshotStorage::new(directory.path()).unwrap();
+        storage.save(&Snapshot::new(10, 3, "state".as_bytes().to_vec())).unwrap();
+
+        let path = directory.path().join(SNAPSHOT_FILE_NAME);
+        let mut bytes = fs::read(&path).unwrap();
+        let last_byte = bytes.len() - 1;
+        bytes[last_byte] = bytes[last_byte] ^ 0xFF;
+        fs::write(&path, bytes).unwrap();
+
+        assert_eq!(ErrorKind::InvalidData, storage.load().unwrap_err().kind());
+    }
+}
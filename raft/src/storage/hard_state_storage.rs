@@ -0,0 +1,84 @@
+use std::io;
+use std::sync::RwLock;
+
+use replicate::net::replica::ReplicaId;
+
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+pub struct HardState {
+    term: u64,
+    voted_for: Option<ReplicaId>,
+}
+
+pub trait HardStateStorage: Send + Sync {
+    fn save(&self, hard_state: HardState) -> io::Result<()>;
+
+    fn load(&self) -> io::Result<Option<HardState>>;
+}
+
+#[derive(Default)]
+pub struct InMemoryHardStateStorage {
+    hard_state: RwLock<Option<HardState>>,
+}
+
+impl HardState {
+    pub fn new(term: u64, voted_for: Option<ReplicaId>) -> Self {
+        return HardState { term, voted_for };
+    }
+
+    pub fn get_term(&self) -> u64 {
+        return self.term;
+    }
+
+    pub fn get_voted_for(&self) -> Option<ReplicaId> {
+        return self.voted_for;
+    }
+}
+
+impl InMemoryHardStateStorage {
+    pub fn new() -> Self {
+        return InMemoryHardStateStorage { hard_state: RwLock::new(None) };
+    }
+}
+
+impl HardStateStorage for InMemoryHardStateStorage {
+    fn save(&self, hard_state: HardState) -> io::Result<()> {
+        let mut write_guard = self.hard_state.write().unwrap();
+        *write_guard = Some(hard_state);
+        return Ok(());
+    }
+
+    fn load(&self) -> io::Result<Option<HardState>> {
+        let guard = self.hard_state.read().unwrap();
+        return Ok(*guard);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::storage::hard_state_storage::{HardState, HardStateStorage, InMemoryHardStateStorage};
+
+    #[test]
+    fn load_without_save() {
+        let storage = InMemoryHardStateStorage::new();
+        assert_eq!(None, storage.load().unwrap());
+    }
+
+    #[test]
+    fn save_and_load() {
+        let storage = InMemoryHardStateStorage::new();
+        storage.save(HardState::new(5, Some(10))).unwrap();
+
+        let hard_state = storage.load().unwrap().unwrap();
+        assert_eq!(5, hard_state.get_term());
+        assert_eq!(Some(10), hard_state.get_voted_for());
+    }
+
+    #[test]
+    fn save_overwrites_the_previous_hard_state() {
+        let storage = InMemoryHardStateStorage::new();
+        storage.save(HardState::new(5, Some(10))).unwrap();
+        storage.save(HardState::new(6, None)).unwrap();
+
+        assert_eq!(Some(HardState::new(6, None)), storage.load().unwrap());
+    }
+}
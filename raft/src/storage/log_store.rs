@@ -0,0 +1,195 @@
+use std::io;
+use std::io::ErrorKind;
+
+use crate::log_entry::LogEntry;
+
+pub trait LogStore: Send + Sync {
+    fn append(&mut self, entries: Vec<LogEntry>) -> io::Result<()>;
+
+    fn read_range(&self, from_index: u64, to_index: u64) -> io::Result<Vec<LogEntry>>;
+
+    fn truncate_suffix(&mut self, from_index: u64) -> io::Result<()>;
+
+    /// Discards the entries up to and including `up_to_index`.
+    /// An implementation may retain some of these entries, `first_index` reports the first entry that is still readable.
+    fn truncate_prefix(&mut self, up_to_index: u64) -> io::Result<()>;
+
+    fn first_index(&self) -> Option<u64>;
+
+    fn last_index(&self) -> Option<u64>;
+
+    fn last_term(&self) -> Option<u64>;
+
+    fn read(&self, index: u64) -> io::Result<Option<LogEntry>> {
+        return Ok(self.read_range(index, index + 1)?.pop());
+    }
+}
+
+pub(crate) fn ensure_contiguous(last_index: Option<u64>, entries: &Vec<LogEntry>) -> io::Result<()> {
+    let mut expected_index = last_index.map(|index| index + 1);
+    for entry in entries {
+        if let Some(index) = expected_index {
+            if !entry.matches_index(index) {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("log entry at index {} can not be appended, expected index {}", entry.get_index(), index),
+                ));
+            }
+        }
+        expected_index = Some(entry.get_index() + 1);
+    }
+    return Ok(());
+}
+
+#[derive(Default)]
+pub struct InMemoryLogStore {
+    first_index: u64,
+    log_entries: Vec<LogEntry>,
+}
+
+impl InMemoryLogStore {
+    pub fn new() -> Self {
+        return InMemoryLogStore { first_index: 0, log_entries: Vec::new() };
+    }
+}
+
+impl LogStore for InMemoryLogStore {
+    fn append(&mut self, entries: Vec<LogEntry>) -> io::Result<()> {
+        ensure_contiguous(self.last_index(), &entries)?;
+        if self.log_entries.is_empty() {
+            if let Some(entry) = entries.first() {
+                self.first_index = entry.get_index();
+            }
+        }
+        self.log_entries.extend(entries);
+        return Ok(());
+    }
+
+    fn read_range(&self, from_index: u64, to_index: u64) -> io::Result<Vec<LogEntry>> {
+        let end_index = self.first_index + self.log_entries.len() as u64;
+        let from_index = from_index.max(self.first_index).min(end_index);
+        let to_index = to_index.max(from_index).min(end_index);
+
+        let from_position = (from_index - self.first_index) as usize;
+        let to_position = (to_index - self.first_index) as usize;
+        return Ok(self.log_entries[from_position..to_position].iter().map(LogEntry::from).collect());
+    }
+
+    fn truncate_suffix(&mut self, from_index: u64) -> io::Result<()> {
+        let position = from_index.saturating_sub(self.first_index) as usize;
+        self.log_entries.truncate(position);
+        return Ok(());
+    }
+
+    fn truncate_prefix(&mut self, up_to_index: u64) -> io::Result<()> {
+        if up_to_index < self.first_index {
+            return Ok(());
+        }
+        let position = ((up_to_index - self.first_index + 1) as usize).min(self.log_entries.len());
+        self.log_entries.drain(0..position);
+        self.first_index = up_to_index + 1;
+        return Ok(());
+    }
+
+    fn first_index(&self) -> Option<u64> {
+        return self.log_entries.first().map(|entry| entry.get_index());
+    }
+
+    fn last_index(&self) -> Option<u64> {
+        return self.log_entries.last().map(|entry| entry.get_index());
+    }
+
+    fn last_term(&self) -> Option<u64> {
+        return self.log_entries.last().map(|entry| entry.get_term());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::ErrorKind;
+
+    use crate::log_entry::LogEntry;
+    use crate::net::rpc::grpc::Command;
+    use crate::storage::log_store::{InMemoryLogStore, LogStore};
+
+    fn log_entry(term: u64, index: u64) -> LogEntry {
+        let command = Command { command: format!("Content-{}", index).as_bytes().to_vec(), client_id: None, sequence: 0 };
+        return LogEntry::new(term, index, &command);
+    }
+
+    #[test]
+    fn empty_log_store() {
+        let log_store = InMemoryLogStore::new();
+
+        assert_eq!(None, log_store.last_index());
+        assert_eq!(None, log_store.last_term());
+        assert_eq!(None, log_store.read(0).unwrap());
+    }
+
+    #[test]
+    fn append_and_read() {
+        let mut log_store = InMemoryLogStore::new();
+        log_store.append(vec![log_entry(1, 0), log_entry(1, 1), log_entry(2, 2)]).unwrap();
+
+        assert_eq!(Some(2), log_store.last_index());
+        assert_eq!(Some(2), log_store.last_term());
+        assert_eq!(Some(log_entry(1, 1)), log_store.read(1).unwrap());
+    }
+
+    #[test]
+    fn read_range() {
+        let mut log_store = InMemoryLogStore::new();
+        log_store.append(vec![log_entry(1, 0), log_entry(1, 1), log_entry(2, 2)]).unwrap();
+
+        assert_eq!(vec![log_entry(1, 1), log_entry(2, 2)], log_store.read_range(1, 10).unwrap());
+    }
+
+    #[test]
+    fn append_a_non_contiguous_entry() {
+        let mut log_store = InMemoryLogStore::new();
+        log_store.append(vec![log_entry(1, 0)]).unwrap();
+
+        let result = log_store.append(vec![log_entry(1, 5)]);
+        assert_eq!(ErrorKind::InvalidInput, result.unwrap_err().kind());
+    }
+
+    #[test]
+    fn truncate_suffix() {
+        let mut log_store = InMemoryLogStore::new();
+        log_store.append(vec![log_entry(1, 0), log_entry(1, 1), log_entry(2, 2)]).unwrap();
+        log_store.truncate_suffix(1).unwrap();
+
+        assert_eq!(Some(0), log_store.last_index());
+        assert_eq!(None, log_store.read(1).unwrap());
+
+        log_store.append(vec![log_entry(3, 1)]).unwrap();
+        assert_eq!(Some(3), log_store.last_term());
+    }
+
+    #[test]
+    fn truncate_prefix() {
+        let mut log_store = InMemoryLogStore::new();
+        log_store.append(vec![log_entry(1, 0), log_entry(1, 1), log_entry(2, 2)]).unwrap();
+        log_store.truncate_prefix(1).unwrap();
+
+        assert_eq!(Some(2), log_store.first_index());
+        assert_eq!(None, log_store.read(1).unwrap());
+        assert_eq!(Some(log_entry(2, 2)), log_store.read(2).unwrap());
+
+        log_store.append(vec![log_entry(2, 3)]).unwrap();
+        assert_eq!(vec![log_entry(2, 2), log_entry(2, 3)], log_store.read_range(0, 4).unwrap());
+    }
+
+    #[test]
+    fn truncate_prefix_beyond_the_last_entry() {
+        let mut log_store = InMemoryLogStore::new();
+        log_store.append(vec![log_entry(1, 0), log_entry(1, 1)]).unwrap();
+        log_store.truncate_prefix(5).unwrap();
+
+        assert_eq!(None, log_store.first_index());
+        assert_eq!(None, log_store.last_index());
+
+        log_store.append(vec![log_entry(2, 6)]).unwrap();
+        assert_eq!(Some(log_entry(2, 6)), log_store.read(6).unwrap());
+    }
+}
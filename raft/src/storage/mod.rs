@@ -0,0 +1,6 @@
+pub mod hard_state_storage;
+pub mod file_hard_state_storage;
+pub mod log_store;
+pub mod segmented_log_store;
+pub mod snapshot_storage;
+pub mod file_snapshot_storage;
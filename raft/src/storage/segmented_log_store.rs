@@ -0,0 +1,603 @@
+use std::fs;
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::io::{BufReader, ErrorKind, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use prost::Message;
+
+use crate::log_entry::{LogEntry, LogEntryType};
+use crate::net::rpc::grpc::Entry;
+use crate::storage::log_store::{ensure_contiguous, LogStore};
+
+const LOG_FILE_EXTENSION: &str = "log";
+const INDEX_FILE_EXTENSION: &str = "index";
+const RECORD_HEADER_SIZE: usize = 8;
+const INDEX_ENTRY_SIZE: u64 = 8;
+const DEFAULT_MAXIMUM_SEGMENT_SIZE: u64 = 64 * 1024 * 1024;
+
+/// An append-only log split into segments. Every segment is a pair of files named after the index of its first entry:
+/// `<base_index>.log` holds the records (`[payload length: u32][crc32 of payload: u32][payload]`)
+/// and `<base_index>.index` holds the offset (u64) of every record in the log file.
+/// Only the last segment is written to, the earlier segments are sealed (and fsynced) when a new segment is rolled.
+/// Prefix truncation removes whole segments only, so the entries of a partially truncated segment remain readable.
+/// On open, the last segment is scanned and a torn or corrupted tail is truncated, the index file is rebuilt if it disagrees.
+pub struct SegmentedLogStore {
+    directory: PathBuf,
+    maximum_segment_size: u64,
+    segments: Vec<Segment>,
+    last_term: Option<u64>,
+}
+
+struct Segment {
+    base_index: u64,
+    log_path: PathBuf,
+    log_file: File,
+    index_path: PathBuf,
+    index_file: File,
+    offsets: Vec<u64>,
+    size: u64,
+}
+
+impl SegmentedLogStore {
+    pub fn new<P: AsRef<Path>>(directory: P) -> io::Result<Self> {
+        return Self::new_with_maximum_segment_size(directory, DEFAULT_MAXIMUM_SEGMENT_SIZE);
+    }
+
+    pub fn new_with_maximum_segment_size<P: AsRef<Path>>(directory: P, maximum_segment_size: u64) -> io::Result<Self> {
+        let directory = directory.as_ref().to_path_buf();
+        fs::create_dir_all(&directory)?;
+
+        let mut base_indices = Vec::new();
+        for dir_entry in fs::read_dir(&directory)? {
+            let path = dir_entry?.path();
+            if path.extension().is_some_and(|extension| extension == LOG_FILE_EXTENSION) {
+                if let Some(base_index) = path.file_stem().and_then(|stem| stem.to_str()).and_then(|stem| stem.parse::<u64>().ok()) {
+                    base_indices.push(base_index);
+                }
+            }
+        }
+        base_indices.sort();
+
+        let total_segments = base_indices.len();
+        let mut segments = Vec::with_capacity(total_segments);
+        for (position, base_index) in base_indices.into_iter().enumerate() {
+            let is_last = position == total_segments - 1;
+            let segment = Segment::open(&directory, base_index, is_last)?;
+            if segment.is_empty() {
+                segment.remove()?;
+            } else {
+                segments.push(segment);
+            }
+        }
+
+        let mut log_store = SegmentedLogStore { directory, maximum_segment_size, segments, last_term: None };
+        log_store.last_term = log_store.read_last_term()?;
+        return Ok(log_store);
+    }
+
+    fn roll_segment(&mut self, base_index: u64) -> io::Result<()> {
+        if let Some(segment) = self.segments.last() {
+            segment.sync()?;
+        }
+        self.segments.push(Segment::create(&self.directory, base_index)?);
+        return self.sync_directory();
+    }
+
+    fn segment_position_for(&self, index: u64) -> Option<usize> {
+        let position = self.segments.partition_point(|segment| segment.base_index <= index);
+        if position == 0 {
+            return None;
+        }
+        return Some(position - 1);
+    }
+
+    fn read_last_term(&self) -> io::Result<Option<u64>> {
+        return match self.last_index() {
+            None => Ok(None),
+            Some(last_index) => Ok(self.read(last_index)?.map(|entry| entry.get_term()))
+        };
+    }
+
+    fn sync_directory(&self) -> io::Result<()> {
+        return File::open(&self.directory)?.sync_all();
+    }
+}
+
+impl LogStore for SegmentedLogStore {
+    fn append(&mut self, entries: Vec<LogEntry>) -> io::Result<()> {
+        ensure_contiguous(self.last_index(), &entries)?;
+        let last_term = match entries.last() {
+            None => return Ok(()),
+            Some(entry) => entry.get_term()
+        };
+
+        for entry in &entries {
+            let should_roll = match self.segments.last() {
+                None => true,
+                Some(segment) => segment.size >= self.maximum_segment_size
+            };
+            if should_roll {
+                self.roll_segment(entry.get_index())?;
+            }
+            self.segments.last_mut().unwrap().append(entry)?;
+        }
+        self.segments.last().unwrap().sync()?;
+        self.last_term = Some(last_term);
+        return Ok(());
+    }
+
+    fn read_range(&self, from_index: u64, to_index: u64) -> io::Result<Vec<LogEntry>> {
+        let mut entries = Vec::new();
+        let to_index = match self.last_index() {
+            None => return Ok(entries),
+            Some(last_index) => to_index.min(last_index + 1)
+        };
+        let mut index = from_index.max(self.segments[0].base_index);
+        while index < to_index {
+            let segment = &self.segments[self.segment_position_for(index).unwrap()];
+            let segment_to_index = to_index.min(segment.end_index());
+            entries.extend(segment.read_range(index, segment_to_index)?);
+            index = segment_to_index;
+        }
+        return Ok(entries);
+    }
+
+    fn truncate_suffix(&mut self, from_index: u64) -> io::Result<()> {
+        let mut segments_removed = false;
+        while self.segments.last().is_some_and(|segment| segment.base_index >= from_index) {
+            self.segments.pop().unwrap().remove()?;
+            segments_removed = true;
+        }
+        if let Some(segment) = self.segments.last_mut() {
+            if from_index < segment.end_index() {
+                segment.truncate((from_index - segment.base_index) as usize)?;
+            }
+        }
+        if segments_removed {
+            self.sync_directory()?;
+        }
+        self.last_term = self.read_last_term()?;
+        return Ok(());
+    }
+
+    fn truncate_prefix(&mut self, up_to_index: u64) -> io::Result<()> {
+        let total_removable_segments = self.segments.iter()
+            .take_while(|segment| segment.end_index() <= up_to_index + 1)
+            .count();
+        if total_removable_segments == 0 {
+            return Ok(());
+        }
+        for segment in self.segments.drain(0..total_removable_segments) {
+            segment.remove()?;
+        }
+        self.sync_directory()?;
+        self.last_term = self.read_last_term()?;
+        return Ok(());
+    }
+
+    fn first_index(&self) -> Option<u64> {
+        return self.segments.first().map(|segment| segment.base_index);
+    }
+
+    fn last_index(&self) -> Option<u64> {
+        return self.segments.last().map(|segment| segment.end_index() - 1);
+    }
+
+    fn last_term(&self) -> Option<u64> {
+        return self.last_term;
+    }
+}
+
+impl Segment {
+    fn create(directory: &Path, base_index: u64) -> io::Result<Segment> {
+        let segment = Self::open_files(directory, base_index, Vec::new())?;
+        segment.log_file.set_len(0)?;
+        segment.index_file.set_len(0)?;
+        return Ok(segment);
+    }
+
+    fn open(directory: &Path, base_index: u64, is_last: bool) -> io::Result<Segment> {
+        let (log_path, index_path) = Self::paths(directory, base_index);
+        let offsets = if is_last {
+            Self::recover(&log_path, base_index)?
+        } else {
+            Self::read_offsets(&index_path)?
+        };
+
+        let mut segment = Self::open_files(directory, base_index, offsets)?;
+        if is_last {
+            segment.log_file.set_len(segment.size)?;
+            if Self::read_offsets(&index_path)? != segment.offsets {
+                segment.index_file.set_len(0)?;
+                segment.index_file.write_all(&Self::encode_offsets(&segment.offsets))?;
+            }
+            segment.sync()?;
+        }
+        return Ok(segment);
+    }
+
+    fn open_files(directory: &Path, base_index: u64, offsets: Vec<u64>) -> io::Result<Segment> {
+        let (log_path, index_path) = Self::paths(directory, base_index);
+        let log_file = OpenOptions::new().create(true).append(true).open(&log_path)?;
+        let index_file = OpenOptions::new().create(true).append(true).open(&index_path)?;
+
+        let size = match offsets.last() {
+            None => 0,
+            Some(offset) => {
+                let mut file = File::open(&log_path)?;
+                file.seek(SeekFrom::Start(*offset))?;
+                let (payload_length, _) = Self::read_header(&mut file)?
+                    .ok_or_else(|| io::Error::new(ErrorKind::UnexpectedEof, "index file points past the end of the log segment"))?;
+                offset + (RECORD_HEADER_SIZE + payload_length) as u64
+            }
+        };
+        return Ok(Segment { base_index, log_path, log_file, index_path, index_file, offsets, size });
+    }
+
+    fn paths(directory: &Path, base_index: u64) -> (PathBuf, PathBuf) {
+        let file_name = format!("{:020}", base_index);
+        return (
+            directory.join(&file_name).with_extension(LOG_FILE_EXTENSION),
+            directory.join(&file_name).with_extension(INDEX_FILE_EXTENSION),
+        );
+    }
+
+    /// Scans the log file and returns the offsets of all the valid records.
+    /// The scan stops at the first record which is incomplete, fails the checksum, can not be decoded, or does not carry the expected index.
+    fn recover(log_path: &Path, base_index: u64) -> io::Result<Vec<u64>> {
+        let mut offsets = Vec::new();
+        let mut reader = BufReader::new(File::open(log_path)?);
+        let mut offset: u64 = 0;
+        loop {
+            let record = match Self::read_record(&mut reader) {
+                Ok(Some(record)) => record,
+                Ok(None) => break,
+                Err(err) if err.kind() == ErrorKind::UnexpectedEof || err.kind() == ErrorKind::InvalidData => break,
+                Err(err) => return Err(err),
+            };
+            let (entry, record_size) = record;
+            if !entry.matches_index(base_index + offsets.len() as u64) {
+                break;
+            }
+            offsets.push(offset);
+            offset = offset + record_size as u64;
+        }
+        return Ok(offsets);
+    }
+
+    fn read_offsets(index_path: &Path) -> io::Result<Vec<u64>> {
+        let mut bytes = Vec::new();
+        match File::open(index_path) {
+            Ok(mut file) => { file.read_to_end(&mut bytes)?; }
+            Err(err) if err.kind() == ErrorKind::NotFound => {}
+            Err(err) => return Err(err),
+        };
+        return Ok(
+            bytes.chunks_exact(INDEX_ENTRY_SIZE as usize)
+                .map(|chunk| u64::from_be_bytes(chunk.try_into().unwrap()))
+                .collect()
+        );
+    }
+
+    fn encode_offsets(offsets: &[u64]) -> Vec<u8> {
+        return offsets.iter().flat_map(|offset| offset.to_be_bytes()).collect();
+    }
+
+    fn append(&mut self, log_entry: &LogEntry) -> io::Result<()> {
+        let payload = Entry {
+            command: Some(log_entry.get_command()),
+            term: log_entry.get_term(),
+            index: log_entry.get_index(),
+            entry_type: log_entry.get_entry_type().as_entry_type(),
+            time: log_entry.get_time(),
+        }.encode_to_vec();
+
+        let mut record = Vec::with_capacity(RECORD_HEADER_SIZE + payload.len());
+        record.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+        record.extend_from_slice(&crc32fast::hash(&payload).to_be_bytes());
+        record.extend_from_slice(&payload);
+
+        self.log_file.write_all(&record)?;
+        self.index_file.write_all(&self.size.to_be_bytes())?;
+        self.offsets.push(self.size);
+        self.size = self.size + record.len() as u64;
+        return Ok(());
+    }
+
+    fn read_range(&self, from_index: u64, to_index: u64) -> io::Result<Vec<LogEntry>> {
+        let from_position = (from_index - self.base_index) as usize;
+        let to_position = (to_index - self.base_index) as usize;
+
+        let mut reader = BufReader::new(File::open(&self.log_path)?);
+        reader.seek(SeekFrom::Start(self.offsets[from_position]))?;
+
+        let mut entries = Vec::with_capacity(to_position - from_position);
+        for _ in from_position..to_position {
+            match Self::read_record(&mut reader)? {
+                None => return Err(io::Error::new(ErrorKind::UnexpectedEof, "log segment ended before the expected record")),
+                Some((entry, _)) => entries.push(entry),
+            }
+        }
+        return Ok(entries);
+    }
+
+    fn truncate(&mut self, position: usize) -> io::Result<()> {
+        let offset = self.offsets[position];
+        self.log_file.set_len(offset)?;
+        self.index_file.set_len(position as u64 * INDEX_ENTRY_SIZE)?;
+        self.offsets.truncate(position);
+        self.size = offset;
+        return self.sync();
+    }
+
+    fn remove(self) -> io::Result<()> {
+        fs::remove_file(&self.log_path)?;
+        return fs::remove_file(&self.index_path);
+    }
+
+    fn sync(&self) -> io::Result<()> {
+        self.log_file.sync_data()?;
+        return self.index_file.sync_data();
+    }
+
+    fn end_index(&self) -> u64 {
+        return self.base_index + self.offsets.len() as u64;
+    }
+
+    fn is_empty(&self) -> bool {
+        return self.offsets.is_empty();
+    }
+
+    fn read_header<R: Read>(reader: &mut R) -> io::Result<Option<(usize, u32)>> {
+        let mut header = [0; RECORD_HEADER_SIZE];
+        let mut read = 0;
+        while read < RECORD_HEADER_SIZE {
+            match reader.read(&mut header[read..])? {
+                0 if read == 0 => return Ok(None),
+                0 => return Err(io::Error::new(ErrorKind::UnexpectedEof, "incomplete record header")),
+                bytes_read => read = read + bytes_read,
+            }
+        }
+        let payload_length = u32::from_be_bytes(header[0..4].try_into().unwrap()) as usize;
+        let checksum = u32::from_be_bytes(header[4..8].try_into().unwrap());
+        return Ok(Some((payload_length, checksum)));
+    }
+
+    fn read_record<R: Read>(reader: &mut R) -> io::Result<Option<(LogEntry, usize)>> {
+        let (payload_length, checksum) = match Self::read_header(reader)? {
+            None => return Ok(None),
+            Some(header) => header
+        };
+        let mut payload = vec![0; payload_length];
+        reader.read_exact(&mut payload)?;
+        if crc32fast::hash(&payload) != checksum {
+            return Err(io::Error::new(ErrorKind::InvalidData, "log record checksum mismatch"));
+        }
+
+        let entry = Entry::decode(payload.as_slice()).map_err(|err| io::Error::new(ErrorKind::InvalidData, err))?;
+        let log_entry = LogEntry::new(entry.term, entry.index, &entry.command.unwrap_or_default())
+            .with_entry_type(LogEntryType::from(entry.entry_type))
+            .with_time(entry.time);
+        return Ok(Some((log_entry, RECORD_HEADER_SIZE + payload_length)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::fs::OpenOptions;
+    use std::io::Write;
+
+    use crate::log_entry::{LogEntry, LogEntryType};
+    use crate::net::rpc::grpc::Command;
+    use crate::storage::log_store::LogStore;
+    use crate::storage::segmented_log_store::SegmentedLogStore;
+
+    fn log_entry(term: u64, index: u64) -> LogEntry {
+        let command = Command { command: format!("Content-{}", index).as_bytes().to_vec(), client_id: None, sequence: 0 };
+        return LogEntry::new(term, index, &command);
+    }
+
+    fn total_files_with_extension(directory: &std::path::Path, extension: &str) -> usize {
+        return fs::read_dir(directory).unwrap()
+            .filter(|entry| entry.as_ref().unwrap().path().extension().is_some_and(|ext| ext == extension))
+            .count();
+    }
+
+    #[test]
+    fn empty_log_store() {
+        let directory = tempfile::tempdir().unwrap();
+        let log_store = SegmentedLogStore::new(directory.path()).unwrap();
+
+        assert_eq!(None, log_store.last_index());
+        assert_eq!(None, log_store.last_term());
+        assert_eq!(None, log_store.read(0).unwrap());
+    }
+
+    #[test]
+    fn append_and_read() {
+        let directory = tempfile::tempdir().unwrap();
+        let mut log_store = SegmentedLogStore::new(directory.path()).unwrap();
+        log_store.append(vec![log_entry(1, 0), log_entry(1, 1), log_entry(2, 2)]).unwrap();
+
+        assert_eq!(Some(2), log_store.last_index());
+        assert_eq!(Some(2), log_store.last_term());
+        assert_eq!(vec![log_entry(1, 1), log_entry(2, 2)], log_store.read_range(1, 10).unwrap());
+    }
+
+    #[test]
+    fn append_and_read_after_reopen() {
+        let directory = tempfile::tempdir().unwrap();
+        {
+            let mut log_store = SegmentedLogStore::new(directory.path()).unwrap();
+            log_store.append(vec![log_entry(1, 0), log_entry(2, 1)]).unwrap();
+        }
+
+        let mut log_store = SegmentedLogStore::new(directory.path()).unwrap();
+        assert_eq!(Some(1), log_store.last_index());
+        assert_eq!(Some(2), log_store.last_term());
+        assert_eq!(vec![log_entry(1, 0), log_entry(2, 1)], log_store.read_range(0, 2).unwrap());
+
+        log_store.append(vec![log_entry(3, 2)]).unwrap();
+        assert_eq!(Some(log_entry(3, 2)), log_store.read(2).unwrap());
+    }
+
+    #[test]
+    fn append_and_read_the_entry_type_after_reopen() {
+        let directory = tempfile::tempdir().unwrap();
+        {
+            let mut log_store = SegmentedLogStore::new(directory.path()).unwrap();
+            log_store.append(vec![log_entry(1, 0), log_entry(1, 1).with_entry_type(LogEntryType::Configuration)]).unwrap();
+        }
+
+        let log_store = SegmentedLogStore::new(directory.path()).unwrap();
+        assert_eq!(LogEntryType::Command, log_store.read(0).unwrap().unwrap().get_entry_type());
+        assert_eq!(LogEntryType::Configuration, log_store.read(1).unwrap().unwrap().get_entry_type());
+    }
+
+    #[test]
+    fn roll_segments() {
+        let directory = tempfile::tempdir().unwrap();
+        let mut log_store = SegmentedLogStore::new_with_maximum_segment_size(directory.path(), 64).unwrap();
+        for index in 0..10 {
+            log_store.append(vec![log_entry(1, index)]).unwrap();
+        }
+
+        assert!(total_files_with_extension(directory.path(), "log") > 1);
+        assert_eq!(
+            (0..10).map(|index| log_entry(1, index)).collect::<Vec<LogEntry>>(),
+            log_store.read_range(0, 10).unwrap()
+        );
+
+        let log_store = SegmentedLogStore::new_with_maximum_segment_size(directory.path(), 64).unwrap();
+        assert_eq!(Some(9), log_store.last_index());
+        assert_eq!(Some(log_entry(1, 4)), log_store.read(4).unwrap());
+    }
+
+    #[test]
+    fn truncate_suffix_across_segments() {
+        let directory = tempfile::tempdir().unwrap();
+        let mut log_store = SegmentedLogStore::new_with_maximum_segment_size(directory.path(), 64).unwrap();
+        for index in 0..10 {
+            log_store.append(vec![log_entry(1, index)]).unwrap();
+        }
+        let total_segments = total_files_with_extension(directory.path(), "log");
+
+        log_store.truncate_suffix(3).unwrap();
+        assert_eq!(Some(2), log_store.last_index());
+        assert_eq!(None, log_store.read(3).unwrap());
+        assert!(total_files_with_extension(directory.path(), "log") < total_segments);
+
+        log_store.append(vec![log_entry(2, 3)]).unwrap();
+        let log_store = SegmentedLogStore::new_with_maximum_segment_size(directory.path(), 64).unwrap();
+        assert_eq!(Some(3), log_store.last_index());
+        assert_eq!(Some(2), log_store.last_term());
+    }
+
+    #[test]
+    fn truncate_prefix_removes_whole_segments() {
+        let directory = tempfile::tempdir().unwrap();
+        let mut log_store = SegmentedLogStore::new_with_maximum_segment_size(directory.path(), 64).unwrap();
+        for index in 0..10 {
+            log_store.append(vec![log_entry(1, index)]).unwrap();
+        }
+        let total_segments = total_files_with_extension(directory.path(), "log");
+
+        log_store.truncate_prefix(5).unwrap();
+        let first_index = log_store.first_index().unwrap();
+        assert!(first_index > 0 && first_index <= 6);
+        assert!(total_files_with_extension(directory.path(), "log") < total_segments);
+        assert_eq!(Some(log_entry(1, 6)), log_store.read(6).unwrap());
+
+        let log_store = SegmentedLogStore::new_with_maximum_segment_size(directory.path(), 64).unwrap();
+        assert_eq!(Some(first_index), log_store.first_index());
+        assert_eq!(Some(9), log_store.last_index());
+    }
+
+    #[test]
+    fn truncate_prefix_beyond_the_last_entry() {
+        let directory = tempfile::tempdir().unwrap();
+        let mut log_store = SegmentedLogStore::new(directory.path()).unwrap();
+        log_store.append(vec![log_entry(1, 0), log_entry(1, 1)]).unwrap();
+        log_store.truncate_prefix(5).unwrap();
+
+        assert_eq!(None, log_store.first_index());
+        assert_eq!(None, log_store.last_term());
+
+        log_store.append(vec![log_entry(2, 6)]).unwrap();
+        let log_store = SegmentedLogStore::new(directory.path()).unwrap();
+        assert_eq!(Some(6), log_store.first_index());
+        assert_eq!(Some(log_entry(2, 6)), log_store.read(6).unwrap());
+    }
+
+    #[test]
+    fn truncate_the_entire_log() {
+        let directory = tempfile::tempdir().unwrap();
+        let mut log_store = SegmentedLogStore::new(directory.path()).unwrap();
+        log_store.append(vec![log_entry(1, 0), log_entry(1, 1)]).unwrap();
+        log_store.truncate_suffix(0).unwrap();
+
+        assert_eq!(None, log_store.last_index());
+        assert_eq!(None, log_store.last_term());
+
+        log_store.append(vec![log_entry(2, 0)]).unwrap();
+        assert_eq!(Some(log_entry(2, 0)), log_store.read(0).unwrap());
+    }
+
+    #[test]
+    fn recover_from_a_torn_write() {
+        let directory = tempfile::tempdir().unwrap();
+        {
+            let mut log_store = SegmentedLogStore::new(directory.path()).unwrap();
+            log_store.append(vec![log_entry(1, 0), log_entry(1, 1)]).unwrap();
+        }
+        let log_path = directory.path().join(format!("{:020}.log", 0));
+        let mut log_file = OpenOptions::new().append(true).open(&log_path).unwrap();
+        log_file.write_all(&[0, 0, 0, 40, 1, 2, 3, 4, 5]).unwrap();
+
+        let mut log_store = SegmentedLogStore::new(directory.path()).unwrap();
+        assert_eq!(Some(1), log_store.last_index());
+
+        log_store.append(vec![log_entry(2, 2)]).unwrap();
+        let log_store = SegmentedLogStore::new(directory.path()).unwrap();
+        assert_eq!(
+            vec![log_entry(1, 0), log_entry(1, 1), log_entry(2, 2)],
+            log_store.read_range(0, 3).unwrap()
+        );
+    }
+
+    #[test]
+    fn recover_from_a_corrupted_record() {
+        let directory = tempfile::tempdir().unwrap();
+        {
+            let mut log_store = SegmentedLogStore::new(directory.path()).unwrap();
+            log_store.append(vec![log_entry(1, 0), log_entry(1, 1)]).unwrap();
+        }
+        let log_path = directory.path().join(format!("{:020}.log", 0));
+        let mut bytes = fs::read(&log_path).unwrap();
+        let last_byte = bytes.len() - 1;
+        bytes[last_byte] = bytes[last_byte] ^ 0xFF;
+        fs::write(&log_path, bytes).unwrap();
+
+        let log_store = SegmentedLogStore::new(directory.path()).unwrap();
+        assert_eq!(Some(0), log_store.last_index());
+        assert_eq!(Some(1), log_store.last_term());
+    }
+
+    #[test]
+    fn recover_with_an_index_ahead_of_the_log() {
+        let directory = tempfile::tempdir().unwrap();
+        {
+            let mut log_store = SegmentedLogStore::new(directory.path()).unwrap();
+            log_store.append(vec![log_entry(1, 0)]).unwrap();
+        }
+        let index_path = directory.path().join(format!("{:020}.index", 0));
+        let mut index_file = OpenOptions::new().append(true).open(&index_path).unwrap();
+        index_file.write_all(&1000u64.to_be_bytes()).unwrap();
+
+        let log_store = SegmentedLogStore::new(directory.path()).unwrap();
+        assert_eq!(Some(0), log_store.last_index());
+        assert_eq!(8, fs::metadata(&index_path).unwrap().len());
+    }
+}
@@ -0,0 +1,135 @@
+use std::io;
+use std::sync::RwLock;
+
+use bytes::Bytes;
+
+use crate::membership::Membership;
+
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct Snapshot {
+    last_included_index: u64,
+    last_included_term: u64,
+    data: Bytes,
+    client_sessions: Bytes,
+    membership: Option<Membership>,
+}
+
+pub trait SnapshotStorage: Send + Sync {
+    fn save(&self, snapshot: &Snapshot) -> io::Result<()>;
+
+    fn load(&self) -> io::Result<Option<Snapshot>>;
+}
+
+#[derive(Default)]
+pub struct InMemorySnapshotStorage {
+    snapshot: RwLock<Option<Snapshot>>,
+}
+
+impl Snapshot {
+    pub fn new(last_included_index: u64, last_included_term: u64, data: Vec<u8>) -> Self {
+        return Snapshot { last_included_index, last_included_term, data: Bytes::from(data), client_sessions: Bytes::new(), membership: None };
+    }
+
+    /// The encoded client sessions as of the last included index, restored along with the state machine.
+    pub fn with_client_sessions(mut self, client_sessions: Vec<u8>) -> Self {
+        self.client_sessions = Bytes::from(client_sessions);
+        return self;
+    }
+
+    /// The latest membership as of the last included index, it remains in effect until a later one is appended.
+    pub fn with_membership(mut self, membership: Membership) -> Self {
+        self.membership = Some(membership);
+        return self;
+    }
+
+    pub fn get_last_included_index(&self) -> u64 {
+        return self.last_included_index;
+    }
+
+    pub fn get_last_included_term(&self) -> u64 {
+        return self.last_included_term;
+    }
+
+    pub fn get_data_as_vec(&self) -> Vec<u8> {
+        return self.data.to_vec();
+    }
+
+    pub fn get_client_sessions_as_vec(&self) -> Vec<u8> {
+        return self.client_sessions.to_vec();
+    }
+
+    /// The latest membership as of the last included index, none if the membership had never changed.
+    pub fn get_membership(&self) -> Option<&Membership> {
+        return self.membership.as_ref();
+    }
+
+    /// The encoded membership, empty if the snapshot has no membership.
+    pub(crate) fn get_membership_as_vec(&self) -> Vec<u8> {
+        return match &self.membership {
+            None => Vec::new(),
+            Some(membership) => membership.encode()
+        };
+    }
+
+    /// The snapshot along with the membership encoded by `get_membership_as_vec`, none if the encoded membership can not be decoded.
+    pub(crate) fn with_encoded_membership(self, membership: &[u8]) -> Option<Self> {
+        if membership.is_empty() {
+            return Some(self);
+        }
+        return Membership::decode(membership).map(|membership| self.with_membership(membership));
+    }
+
+    pub(crate) fn includes(&self, index: u64) -> bool {
+        return index <= self.last_included_index;
+    }
+}
+
+impl InMemorySnapshotStorage {
+    pub fn new() -> Self {
+        return InMemorySnapshotStorage { snapshot: RwLock::new(None) };
+    }
+}
+
+impl SnapshotStorage for InMemorySnapshotStorage {
+    fn save(&self, snapshot: &Snapshot) -> io::Result<()> {
+        let mut write_guard = self.snapshot.write().unwrap();
+        *write_guard = Some(snapshot.clone());
+        return Ok(());
+    }
+
+    fn load(&self) -> io::Result<Option<Snapshot>> {
+        let guard = self.snapshot.read().unwrap();
+        return Ok(guard.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::storage::snapshot_storage::{InMemorySnapshotStorage, Snapshot, SnapshotStorage};
+
+    #[test]
+    fn load_without_save() {
+        let storage = InMemorySnapshotStorage::new();
+        assert_eq!(None, storage.load().unwrap());
+    }
+
+    #[test]
+    fn save_and_load() {
+        let storage = InMemorySnapshotStorage::new();
+        storage.save(&Snapshot::new(5, 2, "state".as_bytes().to_vec())).unwrap();
+
+        let snapshot = storage.load().unwrap().unwrap();
+        assert_eq!(5, snapshot.get_last_included_index());
+        assert_eq!(2, snapshot.get_last_included_term());
+        assert_eq!("state".as_bytes().to_vec(), snapshot.get_data_as_vec());
+    }
+
+    #[test]
+    fn snapshot_includes_index() {
+        let snapshot = Snapshot::new(5, 2, Vec::new());
+
+        assert!(snapshot.includes(5));
+        assert!(snapshot.includes(0));
+        assert_eq!(false, snapshot.includes(6));
+    }
+}
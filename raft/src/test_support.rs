@@ -0,0 +1,25 @@
+use std::sync::RwLock;
+use std::time::{Duration, SystemTime};
+
+use replicate::clock::clock::Clock;
+
+pub(crate) struct ControllableClock {
+    now: RwLock<SystemTime>,
+}
+
+impl ControllableClock {
+    pub(crate) fn new() -> Self {
+        return ControllableClock { now: RwLock::new(SystemTime::now()) };
+    }
+
+    pub(crate) fn advance_by(&self, duration: Duration) {
+        let mut now = self.now.write().unwrap();
+        *now = *now + duration;
+    }
+}
+
+impl Clock for ControllableClock {
+    fn now(&self) -> SystemTime {
+        return *self.now.read().unwrap();
+    }
+}
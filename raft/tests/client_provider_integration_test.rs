@@ -5,7 +5,7 @@ use std::time::Duration;
 
 use tokio::runtime::{Builder, Runtime};
 use tonic::{Code, Request, Status};
-use raft::heartbeat_config::HeartbeatConfig;
+use raft::raft_config::RaftConfig;
 
 use raft::net::factory::client_provider::{RequestVoteClient, RequestVoteResponseClient};
 use raft::net::rpc::grpc::raft_server::RaftServer;
@@ -35,7 +35,7 @@ fn request_vote_client_with_missing_host_port_in_request() {
     let_services_start();
 
     let client = RequestVoteClient {};
-    let request = Request::new(RequestVote { term: 1, replica_id: 10, correlation_id: 10 });
+    let request = Request::new(RequestVote { term: 1, replica_id: 10, correlation_id: 10, last_log_index: None, last_log_term: None, leadership_transfer: false });
 
     let blocking_runtime = Builder::new_current_thread().enable_all().build().unwrap();
     blocking_runtime.block_on(async move {
@@ -94,7 +94,7 @@ fn spin_self(runtime: &Runtime, self_host_and_port: HostAndPort, peers: Vec<Host
 
     let blocking_runtime = Builder::new_current_thread().enable_all().build().unwrap();
     let state = blocking_runtime.block_on(async move {
-        return State::new(Arc::new(replica), HeartbeatConfig::default());
+        return State::new(Arc::new(replica), RaftConfig::default());
     });
 
     let inner_state = state.clone();
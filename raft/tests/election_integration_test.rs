@@ -1,14 +1,14 @@
 use std::net::{IpAddr, Ipv4Addr};
 use std::sync::Arc;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use tokio::runtime::{Builder, Runtime};
 
 use raft::election::election::Election;
-use raft::heartbeat_config::HeartbeatConfig;
 use raft::net::rpc::grpc::raft_server::RaftServer;
 use raft::net::service::raft_service::RaftService;
+use raft::raft_config::RaftConfig;
 use raft::state::{ReplicaRole, State};
 use replicate::clock::clock::SystemClock;
 use replicate::net::connect::host_and_port::HostAndPort;
@@ -39,7 +39,7 @@ fn start_elections_with_new_term() {
     assert_eq!(1, state.get_term());
 
     election.start();
-    thread::sleep(Duration::from_millis(20));
+    wait_until(Duration::from_millis(500), || state.get_term() == 2 && state.get_role() == ReplicaRole::Leader);
     assert_eq!(2, state.get_term());
     assert_eq!(ReplicaRole::Leader, state.get_role());
 
@@ -96,6 +96,44 @@ fn elect_a_leader() {
     assert_eq!(1, leader_count);
 }
 
+#[test]
+fn leader_steps_down_after_losing_contact_with_a_majority() {
+    let runtime = Builder::new_multi_thread()
+        .thread_name("leader_steps_down_after_losing_contact_with_a_majority".to_string())
+        .worker_threads(2)
+        .enable_all()
+        .build()
+        .unwrap();
+
+    let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 4880);
+    let peer_one = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 4881);
+    let peer_other = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 4882);
+
+    let (all_services_shutdown_handle_one, state) = spin_self(&runtime, self_host_and_port.clone(), vec![peer_one, peer_other]);
+    let (all_services_shutdown_handle_two, _) = spin_peer(&runtime, peer_one.clone(), vec![self_host_and_port, peer_other]);
+    let (all_services_shutdown_handle_three, _) = spin_other_peer(&runtime, peer_other.clone(), vec![self_host_and_port, peer_one]);
+
+    let election = Election::new(state.clone());
+    election.start();
+
+    wait_until(Duration::from_millis(500), || state.get_role() == ReplicaRole::Leader);
+    assert_eq!(ReplicaRole::Leader, state.get_role());
+
+    let blocking_runtime = Builder::new_current_thread().enable_all().build().unwrap();
+    blocking_runtime.block_on(async {
+        all_services_shutdown_handle_two.shutdown().await.unwrap();
+        all_services_shutdown_handle_three.shutdown().await.unwrap();
+    });
+
+    //the heartbeats go unanswered for longer than the heartbeat timeout, the replica steps down (and may start an election later)
+    wait_until(Duration::from_millis(1000), || state.get_role() != ReplicaRole::Leader);
+    assert_ne!(ReplicaRole::Leader, state.get_role());
+
+    blocking_runtime.block_on(async move {
+        all_services_shutdown_handle_one.shutdown().await.unwrap();
+    });
+}
+
 fn spin_self(runtime: &Runtime, self_host_and_port: HostAndPort, peers: Vec<HostAndPort>) -> (AllServicesShutdownHandle, Arc<State>) {
     let (all_services_shutdown_handle, all_services_shutdown_receiver) = AllServicesShutdownHandle::new();
     let replica = Replica::new(
@@ -106,7 +144,7 @@ fn spin_self(runtime: &Runtime, self_host_and_port: HostAndPort, peers: Vec<Host
     );
 
     let state = runtime.block_on(async move {
-        return State::new(Arc::new(replica), HeartbeatConfig::default());
+        return State::new(Arc::new(replica), RaftConfig::default());
     });
     let inner_state = state.clone();
     runtime.spawn(async move {
@@ -128,7 +166,7 @@ fn spin_peer(runtime: &Runtime, self_host_and_port: HostAndPort, peers: Vec<Host
         Arc::new(SystemClock::new()),
     );
     let state = runtime.block_on(async move {
-        return State::new(Arc::new(replica), HeartbeatConfig::default());
+        return State::new(Arc::new(replica), RaftConfig::default());
     });
     let inner_state = state.clone();
     runtime.spawn(async move {
@@ -151,7 +189,7 @@ fn spin_other_peer(runtime: &Runtime, self_host_and_port: HostAndPort, peers: Ve
     );
 
     let state = runtime.block_on(async move {
-        return State::new(Arc::new(replica), HeartbeatConfig::default());
+        return State::new(Arc::new(replica), RaftConfig::default());
     });
     let inner_state = state.clone();
     runtime.spawn(async move {
@@ -163,3 +201,11 @@ fn spin_other_peer(runtime: &Runtime, self_host_and_port: HostAndPort, peers: Ve
     });
     (all_services_shutdown_handle, state.clone())
 }
+
+fn wait_until<F>(timeout: Duration, condition: F)
+    where F: Fn() -> bool {
+    let start = Instant::now();
+    while !condition() && start.elapsed() < timeout {
+        thread::sleep(Duration::from_millis(5));
+    }
+}
@@ -0,0 +1,169 @@
+use std::net::{IpAddr, Ipv4Addr};
+use std::path::Path;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use tokio::runtime::{Builder, Runtime};
+
+use raft::net::factory::client_provider::RequestVoteClient;
+use raft::net::rpc::grpc::{RequestVote, RequestVoteResponse};
+use raft::net::rpc::grpc::raft_server::RaftServer;
+use raft::net::service::raft_service::RaftService;
+use raft::raft_config::RaftConfig;
+use raft::state::State;
+use raft::state_machine::NoOpStateMachine;
+use raft::storage::file_hard_state_storage::FileHardStateStorage;
+use raft::storage::hard_state_storage::HardStateStorage;
+use raft::storage::log_store::InMemoryLogStore;
+use raft::storage::snapshot_storage::InMemorySnapshotStorage;
+use replicate::callback::quorum_completion_response::QuorumCompletionResponse;
+use replicate::callback::single_response_completion_callback::SingleResponseCompletionCallback;
+use replicate::clock::clock::SystemClock;
+use replicate::net::connect::host_and_port::HostAndPort;
+use replicate::net::connect::service_client::ServiceRequest;
+use replicate::net::connect::service_registration::{AllServicesShutdownHandle, ServiceRegistration};
+use replicate::net::replica::{Replica, ReplicaId};
+
+#[test]
+fn do_not_vote_for_two_candidates_in_the_same_term_across_restart() {
+    let runtime = Builder::new_multi_thread()
+        .thread_name("do_not_vote_for_two_candidates_in_the_same_term_across_restart".to_string())
+        .worker_threads(2)
+        .enable_all()
+        .build()
+        .unwrap();
+
+    let directory = tempfile::tempdir().unwrap();
+    let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 4760);
+    let candidate_one = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 4761);
+    let candidate_other = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 4762);
+    let (directory_one, directory_other) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+
+    let (all_services_shutdown_handle, state, _) = spin(&runtime, 10, self_host_and_port, vec![candidate_one, candidate_other], directory.path());
+    let (all_services_shutdown_handle_one, _, replica_one) = spin(&runtime, 20, candidate_one, vec![self_host_and_port, candidate_other], directory_one.path());
+    let (all_services_shutdown_handle_other, _, replica_other) = spin(&runtime, 30, candidate_other, vec![self_host_and_port, candidate_one], directory_other.path());
+    let_services_start();
+
+    let response = send_request_vote(&replica_one, self_host_and_port, RequestVote { term: 100, replica_id: 20, correlation_id: 10, last_log_index: None, last_log_term: None, leadership_transfer: false });
+    assert!(response.voted);
+    assert_eq!(100, state.get_term());
+    assert_eq!(Some(20), state.get_voted_for());
+
+    shutdown(all_services_shutdown_handle);
+    let_services_stop();
+
+    let (all_services_shutdown_handle, restarted_state, _) = spin(&runtime, 10, self_host_and_port, vec![candidate_one, candidate_other], directory.path());
+    let_services_start();
+    assert_eq!(100, restarted_state.get_term());
+    assert_eq!(Some(20), restarted_state.get_voted_for());
+
+    let response = send_request_vote(&replica_other, self_host_and_port, RequestVote { term: 100, replica_id: 30, correlation_id: 20, last_log_index: None, last_log_term: None, leadership_transfer: false });
+    assert_eq!(false, response.voted);
+    assert_eq!(100, response.term);
+    assert_eq!(100, restarted_state.get_term());
+    assert_eq!(Some(20), restarted_state.get_voted_for());
+
+    let hard_state = FileHardStateStorage::new(directory.path()).unwrap().load().unwrap().unwrap();
+    assert_eq!(100, hard_state.get_term());
+    assert_eq!(Some(20), hard_state.get_voted_for());
+
+    shutdown(all_services_shutdown_handle);
+    shutdown(all_services_shutdown_handle_one);
+    shutdown(all_services_shutdown_handle_other);
+}
+
+#[test]
+fn recover_the_term_and_the_vote_across_restart() {
+    let runtime = Builder::new_multi_thread()
+        .thread_name("recover_the_term_and_the_vote_across_restart".to_string())
+        .worker_threads(2)
+        .enable_all()
+        .build()
+        .unwrap();
+
+    let directory = tempfile::tempdir().unwrap();
+    let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 4770);
+    let candidate = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 4771);
+    let directory_candidate = tempfile::tempdir().unwrap();
+
+    let (all_services_shutdown_handle, state, _) = spin(&runtime, 10, self_host_and_port, vec![candidate], directory.path());
+    let (all_services_shutdown_handle_candidate, _, replica_candidate) = spin(&runtime, 20, candidate, vec![self_host_and_port], directory_candidate.path());
+    let_services_start();
+
+    let response = send_request_vote(&replica_candidate, self_host_and_port, RequestVote { term: 200, replica_id: 20, correlation_id: 10, last_log_index: None, last_log_term: None, leadership_transfer: false });
+    assert!(response.voted);
+    assert_eq!(Some(20), state.get_voted_for());
+
+    shutdown(all_services_shutdown_handle);
+    shutdown(all_services_shutdown_handle_candidate);
+
+    let hard_state = FileHardStateStorage::new(directory.path()).unwrap().load().unwrap().unwrap();
+    assert_eq!(200, hard_state.get_term());
+    assert_eq!(Some(20), hard_state.get_voted_for());
+}
+
+fn send_request_vote(candidate: &Arc<Replica>, target: HostAndPort, request_vote: RequestVote) -> RequestVoteResponse {
+    let blocking_runtime = Builder::new_current_thread().enable_all().build().unwrap();
+    let candidate = candidate.clone();
+    return blocking_runtime.block_on(async move {
+        let correlation_id = request_vote.correlation_id;
+        let response_callback = SingleResponseCompletionCallback::<RequestVoteResponse>::new();
+        let service_request_constructor = || ServiceRequest::new(request_vote.clone(), Box::new(RequestVoteClient {}), correlation_id);
+
+        let total_failed_sends = candidate.send_to(&vec![target], service_request_constructor, response_callback.clone()).await;
+        assert_eq!(0, total_failed_sends);
+
+        return match response_callback.handle().await {
+            QuorumCompletionResponse::Success(responses) => responses.into_values().next().unwrap(),
+            other => panic!("expected a RequestVoteResponse, received {:?}", other),
+        };
+    });
+}
+
+fn shutdown(all_services_shutdown_handle: AllServicesShutdownHandle) {
+    let blocking_runtime = Builder::new_current_thread().enable_all().build().unwrap();
+    blocking_runtime.block_on(async move {
+        all_services_shutdown_handle.shutdown().await.unwrap();
+    });
+}
+
+//elections are kept out of the way with an election timeout longer than the test, only the test's candidates ask for votes
+fn spin(runtime: &Runtime, replica_id: ReplicaId, self_host_and_port: HostAndPort, peers: Vec<HostAndPort>, directory: &Path) -> (AllServicesShutdownHandle, Arc<State>, Arc<Replica>) {
+    let (all_services_shutdown_handle, all_services_shutdown_receiver) = AllServicesShutdownHandle::new();
+    let replica = Arc::new(Replica::new(
+        replica_id,
+        self_host_and_port.clone(),
+        peers,
+        Arc::new(SystemClock::new()),
+    ));
+    let hard_state_storage = Arc::new(FileHardStateStorage::new(directory).unwrap());
+    let raft_config = RaftConfig::builder()
+        .with_election_timeout_range(Duration::from_secs(60)..=Duration::from_secs(60))
+        .build()
+        .unwrap();
+
+    let inner_replica = replica.clone();
+    let blocking_runtime = Builder::new_current_thread().enable_all().build().unwrap();
+    let state = blocking_runtime.block_on(async move {
+        return State::new_with_storage(inner_replica, raft_config, hard_state_storage, Box::new(InMemoryLogStore::new()), Box::new(InMemorySnapshotStorage::new()), Arc::new(NoOpStateMachine::new()));
+    });
+
+    let inner_state = state.clone();
+    runtime.spawn(async move {
+        ServiceRegistration::register_services_on(
+            &self_host_and_port,
+            RaftServer::new(RaftService::new(inner_state, Arc::new(SystemClock::new()))),
+            all_services_shutdown_receiver,
+        ).await;
+    });
+    (all_services_shutdown_handle, state, replica)
+}
+
+fn let_services_start() {
+    thread::sleep(Duration::from_millis(50));
+}
+
+fn let_services_stop() {
+    thread::sleep(Duration::from_millis(100));
+}
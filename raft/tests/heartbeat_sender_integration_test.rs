@@ -3,8 +3,8 @@ use std::sync::Arc;
 use std::thread;
 use std::time::Duration;
 use tokio::runtime::{Builder, Runtime};
-use raft::heartbeat_config::HeartbeatConfig;
 use raft::net::service::raft_service::RaftService;
+use raft::raft_config::RaftConfig;
 use raft::state::State;
 use replicate::clock::clock::SystemClock;
 use replicate::net::connect::host_and_port::HostAndPort;
@@ -53,7 +53,7 @@ fn spin_self(runtime: &Runtime, self_host_and_port: HostAndPort, peers: Vec<Host
 
     let blocking_runtime = Builder::new_current_thread().enable_all().build().unwrap();
     let state = blocking_runtime.block_on(async move {
-        return State::new(Arc::new(replica), HeartbeatConfig::default());
+        return State::new(Arc::new(replica), RaftConfig::default());
     });
     let inner_state = state.clone();
     runtime.spawn(async move {
@@ -77,7 +77,7 @@ fn spin_peer(runtime: &Runtime, self_host_and_port: HostAndPort, peers: Vec<Host
 
     let blocking_runtime = Builder::new_current_thread().enable_all().build().unwrap();
     let state = blocking_runtime.block_on(async move {
-        return State::new(Arc::new(replica), HeartbeatConfig::default());
+        return State::new(Arc::new(replica), RaftConfig::default());
     });
     runtime.spawn(async move {
         ServiceRegistration::register_services_on(
@@ -100,7 +100,7 @@ fn spin_other_peer(runtime: &Runtime, self_host_and_port: HostAndPort, peers: Ve
 
     let blocking_runtime = Builder::new_current_thread().enable_all().build().unwrap();
     let state = blocking_runtime.block_on(async move {
-        return State::new(Arc::new(replica), HeartbeatConfig::default());
+        return State::new(Arc::new(replica), RaftConfig::default());
     });
     runtime.spawn(async move {
         ServiceRegistration::register_services_on(
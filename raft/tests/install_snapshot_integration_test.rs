@@ -0,0 +1,116 @@
+use std::net::{IpAddr, Ipv4Addr};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use tokio::runtime::{Builder, Runtime};
+use tonic::{Request, Response};
+
+use raft::election::election::Election;
+use raft::net::rpc::grpc::Command;
+use raft::net::rpc::grpc::raft_client::RaftClient;
+use raft::net::rpc::grpc::raft_server::RaftServer;
+use raft::net::service::raft_service::RaftService;
+use raft::raft_config::RaftConfig;
+use raft::state::{ReplicaRole, State};
+use replicate::clock::clock::SystemClock;
+use replicate::net::connect::error::ServiceResponseError;
+use replicate::net::connect::host_and_port::HostAndPort;
+use replicate::net::connect::service_registration::{AllServicesShutdownHandle, ServiceRegistration};
+use replicate::net::replica::{Replica, ReplicaId};
+
+#[test]
+fn install_snapshot_on_a_lagging_follower() {
+    let runtime = Builder::new_multi_thread()
+        .thread_name("install_snapshot_on_a_lagging_follower".to_string())
+        .worker_threads(2)
+        .enable_all()
+        .build()
+        .unwrap();
+
+    let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 4790);
+    let peer_one = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 4791);
+    let peer_other = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 4792);
+
+    let (all_services_shutdown_handle_one, state) = spin(&runtime, 10, self_host_and_port, vec![peer_one, peer_other]);
+    let (all_services_shutdown_handle_two, _) = spin(&runtime, 20, peer_one, vec![self_host_and_port, peer_other]);
+    let (all_services_shutdown_handle_three, _) = spin(&runtime, 30, peer_other, vec![self_host_and_port, peer_one]);
+
+    let election = Election::new(state.clone());
+    election.start();
+
+    thread::sleep(Duration::from_millis(30));
+    assert_eq!(ReplicaRole::Leader, state.get_role());
+
+    let blocking_runtime = Builder::new_current_thread().enable_all().build().unwrap();
+    blocking_runtime.block_on(async {
+        send_commands(
+            self_host_and_port,
+            vec![
+                Command { command: "replicate".as_bytes().to_vec(), client_id: None, sequence: 0 },
+                Command { command: "raft".as_bytes().to_vec(), client_id: None, sequence: 0 },
+            ],
+        ).await.unwrap();
+        all_services_shutdown_handle_three.shutdown().await.unwrap();
+    });
+
+    let snapshot = state.get_replicated_log().compact(1, "snapshot".as_bytes().to_vec()).unwrap();
+    assert_eq!(1, snapshot.get_last_included_index());
+
+    thread::sleep(Duration::from_millis(100));
+    let (all_services_shutdown_handle_three, state_peer_other) = spin(&runtime, 30, peer_other, vec![self_host_and_port, peer_one]);
+    thread::sleep(Duration::from_millis(50));
+
+    blocking_runtime.block_on(async {
+        send_commands(
+            self_host_and_port,
+            vec![Command { command: "log".as_bytes().to_vec(), client_id: None, sequence: 0 }],
+        ).await.unwrap();
+    });
+    //the command is committed once a majority (the leader included) has it, the lagging follower may still be installing the snapshot
+    thread::sleep(Duration::from_millis(50));
+
+    let replicated_log = state_peer_other.get_replicated_log();
+    let installed_snapshot = replicated_log.get_snapshot().unwrap();
+    assert_eq!(1, installed_snapshot.get_last_included_index());
+    assert_eq!("snapshot".as_bytes().to_vec(), installed_snapshot.get_data_as_vec());
+    assert_eq!(4, replicated_log.total_log_entries());
+    assert_eq!("log".as_bytes().to_vec(), replicated_log.get_log_entry_at(3).unwrap().get_bytes_as_vec());
+
+    blocking_runtime.block_on(async move {
+        all_services_shutdown_handle_one.shutdown().await.unwrap();
+        all_services_shutdown_handle_two.shutdown().await.unwrap();
+        all_services_shutdown_handle_three.shutdown().await.unwrap();
+    });
+}
+
+async fn send_commands(address: HostAndPort, commands: Vec<Command>) -> Result<Response<()>, ServiceResponseError> {
+    let mut client = RaftClient::connect(address.as_string_with_http()).await?;
+    for command in commands {
+        client.execute(Request::new(command)).await?;
+    }
+    return Ok(Response::new(()));
+}
+
+fn spin(runtime: &Runtime, replica_id: ReplicaId, self_host_and_port: HostAndPort, peers: Vec<HostAndPort>) -> (AllServicesShutdownHandle, Arc<State>) {
+    let (all_services_shutdown_handle, all_services_shutdown_receiver) = AllServicesShutdownHandle::new();
+    let replica = Replica::new(
+        replica_id,
+        self_host_and_port.clone(),
+        peers,
+        Arc::new(SystemClock::new()),
+    );
+
+    let state = runtime.block_on(async move {
+        return State::new(Arc::new(replica), RaftConfig::default());
+    });
+    let inner_state = state.clone();
+    runtime.spawn(async move {
+        ServiceRegistration::register_services_on(
+            &self_host_and_port,
+            RaftServer::new(RaftService::new(inner_state, Arc::new(SystemClock::new()))),
+            all_services_shutdown_receiver,
+        ).await;
+    });
+    (all_services_shutdown_handle, state)
+}
@@ -0,0 +1,132 @@
+use std::net::{IpAddr, Ipv4Addr};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use tokio::runtime::{Builder, Runtime};
+use tonic::Request;
+
+use raft::election::election::Election;
+use raft::net::rpc::grpc::raft_client::RaftClient;
+use raft::net::rpc::grpc::raft_server::RaftServer;
+use raft::net::rpc::grpc::{Command, TransferLeadership};
+use raft::net::service::raft_service::RaftService;
+use raft::raft_config::RaftConfig;
+use raft::state::{ReplicaRole, State};
+use replicate::clock::clock::SystemClock;
+use replicate::net::connect::host_and_port::HostAndPort;
+use replicate::net::connect::service_registration::{AllServicesShutdownHandle, ServiceRegistration};
+use replicate::net::replica::{Replica, ReplicaId};
+
+#[test]
+fn transfer_leadership_to_a_peer() {
+    let runtime = Builder::new_multi_thread()
+        .thread_name("transfer_leadership_to_a_peer".to_string())
+        .worker_threads(2)
+        .enable_all()
+        .build()
+        .unwrap();
+
+    let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 4820);
+    let peer_one = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 4821);
+    let peer_other = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 4822);
+
+    let (all_services_shutdown_handle_one, state) = spin(&runtime, 10, self_host_and_port, vec![peer_one, peer_other], RaftConfig::default());
+    let (all_services_shutdown_handle_two, state_peer_one) = spin(&runtime, 20, peer_one, vec![self_host_and_port, peer_other], RaftConfig::default());
+    let (all_services_shutdown_handle_three, _) = spin(&runtime, 30, peer_other, vec![self_host_and_port, peer_one], RaftConfig::default());
+
+    let election = Election::new(state.clone());
+    election.start();
+
+    thread::sleep(Duration::from_millis(30));
+    assert_eq!(ReplicaRole::Leader, state.get_role());
+
+    let blocking_runtime = Builder::new_current_thread().enable_all().build().unwrap();
+    blocking_runtime.block_on(async move {
+        let mut client = RaftClient::connect(self_host_and_port.as_string_with_http()).await.unwrap();
+        client.execute(Request::new(Command { command: "replicate".as_bytes().to_vec(), client_id: None, sequence: 0 })).await.unwrap();
+
+        let result = client.transfer_leadership(Request::new(TransferLeadership {
+            target_host: peer_one.host_as_string(),
+            target_port: peer_one.port() as u32,
+        })).await;
+        assert!(result.is_ok());
+
+        assert_eq!(ReplicaRole::Leader, state_peer_one.get_role());
+        assert_eq!(ReplicaRole::Follower, state.get_role());
+        //the no-op entries of both the leaders along with the command
+        assert_eq!(3, state_peer_one.get_replicated_log().total_log_entries());
+
+        all_services_shutdown_handle_one.shutdown().await.unwrap();
+        all_services_shutdown_handle_two.shutdown().await.unwrap();
+        all_services_shutdown_handle_three.shutdown().await.unwrap();
+    });
+}
+
+#[test]
+fn transfer_leadership_to_a_peer_with_the_leader_lease_enabled() {
+    let runtime = Builder::new_multi_thread()
+        .thread_name("transfer_leadership_to_a_peer_with_the_leader_lease_enabled".to_string())
+        .worker_threads(2)
+        .enable_all()
+        .build()
+        .unwrap();
+
+    let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 4900);
+    let peer_one = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 4901);
+    let peer_other = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 4902);
+
+    let raft_config = || RaftConfig::builder().with_leader_lease(Duration::from_millis(10)).build().unwrap();
+    let (all_services_shutdown_handle_one, state) = spin(&runtime, 10, self_host_and_port, vec![peer_one, peer_other], raft_config());
+    let (all_services_shutdown_handle_two, state_peer_one) = spin(&runtime, 20, peer_one, vec![self_host_and_port, peer_other], raft_config());
+    let (all_services_shutdown_handle_three, _) = spin(&runtime, 30, peer_other, vec![self_host_and_port, peer_one], raft_config());
+
+    let election = Election::new(state.clone());
+    election.start();
+
+    thread::sleep(Duration::from_millis(30));
+    assert_eq!(ReplicaRole::Leader, state.get_role());
+
+    let blocking_runtime = Builder::new_current_thread().enable_all().build().unwrap();
+    blocking_runtime.block_on(async move {
+        let mut client = RaftClient::connect(self_host_and_port.as_string_with_http()).await.unwrap();
+        client.execute(Request::new(Command { command: "replicate".as_bytes().to_vec(), client_id: None, sequence: 0 })).await.unwrap();
+
+        //the peers have just heard from the leader, they refuse a regular vote request within the lease
+        let result = client.transfer_leadership(Request::new(TransferLeadership {
+            target_host: peer_one.host_as_string(),
+            target_port: peer_one.port() as u32,
+        })).await;
+        assert!(result.is_ok());
+
+        assert_eq!(ReplicaRole::Leader, state_peer_one.get_role());
+        assert_eq!(ReplicaRole::Follower, state.get_role());
+
+        all_services_shutdown_handle_one.shutdown().await.unwrap();
+        all_services_shutdown_handle_two.shutdown().await.unwrap();
+        all_services_shutdown_handle_three.shutdown().await.unwrap();
+    });
+}
+
+fn spin(runtime: &Runtime, replica_id: ReplicaId, self_host_and_port: HostAndPort, peers: Vec<HostAndPort>, raft_config: RaftConfig) -> (AllServicesShutdownHandle, Arc<State>) {
+    let (all_services_shutdown_handle, all_services_shutdown_receiver) = AllServicesShutdownHandle::new();
+    let replica = Replica::new(
+        replica_id,
+        self_host_and_port.clone(),
+        peers,
+        Arc::new(SystemClock::new()),
+    );
+
+    let state = runtime.block_on(async move {
+        return State::new(Arc::new(replica), raft_config);
+    });
+    let inner_state = state.clone();
+    runtime.spawn(async move {
+        ServiceRegistration::register_services_on(
+            &self_host_and_port,
+            RaftServer::new(RaftService::new(inner_state, Arc::new(SystemClock::new()))),
+            all_services_shutdown_receiver,
+        ).await;
+    });
+    (all_services_shutdown_handle, state)
+}
@@ -0,0 +1,114 @@
+use std::net::{IpAddr, Ipv4Addr};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use tokio::runtime::{Builder, Runtime};
+use tonic::Request;
+
+use raft::election::election::Election;
+use raft::membership::Membership;
+use raft::net::rpc::grpc::raft_client::RaftClient;
+use raft::net::rpc::grpc::raft_server::RaftServer;
+use raft::net::rpc::grpc::{Command, Member};
+use raft::net::service::raft_service::RaftService;
+use raft::raft_config::RaftConfig;
+use raft::state::{ReplicaRole, State};
+use replicate::clock::clock::SystemClock;
+use replicate::net::connect::host_and_port::HostAndPort;
+use replicate::net::connect::service_registration::{AllServicesShutdownHandle, ServiceRegistration};
+use replicate::net::replica::{Replica, ReplicaId};
+
+#[test]
+fn add_and_promote_a_learner() {
+    let runtime = Builder::new_multi_thread()
+        .thread_name("add_and_promote_a_learner".to_string())
+        .worker_threads(2)
+        .enable_all()
+        .build()
+        .unwrap();
+
+    let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 4840);
+    let peer_one = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 4841);
+    let peer_other = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 4842);
+    let learner = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 4843);
+
+    let (all_services_shutdown_handle_one, state) = spin(&runtime, 10, self_host_and_port, vec![peer_one, peer_other], false);
+    let (all_services_shutdown_handle_two, _) = spin(&runtime, 20, peer_one, vec![self_host_and_port, peer_other], false);
+    let (all_services_shutdown_handle_three, _) = spin(&runtime, 30, peer_other, vec![self_host_and_port, peer_one], false);
+    let (all_services_shutdown_handle_four, state_learner) = spin(&runtime, 40, learner, vec![self_host_and_port, peer_one, peer_other], true);
+
+    let election = Election::new(state.clone());
+    election.start();
+
+    thread::sleep(Duration::from_millis(50));
+    assert_eq!(ReplicaRole::Leader, state.get_role());
+    assert_eq!(ReplicaRole::Learner, state_learner.get_role());
+
+    let blocking_runtime = Builder::new_current_thread().enable_all().build().unwrap();
+    blocking_runtime.block_on(async move {
+        let mut client = RaftClient::connect(self_host_and_port.as_string_with_http()).await.unwrap();
+        client.execute(Request::new(Command { command: "replicate".as_bytes().to_vec(), client_id: None, sequence: 0 })).await.unwrap();
+
+        let result = client.add_learner(Request::new(Member {
+            host: learner.host_as_string(),
+            port: learner.port() as u32,
+        })).await;
+        assert!(result.is_ok());
+
+        let membership = state.get_membership();
+        assert_eq!(&vec![self_host_and_port, peer_one, peer_other], membership.get_voters());
+        assert_eq!(&vec![learner], membership.get_learners());
+
+        client.execute(Request::new(Command { command: "raft".as_bytes().to_vec(), client_id: None, sequence: 0 })).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(membership, state_learner.get_membership());
+        assert_eq!(ReplicaRole::Learner, state_learner.get_role());
+        assert_eq!(4, state_learner.get_replicated_log().total_log_entries());
+
+        let result = client.promote_learner(Request::new(Member {
+            host: learner.host_as_string(),
+            port: learner.port() as u32,
+        })).await;
+        assert!(result.is_ok());
+
+        let membership = Membership::new(vec![self_host_and_port, peer_one, peer_other, learner]);
+        assert_eq!(membership, state.get_membership());
+
+        client.execute(Request::new(Command { command: "promoted".as_bytes().to_vec(), client_id: None, sequence: 0 })).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(membership, state_learner.get_membership());
+        assert_eq!(ReplicaRole::Follower, state_learner.get_role());
+
+        all_services_shutdown_handle_one.shutdown().await.unwrap();
+        all_services_shutdown_handle_two.shutdown().await.unwrap();
+        all_services_shutdown_handle_three.shutdown().await.unwrap();
+        all_services_shutdown_handle_four.shutdown().await.unwrap();
+    });
+}
+
+fn spin(runtime: &Runtime, replica_id: ReplicaId, self_host_and_port: HostAndPort, peers: Vec<HostAndPort>, learner: bool) -> (AllServicesShutdownHandle, Arc<State>) {
+    let (all_services_shutdown_handle, all_services_shutdown_receiver) = AllServicesShutdownHandle::new();
+    let replica = Replica::new(
+        replica_id,
+        self_host_and_port.clone(),
+        peers,
+        Arc::new(SystemClock::new()),
+    );
+
+    let state = runtime.block_on(async move {
+        if learner {
+            return State::new_learner(Arc::new(replica), RaftConfig::default());
+        }
+        return State::new(Arc::new(replica), RaftConfig::default());
+    });
+    let inner_state = state.clone();
+    runtime.spawn(async move {
+        ServiceRegistration::register_services_on(
+            &self_host_and_port,
+            RaftServer::new(RaftService::new(inner_state, Arc::new(SystemClock::new()))),
+            all_services_shutdown_receiver,
+        ).await;
+    });
+    (all_services_shutdown_handle, state)
+}
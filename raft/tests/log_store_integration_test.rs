@@ -0,0 +1,143 @@
+use std::net::{IpAddr, Ipv4Addr};
+use std::path::Path;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use tokio::runtime::{Builder, Runtime};
+use tonic::{Request, Response};
+
+use raft::election::election::Election;
+use raft::log_entry::LogEntryType;
+use raft::net::rpc::grpc::Command;
+use raft::net::rpc::grpc::raft_client::RaftClient;
+use raft::net::rpc::grpc::raft_server::RaftServer;
+use raft::net::service::raft_service::RaftService;
+use raft::raft_config::RaftConfig;
+use raft::state::{ReplicaRole, State};
+use raft::state_machine::NoOpStateMachine;
+use raft::storage::file_hard_state_storage::FileHardStateStorage;
+use raft::storage::file_snapshot_storage::FileSnapshotStorage;
+use raft::storage::segmented_log_store::SegmentedLogStore;
+use replicate::clock::clock::SystemClock;
+use replicate::net::connect::error::ServiceResponseError;
+use replicate::net::connect::host_and_port::HostAndPort;
+use replicate::net::connect::service_registration::{AllServicesShutdownHandle, ServiceRegistration};
+use replicate::net::replica::{Replica, ReplicaId};
+
+#[test]
+fn recover_the_replicated_log_across_restart() {
+    let runtime = Builder::new_multi_thread()
+        .thread_name("recover_the_replicated_log_across_restart".to_string())
+        .worker_threads(2)
+        .enable_all()
+        .build()
+        .unwrap();
+
+    let directory_self = tempfile::tempdir().unwrap();
+    let directory_peer_one = tempfile::tempdir().unwrap();
+    let directory_peer_other = tempfile::tempdir().unwrap();
+
+    let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 4780);
+    let peer_one = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 4781);
+    let peer_other = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 4782);
+
+    let (all_services_shutdown_handle_one, state) = spin(&runtime, 10, self_host_and_port, vec![peer_one, peer_other], directory_self.path());
+    let (all_services_shutdown_handle_two, _) = spin(&runtime, 20, peer_one, vec![self_host_and_port, peer_other], directory_peer_one.path());
+    let (all_services_shutdown_handle_three, _) = spin(&runtime, 30, peer_other, vec![self_host_and_port, peer_one], directory_peer_other.path());
+
+    let election = Election::new(state.clone());
+    election.start();
+
+    wait_until(Duration::from_millis(500), || state.get_role() == ReplicaRole::Leader);
+    assert_eq!(ReplicaRole::Leader, state.get_role());
+
+    let content_replicate = String::from("replicate");
+    let content_raft = String::from("raft");
+
+    let blocking_runtime = Builder::new_current_thread().enable_all().build().unwrap();
+    blocking_runtime.block_on(async {
+        send_commands(
+            self_host_and_port,
+            vec![
+                Command { command: content_replicate.as_bytes().to_vec(), client_id: None, sequence: 0 },
+                Command { command: content_raft.as_bytes().to_vec(), client_id: None, sequence: 0 },
+            ],
+        ).await.unwrap();
+        //the commands are committed once a majority (the leader included) has them, the other follower may still be receiving them
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        all_services_shutdown_handle_one.shutdown().await.unwrap();
+        all_services_shutdown_handle_two.shutdown().await.unwrap();
+        all_services_shutdown_handle_three.shutdown().await.unwrap();
+    });
+    thread::sleep(Duration::from_millis(100));
+
+    for (replica_id, directory) in vec![(10, directory_self.path()), (20, directory_peer_one.path()), (30, directory_peer_other.path())] {
+        let restarted_state = restart(&runtime, replica_id, directory);
+        let replicated_log = restarted_state.get_replicated_log();
+
+        assert_eq!(3, replicated_log.total_log_entries());
+        assert_eq!(LogEntryType::NoOp, replicated_log.get_log_entry_at(0).unwrap().get_entry_type());
+        assert_eq!(content_replicate.as_bytes().to_vec(), replicated_log.get_log_entry_at(1).unwrap().get_bytes_as_vec());
+        assert_eq!(content_raft.as_bytes().to_vec(), replicated_log.get_log_entry_at(2).unwrap().get_bytes_as_vec());
+        assert_eq!(state.get_term(), replicated_log.get_log_entry_at(2).unwrap().get_term());
+    }
+}
+
+async fn send_commands(address: HostAndPort, commands: Vec<Command>) -> Result<Response<()>, ServiceResponseError> {
+    let mut client = RaftClient::connect(address.as_string_with_http()).await?;
+    for command in commands {
+        client.execute(Request::new(command)).await?;
+    }
+    return Ok(Response::new(()));
+}
+
+fn new_state(runtime: &Runtime, replica: Replica, directory: &Path) -> Arc<State> {
+    let hard_state_storage = Arc::new(FileHardStateStorage::new(directory).unwrap());
+    let log_store = Box::new(SegmentedLogStore::new(directory).unwrap());
+    let snapshot_storage = Box::new(FileSnapshotStorage::new(directory).unwrap());
+
+    return runtime.block_on(async move {
+        return State::new_with_storage(Arc::new(replica), RaftConfig::default(), hard_state_storage, log_store, snapshot_storage, Arc::new(NoOpStateMachine::new()));
+    });
+}
+
+fn restart(runtime: &Runtime, replica_id: ReplicaId, directory: &Path) -> Arc<State> {
+    let replica = Replica::new(
+        replica_id,
+        HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 4783),
+        vec![HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 4784)],
+        Arc::new(SystemClock::new()),
+    );
+    return new_state(runtime, replica, directory);
+}
+
+fn spin(runtime: &Runtime, replica_id: ReplicaId, self_host_and_port: HostAndPort, peers: Vec<HostAndPort>, directory: &Path) -> (AllServicesShutdownHandle, Arc<State>) {
+    let (all_services_shutdown_handle, all_services_shutdown_receiver) = AllServicesShutdownHandle::new();
+    let replica = Replica::new(
+        replica_id,
+        self_host_and_port.clone(),
+        peers,
+        Arc::new(SystemClock::new()),
+    );
+
+    let state = new_state(runtime, replica, directory);
+    let inner_state = state.clone();
+    runtime.spawn(async move {
+        ServiceRegistration::register_services_on(
+            &self_host_and_port,
+            RaftServer::new(RaftService::new(inner_state, Arc::new(SystemClock::new()))),
+            all_services_shutdown_receiver,
+        ).await;
+    });
+    (all_services_shutdown_handle, state)
+}
+
+fn wait_until<F>(timeout: Duration, condition: F)
+    where F: Fn() -> bool {
+    let start = Instant::now();
+    while !condition() && start.elapsed() < timeout {
+        thread::sleep(Duration::from_millis(5));
+    }
+}
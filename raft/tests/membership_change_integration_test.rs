@@ -0,0 +1,203 @@
+use std::net::{IpAddr, Ipv4Addr};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use tokio::runtime::{Builder, Runtime};
+use tonic::Request;
+
+use raft::election::election::Election;
+use raft::membership::Membership;
+use raft::net::rpc::grpc::raft_client::RaftClient;
+use raft::net::rpc::grpc::raft_server::RaftServer;
+use raft::net::rpc::grpc::{Command, Member};
+use raft::net::service::raft_service::RaftService;
+use raft::raft_config::RaftConfig;
+use raft::state::{ReplicaRole, State};
+use replicate::clock::clock::SystemClock;
+use replicate::net::connect::host_and_port::HostAndPort;
+use replicate::net::connect::service_registration::{AllServicesShutdownHandle, ServiceRegistration};
+use replicate::net::replica::{Replica, ReplicaId};
+
+#[test]
+fn add_a_voter() {
+    let runtime = Builder::new_multi_thread()
+        .thread_name("add_a_voter".to_string())
+        .worker_threads(2)
+        .enable_all()
+        .build()
+        .unwrap();
+
+    let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 4830);
+    let peer_one = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 4831);
+    let peer_other = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 4832);
+    let new_voter = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 4833);
+
+    let (all_services_shutdown_handle_one, state) = spin(&runtime, 10, self_host_and_port, vec![peer_one, peer_other], RaftConfig::builder().with_pre_vote().build().unwrap());
+    let (all_services_shutdown_handle_two, _) = spin(&runtime, 20, peer_one, vec![self_host_and_port, peer_other], RaftConfig::builder().with_pre_vote().build().unwrap());
+    let (all_services_shutdown_handle_three, _) = spin(&runtime, 30, peer_other, vec![self_host_and_port, peer_one], RaftConfig::builder().with_pre_vote().build().unwrap());
+    //the new voter knows the cluster, pre-vote keeps it from disrupting the leader until it receives the leader's heartbeats
+    let (all_services_shutdown_handle_four, state_new_voter) = spin(&runtime, 40, new_voter, vec![self_host_and_port, peer_one, peer_other], RaftConfig::builder().with_pre_vote().build().unwrap());
+
+    let election = Election::new(state.clone());
+    election.start();
+
+    thread::sleep(Duration::from_millis(50));
+    assert_eq!(ReplicaRole::Leader, state.get_role());
+
+    let blocking_runtime = Builder::new_current_thread().enable_all().build().unwrap();
+    blocking_runtime.block_on(async move {
+        let mut client = RaftClient::connect(self_host_and_port.as_string_with_http()).await.unwrap();
+        client.execute(Request::new(Command { command: "replicate".as_bytes().to_vec(), client_id: None, sequence: 0 })).await.unwrap();
+
+        let result = client.add_voter(Request::new(Member {
+            host: new_voter.host_as_string(),
+            port: new_voter.port() as u32,
+        })).await;
+        assert!(result.is_ok());
+
+        let membership = Membership::new(vec![self_host_and_port, peer_one, peer_other, new_voter]);
+        assert_eq!(membership, state.get_membership());
+        assert_eq!(Some((3, membership.clone())), state.get_replicated_log().get_membership());
+        assert_eq!(ReplicaRole::Leader, state.get_role());
+
+        client.execute(Request::new(Command { command: "raft".as_bytes().to_vec(), client_id: None, sequence: 0 })).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(membership, state_new_voter.get_membership());
+        assert!(state_new_voter.get_replicated_log().total_log_entries() >= 4);
+
+        all_services_shutdown_handle_one.shutdown().await.unwrap();
+        all_services_shutdown_handle_two.shutdown().await.unwrap();
+        all_services_shutdown_handle_three.shutdown().await.unwrap();
+        all_services_shutdown_handle_four.shutdown().await.unwrap();
+    });
+}
+
+#[test]
+fn remove_the_leader() {
+    let runtime = Builder::new_multi_thread()
+        .thread_name("remove_the_leader".to_string())
+        .worker_threads(2)
+        .enable_all()
+        .build()
+        .unwrap();
+
+    let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 4834);
+    let peer_one = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 4835);
+    let peer_other = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 4836);
+
+    let (all_services_shutdown_handle_one, state) = spin(&runtime, 10, self_host_and_port, vec![peer_one, peer_other], RaftConfig::builder().with_pre_vote().build().unwrap());
+    let (all_services_shutdown_handle_two, state_peer_one) = spin(&runtime, 20, peer_one, vec![self_host_and_port, peer_other], RaftConfig::builder().with_pre_vote().build().unwrap());
+    let (all_services_shutdown_handle_three, state_peer_other) = spin(&runtime, 30, peer_other, vec![self_host_and_port, peer_one], RaftConfig::builder().with_pre_vote().build().unwrap());
+
+    let election = Election::new(state.clone());
+    election.start();
+
+    thread::sleep(Duration::from_millis(50));
+    assert_eq!(ReplicaRole::Leader, state.get_role());
+
+    let blocking_runtime = Builder::new_current_thread().enable_all().build().unwrap();
+    blocking_runtime.block_on(async {
+        let mut client = RaftClient::connect(self_host_and_port.as_string_with_http()).await.unwrap();
+        client.execute(Request::new(Command { command: "replicate".as_bytes().to_vec(), client_id: None, sequence: 0 })).await.unwrap();
+
+        let result = client.remove_voter(Request::new(Member {
+            host: self_host_and_port.host_as_string(),
+            port: self_host_and_port.port() as u32,
+        })).await;
+        assert!(result.is_ok());
+    });
+
+    assert_eq!(ReplicaRole::Follower, state.get_role());
+    assert_eq!(Membership::new(vec![peer_one, peer_other]), state.get_membership());
+
+    thread::sleep(Duration::from_millis(1000));
+    assert_eq!(ReplicaRole::Follower, state.get_role());
+    let leaders = vec![&state_peer_one, &state_peer_other]
+        .into_iter()
+        .filter(|state| state.get_role() == ReplicaRole::Leader)
+        .count();
+    assert_eq!(1, leaders);
+
+    blocking_runtime.block_on(async move {
+        all_services_shutdown_handle_one.shutdown().await.unwrap();
+        all_services_shutdown_handle_two.shutdown().await.unwrap();
+        all_services_shutdown_handle_three.shutdown().await.unwrap();
+    });
+}
+
+#[test]
+fn a_removed_voter_does_not_disrupt_the_new_configuration() {
+    let runtime = Builder::new_multi_thread()
+        .thread_name("a_removed_voter_does_not_disrupt_the_new_configuration".to_string())
+        .worker_threads(2)
+        .enable_all()
+        .build()
+        .unwrap();
+
+    let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 4910);
+    let peer_one = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 4911);
+    let peer_other = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 4912);
+
+    //without pre-vote, only ignoring the vote requests within the minimum election timeout of hearing from the leader protects the new configuration
+    let (all_services_shutdown_handle_one, state) = spin(&runtime, 10, self_host_and_port, vec![peer_one, peer_other], RaftConfig::default());
+    let (all_services_shutdown_handle_two, state_peer_one) = spin(&runtime, 20, peer_one, vec![self_host_and_port, peer_other], RaftConfig::default());
+    let (all_services_shutdown_handle_three, state_peer_other) = spin(&runtime, 30, peer_other, vec![self_host_and_port, peer_one], RaftConfig::default());
+
+    let election = Election::new(state.clone());
+    election.start();
+
+    thread::sleep(Duration::from_millis(50));
+    assert_eq!(ReplicaRole::Leader, state.get_role());
+
+    let blocking_runtime = Builder::new_current_thread().enable_all().build().unwrap();
+    blocking_runtime.block_on(async {
+        let mut client = RaftClient::connect(self_host_and_port.as_string_with_http()).await.unwrap();
+        client.execute(Request::new(Command { command: "replicate".as_bytes().to_vec(), client_id: None, sequence: 0 })).await.unwrap();
+
+        //the removed voter no longer hears from the leader, it never learns its removal and keeps starting elections
+        all_services_shutdown_handle_three.shutdown().await.unwrap();
+        let result = client.remove_voter(Request::new(Member {
+            host: peer_other.host_as_string(),
+            port: peer_other.port() as u32,
+        })).await;
+        assert!(result.is_ok());
+    });
+
+    let term = state.get_term();
+    assert_eq!(Membership::new(vec![self_host_and_port, peer_one]), state.get_membership());
+
+    thread::sleep(Duration::from_millis(1000));
+    assert!(state_peer_other.get_term() > term);
+    assert_eq!(ReplicaRole::Leader, state.get_role());
+    assert_eq!(term, state.get_term());
+    assert_eq!(term, state_peer_one.get_term());
+
+    blocking_runtime.block_on(async move {
+        all_services_shutdown_handle_one.shutdown().await.unwrap();
+        all_services_shutdown_handle_two.shutdown().await.unwrap();
+    });
+}
+
+fn spin(runtime: &Runtime, replica_id: ReplicaId, self_host_and_port: HostAndPort, peers: Vec<HostAndPort>, raft_config: RaftConfig) -> (AllServicesShutdownHandle, Arc<State>) {
+    let (all_services_shutdown_handle, all_services_shutdown_receiver) = AllServicesShutdownHandle::new();
+    let replica = Replica::new(
+        replica_id,
+        self_host_and_port.clone(),
+        peers,
+        Arc::new(SystemClock::new()),
+    );
+
+    let state = runtime.block_on(async move {
+        return State::new(Arc::new(replica), raft_config);
+    });
+    let inner_state = state.clone();
+    runtime.spawn(async move {
+        ServiceRegistration::register_services_on(
+            &self_host_and_port,
+            RaftServer::new(RaftService::new(inner_state, Arc::new(SystemClock::new()))),
+            all_services_shutdown_receiver,
+        ).await;
+    });
+    (all_services_shutdown_handle, state)
+}
@@ -0,0 +1,128 @@
+use std::net::{IpAddr, Ipv4Addr};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use tokio::runtime::{Builder, Runtime};
+use tonic::{Code, Request};
+
+use raft::election::election::Election;
+use raft::net::rpc::grpc::raft_client::RaftClient;
+use raft::net::rpc::grpc::raft_server::RaftServer;
+use raft::net::rpc::grpc::{Command, NotLeader};
+use raft::net::service::raft_service::RaftService;
+use raft::raft_config::RaftConfig;
+use raft::state::{ReplicaRole, State};
+use replicate::clock::clock::SystemClock;
+use replicate::net::connect::host_and_port::HostAndPort;
+use replicate::net::connect::service_registration::{AllServicesShutdownHandle, ServiceRegistration};
+use replicate::net::replica::{Replica, ReplicaId};
+
+#[test]
+fn reject_command_on_a_follower_with_the_leader_address() {
+    let runtime = Builder::new_multi_thread()
+        .thread_name("reject_command_on_a_follower_with_the_leader_address".to_string())
+        .worker_threads(2)
+        .enable_all()
+        .build()
+        .unwrap();
+
+    let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 4850);
+    let peer_one = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 4851);
+    let peer_other = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 4852);
+
+    let (all_services_shutdown_handle_one, state) = spin(&runtime, 10, self_host_and_port, vec![peer_one, peer_other], RaftConfig::default());
+    let (all_services_shutdown_handle_two, state_peer_one) = spin(&runtime, 20, peer_one, vec![self_host_and_port, peer_other], RaftConfig::default());
+    let (all_services_shutdown_handle_three, _) = spin(&runtime, 30, peer_other, vec![self_host_and_port, peer_one], RaftConfig::default());
+
+    let election = Election::new(state.clone());
+    election.start();
+
+    thread::sleep(Duration::from_millis(100));
+    assert_eq!(ReplicaRole::Leader, state.get_role());
+    assert_eq!(Some(10), state_peer_one.get_leader_id());
+
+    let blocking_runtime = Builder::new_current_thread().enable_all().build().unwrap();
+    blocking_runtime.block_on(async move {
+        let mut client = RaftClient::connect(peer_one.as_string_with_http()).await.unwrap();
+        let result = client.execute(Request::new(Command { command: "replicate".as_bytes().to_vec(), client_id: None, sequence: 0 })).await;
+
+        let status = result.unwrap_err();
+        assert_eq!(Code::FailedPrecondition, status.code());
+
+        let not_leader = NotLeader::from_status(&status).unwrap();
+        assert_eq!(Some(10), not_leader.leader_id);
+        assert_eq!(Some(self_host_and_port), not_leader.get_leader_address());
+        //the follower has only the leader's no-op entry
+        assert_eq!(1, state_peer_one.get_replicated_log().total_log_entries());
+
+        all_services_shutdown_handle_one.shutdown().await.unwrap();
+        all_services_shutdown_handle_two.shutdown().await.unwrap();
+        all_services_shutdown_handle_three.shutdown().await.unwrap();
+    });
+}
+
+#[test]
+fn forward_command_to_the_leader() {
+    let runtime = Builder::new_multi_thread()
+        .thread_name("forward_command_to_the_leader".to_string())
+        .worker_threads(2)
+        .enable_all()
+        .build()
+        .unwrap();
+
+    let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 4853);
+    let peer_one = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 4854);
+    let peer_other = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 4855);
+
+    let raft_config = || RaftConfig::builder().with_forwarding_to_leader().build().unwrap();
+    let (all_services_shutdown_handle_one, state) = spin(&runtime, 10, self_host_and_port, vec![peer_one, peer_other], raft_config());
+    let (all_services_shutdown_handle_two, state_peer_one) = spin(&runtime, 20, peer_one, vec![self_host_and_port, peer_other], raft_config());
+    let (all_services_shutdown_handle_three, _) = spin(&runtime, 30, peer_other, vec![self_host_and_port, peer_one], raft_config());
+
+    let election = Election::new(state.clone());
+    election.start();
+
+    thread::sleep(Duration::from_millis(100));
+    assert_eq!(ReplicaRole::Leader, state.get_role());
+
+    let blocking_runtime = Builder::new_current_thread().enable_all().build().unwrap();
+    blocking_runtime.block_on(async move {
+        let mut client = RaftClient::connect(peer_one.as_string_with_http()).await.unwrap();
+        let result = client.execute(Request::new(Command { command: "replicate".as_bytes().to_vec(), client_id: None, sequence: 0 })).await;
+        assert!(result.is_ok());
+
+        assert_eq!(2, state.get_replicated_log().total_log_entries());
+        assert_eq!(Some(1), state.get_replicated_log().get_commit_index());
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!("replicate".as_bytes().to_vec(), state_peer_one.get_replicated_log().get_log_entry_at(1).unwrap().get_bytes_as_vec());
+
+        all_services_shutdown_handle_one.shutdown().await.unwrap();
+        all_services_shutdown_handle_two.shutdown().await.unwrap();
+        all_services_shutdown_handle_three.shutdown().await.unwrap();
+    });
+}
+
+fn spin(runtime: &Runtime, replica_id: ReplicaId, self_host_and_port: HostAndPort, peers: Vec<HostAndPort>, raft_config: RaftConfig) -> (AllServicesShutdownHandle, Arc<State>) {
+    let (all_services_shutdown_handle, all_services_shutdown_receiver) = AllServicesShutdownHandle::new();
+    let replica = Replica::new(
+        replica_id,
+        self_host_and_port.clone(),
+        peers,
+        Arc::new(SystemClock::new()),
+    );
+
+    let state = runtime.block_on(async move {
+        return State::new(Arc::new(replica), raft_config);
+    });
+    let inner_state = state.clone();
+    runtime.spawn(async move {
+        ServiceRegistration::register_services_on(
+            &self_host_and_port,
+            RaftServer::new(RaftService::new(inner_state, Arc::new(SystemClock::new()))),
+            all_services_shutdown_receiver,
+        ).await;
+    });
+    (all_services_shutdown_handle, state)
+}
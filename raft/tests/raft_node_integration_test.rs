@@ -0,0 +1,115 @@
+use std::net::{IpAddr, Ipv4Addr};
+use std::sync::{Arc, RwLock};
+use std::thread;
+use std::time::Duration;
+
+use tokio::runtime::{Builder, Runtime};
+
+use raft::log_entry::LogEntry;
+use raft::net::rpc::grpc::NotLeader;
+use raft::raft_node::{RaftNode, RaftNodeConfig};
+use raft::state::ReplicaRole;
+use raft::state_machine::StateMachine;
+use raft::storage::snapshot_storage::Snapshot;
+use replicate::net::connect::host_and_port::HostAndPort;
+use replicate::net::replica::ReplicaId;
+
+struct AppendOnlyStateMachine {
+    applied: RwLock<Vec<Vec<u8>>>,
+}
+
+impl AppendOnlyStateMachine {
+    fn new() -> Self {
+        return AppendOnlyStateMachine { applied: RwLock::new(Vec::new()) };
+    }
+}
+
+impl StateMachine for AppendOnlyStateMachine {
+    fn apply(&self, log_entry: &LogEntry) -> Vec<u8> {
+        let mut applied = self.applied.write().unwrap();
+        applied.push(log_entry.get_bytes_as_vec());
+        return applied.len().to_string().as_bytes().to_vec();
+    }
+
+    fn read(&self, _: &[u8]) -> Vec<u8> {
+        return self.applied.read().unwrap().len().to_string().as_bytes().to_vec();
+    }
+
+    fn restore(&self, _: &Snapshot) {
+        self.applied.write().unwrap().clear();
+    }
+}
+
+#[test]
+fn elect_a_leader_propose_and_read() {
+    let runtime = Builder::new_multi_thread()
+        .thread_name("elect_a_leader_propose_and_read".to_string())
+        .worker_threads(2)
+        .enable_all()
+        .build()
+        .unwrap();
+
+    let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 4890);
+    let peer_one = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 4891);
+    let peer_other = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 4892);
+
+    let nodes = vec![
+        start(&runtime, 10, self_host_and_port, vec![peer_one, peer_other]),
+        start(&runtime, 20, peer_one, vec![self_host_and_port, peer_other]),
+        start(&runtime, 30, peer_other, vec![self_host_and_port, peer_one]),
+    ];
+
+    //the leader's heartbeats settle the cluster, a replica that timed out with the leader's election may still hold another election
+    await_leader(&nodes);
+    thread::sleep(Duration::from_millis(300));
+    let leader = await_leader(&nodes);
+    let follower = nodes.iter().find(|node| !node.status().is_leader()).unwrap();
+
+    let leader_status = leader.status();
+    assert_eq!(ReplicaRole::Leader, leader_status.get_role());
+    assert!(leader_status.get_term() >= 1);
+
+    runtime.block_on(async {
+        leader.propose("replicate".as_bytes().to_vec()).await.unwrap();
+        let result = leader.propose("raft".as_bytes().to_vec()).await.unwrap();
+        let read = leader.read(Vec::new()).await.unwrap();
+        assert_eq!("2".as_bytes().to_vec(), result);
+        assert_eq!(result, read);
+
+        let status = follower.propose("raft".as_bytes().to_vec()).await.unwrap_err();
+        let not_leader = NotLeader::from_status(&status).unwrap();
+        assert_eq!(Some(leader_status.get_replica_id()), not_leader.leader_id);
+    });
+
+    let leader_status = leader.status();
+    assert_eq!(Some(2), leader_status.get_commit_index());
+    assert_eq!(leader_status.get_commit_index(), leader_status.get_last_applied());
+
+    thread::sleep(Duration::from_millis(100));
+    let follower_status = follower.status();
+    assert_eq!(ReplicaRole::Follower, follower_status.get_role());
+    assert_eq!(Some(leader_status.get_replica_id()), follower_status.get_leader_id());
+    //a follower learns the commit index of an entry with the next entry it receives
+    assert!(follower_status.get_commit_index() >= Some(1));
+
+    runtime.block_on(async {
+        for node in &nodes {
+            node.shutdown().await;
+        }
+    });
+}
+
+fn start(runtime: &Runtime, replica_id: ReplicaId, self_host_and_port: HostAndPort, peers: Vec<HostAndPort>) -> RaftNode {
+    let config = RaftNodeConfig::new(replica_id, self_host_and_port, peers);
+    return runtime.block_on(RaftNode::start(config, Arc::new(AppendOnlyStateMachine::new())));
+}
+
+fn await_leader(nodes: &[RaftNode]) -> &RaftNode {
+    for _attempt in 1..=100 {
+        if let Some(leader) = nodes.iter().find(|node| node.status().is_leader()) {
+            return leader;
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+    panic!("no leader was elected");
+}
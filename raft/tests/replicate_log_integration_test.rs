@@ -7,17 +7,18 @@ use tokio::runtime::{Builder, Runtime};
 use tonic::{Request, Response};
 
 use raft::election::election::Election;
-use raft::heartbeat_config::HeartbeatConfig;
+use raft::log_entry::LogEntryType;
 use raft::net::rpc::grpc::Command;
 use raft::net::rpc::grpc::raft_client::RaftClient;
 use raft::net::rpc::grpc::raft_server::RaftServer;
 use raft::net::service::raft_service::RaftService;
+use raft::raft_config::RaftConfig;
 use raft::state::{ReplicaRole, State};
 use replicate::clock::clock::SystemClock;
 use replicate::net::connect::error::ServiceResponseError;
 use replicate::net::connect::host_and_port::HostAndPort;
 use replicate::net::connect::service_registration::{AllServicesShutdownHandle, ServiceRegistration};
-use replicate::net::replica::Replica;
+use replicate::net::replica::{Replica, ReplicaId};
 
 #[test]
 fn replicate_log() {
@@ -48,18 +49,22 @@ fn replicate_log() {
     blocking_runtime.block_on(async {
         send_commands(
             self_host_and_port,
-            vec![Command { command: content.as_bytes().to_vec() }],
+            vec![Command { command: content.as_bytes().to_vec(), client_id: None, sequence: 0 }],
         ).await.unwrap();
     });
+    //the command is committed once a majority (the leader included) has it, the other follower may still be receiving it
+    thread::sleep(Duration::from_millis(50));
 
     blocking_runtime.block_on(async move {
-        assert_eq!(1, state.get_replicated_log().total_log_entries());
-        assert_eq!(1, state_peer_one.get_replicated_log().total_log_entries());
-        assert_eq!(1, state_peer_other.get_replicated_log().total_log_entries());
+        //the leader's no-op entry followed by the command
+        assert_eq!(2, state.get_replicated_log().total_log_entries());
+        assert_eq!(2, state_peer_one.get_replicated_log().total_log_entries());
+        assert_eq!(2, state_peer_other.get_replicated_log().total_log_entries());
 
-        assert_eq!(content.as_bytes().to_vec(), state_peer_one.get_replicated_log().get_log_entry_at(0).unwrap().get_bytes_as_vec());
-        assert_eq!(content.as_bytes().to_vec(), state_peer_other.get_replicated_log().get_log_entry_at(0).unwrap().get_bytes_as_vec());
-        assert_eq!(2, state.get_replicated_log().get_log_entry_at(0).unwrap().get_acknowledgements());
+        assert_eq!(LogEntryType::NoOp, state_peer_one.get_replicated_log().get_log_entry_at(0).unwrap().get_entry_type());
+        assert_eq!(content.as_bytes().to_vec(), state_peer_one.get_replicated_log().get_log_entry_at(1).unwrap().get_bytes_as_vec());
+        assert_eq!(content.as_bytes().to_vec(), state_peer_other.get_replicated_log().get_log_entry_at(1).unwrap().get_bytes_as_vec());
+        assert_eq!(Some(1), state.get_replicated_log().get_commit_index());
 
         all_services_shutdown_handle_one.shutdown().await.unwrap();
         all_services_shutdown_handle_two.shutdown().await.unwrap();
@@ -99,31 +104,33 @@ fn replicate_multiple_logs_sequentially() {
         send_commands(
             self_host_and_port,
             vec![
-                Command { command: content_replicate.as_bytes().to_vec() },
-                Command { command: content_raft.as_bytes().to_vec() },
-                Command { command: content_log.as_bytes().to_vec() },
+                Command { command: content_replicate.as_bytes().to_vec(), client_id: None, sequence: 0 },
+                Command { command: content_raft.as_bytes().to_vec(), client_id: None, sequence: 0 },
+                Command { command: content_log.as_bytes().to_vec(), client_id: None, sequence: 0 },
             ]
         ).await.unwrap();
     });
+    thread::sleep(Duration::from_millis(50));
 
     blocking_runtime.block_on(async move {
         for state in vec![&state, &state_peer_one, &state_peer_other] {
-            assert_eq!(3, state.get_replicated_log().total_log_entries());
+            assert_eq!(4, state.get_replicated_log().total_log_entries());
 
             assert_eq!(content_replicate.as_bytes().to_vec(),
-                       state.get_replicated_log().get_log_entry_at(0).unwrap().get_bytes_as_vec()
+                       state.get_replicated_log().get_log_entry_at(1).unwrap().get_bytes_as_vec()
             );
             assert_eq!(content_raft.as_bytes().to_vec(),
-                       state.get_replicated_log().get_log_entry_at(1).unwrap().get_bytes_as_vec()
+                       state.get_replicated_log().get_log_entry_at(2).unwrap().get_bytes_as_vec()
             );
             assert_eq!(content_log.as_bytes().to_vec(),
-                       state.get_replicated_log().get_log_entry_at(2).unwrap().get_bytes_as_vec()
+                       state.get_replicated_log().get_log_entry_at(3).unwrap().get_bytes_as_vec()
             );
         }
 
-        assert_eq!(Some(2), state.get_replicated_log().get_commit_index());
-        assert_eq!(Some(1), state_peer_one.get_replicated_log().get_commit_index());
-        assert_eq!(Some(1), state_peer_other.get_replicated_log().get_commit_index());
+        assert_eq!(Some(3), state.get_replicated_log().get_commit_index());
+        //a follower learns the commit index with a later request, the lagging follower may receive the last entry after it is committed
+        assert!(state_peer_one.get_replicated_log().get_commit_index() >= Some(2));
+        assert!(state_peer_other.get_replicated_log().get_commit_index() >= Some(2));
 
         all_services_shutdown_handle_one.shutdown().await.unwrap();
         all_services_shutdown_handle_two.shutdown().await.unwrap();
@@ -131,6 +138,71 @@ fn replicate_multiple_logs_sequentially() {
     });
 }
 
+#[test]
+fn replicate_log_to_lagging_peers_with_pipelined_requests() {
+    let runtime = Builder::new_multi_thread()
+        .thread_name("replicate_log_to_lagging_peers_with_pipelined_requests".to_string())
+        .worker_threads(2)
+        .enable_all()
+        .build()
+        .unwrap();
+
+    let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 4870);
+    let peer_one = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 4871);
+    let peer_other = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 4872);
+
+    //a request carries a single entry, the peers catch up through the requests pipelined after each response
+    let raft_config = || RaftConfig::builder()
+        .with_max_entries_per_append(1)
+        .with_max_bytes_per_append(1024)
+        .with_max_in_flight_appends(3)
+        .build()
+        .unwrap();
+    let (all_services_shutdown_handle_one, state) = spin_with_raft_config(&runtime, 10, self_host_and_port, vec![peer_one, peer_other], raft_config());
+    let (all_services_shutdown_handle_two, state_peer_one) = spin_with_raft_config(&runtime, 20, peer_one, vec![self_host_and_port, peer_other], raft_config());
+    let (all_services_shutdown_handle_three, state_peer_other) = spin_with_raft_config(&runtime, 30, peer_other, vec![self_host_and_port, peer_one], raft_config());
+
+    for count in 0..6 {
+        let command = Command { command: format!("Content-{}", count).as_bytes().to_vec(), client_id: None, sequence: 0 };
+        state.get_replicated_log().append_command(&command, 0);
+    }
+
+    let election = Election::new(state.clone());
+    election.start();
+
+    thread::sleep(Duration::from_millis(30));
+    assert_eq!(ReplicaRole::Leader, state.get_role());
+
+    let blocking_runtime = Builder::new_current_thread().enable_all().build().unwrap();
+    blocking_runtime.block_on(async {
+        send_commands(
+            self_host_and_port,
+            vec![Command { command: "Content-6".as_bytes().to_vec(), client_id: None, sequence: 0 }],
+        ).await.unwrap();
+    });
+    thread::sleep(Duration::from_millis(50));
+
+    //the entries of the earlier term, the leader's no-op entry and the command
+    for state in vec![&state_peer_one, &state_peer_other] {
+        assert_eq!(8, state.get_replicated_log().total_log_entries());
+        for index in 0..6 {
+            assert_eq!(
+                format!("Content-{}", index).as_bytes().to_vec(),
+                state.get_replicated_log().get_log_entry_at(index).unwrap().get_bytes_as_vec()
+            );
+        }
+        assert_eq!(LogEntryType::NoOp, state.get_replicated_log().get_log_entry_at(6).unwrap().get_entry_type());
+        assert_eq!("Content-6".as_bytes().to_vec(), state.get_replicated_log().get_log_entry_at(7).unwrap().get_bytes_as_vec());
+    }
+    assert_eq!(Some(7), state.get_replicated_log().get_commit_index());
+
+    blocking_runtime.block_on(async move {
+        all_services_shutdown_handle_one.shutdown().await.unwrap();
+        all_services_shutdown_handle_two.shutdown().await.unwrap();
+        all_services_shutdown_handle_three.shutdown().await.unwrap();
+    });
+}
+
 async fn send_commands(address: HostAndPort, commands: Vec<Command>) -> Result<Response<()>, ServiceResponseError> {
     let mut client = RaftClient::connect(address.as_string_with_http()).await?;
     for command in commands {
@@ -149,7 +221,7 @@ fn spin_self(runtime: &Runtime, self_host_and_port: HostAndPort, peers: Vec<Host
     );
 
     let state = runtime.block_on(async move {
-        return State::new(Arc::new(replica), HeartbeatConfig::default());
+        return State::new(Arc::new(replica), RaftConfig::default());
     });
     let inner_state = state.clone();
     runtime.spawn(async move {
@@ -171,7 +243,7 @@ fn spin_peer(runtime: &Runtime, self_host_and_port: HostAndPort, peers: Vec<Host
         Arc::new(SystemClock::new()),
     );
     let state = runtime.block_on(async move {
-        return State::new(Arc::new(replica), HeartbeatConfig::default());
+        return State::new(Arc::new(replica), RaftConfig::default());
     });
     let inner_state = state.clone();
     runtime.spawn(async move {
@@ -194,7 +266,36 @@ fn spin_other_peer(runtime: &Runtime, self_host_and_port: HostAndPort, peers: Ve
     );
 
     let state = runtime.block_on(async move {
-        return State::new(Arc::new(replica), HeartbeatConfig::default());
+        return State::new(Arc::new(replica), RaftConfig::default());
+    });
+    let inner_state = state.clone();
+    runtime.spawn(async move {
+        ServiceRegistration::register_services_on(
+            &self_host_and_port,
+            RaftServer::new(RaftService::new(inner_state, Arc::new(SystemClock::new()))),
+            all_services_shutdown_receiver,
+        ).await;
+    });
+    (all_services_shutdown_handle, state.clone())
+}
+
+fn spin_with_raft_config(
+    runtime: &Runtime,
+    replica_id: ReplicaId,
+    self_host_and_port: HostAndPort,
+    peers: Vec<HostAndPort>,
+    raft_config: RaftConfig,
+) -> (AllServicesShutdownHandle, Arc<State>) {
+    let (all_services_shutdown_handle, all_services_shutdown_receiver) = AllServicesShutdownHandle::new();
+    let replica = Replica::new(
+        replica_id,
+        self_host_and_port.clone(),
+        peers,
+        Arc::new(SystemClock::new()),
+    );
+
+    let state = runtime.block_on(async move {
+        return State::new(Arc::new(replica), raft_config);
     });
     let inner_state = state.clone();
     runtime.spawn(async move {
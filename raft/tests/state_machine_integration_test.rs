@@ -0,0 +1,300 @@
+use std::net::{IpAddr, Ipv4Addr};
+use std::sync::{Arc, RwLock};
+use std::thread;
+use std::time::Duration;
+
+use tokio::runtime::{Builder, Runtime};
+use tonic::{Code, Request, Response};
+
+use raft::election::election::Election;
+use raft::log_entry::LogEntry;
+use raft::net::rpc::grpc::raft_client::RaftClient;
+use raft::net::rpc::grpc::raft_server::RaftServer;
+use raft::net::rpc::grpc::{Command, Query};
+use raft::net::service::raft_service::RaftService;
+use raft::raft_config::RaftConfig;
+use raft::state::{ReplicaRole, State};
+use raft::state_machine::StateMachine;
+use raft::storage::hard_state_storage::InMemoryHardStateStorage;
+use raft::storage::log_store::InMemoryLogStore;
+use raft::storage::snapshot_storage::{InMemorySnapshotStorage, Snapshot};
+use replicate::clock::clock::SystemClock;
+use replicate::net::connect::error::ServiceResponseError;
+use replicate::net::connect::host_and_port::HostAndPort;
+use replicate::net::connect::service_registration::{AllServicesShutdownHandle, ServiceRegistration};
+use replicate::net::replica::{Replica, ReplicaId};
+
+struct AppendOnlyStateMachine {
+    applied: RwLock<Vec<(u64, Vec<u8>)>>,
+}
+
+impl AppendOnlyStateMachine {
+    fn new() -> Self {
+        return AppendOnlyStateMachine { applied: RwLock::new(Vec::new()) };
+    }
+
+    fn get_applied(&self) -> Vec<(u64, Vec<u8>)> {
+        return self.applied.read().unwrap().clone();
+    }
+}
+
+impl StateMachine for AppendOnlyStateMachine {
+    fn apply(&self, log_entry: &LogEntry) -> Vec<u8> {
+        let mut applied = self.applied.write().unwrap();
+        applied.push((log_entry.get_index(), log_entry.get_bytes_as_vec()));
+        return applied.len().to_string().as_bytes().to_vec();
+    }
+
+    fn read(&self, _: &[u8]) -> Vec<u8> {
+        return self.applied.read().unwrap().len().to_string().as_bytes().to_vec();
+    }
+
+    fn restore(&self, _: &Snapshot) {
+        self.applied.write().unwrap().clear();
+    }
+}
+
+#[test]
+fn apply_committed_log_entries_on_all_the_replicas() {
+    let runtime = Builder::new_multi_thread()
+        .thread_name("apply_committed_log_entries_on_all_the_replicas".to_string())
+        .worker_threads(2)
+        .enable_all()
+        .build()
+        .unwrap();
+
+    let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 4800);
+    let peer_one = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 4801);
+    let peer_other = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 4802);
+
+    let (all_services_shutdown_handle_one, state, state_machine) = spin(&runtime, 10, self_host_and_port, vec![peer_one, peer_other]);
+    let (all_services_shutdown_handle_two, state_peer_one, state_machine_peer_one) = spin(&runtime, 20, peer_one, vec![self_host_and_port, peer_other]);
+    let (all_services_shutdown_handle_three, state_peer_other, state_machine_peer_other) = spin(&runtime, 30, peer_other, vec![self_host_and_port, peer_one]);
+
+    let election = Election::new(state.clone());
+    election.start();
+
+    thread::sleep(Duration::from_millis(30));
+    assert_eq!(ReplicaRole::Leader, state.get_role());
+
+    let content_replicate = String::from("replicate");
+    let content_raft = String::from("raft");
+    let content_log = String::from("log");
+
+    let blocking_runtime = Builder::new_current_thread().enable_all().build().unwrap();
+    blocking_runtime.block_on(async {
+        send_commands(
+            self_host_and_port,
+            vec![
+                Command { command: content_replicate.as_bytes().to_vec(), client_id: None, sequence: 0 },
+                Command { command: content_raft.as_bytes().to_vec(), client_id: None, sequence: 0 },
+                Command { command: content_log.as_bytes().to_vec(), client_id: None, sequence: 0 },
+            ],
+        ).await.unwrap();
+    });
+
+    //the leader's no-op entry at index 0 is not applied to the state machine
+    assert_eq!(
+        vec![
+            (1, content_replicate.as_bytes().to_vec()),
+            (2, content_raft.as_bytes().to_vec()),
+            (3, content_log.as_bytes().to_vec()),
+        ],
+        state_machine.get_applied()
+    );
+    assert_eq!(Some(3), state.get_last_applied());
+
+    //the commands are committed once a majority (the leader included) has them, the other follower may still be receiving them
+    thread::sleep(Duration::from_millis(50));
+    for (state, state_machine) in vec![(&state_peer_one, &state_machine_peer_one), (&state_peer_other, &state_machine_peer_other)] {
+        assert_eq!(
+            vec![
+                (1, content_replicate.as_bytes().to_vec()),
+                (2, content_raft.as_bytes().to_vec()),
+            ],
+            state_machine.get_applied()
+        );
+        assert_eq!(Some(2), state.get_last_applied());
+    }
+
+    blocking_runtime.block_on(async move {
+        all_services_shutdown_handle_one.shutdown().await.unwrap();
+        all_services_shutdown_handle_two.shutdown().await.unwrap();
+        all_services_shutdown_handle_three.shutdown().await.unwrap();
+    });
+}
+
+#[test]
+fn return_the_state_machine_output_to_the_client() {
+    let runtime = Builder::new_multi_thread()
+        .thread_name("return_the_state_machine_output_to_the_client".to_string())
+        .worker_threads(2)
+        .enable_all()
+        .build()
+        .unwrap();
+
+    let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 4803);
+    let peer_one = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 4804);
+    let peer_other = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 4805);
+
+    let (all_services_shutdown_handle_one, state, _) = spin(&runtime, 10, self_host_and_port, vec![peer_one, peer_other]);
+    let (all_services_shutdown_handle_two, _, _) = spin(&runtime, 20, peer_one, vec![self_host_and_port, peer_other]);
+    let (all_services_shutdown_handle_three, _, _) = spin(&runtime, 30, peer_other, vec![self_host_and_port, peer_one]);
+
+    let election = Election::new(state.clone());
+    election.start();
+
+    thread::sleep(Duration::from_millis(30));
+    assert_eq!(ReplicaRole::Leader, state.get_role());
+
+    let blocking_runtime = Builder::new_current_thread().enable_all().build().unwrap();
+    blocking_runtime.block_on(async {
+        let mut client = RaftClient::connect(self_host_and_port.as_string_with_http()).await.unwrap();
+        for expected_output in vec!["1", "2"] {
+            let command_response = client.execute(Request::new(Command { command: "replicate".as_bytes().to_vec(), client_id: None, sequence: 0 })).await.unwrap();
+            assert_eq!(expected_output.as_bytes().to_vec(), command_response.into_inner().result);
+        }
+    });
+
+    blocking_runtime.block_on(async move {
+        all_services_shutdown_handle_one.shutdown().await.unwrap();
+        all_services_shutdown_handle_two.shutdown().await.unwrap();
+        all_services_shutdown_handle_three.shutdown().await.unwrap();
+    });
+}
+
+#[test]
+fn return_the_same_output_to_a_retried_client_command() {
+    let runtime = Builder::new_multi_thread()
+        .thread_name("return_the_same_output_to_a_retried_client_command".to_string())
+        .worker_threads(2)
+        .enable_all()
+        .build()
+        .unwrap();
+
+    let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 4860);
+    let peer_one = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 4861);
+    let peer_other = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 4862);
+
+    let (all_services_shutdown_handle_one, state, state_machine) = spin(&runtime, 10, self_host_and_port, vec![peer_one, peer_other]);
+    let (all_services_shutdown_handle_two, _, _) = spin(&runtime, 20, peer_one, vec![self_host_and_port, peer_other]);
+    let (all_services_shutdown_handle_three, _, _) = spin(&runtime, 30, peer_other, vec![self_host_and_port, peer_one]);
+
+    let election = Election::new(state.clone());
+    election.start();
+
+    thread::sleep(Duration::from_millis(30));
+    assert_eq!(ReplicaRole::Leader, state.get_role());
+
+    let blocking_runtime = Builder::new_current_thread().enable_all().build().unwrap();
+    blocking_runtime.block_on(async {
+        let mut client = RaftClient::connect(self_host_and_port.as_string_with_http()).await.unwrap();
+        for _ in 0..2 {
+            let command_response = client.execute(Request::new(Command { command: "replicate".as_bytes().to_vec(), client_id: Some(100), sequence: 0 })).await.unwrap();
+            assert_eq!("1".as_bytes().to_vec(), command_response.into_inner().result);
+        }
+        let command_response = client.execute(Request::new(Command { command: "raft".as_bytes().to_vec(), client_id: Some(100), sequence: 1 })).await.unwrap();
+        assert_eq!("2".as_bytes().to_vec(), command_response.into_inner().result);
+
+        //the session has moved past the first sequence, its response is no longer stored
+        let status = client.execute(Request::new(Command { command: "replicate".as_bytes().to_vec(), client_id: Some(100), sequence: 0 })).await.unwrap_err();
+        assert_eq!(Code::OutOfRange, status.code());
+
+        //a client without a session has either not opened it with sequence 0 or its session has expired
+        let status = client.execute(Request::new(Command { command: "raft".as_bytes().to_vec(), client_id: Some(200), sequence: 1 })).await.unwrap_err();
+        assert_eq!(Code::NotFound, status.code());
+    });
+
+    assert_eq!(2, state_machine.get_applied().len());
+
+    blocking_runtime.block_on(async move {
+        all_services_shutdown_handle_one.shutdown().await.unwrap();
+        all_services_shutdown_handle_two.shutdown().await.unwrap();
+        all_services_shutdown_handle_three.shutdown().await.unwrap();
+    });
+}
+
+#[test]
+fn read_the_state_machine_with_read_index() {
+    let runtime = Builder::new_multi_thread()
+        .thread_name("read_the_state_machine_with_read_index".to_string())
+        .worker_threads(2)
+        .enable_all()
+        .build()
+        .unwrap();
+
+    let self_host_and_port = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 4806);
+    let peer_one = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 4807);
+    let peer_other = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 4808);
+
+    let (all_services_shutdown_handle_one, state, _) = spin(&runtime, 10, self_host_and_port, vec![peer_one, peer_other]);
+    let (all_services_shutdown_handle_two, _, _) = spin(&runtime, 20, peer_one, vec![self_host_and_port, peer_other]);
+    let (all_services_shutdown_handle_three, _, _) = spin(&runtime, 30, peer_other, vec![self_host_and_port, peer_one]);
+
+    let election = Election::new(state.clone());
+    election.start();
+
+    thread::sleep(Duration::from_millis(30));
+    assert_eq!(ReplicaRole::Leader, state.get_role());
+
+    let blocking_runtime = Builder::new_current_thread().enable_all().build().unwrap();
+    blocking_runtime.block_on(async {
+        send_commands(
+            self_host_and_port,
+            vec![
+                Command { command: "replicate".as_bytes().to_vec(), client_id: None, sequence: 0 },
+                Command { command: "raft".as_bytes().to_vec(), client_id: None, sequence: 0 },
+            ],
+        ).await.unwrap();
+
+        let mut client = RaftClient::connect(self_host_and_port.as_string_with_http()).await.unwrap();
+        let query_response = client.read_index(Request::new(Query { query: vec![] })).await.unwrap();
+        assert_eq!("2".as_bytes().to_vec(), query_response.into_inner().result);
+    });
+
+    blocking_runtime.block_on(async move {
+        all_services_shutdown_handle_one.shutdown().await.unwrap();
+        all_services_shutdown_handle_two.shutdown().await.unwrap();
+        all_services_shutdown_handle_three.shutdown().await.unwrap();
+    });
+}
+
+async fn send_commands(address: HostAndPort, commands: Vec<Command>) -> Result<Response<()>, ServiceResponseError> {
+    let mut client = RaftClient::connect(address.as_string_with_http()).await?;
+    for command in commands {
+        client.execute(Request::new(command)).await?;
+    }
+    return Ok(Response::new(()));
+}
+
+fn spin(runtime: &Runtime, replica_id: ReplicaId, self_host_and_port: HostAndPort, peers: Vec<HostAndPort>) -> (AllServicesShutdownHandle, Arc<State>, Arc<AppendOnlyStateMachine>) {
+    let (all_services_shutdown_handle, all_services_shutdown_receiver) = AllServicesShutdownHandle::new();
+    let replica = Replica::new(
+        replica_id,
+        self_host_and_port.clone(),
+        peers,
+        Arc::new(SystemClock::new()),
+    );
+
+    let state_machine = Arc::new(AppendOnlyStateMachine::new());
+    let inner_state_machine = state_machine.clone();
+    let state = runtime.block_on(async move {
+        return State::new_with_storage(
+            Arc::new(replica),
+            RaftConfig::default(),
+            Arc::new(InMemoryHardStateStorage::new()),
+            Box::new(InMemoryLogStore::new()),
+            Box::new(InMemorySnapshotStorage::new()),
+            inner_state_machine,
+        );
+    });
+    let inner_state = state.clone();
+    runtime.spawn(async move {
+        ServiceRegistration::register_services_on(
+            &self_host_and_port,
+            RaftServer::new(RaftService::new(inner_state, Arc::new(SystemClock::new()))),
+            all_services_shutdown_receiver,
+        ).await;
+    });
+    (all_services_shutdown_handle, state, state_machine)
+}
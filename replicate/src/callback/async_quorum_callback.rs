@@ -10,6 +10,10 @@ use crate::net::request_waiting_list::response_callback::{AnyResponse, ResponseC
 
 pub type SuccessCondition<Response> = Box<dyn Fn(&Response) -> bool + Send + Sync>;
 
+pub type QuorumCondition = Box<dyn Fn(&Vec<HostAndPort>) -> bool + Send + Sync>;
+
+pub type StopCondition<Response> = Box<dyn Fn(&Response) -> bool + Send + Sync>;
+
 #[derive(Debug)]
 pub struct UnexpectedQuorumCallbackResponseError {
     pub response_type_id: TypeId,
@@ -45,6 +49,16 @@ impl<Response: Any + Send + Sync + Debug> AsyncQuorumCallback<Response> {
         cluster_size: usize,
         expected_total_responses: usize,
         success_condition: SuccessCondition<Response>,
+    ) -> Arc<AsyncQuorumCallback<Response>> {
+        return Self::new_with_stop_condition(cluster_size, expected_total_responses, success_condition, None);
+    }
+
+    /// Completes with the responses not meeting the success condition as soon as any response meets the stop condition.
+    pub fn new_with_stop_condition<>(
+        cluster_size: usize,
+        expected_total_responses: usize,
+        success_condition: SuccessCondition<Response>,
+        stop_condition: Option<StopCondition<Response>>,
     ) -> Arc<AsyncQuorumCallback<Response>> {
         return Arc::new(AsyncQuorumCallback {
             quorum_completion_handle: QuorumCompletionHandle {
@@ -52,6 +66,39 @@ impl<Response: Any + Send + Sync + Debug> AsyncQuorumCallback<Response> {
                 expected_total_responses,
                 majority_quorum: (cluster_size / 2) + 1,
                 success_condition,
+                quorum_condition: None,
+                stop_condition,
+                waker_state: Arc::new(Mutex::new(WakerState { waker: None })),
+            },
+        });
+    }
+
+    /// Completes successfully as soon as the replicas meeting the success condition satisfy the quorum condition,
+    /// otherwise completes once all the expected responses are received.
+    pub fn new_with_quorum_condition<>(
+        expected_total_responses: usize,
+        success_condition: SuccessCondition<Response>,
+        quorum_condition: QuorumCondition,
+    ) -> Arc<AsyncQuorumCallback<Response>> {
+        return Self::new_with_quorum_and_stop_condition(expected_total_responses, success_condition, quorum_condition, None);
+    }
+
+    /// Same as `new_with_quorum_condition`, but completes with the responses not meeting the success condition
+    /// as soon as any response meets the stop condition.
+    pub fn new_with_quorum_and_stop_condition<>(
+        expected_total_responses: usize,
+        success_condition: SuccessCondition<Response>,
+        quorum_condition: QuorumCondition,
+        stop_condition: Option<StopCondition<Response>>,
+    ) -> Arc<AsyncQuorumCallback<Response>> {
+        return Arc::new(AsyncQuorumCallback {
+            quorum_completion_handle: QuorumCompletionHandle {
+                responses: RwLock::new(HashMap::new()),
+                expected_total_responses,
+                majority_quorum: (expected_total_responses / 2) + 1,
+                success_condition,
+                quorum_condition: Some(quorum_condition),
+                stop_condition,
                 waker_state: Arc::new(Mutex::new(WakerState { waker: None })),
             },
         });
@@ -352,4 +399,101 @@ mod tests {
         let test_error_two = error_responses.get(&peer_1).unwrap().downcast_ref::<TestError>().unwrap();
         assert_eq!("test error two", test_error_two.message);
     }
-}
\ No newline at end of file
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn successful_responses_with_quorum_condition() {
+        let response_from_1 = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 50051);
+        let response_from_2 = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 50052);
+        let response_from_3 = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 50053);
+
+        let success_condition = Box::new(|response: &GetValueResponse| response.value == "ok");
+        let quorum_condition = Box::new(move |hosts: &Vec<HostAndPort>| hosts.contains(&response_from_1) && hosts.contains(&response_from_3));
+        let async_quorum_callback = AsyncQuorumCallback::<GetValueResponse>::new_with_quorum_condition(
+            3,
+            success_condition,
+            quorum_condition,
+        );
+
+        async_quorum_callback.on_response(response_from_1.clone(), Ok(Box::new(GetValueResponse { value: "ok".to_string() })));
+        async_quorum_callback.on_response(response_from_2.clone(), Ok(Box::new(GetValueResponse { value: "ok".to_string() })));
+        async_quorum_callback.on_response(response_from_3.clone(), Ok(Box::new(GetValueResponse { value: "ok".to_string() })));
+
+        let completion_response = async_quorum_callback.handle().await;
+        assert!(completion_response.is_success());
+        assert_eq!(3, completion_response.response_len());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn responses_with_quorum_condition_not_met() {
+        let response_from_1 = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 50051);
+        let response_from_2 = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 50052);
+        let response_from_3 = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 50053);
+
+        let success_condition = Box::new(|response: &GetValueResponse| response.value == "ok");
+        let quorum_condition = Box::new(move |hosts: &Vec<HostAndPort>| hosts.contains(&response_from_1) && hosts.contains(&response_from_3));
+        let async_quorum_callback = AsyncQuorumCallback::<GetValueResponse>::new_with_quorum_condition(
+            3,
+            success_condition,
+            quorum_condition,
+        );
+
+        async_quorum_callback.on_response(response_from_1.clone(), Ok(Box::new(GetValueResponse { value: "ok".to_string() })));
+        async_quorum_callback.on_response(response_from_2.clone(), Ok(Box::new(GetValueResponse { value: "ok".to_string() })));
+        async_quorum_callback.on_response(response_from_3.clone(), Ok(Box::new(GetValueResponse { value: "not_ok".to_string() })));
+
+        let completion_response = async_quorum_callback.handle().await;
+
+        let mut expected = HashMap::new();
+        expected.insert(response_from_3, GetValueResponse { value: "not_ok".to_string() });
+        assert_eq!(&expected, completion_response.success_condition_not_met_response().unwrap());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn stop_on_a_response_meeting_the_stop_condition() {
+        let response_from_1 = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 50051);
+        let response_from_2 = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 50052);
+
+        let success_condition = Box::new(|response: &GetValueResponse| response.value == "ok");
+        let stop_condition = Box::new(|response: &GetValueResponse| response.value == "stop");
+        let async_quorum_callback = AsyncQuorumCallback::<GetValueResponse>::new_with_stop_condition(
+            3,
+            3,
+            success_condition,
+            Some(stop_condition),
+        );
+
+        async_quorum_callback.on_response(response_from_1.clone(), Ok(Box::new(GetValueResponse { value: "ok".to_string() })));
+        async_quorum_callback.on_response(response_from_2.clone(), Ok(Box::new(GetValueResponse { value: "stop".to_string() })));
+
+        let completion_response = async_quorum_callback.handle().await;
+
+        let mut expected = HashMap::new();
+        expected.insert(response_from_2, GetValueResponse { value: "stop".to_string() });
+        assert_eq!(&expected, completion_response.success_condition_not_met_response().unwrap());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn stop_on_a_response_meeting_the_stop_condition_with_quorum_condition() {
+        let response_from_1 = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 50051);
+        let response_from_2 = HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 50052);
+
+        let success_condition = Box::new(|response: &GetValueResponse| response.value == "ok");
+        let quorum_condition = Box::new(|hosts: &Vec<HostAndPort>| hosts.len() >= 2);
+        let stop_condition = Box::new(|response: &GetValueResponse| response.value == "stop");
+        let async_quorum_callback = AsyncQuorumCallback::<GetValueResponse>::new_with_quorum_and_stop_condition(
+            3,
+            success_condition,
+            quorum_condition,
+            Some(stop_condition),
+        );
+
+        async_quorum_callback.on_response(response_from_1.clone(), Err(Box::new(TestError { message: "test error".to_string() })));
+        async_quorum_callback.on_response(response_from_2.clone(), Ok(Box::new(GetValueResponse { value: "stop".to_string() })));
+
+        let completion_response = async_quorum_callback.handle().await;
+
+        let mut expected = HashMap::new();
+        expected.insert(response_from_2, GetValueResponse { value: "stop".to_string() });
+        assert_eq!(&expected, completion_response.success_condition_not_met_response().unwrap());
+    }
+}
@@ -8,7 +8,7 @@ use std::task::{Context, Poll, Waker};
 
 use QuorumCompletionResponse::{Error, Success, SuccessConditionNotMet};
 
-use crate::callback::async_quorum_callback::{SuccessCondition, UnexpectedQuorumCallbackResponseError};
+use crate::callback::async_quorum_callback::{QuorumCondition, StopCondition, SuccessCondition, UnexpectedQuorumCallbackResponseError};
 use crate::callback::quorum_completion_response::QuorumCompletionResponse;
 use crate::net::connect::host_and_port::HostAndPort;
 use crate::net::request_waiting_list::response_callback::{AnyResponse, ResponseErrorType};
@@ -18,6 +18,8 @@ pub struct QuorumCompletionHandle<Response: Any + Send + Sync + Debug> {
     pub(crate) expected_total_responses: usize,
     pub(crate) majority_quorum: usize,
     pub(crate) success_condition: SuccessCondition<Response>,
+    pub(crate) quorum_condition: Option<QuorumCondition>,
+    pub(crate) stop_condition: Option<StopCondition<Response>>,
     pub(crate) waker_state: Arc<Mutex<WakerState>>,
 }
 
@@ -69,17 +71,25 @@ impl<Response: Any + Send + Sync + Debug> Future for &QuorumCompletionHandle<Res
         let mut write_guard = self.responses.write().unwrap();
 
         let total_non_error_responses = self.non_error_response_count(&write_guard);
-        let success_response_count = self.success_response_count(&mut write_guard);
-        if success_response_count >= self.majority_quorum {
-            return Poll::Ready(Success(self.all_success_responses(&mut write_guard)));
-        }
-        if self.missing_success_condition_response_count(&write_guard) >= self.majority_quorum {
+        let error_response_count = self.error_response_count(&write_guard);
+        if self.has_stop_response(&write_guard) {
             return Poll::Ready(SuccessConditionNotMet(self.all_missing_success_condition_responses(&mut write_guard)));
         }
-
-        let error_response_count = self.error_response_count(&write_guard);
-        if error_response_count >= self.majority_quorum {
-            return Poll::Ready(Error(self.all_error_responses(&mut write_guard)));
+        if let Some(quorum_condition) = &self.quorum_condition {
+            if quorum_condition(&self.success_response_hosts(&write_guard)) {
+                return Poll::Ready(Success(self.all_success_responses(&mut write_guard)));
+            }
+        } else {
+            let success_response_count = self.success_response_count(&mut write_guard);
+            if success_response_count >= self.majority_quorum {
+                return Poll::Ready(Success(self.all_success_responses(&mut write_guard)));
+            }
+            if self.missing_success_condition_response_count(&write_guard) >= self.majority_quorum {
+                return Poll::Ready(SuccessConditionNotMet(self.all_missing_success_condition_responses(&mut write_guard)));
+            }
+            if error_response_count >= self.majority_quorum {
+                return Poll::Ready(Error(self.all_error_responses(&mut write_guard)));
+            }
         }
         if total_non_error_responses + error_response_count >= self.expected_total_responses {
             if error_response_count >= 1 {
@@ -100,6 +110,16 @@ impl<Response: Any + Send + Sync + Debug> QuorumCompletionHandle<Response> {
         return responses_guard.iter().filter(|response| response.1.is_err()).count();
     }
 
+    fn has_stop_response(&self, responses_guard: &RwLockWriteGuard<HashMap<HostAndPort, Result<Response, ResponseErrorType>>>) -> bool {
+        return match &self.stop_condition {
+            None => false,
+            Some(stop_condition) => responses_guard
+                .iter()
+                .filter_map(|response| response.1.as_ref().ok())
+                .any(stop_condition)
+        };
+    }
+
     fn missing_success_condition_response_count(&self, responses_guard: &RwLockWriteGuard<HashMap<HostAndPort, Result<Response, ResponseErrorType>>>) -> usize {
         return responses_guard
             .iter()
@@ -120,6 +140,17 @@ impl<Response: Any + Send + Sync + Debug> QuorumCompletionHandle<Response> {
             .count();
     }
 
+    fn success_response_hosts(&self, responses_guard: &RwLockWriteGuard<HashMap<HostAndPort, Result<Response, ResponseErrorType>>>) -> Vec<HostAndPort> {
+        return responses_guard
+            .iter()
+            .filter(|response| match response.1 {
+                Ok(response) => (self.success_condition)(response),
+                Err(_) => false
+            })
+            .map(|response| response.0.clone())
+            .collect();
+    }
+
     fn all_success_responses(&self, responses_guard: &mut RwLockWriteGuard<HashMap<HostAndPort, Result<Response, ResponseErrorType>>>) -> HashMap<HostAndPort, Response> {
         return responses_guard
             .drain()
@@ -26,6 +26,8 @@ impl<Response: Any + Send + Sync + Debug> SingleResponseCompletionCallback<Respo
                 expected_total_responses: 1,
                 majority_quorum: 1,
                 success_condition: Box::new(|_: &Response| true),
+                quorum_condition: None,
+                stop_condition: None,
                 waker_state: Arc::new(Mutex::new(WakerState { waker: None })),
             },
         });
@@ -1,6 +1,6 @@
 use std::fmt::Debug;
 use std::future::Future;
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 
 use tokio::task::JoinHandle;
 
@@ -22,7 +22,7 @@ pub type ReplicaId = u64;
 pub struct Replica {
     id: ReplicaId,
     self_address: HostAndPort,
-    peer_addresses: Vec<HostAndPort>,
+    peer_addresses: RwLock<Vec<HostAndPort>>,
     request_waiting_list: RequestWaitingList,
     singular_update_queue: Arc<SingularUpdateQueue>,
     clock: Arc<dyn Clock>,
@@ -55,7 +55,7 @@ impl Replica {
         return Replica {
             id,
             self_address,
-            peer_addresses,
+            peer_addresses: RwLock::new(peer_addresses),
             request_waiting_list,
             singular_update_queue: Arc::new(SingularUpdateQueue::new()),
             clock,
@@ -68,7 +68,8 @@ impl Replica {
         where Payload: Send + 'static,
               Response: Send + Debug + 'static,
               S: Fn() -> ServiceRequest<Payload, Response> {
-        return self.send_to(&self.peer_addresses, service_request_constructor, response_callback).await;
+        let peer_addresses = self.peer_addresses.read().unwrap().clone();
+        return self.send_to(&peer_addresses, service_request_constructor, response_callback).await;
     }
 
     pub async fn send_to<Payload, S, Response>(&self,
@@ -110,9 +111,9 @@ impl Replica {
         where Payload: Send + 'static,
               Response: Send + Debug + 'static,
               S: Fn() -> ServiceRequest<Payload, Response>,
-              F: Fn(Result<Response, ServiceResponseError>) -> Option<T> + Send + Sync + 'static,
+              F: Fn(HostAndPort, Result<Response, ServiceResponseError>) -> Option<T> + Send + Sync + 'static,
               T: Future<Output=()> + Send + 'static {
-        let peer_addresses = self.peer_addresses.clone();
+        let peer_addresses = self.peer_addresses.read().unwrap().clone();
 
         for address in peer_addresses {
             if address.eq(&self.self_address) {
@@ -122,14 +123,16 @@ impl Replica {
             let singular_update_queue = self.singular_update_queue.clone();
             let service_request: ServiceRequest<Payload, Response> = service_request_constructor();
             let peer_handler_generator = response_handler_generator.clone();
+            let source_address = self.self_address;
 
             tokio::spawn(async move {
-                let response = AsyncNetwork::send_without_source_footprint(
+                let response = AsyncNetwork::send_with_source_footprint(
                     service_request,
+                    source_address,
                     address,
                 ).await;
 
-                if let Some(handler) = peer_handler_generator(response) {
+                if let Some(handler) = peer_handler_generator(address, response) {
                     let _ = singular_update_queue.add_async(handler).await;
                 }
             });
@@ -160,7 +163,7 @@ impl Replica {
 
     pub fn total_peer_count(&self) -> usize {
         let self_address = self.self_address;
-        return self.peer_addresses.iter().filter(|peer_address| peer_address.ne(&&self_address)).count();
+        return self.peer_addresses.read().unwrap().iter().filter(|peer_address| peer_address.ne(&&self_address)).count();
     }
 
     pub fn get_self_address(&self) -> HostAndPort {
@@ -170,12 +173,20 @@ impl Replica {
     pub fn get_peers(&self) -> Vec<HostAndPort> {
         let self_address = self.self_address;
         return self.peer_addresses
+            .read()
+            .unwrap()
             .iter()
             .filter(|peer_address| peer_address.ne(&&self_address))
             .map(|peer_address| peer_address.clone())
             .collect();
     }
 
+    /// Replaces the peers, the replicas that the subsequent requests are sent to.
+    pub fn update_peers(&self, peer_addresses: Vec<HostAndPort>) {
+        let mut write_guard = self.peer_addresses.write().unwrap();
+        *write_guard = peer_addresses;
+    }
+
     pub fn get_id(&self) -> ReplicaId {
         return self.id;
     }
@@ -591,6 +602,31 @@ mod tests {
         ], all_peers);
     }
 
+    #[test]
+    fn update_peers() {
+        let replica = Replica::new(
+            10,
+            HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 7080),
+            vec![
+                HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8989),
+                HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 9090),
+            ],
+            Arc::new(SystemClock::new()),
+        );
+
+        replica.update_peers(vec![
+            HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 9090),
+            HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 9098),
+            HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 7080),
+        ]);
+
+        assert_eq!(vec![
+            HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 9090),
+            HostAndPort::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 9098),
+        ], replica.get_peers());
+        assert_eq!(3, replica.cluster_size());
+    }
+
     #[test]
     fn send_one_way_to_the_replicas_without_callback_successfully() {
         let runtime = Builder::new_multi_thread().worker_threads(2).enable_all().build().unwrap();
@@ -619,7 +655,7 @@ mod tests {
 
             let response_counter = Arc::new(ResponseCounter { counter: AtomicI8::new(0) });
             let inner_response_counter = response_counter.clone();
-            let response_handler_generator = move |response: Result<(), ServiceResponseError>| {
+            let response_handler_generator = move |_: HostAndPort, response: Result<(), ServiceResponseError>| {
                 if response.is_ok() {
                     return Some(handler(&response_counter, 1, sender.clone()));
                 }
@@ -662,7 +698,7 @@ mod tests {
 
             let response_counter = Arc::new(ResponseCounter { counter: AtomicI8::new(0) });
             let inner_response_counter = response_counter.clone();
-            let response_handler_generator = Arc::new(move |response: Result<(), ServiceResponseError>| {
+            let response_handler_generator = Arc::new(move |_: HostAndPort, response: Result<(), ServiceResponseError>| {
                 if response.is_ok() {
                     return Some(handler(&response_counter, 1, sender.clone()));
                 }